@@ -55,6 +55,8 @@ REPO_PATH = os.path.normpath(
 
 BIN_PATH = os.path.normpath(os.path.join(REPO_PATH, "target/release"))
 
+CONFIG_PATH = os.path.normpath(os.path.join(REPO_PATH, "experiment_configs"))
+
 GRAPH_PATH = os.path.normpath(
     os.path.join(os.path.dirname(os.path.abspath(__file__)), "../../../")
 )
@@ -87,33 +89,43 @@ def load_bin_hashes():
     return hashes
 
 
-def load_bin_hash(bin):
+def load_bin_hash(name):
     hashes = load_bin_hashes()
 
-    if bin in hashes.keys():
-        return hashes[bin]
+    if name in hashes.keys():
+        return hashes[name]
     else:
         None
 
 
-def update_file_hash(bin):
+# measurements of an experiment config are stored under the config name and also depend on the config
+def update_file_hash(bin, config=None):
     hashes = load_bin_hashes()
-    hashes[bin] = create_file_hash(bin)
+    hashes[config or bin] = create_file_hash(bin, config)
 
     with open(PLOTDATA_PATH, "wb") as f:
         pickle.dump(hashes, f)
 
 
-def create_file_hash(bin):
+def create_file_hash(bin, config=None):
+    h = hashlib.md5()
     with open(to_local_os_binary_file_name(os.path.join(BIN_PATH, bin)), "rb") as f:
-        h = hashlib.md5(f.read()).digest()
-        f.close()
-        return h
+        h.update(f.read())
+    if config is not None:
+        with open(config_file(config), "rb") as f:
+            h.update(f.read())
+    return h.digest()
 
 
-def is_hash_up_to_date(bin):
+def is_hash_up_to_date(bin, config=None):
     fpath = to_local_os_binary_file_name(os.path.join(BIN_PATH, bin))
-    return is_bin(fpath) and create_file_hash(bin) == load_bin_hash(bin)
+    return is_bin(fpath) and create_file_hash(bin, config) == load_bin_hash(
+        config or bin
+    )
+
+
+def config_file(config):
+    return os.path.join(CONFIG_PATH, config + ".json")
 
 
 def run_bin(bin, args=[]):
@@ -173,6 +185,30 @@ def run_measurement(bin, graph):
     update_file_hash(bin)
 
 
+def run_experiment_conditionally(config, graph):
+    bin = "run_experiment"
+    build_successful = run_build(bin)
+    should_skip = (
+        is_hash_up_to_date(bin, config)
+        and exists_measurement(config, graph)
+        and not RUN_ALL_MEASUREMENTS
+    )
+
+    if not build_successful or should_skip:
+        print('Skipping experiment "' + config + '" with "' + graph + '"')
+    else:
+        run_bin(
+            bin,
+            [
+                "--",
+                config_file(config),
+                os.path.join(GRAPH_PATH, graph),
+                config + "-" + graph + ".txt",
+            ],
+        )
+        update_file_hash(bin, config)
+
+
 def build_measurement(bin, graph):
     run_bin(bin, ["--", os.path.join(GRAPH_PATH, graph)])
 
@@ -233,9 +269,9 @@ def get_boxplot_outliers(df, by_column_name):
 
 
 def plot_all_rank_times(problem, graph):
-    name = "measure_all_" + problem + "_1000_queries_rank_times"
+    name = "thesis_rank_times_all-" + problem
 
-    run_measurement_conditionally(name, graph)
+    run_experiment_conditionally(name, graph)
 
     queries_all = read_measurement(
         name + "-" + graph,
@@ -250,8 +286,8 @@ def plot_all_rank_times(problem, graph):
         ("time_ms", "log"),
     ]
 
-    # algos = ["astar_chpot", "astar_bidir_chpot", "core_ch", "core_ch_chpot"]
-    algos = ["astar_chpot", "core_ch", "core_ch_chpot"]
+    # algos = ["astar", "bidir_astar", "core_ch", "core_ch_astar"]
+    algos = ["astar", "core_ch", "core_ch_astar"]
 
     for algo in algos:
         queries = queries_all.loc[queries_all["algo"] == algo]
@@ -285,16 +321,15 @@ def plot_all_rank_times(problem, graph):
 
 
 def plot_rank_times_perf_profile(problem, graph):
-    algos = ["astar_chpot", "core_ch_chpot"]
+    algos = ["astar", "core_ch_astar"]
     algo_times = np.ndarray([])
     linespecs = ["r-", "b-"]  # , "g-"]
+    name = "thesis_rank_times_all-" + problem
 
-    run_measurement_conditionally(
-        "measure_all_" + problem + "_1000_queries_rank_times", graph
-    )
+    run_experiment_conditionally(name, graph)
 
     algo_results = read_measurement(
-        "measure_all_" + problem + "_1000_queries_rank_times-" + graph,
+        name + "-" + graph,
     )
 
     algo_results = algo_results.loc[algo_results["dijkstra_rank_exponent"] >= 10]
@@ -307,13 +342,11 @@ def plot_rank_times_perf_profile(problem, graph):
 
     algo_times = np.asarray(algo_times).T
     perfprof(algo_times, linespecs=linespecs, legendnames=algos)
-    write_plt(
-        "measure_all_" + problem + "_1000_queries_rank_times-perfprofile.png", graph
-    )
+    write_plt(name + "-perfprofile.png", graph)
 
 
 def plot_core_size_experiments(problem, graph):
-    name = "measure_" + problem + "_core_sizes"
+    name = "thesis_core_sizes-" + problem
 
     run_measurement_conditionally(name, graph)
 
@@ -327,7 +360,7 @@ def plot_core_size_experiments(problem, graph):
     colors = ggPlotColors(4)
 
     fig, ax = plt.subplots(figsize=(10, 5))
-    bp = queries.boxplot(ax=ax, by="degree_limit", column="time_ms")
+    bp = queries.boxplot(ax=ax, by="rel_core_size", column="time_ms")
 
     bp.get_figure().gca().set_title("")
     fig.suptitle("")
@@ -352,17 +385,17 @@ def plot_core_size_experiments(problem, graph):
 
 def run_avg_all_times(problem, graph):
     name = "thesis_avg_all-" + problem
-    run_measurement_conditionally(name, graph)
+    run_experiment_conditionally(name, graph)
 
 
 def run_avg_mid_times(problem, graph):
     name = "thesis_avg_mid-" + problem
-    run_measurement_conditionally(name, graph)
+    run_experiment_conditionally(name, graph)
 
 
 def run_avg_fast_times(problem, graph):
     name = "thesis_avg_fast-" + problem
-    run_measurement_conditionally(name, graph)
+    run_experiment_conditionally(name, graph)
 
 
 def run_avg_opt(problem, graph):
@@ -372,7 +405,7 @@ def run_avg_opt(problem, graph):
 
 def run_rank_times(problem, graph):
     name = "thesis_rank_times-" + problem
-    run_measurement_conditionally(name, graph)
+    run_experiment_conditionally(name, graph)
 
 
 def run_constraint_experiments(graph):
@@ -0,0 +1,374 @@
+//! Alternative routes which respect the driving time restrictions.
+//!
+//! Candidates are generated without restrictions, by the via-node or the penalty method, and then get their
+//! breaks planned along their fixed node sequence with the same CSP search as the optimal route. A candidate is
+//! kept if it can be driven within the restrictions, its travel time including breaks is at most `max_stretch`
+//! times the optimum and at most `max_overlap` of its driving time is shared with the optimum or any alternative
+//! kept before.
+//!
+//! - Via-node: the shortest paths from `s` and to `t` are computed once. Every node `v` gives the candidate of the
+//!   shortest path `s -> v` followed by the shortest path `v -> t`, nodes with a shorter concatenation are tried first.
+//! - Penalty: the arcs of the last found route are made longer by `penalty_factor` and the restricted search is
+//!   repeated on the penalized graph until enough alternatives were found or `max_penalty_iterations` is reached.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use rust_truck_router::{algo::alternatives::*, query::RoutingData, types::*};
+//! # fn example(data: &RoutingData) {
+//! let mut query = AlternativeRouteQuery::new(data.graph.borrow(), data.bw_graph.borrow(), &data.is_parking_node);
+//! query.set_restrictions(&[EU_SHORT]);
+//! for route in query.alternatives(0, 42, AlternativeMethod::ViaNode) {
+//!     println!("{} ms with {} breaks, {:.0}% shared", route.distance, route.breaks.len(), route.overlap * 100.0);
+//! }
+//! # }
+//! ```
+
+use std::collections::HashSet;
+
+use bit_vec::BitVec;
+
+use crate::types::*;
+
+use super::{
+    csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+    csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+    dijkstra::{Dijkstra, DijkstraData},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlternativeConfig {
+    /// Number of routes returned in addition to the optimal one
+    pub max_alternatives: usize,
+    /// Upper bound of the travel time of an alternative relative to the optimum
+    pub max_stretch: f64,
+    /// Upper bound of the share of the driving time on arcs of the optimum or another alternative
+    pub max_overlap: f64,
+    /// Via nodes tried by the via-node method
+    pub max_via_candidates: usize,
+    /// Arcs of found routes are made longer by this fraction of their weight in the penalty method
+    pub penalty_factor: f64,
+    pub max_penalty_iterations: usize,
+}
+
+impl Default for AlternativeConfig {
+    fn default() -> Self {
+        Self {
+            max_alternatives: 2,
+            max_stretch: 1.25,
+            max_overlap: 0.7,
+            max_via_candidates: 200,
+            penalty_factor: 0.5,
+            max_penalty_iterations: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlternativeMethod {
+    ViaNode,
+    Penalty,
+}
+
+/// A break along a route
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlannedBreak {
+    pub node: NodeId,
+    /// Index of the restriction whose pause is taken
+    pub restriction: usize,
+    /// Time since departure at the start of the break
+    pub start: Weight,
+    pub duration: Weight,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteOption {
+    pub path: Vec<NodeId>,
+    /// Travel time including breaks
+    pub distance: Weight,
+    pub driving_time: Weight,
+    pub breaks: Vec<PlannedBreak>,
+    /// `distance` relative to the optimal route
+    pub stretch: f64,
+    /// Share of the driving time on arcs of the optimal route
+    pub overlap: f64,
+}
+
+impl RouteOption {
+    fn arcs(&self) -> impl Iterator<Item = (NodeId, NodeId)> + '_ {
+        self.path.windows(2).map(|w| (w[0], w[1]))
+    }
+}
+
+pub struct AlternativeRouteQuery<'a> {
+    graph: BorrowedGraph<'a>,
+    bw_graph: BorrowedGraph<'a>,
+    is_parking_node: &'a BitVec,
+    restrictions: Vec<DrivingTimeRestriction>,
+    pub config: AlternativeConfig,
+    fw_state: DijkstraData,
+    bw_state: DijkstraData,
+    csp_state: OneRestrictionDijkstraData,
+    csp_2_state: TwoRestrictionDijkstraData,
+}
+
+impl<'a> AlternativeRouteQuery<'a> {
+    pub fn new(graph: BorrowedGraph<'a>, bw_graph: BorrowedGraph<'a>, is_parking_node: &'a BitVec) -> Self {
+        let n = graph.num_nodes();
+        Self {
+            graph,
+            bw_graph,
+            is_parking_node,
+            restrictions: Vec::new(),
+            config: AlternativeConfig::default(),
+            fw_state: DijkstraData::new(n),
+            bw_state: DijkstraData::new(n),
+            csp_state: OneRestrictionDijkstraData::new(n),
+            csp_2_state: TwoRestrictionDijkstraData::new(n),
+        }
+    }
+
+    /// At most two restrictions ordered by increasing maximum driving time, e.g. `[short, long]` for the EU rules
+    pub fn set_restrictions(&mut self, restrictions: &[DrivingTimeRestriction]) {
+        assert!(restrictions.len() <= 2, "at most two restrictions are supported");
+        assert!(restrictions.is_sorted_by_key(|r| r.max_driving_time));
+        self.restrictions = restrictions.to_vec();
+    }
+
+    pub fn clear_restrictions(&mut self) {
+        self.restrictions.clear();
+    }
+
+    /// The optimal route followed by up to `max_alternatives` alternatives, empty if `t` can not be reached
+    pub fn alternatives(&mut self, s: NodeId, t: NodeId, method: AlternativeMethod) -> Vec<RouteOption> {
+        let optimum = match self.optimal_path(s, t).and_then(|path| self.plan(path)) {
+            Some(optimum) => optimum,
+            None => return Vec::new(),
+        };
+
+        let mut routes = vec![RouteOption {
+            stretch: 1.0,
+            overlap: 1.0,
+            ..optimum
+        }];
+        let mut tried = HashSet::new();
+        tried.insert(routes[0].path.clone());
+
+        match method {
+            AlternativeMethod::ViaNode => self.via_node_alternatives(s, t, &mut routes, &mut tried),
+            AlternativeMethod::Penalty => self.penalty_alternatives(s, t, &mut routes, &mut tried),
+        }
+
+        routes
+    }
+
+    fn via_node_alternatives(&mut self, s: NodeId, t: NodeId, routes: &mut Vec<RouteOption>, tried: &mut HashSet<Vec<NodeId>>) {
+        self.fw_state.init_new_s(s);
+        Dijkstra::new(self.graph).to_all(&mut self.fw_state);
+        self.bw_state.init_new_s(t);
+        Dijkstra::new(self.bw_graph).to_all(&mut self.bw_state);
+
+        let on_optimum: HashSet<NodeId> = routes[0].path.iter().copied().collect();
+        let mut via_nodes: Vec<(Weight, NodeId)> = (0..self.graph.num_nodes() as NodeId)
+            .filter(|v| !on_optimum.contains(v))
+            .map(|v| (self.fw_state.tentative_distance_at(v).link(self.bw_state.tentative_distance_at(v)), v))
+            .filter(|&(length, _)| length < Weight::infinity())
+            .collect();
+        via_nodes.sort_unstable();
+
+        for &(_, v) in via_nodes.iter().take(self.config.max_via_candidates) {
+            if routes.len() > self.config.max_alternatives {
+                break;
+            }
+            // the via node already lies on an alternative
+            if routes.iter().any(|r| r.path.contains(&v)) {
+                continue;
+            }
+
+            let mut path = self.fw_state.path(s, v).unwrap();
+            let mut v_to_t = self.bw_state.path(t, v).unwrap();
+            v_to_t.reverse();
+            path.extend_from_slice(&v_to_t[1..]);
+
+            let mut visited = HashSet::new();
+            if !path.iter().all(|&node| visited.insert(node)) || !tried.insert(path.clone()) {
+                continue;
+            }
+            self.try_candidate(path, routes);
+        }
+    }
+
+    fn penalty_alternatives(&mut self, s: NodeId, t: NodeId, routes: &mut Vec<RouteOption>, tried: &mut HashSet<Vec<NodeId>>) {
+        let mut weights = self.graph.weights().to_vec();
+        let mut last_path = routes[0].path.clone();
+
+        for _ in 0..self.config.max_penalty_iterations {
+            if routes.len() > self.config.max_alternatives {
+                break;
+            }
+
+            for (tail, head) in last_path.windows(2).map(|w| (w[0], w[1])) {
+                let first_out = self.graph.first_out();
+                for arc in first_out[tail as usize]..first_out[tail as usize + 1] {
+                    if self.graph.head()[arc as usize] == head {
+                        let weight = &mut weights[arc as usize];
+                        *weight = (*weight as f64 * (1.0 + self.config.penalty_factor)).round().min(INFINITY as f64) as Weight;
+                    }
+                }
+            }
+
+            let penalized = BorrowedGraph::new(self.graph.first_out(), self.graph.head(), &weights);
+            let states = Some((&mut self.csp_state, &mut self.csp_2_state));
+            let path = match restricted_path(penalized, self.is_parking_node, &self.restrictions, s, t, states) {
+                Some((path, _)) => path,
+                None => break,
+            };
+
+            if tried.insert(path.clone()) {
+                self.try_candidate(path.clone(), routes);
+            }
+            last_path = path;
+        }
+    }
+
+    fn try_candidate(&mut self, path: Vec<NodeId>, routes: &mut Vec<RouteOption>) {
+        let mut candidate = match self.plan(path) {
+            Some(candidate) => candidate,
+            None => return,
+        };
+
+        candidate.stretch = candidate.distance as f64 / routes[0].distance.max(1) as f64;
+        if candidate.stretch > self.config.max_stretch {
+            return;
+        }
+
+        candidate.overlap = self.overlap(&candidate, &routes[0]);
+        if routes.iter().any(|route| self.overlap(&candidate, route) > self.config.max_overlap) {
+            return;
+        }
+
+        routes.push(candidate);
+    }
+
+    /// Share of the driving time of `route` on arcs of `other`
+    fn overlap(&self, route: &RouteOption, other: &RouteOption) -> f64 {
+        if route.driving_time == 0 {
+            return 1.0;
+        }
+
+        let other_arcs: HashSet<(NodeId, NodeId)> = other.arcs().collect();
+        let shared: Weight = route
+            .arcs()
+            .filter(|arc| other_arcs.contains(arc))
+            .map(|(tail, head)| arc_weight(self.graph, tail, head))
+            .sum();
+        shared as f64 / route.driving_time as f64
+    }
+
+    fn optimal_path(&mut self, s: NodeId, t: NodeId) -> Option<Vec<NodeId>> {
+        let states = Some((&mut self.csp_state, &mut self.csp_2_state));
+        restricted_path(self.graph, self.is_parking_node, &self.restrictions, s, t, states).map(|(path, _)| path)
+    }
+
+    /// Plans the breaks along a fixed node sequence, `None` if it can not be driven within the restrictions
+    fn plan(&self, path: Vec<NodeId>) -> Option<RouteOption> {
+        // the path as a graph of its own, position `i` is the `i`-th node of the path
+        let weights: Vec<Weight> = path.windows(2).map(|w| arc_weight(self.graph, w[0], w[1])).collect();
+        let line = OwnedGraph::new(
+            (0..path.len() as EdgeId)
+                .map(|i| i.min(weights.len() as EdgeId))
+                .chain(std::iter::once(weights.len() as EdgeId))
+                .collect(),
+            (1..path.len() as NodeId).collect(),
+            weights.clone(),
+        );
+        let is_parking_node = BitVec::from_fn(path.len(), |i| self.is_parking_node[path[i] as usize]);
+
+        let (_, times) = restricted_path(line.borrow(), &is_parking_node, &self.restrictions, 0, path.len() as NodeId - 1, None)?;
+
+        let breaks = (1..path.len())
+            .filter(|&i| is_parking_node[i])
+            .filter_map(|i| planned_break(path[i], &times[i], &self.restrictions))
+            .collect();
+
+        Some(RouteOption {
+            distance: times.last().unwrap()[0],
+            driving_time: weights.iter().sum(),
+            path,
+            breaks,
+            stretch: 1.0,
+            overlap: 1.0,
+        })
+    }
+}
+
+/// The break taken at a parking node which is reached with `time`, `[distance, driving time since the break of every restriction...]`
+pub(crate) fn planned_break(node: NodeId, time: &[Weight], restrictions: &[DrivingTimeRestriction]) -> Option<PlannedBreak> {
+    // a break of one kind also resets the driving times of all shorter restrictions
+    (0..restrictions.len()).rev().find(|&r| time[r + 1] == 0).map(|restriction| {
+        let duration = restrictions[restriction].pause_time;
+        PlannedBreak {
+            node,
+            restriction,
+            start: time[0] - duration,
+            duration,
+        }
+    })
+}
+
+fn arc_weight(graph: BorrowedGraph, tail: NodeId, head: NodeId) -> Weight {
+    graph
+        .outgoing_edge_iter(tail)
+        .filter(|&(_, &h)| h == head)
+        .map(|(&weight, _)| weight)
+        .min()
+        .expect("consecutive nodes of a path are connected")
+}
+
+type RestrictedStates<'s> = (&'s mut OneRestrictionDijkstraData, &'s mut TwoRestrictionDijkstraData);
+
+/// Best path and the distance of its labels, `[distance, driving time since the break of every restriction...]`.
+/// New search states are created unless `states` are given.
+fn restricted_path(
+    graph: BorrowedGraph,
+    is_parking_node: &BitVec,
+    restrictions: &[DrivingTimeRestriction],
+    s: NodeId,
+    t: NodeId,
+    states: Option<RestrictedStates>,
+) -> Option<(Vec<NodeId>, Vec<Vec<Weight>>)> {
+    let n = graph.num_nodes();
+    if restrictions.len() < 2 {
+        let mut owned_state;
+        let state = match states {
+            Some((state, _)) => state,
+            None => {
+                owned_state = OneRestrictionDijkstraData::new(n);
+                &mut owned_state
+            }
+        };
+        match restrictions.first() {
+            Some(r) => state.set_restriction(r.max_driving_time, r.pause_time),
+            None => state.clear_restriction(),
+        };
+        state.init_new_s(s);
+        OneRestrictionDijkstra::new(graph, is_parking_node).dist_query(state, t)?;
+        let (path, distances) = state.current_best_path_to(t, true)?;
+        Some((path, distances.iter().map(|d| d[..1 + restrictions.len()].to_vec()).collect()))
+    } else {
+        let mut owned_state;
+        let state = match states {
+            Some((_, state)) => state,
+            None => {
+                owned_state = TwoRestrictionDijkstraData::new(n);
+                &mut owned_state
+            }
+        };
+        let (short, long) = (restrictions[0], restrictions[1]);
+        state.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+        state.init_new_s(s);
+        TwoRestrictionDijkstra::new(graph, is_parking_node).dist_query(state, t)?;
+        let (path, distances) = state.current_best_path_to(t, true)?;
+        Some((path, distances.iter().map(|d| d.to_vec()).collect()))
+    }
+}
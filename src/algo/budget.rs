@@ -0,0 +1,172 @@
+//! Limits for the work of a query and cooperative cancellation.
+//!
+//! A `SearchBudget` combines a deadline, a maximum number of settled labels, a maximum label memory and a
+//! `CancellationFlag` which can be triggered from another thread. The queries check the budget before settling
+//! the next label. On abort they return a `QueryAborted` with the reason and the best distance found so far,
+//! `stats` and `search_space` of the query describe the work done until then.
+//!
+//! ```no_run
+//! # use rust_truck_router::{algo::{budget::*, PointToPointQuery}, query::*};
+//! # use std::time::Duration;
+//! # fn example(data: &RoutingData) {
+//! let cancellation = CancellationFlag::new();
+//! let budget = SearchBudget::unlimited()
+//!     .with_timeout(Duration::from_secs(10))
+//!     .with_max_settled_labels(1_000_000)
+//!     .with_cancellation(cancellation.clone());
+//!
+//! let mut query = new_query(data, Algorithm::CoreChAstar, RestrictionPreset::EuLong);
+//! query.init_new_s(0);
+//! query.init_new_t(42);
+//! match query.run_query_with_budget(&budget) {
+//!     Ok(dist) => println!("{:?}", dist),
+//!     Err(aborted) => println!("{}, best distance so far {:?}", aborted.reason, aborted.tentative_distance),
+//! }
+//! # }
+//! ```
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::types::*;
+
+/// Cancels all queries using a budget with this flag, clones share the flag
+#[derive(Debug, Clone, Default)]
+pub struct CancellationFlag(Arc<AtomicBool>);
+
+impl CancellationFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Allows reusing the flag for the next queries
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchBudget {
+    pub deadline: Option<Instant>,
+    /// Summed over all searches of a query
+    pub max_settled_labels: Option<u32>,
+    /// Bytes of all labels created by the searches of a query
+    pub max_label_memory: Option<usize>,
+    pub cancellation: Option<CancellationFlag>,
+}
+
+impl SearchBudget {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Sets the deadline to `timeout` from now
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_max_settled_labels(mut self, max_settled_labels: u32) -> Self {
+        self.max_settled_labels = Some(max_settled_labels);
+        self
+    }
+
+    pub fn with_max_label_memory(mut self, max_label_memory: usize) -> Self {
+        self.max_label_memory = Some(max_label_memory);
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancellation: CancellationFlag) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Checks the work done so far, the cheap checks first
+    #[inline]
+    pub fn check(&self, settled_labels: u32, label_memory: usize) -> Result<(), AbortReason> {
+        if matches!(self.max_settled_labels, Some(max) if settled_labels >= max) {
+            return Err(AbortReason::SettledLabels);
+        }
+        if matches!(self.max_label_memory, Some(max) if label_memory > max) {
+            return Err(AbortReason::LabelMemory);
+        }
+        if matches!(&self.cancellation, Some(flag) if flag.is_cancelled()) {
+            return Err(AbortReason::Cancelled);
+        }
+        if matches!(self.deadline, Some(deadline) if Instant::now() > deadline) {
+            return Err(AbortReason::Deadline);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbortReason {
+    Deadline,
+    SettledLabels,
+    LabelMemory,
+    Cancelled,
+}
+
+impl Display for AbortReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            AbortReason::Deadline => "deadline exceeded",
+            AbortReason::SettledLabels => "too many settled labels",
+            AbortReason::LabelMemory => "too much label memory",
+            AbortReason::Cancelled => "cancelled",
+        })
+    }
+}
+
+/// The partial result of a query which ran out of budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryAborted {
+    pub reason: AbortReason,
+    /// Best distance found before the abort, an upper bound of the shortest distance
+    pub tentative_distance: Option<Weight>,
+}
+
+impl QueryAborted {
+    /// `tentative_distance` is infinity if no path was found yet
+    pub fn new(reason: AbortReason, tentative_distance: Weight) -> Self {
+        Self {
+            reason,
+            tentative_distance: if tentative_distance == Weight::infinity() {
+                None
+            } else {
+                Some(tentative_distance)
+            },
+        }
+    }
+}
+
+impl Display for QueryAborted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.tentative_distance {
+            Some(dist) => write!(f, "query aborted, {}, best distance so far {}", self.reason, dist),
+            None => write!(f, "query aborted, {}", self.reason),
+        }
+    }
+}
+
+impl Error for QueryAborted {}
@@ -1,8 +1,23 @@
-use crate::{io::Load, types::*};
+use crate::{
+    index_heap::IndexdMinHeap,
+    io::{mmap::MappedVec, Load},
+    priority_queue::IndexedPriorityQueue,
+    types::*,
+    validation::*,
+};
 use bit_vec::BitVec;
-use std::path::Path;
-
-use super::dijkstra::{Dijkstra, DijkstraData};
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use super::{
+    astar::NoPotential,
+    budget::{QueryAborted, SearchBudget},
+    dijkstra::{Dijkstra, DijkstraData},
+    search_space::SearchSpace,
+    stats::QueryStats,
+};
 
 #[derive(Clone)]
 pub struct ContractionHierarchy<RankOrderContainer, FirstOutContainer, HeadContainer, WeightsContainer> {
@@ -70,38 +85,18 @@ where
     }
 
     /// From routingkit's check_contraction_hierarchy_for_errors
-    pub fn check(&self) {
-        let node_count = self.rank().len();
-        assert_eq!(self.forward.first_out().len(), node_count + 1);
-        assert_eq!(self.backward.first_out().len(), node_count + 1);
-
-        let forward_arc_count = *self.forward.first_out().last().unwrap();
+    pub fn check(&self) -> Result<(), ValidationError> {
+        check_rank_order(self.rank(), self.order())?;
+        check_graph("forward", &self.forward)?;
+        check_graph("backward", &self.backward)?;
 
-        assert_eq!(*self.forward.first_out().first().unwrap(), 0);
-        assert!(self.forward.first_out().is_sorted_by(|l, r| Some(l.cmp(r))));
-        assert_eq!(self.forward.head().len(), forward_arc_count as usize);
-        assert_eq!(self.forward.weights().len(), forward_arc_count as usize);
-        assert!(!self.forward.head().is_empty() && *self.forward.head().iter().max().unwrap() < node_count as NodeId);
-
-        let backward_arc_count = *self.backward.first_out().last().unwrap();
-        assert_eq!(*self.backward.first_out().first().unwrap(), 0);
-        assert!(self.backward.first_out().is_sorted_by(|l, r| Some(l.cmp(r))));
-        assert_eq!(self.backward.head().len(), backward_arc_count as usize);
-        assert_eq!(self.backward.weights().len(), backward_arc_count as usize);
-        assert!(!self.backward.head().is_empty() && *self.backward.head().iter().max().unwrap() < node_count as NodeId);
+        let node_count = self.rank().len();
+        check_node_count("forward", node_count, self.forward.num_nodes())?;
+        check_node_count("backward", node_count, self.backward.num_nodes())?;
 
         // only up edges
-        for x in 0..node_count {
-            for xy in self.forward.first_out()[x]..self.forward.first_out()[x + 1] {
-                let y = self.forward.head()[xy as usize] as usize;
-                assert!(y > x);
-            }
-
-            for xy in self.backward.first_out()[x]..self.backward.first_out()[x + 1] {
-                let y = self.backward.head()[xy as usize] as usize;
-                assert!(y > x);
-            }
-        }
+        check_upward("forward", &self.forward)?;
+        check_upward("backward", &self.backward)
     }
 }
 
@@ -118,22 +113,46 @@ impl OwnedContractionHierarchy {
 
 pub type OwnedContractionHierarchy = ContractionHierarchy<Vec<NodeId>, Vec<EdgeId>, Vec<NodeId>, Vec<Weight>>;
 pub type BorrowedContractionHierarchy<'a> = ContractionHierarchy<&'a [NodeId], &'a [EdgeId], &'a [NodeId], &'a [Weight]>;
+pub type MappedContractionHierarchy = ContractionHierarchy<MappedVec<NodeId>, MappedVec<EdgeId>, MappedVec<NodeId>, MappedVec<Weight>>;
+
+impl MappedContractionHierarchy {
+    /// Memory-maps the hierarchy files of a RoutingKit directory instead of copying them into memory
+    pub fn map_routingkit_dir<P: AsRef<Path>>(path: P) -> Result<Self, std::io::Error> {
+        Ok(ContractionHierarchy {
+            rank: MappedVec::map_from(path.as_ref().join("rank"))?,
+            order: MappedVec::map_from(path.as_ref().join("order"))?,
+            forward: MappedGraph::map_routingkit_dir(path.as_ref().join("forward"))?,
+            backward: MappedGraph::map_routingkit_dir(path.as_ref().join("backward"))?,
+        })
+    }
+}
 
 impl<'a> Copy for BorrowedContractionHierarchy<'a> {}
 
-pub struct ContractionHierarchyQuery<'a> {
+pub struct ContractionHierarchyQuery<'a, Q = IndexdMinHeap<State<Weight>>> {
     ch: BorrowedContractionHierarchy<'a>,
-    fw_state: DijkstraData,
-    bw_state: DijkstraData,
+    fw_state: DijkstraData<NoPotential, Q>,
+    bw_state: DijkstraData<NoPotential, Q>,
+    pub last_dist: Option<Weight>,
+    last_time_elapsed: Duration,
 }
 
 impl<'a> ContractionHierarchyQuery<'a> {
     pub fn new(ch: BorrowedContractionHierarchy<'a>) -> Self {
+        Self::with_queue(ch)
+    }
+}
+
+impl<'a, Q: IndexedPriorityQueue<State<Weight>>> ContractionHierarchyQuery<'a, Q> {
+    /// Uses `Q` as priority queue of both searches
+    pub fn with_queue(ch: BorrowedContractionHierarchy<'a>) -> Self {
         let n = ch.forward().num_nodes();
         ContractionHierarchyQuery {
             ch,
-            fw_state: DijkstraData::new(n),
-            bw_state: DijkstraData::new(n),
+            fw_state: DijkstraData::with_queue(n, NoPotential {}),
+            bw_state: DijkstraData::with_queue(n, NoPotential {}),
+            last_dist: None,
+            last_time_elapsed: Duration::ZERO,
         }
     }
 
@@ -148,6 +167,13 @@ impl<'a> ContractionHierarchyQuery<'a> {
     }
 
     pub fn run_query(&mut self) -> Option<Weight> {
+        self.run_query_with_budget(&SearchBudget::unlimited())
+            .expect("an unlimited budget never aborts")
+    }
+
+    /// Like `run_query` but stops when the budget is exhausted
+    pub fn run_query_with_budget(&mut self, budget: &SearchBudget) -> Result<Option<Weight>, QueryAborted> {
+        let start = Instant::now();
         let mut tentative_distance = Weight::infinity();
 
         self.fw_state.reset();
@@ -168,6 +194,15 @@ impl<'a> ContractionHierarchyQuery<'a> {
         let bw_search = Dijkstra::new(self.ch.backward());
 
         while !fw_finished || !bw_finished {
+            if let Err(reason) = budget.check(
+                self.fw_state.stats.num_settled + self.bw_state.stats.num_settled,
+                self.fw_state.label_memory() + self.bw_state.label_memory(),
+            ) {
+                self.last_time_elapsed = start.elapsed();
+                self.last_dist = None;
+                return Err(QueryAborted::new(reason, tentative_distance));
+            }
+
             let tent_dist_at_v;
 
             if bw_finished || !fw_finished && fw_next {
@@ -214,10 +249,28 @@ impl<'a> ContractionHierarchyQuery<'a> {
             }
         }
 
-        if tentative_distance == Weight::infinity() {
-            return None;
+        self.last_time_elapsed = start.elapsed();
+        self.last_dist = if tentative_distance == Weight::infinity() {
+            None
+        } else {
+            Some(tentative_distance)
+        };
+        Ok(self.last_dist)
+    }
+
+    pub fn stats(&self) -> QueryStats {
+        QueryStats {
+            distance: self.last_dist,
+            time: self.last_time_elapsed,
+            num_nodes_searched: self.fw_state.get_number_of_visited_nodes() + self.bw_state.get_number_of_visited_nodes(),
+            forward: self.fw_state.stats,
+            backward: self.bw_state.stats,
+            ..QueryStats::default()
         }
+    }
 
-        Some(tentative_distance)
+    /// Labels of both searches mapped to node ids, the route is unknown as the query does not unpack its path
+    pub fn search_space(&self) -> SearchSpace {
+        SearchSpace::bidirectional(&self.fw_state, &self.bw_state).with_order(self.ch.order())
     }
 }
@@ -1,8 +1,24 @@
-use crate::{io::Load, types::*};
+use crate::{
+    index_heap::IndexdMinHeap,
+    io::{mmap::MappedVec, Load},
+    priority_queue::IndexedPriorityQueue,
+    types::*,
+    validation::*,
+};
 use bit_vec::BitVec;
-use std::{path::Path, rc::Rc};
-
-use super::dijkstra::{Dijkstra, DijkstraData};
+use std::{
+    path::Path,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use super::{
+    astar::NoPotential,
+    budget::{QueryAborted, SearchBudget},
+    dijkstra::{Dijkstra, DijkstraData},
+    search_space::SearchSpace,
+    stats::QueryStats,
+};
 
 #[derive(Clone)]
 pub struct CoreContractionHierarchy<RankOrderContainer, FirstOutContainer, HeadContainer, WeightsContainer> {
@@ -21,6 +37,7 @@ where
     HeadContainer: AsRef<[NodeId]>,
     WeightsContainer: AsRef<[Weight]>,
 {
+    /// Panics if `try_new` fails
     pub fn new(
         rank: RankOrderContainer,
         order: RankOrderContainer,
@@ -28,28 +45,46 @@ where
         forward: FirstOutGraph<FirstOutContainer, HeadContainer, WeightsContainer>,
         backward: FirstOutGraph<FirstOutContainer, HeadContainer, WeightsContainer>,
     ) -> Self {
-        assert_eq!(rank.as_ref().len(), order.as_ref().len());
+        Self::try_new(rank, order, core, forward, backward).expect("invalid core contraction hierarchy")
+    }
 
-        // for &node in core.as_ref() {
-        // assert_eq!(rank.as_ref()[node as usize], node);
-        //     assert_eq!(order.as_ref()[node as usize], node);
-        // }
+    /// Checks that `rank` and `order` have the same length and that `core` only contains valid nodes of valid rank.
+    /// The remaining invariants are checked by `check`.
+    pub fn try_new(
+        rank: RankOrderContainer,
+        order: RankOrderContainer,
+        core: RankOrderContainer,
+        forward: FirstOutGraph<FirstOutContainer, HeadContainer, WeightsContainer>,
+        backward: FirstOutGraph<FirstOutContainer, HeadContainer, WeightsContainer>,
+    ) -> Result<Self, ValidationError> {
+        let node_count = rank.as_ref().len();
+        check_node_count("order", node_count, order.as_ref().len())?;
+        for &node in core.as_ref() {
+            if node as usize >= node_count {
+                return Err(ValidationError::NodeOutOfRange { component: "core", node });
+            }
+            if rank.as_ref()[node as usize] as usize >= node_count {
+                return Err(ValidationError::NodeOutOfRange {
+                    component: "rank",
+                    node: rank.as_ref()[node as usize],
+                });
+            }
+        }
 
         println!("Core size: {}", core.as_ref().len());
-        let node_count = rank.as_ref().len();
 
         let mut is_core = BitVec::from_elem(node_count, false);
         for &n in core.as_ref().iter() {
             is_core.set(rank.as_ref()[n as usize] as usize, true);
         }
 
-        CoreContractionHierarchy {
+        Ok(CoreContractionHierarchy {
             rank,
             order,
             is_core: Rc::new(is_core),
             forward,
             backward,
-        }
+        })
     }
 
     pub fn rank(&self) -> &[NodeId] {
@@ -83,59 +118,77 @@ where
     }
 
     /// Equivalent to routingkit's check_contraction_hierarchy_for_errors except the check for only up edges
-    pub fn check(&self) {
-        let node_count = self.forward.num_nodes();
-        assert_eq!(self.forward.first_out().len(), node_count + 1);
-        assert_eq!(self.backward.first_out().len(), node_count + 1);
-
-        let forward_arc_count = *self.forward.first_out().last().unwrap();
-
-        assert_eq!(*self.forward.first_out().first().unwrap(), 0);
-        assert!(self.forward.first_out().is_sorted_by(|l, r| Some(l.cmp(r))));
-        assert_eq!(self.forward.head().len(), forward_arc_count as usize);
-        assert_eq!(self.forward.weights().len(), forward_arc_count as usize);
-        assert!(!self.forward.head().is_empty() && *self.forward.head().iter().max().unwrap() < node_count as NodeId);
-
-        let backward_arc_count = *self.backward.first_out().last().unwrap();
-        assert_eq!(*self.backward.first_out().first().unwrap(), 0);
-        assert!(self.backward.first_out().is_sorted_by(|l, r| Some(l.cmp(r))));
-        assert_eq!(self.backward.head().len(), backward_arc_count as usize);
-        assert_eq!(self.backward.weights().len(), backward_arc_count as usize);
-        assert!(!self.backward.head().is_empty() && *self.backward.head().iter().max().unwrap() < node_count as NodeId);
+    pub fn check(&self) -> Result<(), ValidationError> {
+        check_rank_order(self.rank(), self.order())?;
+        check_graph("forward", &self.forward)?;
+        check_graph("backward", &self.backward)?;
+
+        let node_count = self.rank().len();
+        check_node_count("forward", node_count, self.forward.num_nodes())?;
+        check_node_count("backward", node_count, self.backward.num_nodes())?;
+        check_node_flags(&self.forward, &self.is_core)
     }
 }
 
 impl OwnedCoreContractionHierarchy {
+    /// Fails with `InvalidData` if `try_new` rejects the files
     pub fn load_from_routingkit_dir<P: AsRef<Path>>(path: P) -> Result<Self, std::io::Error> {
-        Ok(CoreContractionHierarchy::new(
+        CoreContractionHierarchy::try_new(
             Vec::<NodeId>::load_from(path.as_ref().join("rank"))?,
             Vec::<NodeId>::load_from(path.as_ref().join("order"))?,
             Vec::<NodeId>::load_from(path.as_ref().join("core"))?,
             OwnedGraph::load_from_routingkit_dir(path.as_ref().join("forward"))?,
             OwnedGraph::load_from_routingkit_dir(path.as_ref().join("backward"))?,
-        ))
+        )
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
     }
 }
 
 pub type OwnedCoreContractionHierarchy = CoreContractionHierarchy<Vec<NodeId>, Vec<EdgeId>, Vec<NodeId>, Vec<Weight>>;
 pub type BorrowedCoreContractionHierarchy<'a> = CoreContractionHierarchy<&'a [NodeId], &'a [EdgeId], &'a [NodeId], &'a [Weight]>;
+pub type MappedCoreContractionHierarchy = CoreContractionHierarchy<MappedVec<NodeId>, MappedVec<EdgeId>, MappedVec<NodeId>, MappedVec<Weight>>;
+
+impl MappedCoreContractionHierarchy {
+    /// Memory-maps the hierarchy files of a RoutingKit directory instead of copying them into memory
+    pub fn map_routingkit_dir<P: AsRef<Path>>(path: P) -> Result<Self, std::io::Error> {
+        CoreContractionHierarchy::try_new(
+            MappedVec::map_from(path.as_ref().join("rank"))?,
+            MappedVec::map_from(path.as_ref().join("order"))?,
+            MappedVec::map_from(path.as_ref().join("core"))?,
+            MappedGraph::map_routingkit_dir(path.as_ref().join("forward"))?,
+            MappedGraph::map_routingkit_dir(path.as_ref().join("backward"))?,
+        )
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }
+}
 
-pub struct CoreContractionHierarchyQuery<'a> {
+pub struct CoreContractionHierarchyQuery<'a, Q = IndexdMinHeap<State<Weight>>> {
     core_ch: BorrowedCoreContractionHierarchy<'a>,
-    fw_state: DijkstraData,
-    bw_state: DijkstraData,
+    fw_state: DijkstraData<NoPotential, Q>,
+    bw_state: DijkstraData<NoPotential, Q>,
     fw_finished: bool,
     bw_finished: bool,
     s: NodeId,
     t: NodeId,
+    pub last_dist: Option<Weight>,
+    last_time_elapsed: Duration,
 }
 impl<'a> CoreContractionHierarchyQuery<'a> {
     pub fn new(core_ch: BorrowedCoreContractionHierarchy<'a>) -> Self {
+        Self::with_queue(core_ch)
+    }
+}
+
+impl<'a, Q: IndexedPriorityQueue<State<Weight>>> CoreContractionHierarchyQuery<'a, Q> {
+    /// Uses `Q` as priority queue of both searches
+    pub fn with_queue(core_ch: BorrowedCoreContractionHierarchy<'a>) -> Self {
         let n = core_ch.forward().num_nodes();
         CoreContractionHierarchyQuery {
             core_ch,
-            fw_state: DijkstraData::new(n),
-            bw_state: DijkstraData::new(n),
+            fw_state: DijkstraData::with_queue(n, NoPotential {}),
+            bw_state: DijkstraData::with_queue(n, NoPotential {}),
+            last_dist: None,
+            last_time_elapsed: Duration::ZERO,
             fw_finished: false,
             bw_finished: false,
             s: n as NodeId,
@@ -179,6 +232,13 @@ impl<'a> CoreContractionHierarchyQuery<'a> {
     }
 
     pub fn run_query(&mut self) -> Option<Weight> {
+        self.run_query_with_budget(&SearchBudget::unlimited())
+            .expect("an unlimited budget never aborts")
+    }
+
+    /// Like `run_query` but stops when the budget is exhausted
+    pub fn run_query_with_budget(&mut self, budget: &SearchBudget) -> Result<Option<Weight>, QueryAborted> {
+        let start = Instant::now();
         let mut tentative_distance = Weight::infinity();
 
         let mut fw_min_key = 0;
@@ -205,6 +265,15 @@ impl<'a> CoreContractionHierarchyQuery<'a> {
         let bw_search = Dijkstra::new(self.core_ch.backward());
 
         while !self.fw_finished || !self.bw_finished {
+            if let Err(reason) = budget.check(
+                self.fw_state.stats.num_settled + self.bw_state.stats.num_settled,
+                self.fw_state.label_memory() + self.bw_state.label_memory(),
+            ) {
+                self.last_time_elapsed = start.elapsed();
+                self.last_dist = None;
+                return Err(QueryAborted::new(reason, tentative_distance));
+            }
+
             let tent_dist_at_v;
 
             if self.bw_finished || !self.fw_finished && fw_next {
@@ -271,10 +340,28 @@ impl<'a> CoreContractionHierarchyQuery<'a> {
             }
         }
 
-        if tentative_distance == Weight::infinity() {
-            return None;
+        self.last_time_elapsed = start.elapsed();
+        self.last_dist = if tentative_distance == Weight::infinity() {
+            None
+        } else {
+            Some(tentative_distance)
+        };
+        Ok(self.last_dist)
+    }
+
+    pub fn stats(&self) -> QueryStats {
+        QueryStats {
+            distance: self.last_dist,
+            time: self.last_time_elapsed,
+            num_nodes_searched: self.fw_state.get_number_of_visited_nodes() + self.bw_state.get_number_of_visited_nodes(),
+            forward: self.fw_state.stats,
+            backward: self.bw_state.stats,
+            ..QueryStats::default()
         }
+    }
 
-        Some(tentative_distance)
+    /// Labels of both searches mapped to node ids, the route is unknown as the query does not unpack its path
+    pub fn search_space(&self) -> SearchSpace {
+        SearchSpace::bidirectional(&self.fw_state, &self.bw_state).with_order(self.core_ch.order())
     }
 }
@@ -5,83 +5,91 @@ use std::{
 };
 
 use crate::{
-    algo::astar::{NoPotential, Potential},
+    algo::{
+        astar::{NoPotential, Potential},
+        budget::{QueryAborted, SearchBudget},
+        observer::{NoObserver, SearchObserver},
+        stats::{QueryStats, SearchStats},
+    },
     index_heap::*,
-    timestamped_vector::TimestampedVector,
+    label_arena::LabelArena,
+    priority_queue::IndexedPriorityQueue,
     types::*,
 };
 use bit_vec::BitVec;
 use num::Integer;
 
-pub struct OneRestrictionDijkstraData<P = NoPotential>
+pub struct OneRestrictionDijkstraData<P = NoPotential, Q = IndexdMinHeap<State<Weight>>>
 where
     P: Potential,
 {
-    pub queue: IndexdMinHeap<State<Weight>>,
-    pub per_node_labels: TimestampedVector<MCDHeap<Weight2>>,
+    pub queue: Q,
+    pub per_node_labels: LabelArena<Weight2>,
     invalid_node_id: NodeId,
     s: NodeId,
+    start_labels: Vec<Weight2>,
+    required_at_target: Weight2,
     restriction: DrivingTimeRestriction,
     pub potential: P,
-    pub num_queue_pushes: u32,
-    pub num_settled: u32,
-    pub num_labels_propagated: u32,
-    pub num_labels_reset: u32,
-    pub time_elapsed: Duration,
+    pub stats: SearchStats,
     pub last_t: NodeId,
     pub last_distance: Option<Weight>,
+    prune_at_target: bool,
 }
 
 impl OneRestrictionDijkstraData<NoPotential> {
     pub fn new(num_nodes: usize) -> Self {
-        Self {
-            queue: IndexdMinHeap::new(num_nodes),
-            per_node_labels: TimestampedVector::with_size(num_nodes),
-            invalid_node_id: num_nodes as NodeId,
-            s: num_nodes as NodeId,
-            restriction: DrivingTimeRestriction {
-                pause_time: 0,
-                max_driving_time: Weight::infinity(),
-            },
-            potential: NoPotential {},
-            num_queue_pushes: 0,
-            num_settled: 0,
-            num_labels_propagated: 0,
-            num_labels_reset: 0,
-            time_elapsed: Duration::ZERO,
-            last_t: num_nodes as NodeId,
-            last_distance: None,
-        }
+        Self::new_with_potential(num_nodes, NoPotential {})
+    }
+}
+
+impl<P: Potential> OneRestrictionDijkstraData<P> {
+    pub fn new_with_potential(num_nodes: usize, potential: P) -> Self {
+        Self::with_queue(num_nodes, potential)
     }
 }
 
-impl<P> OneRestrictionDijkstraData<P>
+impl<P, Q> OneRestrictionDijkstraData<P, Q>
 where
     P: Potential,
+    Q: IndexedPriorityQueue<State<Weight>>,
 {
     pub fn reset(&mut self) {
         if self.s != self.invalid_node_id {
-            self.num_settled = 0;
-            self.num_labels_propagated = 0;
-            self.num_queue_pushes = 0;
-            self.num_labels_reset = 0;
+            self.stats = SearchStats::default();
 
             self.per_node_labels.reset();
 
             self.queue.clear();
-            self.num_queue_pushes += 1;
-            let pot = self.potential.potential(self.s);
-            self.queue.push(State {
-                node: self.s,
-                distance: self.estimated_dist_with_restriction([0, 0], pot),
-            });
-            let distance_with_potential = self.estimated_dist_with_restriction([0, 0], pot);
-            self.per_node_labels.get_mut(self.s as usize).push(Reverse(Label {
-                distance_with_potential,
-                distance: [0, 0],
-                prev_node: self.invalid_node_id,
-                prev_label: None,
-            }));
+            let pot = self.potential_at(self.s);
+            let mut min_key = Weight::infinity();
+            let mut inserted = false;
+            for i in 0..self.start_labels.len() {
+                let distance = self.start_labels[i];
+                let distance_with_potential = self.estimated_dist_with_restriction(distance, pot);
+                let mut start_label_set = self.per_node_labels.get_mut(self.s as usize);
+                if !start_label_set.is_dominated(&distance) {
+                    inserted = true;
+                    min_key = min_key.min(distance_with_potential);
+                    start_label_set.push(
+                        Reverse(Label {
+                            distance_with_potential,
+                            distance,
+                            prev_node: self.invalid_node_id,
+                            prev_label: None,
+                        }),
+                        |_| (),
+                    );
+                }
+            }
+            // without start labels the queue stays empty and the search finds nothing
+            if inserted {
+                self.stats.num_queue_pushes += 1;
+                self.queue.push(State {
+                    node: self.s,
+                    distance: min_key,
+                });
+            }
         }
     }
 
@@ -90,45 +98,121 @@ where
         self.reset();
     }
 
-    pub fn new_with_potential(num_nodes: usize, potential: P) -> Self {
+    /// Uses `Q` as priority queue
+    pub fn with_queue(num_nodes: usize, potential: P) -> Self {
         Self {
-            queue: IndexdMinHeap::new(num_nodes),
-            per_node_labels: TimestampedVector::with_size(num_nodes),
+            queue: Q::with_max_index(num_nodes),
+            per_node_labels: LabelArena::with_size(num_nodes),
             invalid_node_id: num_nodes as NodeId,
             s: num_nodes as NodeId,
+            start_labels: vec![Weight2::zero()],
+            required_at_target: Weight2::zero(),
             restriction: DrivingTimeRestriction {
                 pause_time: 0,
                 max_driving_time: Weight::infinity(),
             },
             potential,
-            num_queue_pushes: 0,
-            num_settled: 0,
-            num_labels_propagated: 0,
-            num_labels_reset: 0,
-            time_elapsed: Duration::ZERO,
+            stats: SearchStats::default(),
             last_t: num_nodes as NodeId,
             last_distance: None,
+            prune_at_target: true,
         }
     }
 
     pub fn init_new_s(&mut self, s: NodeId) {
+        self.init_new_s_with_labels(s, &[Weight2::zero()]);
+    }
+
+    /// Like `init_new_s` but the search starts with the given labels at `s` instead of a single label with all
+    /// distances zero, e.g. with the labels at the end of the previous leg of a trip
+    pub fn init_new_s_with_labels(&mut self, s: NodeId, labels: &[Weight2]) {
         self.s = s;
+        self.start_labels.clear();
+        self.start_labels.extend_from_slice(labels);
         self.reset();
     }
 
+    /// Like `init_new_s` but for a driver who has already been driving
+    pub fn init_new_s_with_driver_state(&mut self, s: NodeId, driver_state: &DriverState) {
+        self.init_new_s_with_labels(s, &[driver_state.weight2()]);
+    }
+
+    /// Labels at the target must leave at least `remaining` driving time, kept for all following queries
+    pub fn set_required_remaining_driving_time(&mut self, remaining: &RemainingDrivingTime) {
+        self.required_at_target = remaining.weight2();
+    }
+
+    /// Target pruning is only exact if the search ends at `t`. A search whose labels at `t` are continued without a
+    /// break there, e.g. by the next leg of a trip, has to disable it. Kept for all following queries.
+    pub fn set_target_pruning(&mut self, enabled: bool) {
+        self.prune_at_target = enabled;
+    }
+
+    /// Whether `label` at the target leaves the required remaining driving time without ending with a break there
+    pub fn meets_requirement_at_target(&self, label: &Label<Weight2>) -> bool {
+        if self.required_at_target == Weight2::zero() {
+            return true;
+        }
+        if self.ends_with_break(label) {
+            return false;
+        }
+
+        label.distance.add(self.required_at_target)[1] < self.restriction.max_driving_time
+    }
+
+    /// Whether a break is taken at the node of `label` right before it
+    pub fn ends_with_break(&self, label: &Label<Weight2>) -> bool {
+        label.prev_label.is_some_and(|prev| {
+            let prev = self.per_node_labels.label(prev);
+            // without a break every driving time grows like the distance
+            label.distance[1] != prev.0.distance[1] + label.distance[0] - prev.0.distance[0]
+        })
+    }
+
+    /// Target pruning, only labels at `t` which leave the required remaining driving time may prune
+    fn is_dominated_at_target(&self, t: NodeId, distance: &Weight2) -> bool {
+        if !self.prune_at_target {
+            return false;
+        }
+        let labels_at_t = self.per_node_labels.get(t as usize);
+        if self.required_at_target == Weight2::zero() {
+            return labels_at_t.is_dominated(distance);
+        }
+
+        labels_at_t
+            .popped()
+            .chain(labels_at_t.iter())
+            .any(|label| label.0.distance.dominates(distance) && self.meets_requirement_at_target(&label.0))
+    }
+
+    /// With a requirement at the target, a break at `t` is taken on departure instead of on arrival. Labels ending
+    /// with a break at `t` would otherwise dominate the arrivals which leave the required driving time.
+    fn breaks_on_departure_at(&self, node: NodeId, t: NodeId) -> bool {
+        node == t && self.required_at_target != Weight2::zero()
+    }
+
+    /// Distances with which a settled label at `node` is continued
+    fn departures_from(&self, node: NodeId, t: NodeId, distance: Weight2, is_reset_node: bool) -> Vec<Weight2> {
+        let mut departures = vec![distance];
+        if is_reset_node && self.breaks_on_departure_at(node, t) {
+            let mut after_break = distance;
+            after_break.reset_distance(1, self.restriction.pause_time);
+            departures.push(after_break);
+        }
+        departures
+    }
+
     pub fn min_key(&self) -> Option<Weight> {
         self.queue.peek().map(|s| s.distance)
     }
 
     pub fn current_best_path_to(&self, t: NodeId, with_distances: bool) -> Option<(Vec<NodeId>, Vec<Weight2>)> {
-        let mut path = vec![];
-        let mut distances = vec![];
-        let mut current_node = t;
-
         //get best settled or unsettled label
         //max because the type is Reverse<Label<..>>
-        let best_settled_at_t = self.per_node_labels.get(t as usize).popped().max();
-        let best_unsettled_at_t = self.per_node_labels.get(t as usize).iter().max();
+        //at the target of the last query only labels leaving the required remaining driving time count
+        let meets_requirement = |label: &&Reverse<Label<Weight2>>| t != self.last_t || self.meets_requirement_at_target(&label.0);
+        let best_settled_at_t = self.per_node_labels.get(t as usize).popped().filter(meets_requirement).max();
+        let best_unsettled_at_t = self.per_node_labels.get(t as usize).iter().filter(meets_requirement).max();
 
         let best_label_at_t = match (best_settled_at_t, best_unsettled_at_t) {
             (None, Some(best)) => Some(best),
@@ -143,39 +227,34 @@ where
             _ => None,
         };
 
-        if let Some(mut current_label) = best_label_at_t {
-            let mut next_node = current_label.0.prev_node;
-            path.push(current_node);
-            if with_distances {
-                distances.push(current_label.0.distance);
-            }
-
-            while next_node != self.invalid_node_id {
-                current_label = self
-                    .per_node_labels
-                    .get(next_node as usize)
-                    .get_key_by_index(current_label.0.prev_label.unwrap())
-                    .unwrap(); //next_label;
-                current_node = next_node;
-                next_node = current_label.0.prev_node;
-
-                path.push(current_node);
-                if with_distances {
-                    distances.push(current_label.0.distance);
-                }
-            }
+        let (path, distances) = self.path_to_label(t, best_label_at_t?, with_distances);
+        if path[0] != self.s {
+            None
+        } else {
+            Some((path, distances))
+        }
+    }
 
-            path.reverse();
-            distances.reverse();
+    /// Path from a start label to `label` at `t` together with the distances of the labels along it
+    pub fn path_to_label(&self, t: NodeId, label: &Reverse<Label<Weight2>>, with_distances: bool) -> (Vec<NodeId>, Vec<Weight2>) {
+        let mut path = vec![t];
+        let mut distances = vec![];
+        if with_distances {
+            distances.push(label.0.distance);
+        }
 
-            if path[0] != self.s {
-                None
-            } else {
-                Some((path, distances))
+        let mut current_label = label;
+        while current_label.0.prev_node != self.invalid_node_id {
+            path.push(current_label.0.prev_node);
+            current_label = self.per_node_labels.label(current_label.0.prev_label.unwrap());
+            if with_distances {
+                distances.push(current_label.0.distance);
             }
-        } else {
-            None
         }
+
+        path.reverse();
+        distances.reverse();
+        (path, distances)
     }
 
     pub fn current_best_node_path_to(&self, t: NodeId) -> Option<Vec<NodeId>> {
@@ -197,8 +276,43 @@ where
         self
     }
 
-    pub fn get_settled_labels_at(&mut self, node: NodeId) -> impl DoubleEndedIterator<Item = &Reverse<Label<Weight2>>> + '_ {
-        self.per_node_labels.get_mut(node as usize).popped_sorted()
+    /// Settled labels at `node`, best first
+    pub fn get_settled_labels_at(&self, node: NodeId) -> impl Iterator<Item = &Reverse<Label<Weight2>>> + '_ {
+        self.per_node_labels.get(node as usize).popped()
+    }
+
+    /// Shortest distance of a path through `s` combining the settled labels of this backward search at `s` with the
+    /// settled labels of the forward search `fw_state` at `s`. The start label is replaced by `driver_state` and only
+    /// continued by labels which do not end with a break at `s`, the driver does not take a break before departure.
+    pub fn best_distance_from_driver_state_at<P2: Potential, Q2: IndexedPriorityQueue<State<Weight>>>(
+        &self,
+        s: NodeId,
+        driver_state: &DriverState,
+        fw_state: &OneRestrictionDijkstraData<P2, Q2>,
+    ) -> Weight {
+        let start = driver_state.weight2();
+        let mut best_distance = Weight::infinity();
+
+        for label in self.get_settled_labels_at(s) {
+            let ends_with_break = self.ends_with_break(&label.0);
+
+            // labels of the forward search which returned to s after departure, the start label of this search
+            // at s = t can not follow a break
+            let fw_distances = fw_state
+                .get_settled_labels_at(s)
+                .filter(|fw_label| fw_label.0.prev_label.is_some())
+                .filter(|fw_label| label.0.prev_label.is_some() || !fw_state.ends_with_break(&fw_label.0))
+                .map(|fw_label| fw_label.0.distance);
+
+            for fw_distance in (!ends_with_break).then_some(start).into_iter().chain(fw_distances) {
+                let total = fw_distance.add(label.0.distance);
+                if total[1] < self.restriction.max_driving_time {
+                    best_distance = best_distance.min(total[0]);
+                }
+            }
+        }
+
+        best_distance
     }
 
     pub fn get_best_label_at(&self, node: NodeId) -> Option<Label<Weight2>> {
@@ -251,24 +365,74 @@ where
             .collect()
     }
 
+    pub fn get_per_node_number_of_settled_labels(&self) -> Vec<usize> {
+        (0..self.per_node_labels.len()).map(|i| self.per_node_labels.get(i).popped().count()).collect()
+    }
+
     pub fn get_number_of_visited_nodes(&self) -> usize {
         (0..self.per_node_labels.len())
             .filter(|i| self.per_node_labels.get(*i).popped().count() != 0)
             .count()
     }
+
+    /// Bytes of all labels created since the last reset
+    pub fn label_memory(&self) -> usize {
+        self.per_node_labels.label_bytes()
+    }
+
+    /// Bytes allocated for the labels, kept between queries
+    pub fn allocated_label_memory(&self) -> usize {
+        self.per_node_labels.allocated_bytes()
+    }
+
+    /// Potential at `node`, counted in the statistics
+    pub fn potential_at(&mut self, node: NodeId) -> Weight {
+        self.stats.num_potential_evaluations += 1;
+        self.potential.potential(node)
+    }
+
+    /// Statistics of the last `dist_query`, all work is done by the forward search
+    pub fn query_stats(&self) -> QueryStats {
+        QueryStats {
+            distance: self.last_distance,
+            time: self.stats.time,
+            num_nodes_searched: self.get_number_of_visited_nodes(),
+            forward: self.stats,
+            ..QueryStats::default()
+        }
+    }
 }
 
-pub struct OneRestrictionDijkstra<'a> {
+pub struct OneRestrictionDijkstra<'a, O = NoObserver> {
     graph: BorrowedGraph<'a>,
     reset_flags: &'a BitVec,
+    observer: O,
 }
 
 impl<'a> OneRestrictionDijkstra<'a> {
     pub fn new(graph: BorrowedGraph<'a>, reset_flags: &'a BitVec) -> Self {
-        Self { graph, reset_flags }
+        Self::with_observer(graph, reset_flags, NoObserver {})
+    }
+}
+
+impl<'a, O> OneRestrictionDijkstra<'a, O>
+where
+    O: SearchObserver,
+{
+    /// Reports every step of the searches to `observer`
+    pub fn with_observer(graph: BorrowedGraph<'a>, reset_flags: &'a BitVec, observer: O) -> Self {
+        Self { graph, reset_flags, observer }
     }
 
-    pub fn settle_next_label<P: Potential>(&self, state: &mut OneRestrictionDijkstraData<P>, t: NodeId) -> Option<State<Weight>> {
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn settle_next_label<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(
+        &self,
+        state: &mut OneRestrictionDijkstraData<P, Q>,
+        t: NodeId,
+    ) -> Option<State<Weight>> {
         let next = state.queue.pop();
 
         if let Some(State {
@@ -276,19 +440,25 @@ impl<'a> OneRestrictionDijkstra<'a> {
             node: node_id,
         }) = next
         {
-            state.num_settled += 1;
+            state.stats.num_settled += 1;
 
             if node_id == t {
-                state.last_distance = state.per_node_labels.get(node_id as usize).peek().map(|label| label.0.distance[0]);
+                state.last_distance = state
+                    .per_node_labels
+                    .get(node_id as usize)
+                    .peek()
+                    .filter(|label| state.meets_requirement_at_target(&label.0))
+                    .map(|label| label.0.distance[0]);
             }
 
             let label_index = state.per_node_labels.get_mut(node_id as usize).peek_index().unwrap();
             let label = state.per_node_labels.get_mut(node_id as usize).pop().unwrap();
             let tentative_dist_without_pot = label.0.distance;
+            self.observer.label_settled(node_id, &tentative_dist_without_pot);
 
             // check if next unsettled lable exists for node and push to queue
-            if let Some(next_best_label) = state.per_node_labels.get(node_id as usize).peek() {
-                let pot = state.potential.potential(node_id);
+            if let Some(next_best_label) = state.per_node_labels.get(node_id as usize).peek().copied() {
+                let pot = state.potential_at(node_id);
                 state.queue.push(State {
                     distance: state.estimated_dist_with_restriction(next_best_label.0.distance, pot),
                     node: node_id,
@@ -297,48 +467,56 @@ impl<'a> OneRestrictionDijkstra<'a> {
 
             // with hopping reduction
 
-            for (&edge_weight, &neighbor_node) in self.graph.outgoing_edge_iter(node_id).filter(|&s| *(s.1) != node_id) {
+            let departures = state.departures_from(node_id, t, tentative_dist_without_pot, self.reset_flags.get(node_id as usize).unwrap());
+            for (tentative_dist_without_pot, (&edge_weight, &neighbor_node)) in departures.into_iter().flat_map(|departure| {
+                self.graph
+                    .outgoing_edge_iter(node_id)
+                    .filter(move |&s| *(s.1) != node_id)
+                    .map(move |edge| (departure, edge))
+            }) {
                 // [new_dist without, new_dist with parking]
                 let mut new_dist = Vec::with_capacity(2);
                 new_dist.push(tentative_dist_without_pot.link(edge_weight));
 
                 // constraint and target pruning
-                if new_dist[0][1] >= state.restriction.max_driving_time
-                    || state.per_node_labels.get(t as usize).iter().any(|&s| s.0.distance.dominates(&new_dist[0]))
-                {
+                if new_dist[0][1] >= state.restriction.max_driving_time {
+                    self.observer.pruned_by_constraint(neighbor_node, &new_dist[0]);
+                    continue;
+                }
+                if state.is_dominated_at_target(t, &new_dist[0]) {
+                    self.observer.pruned_by_target(neighbor_node, &new_dist[0]);
                     continue;
                 }
 
-                if self.reset_flags.get(neighbor_node as usize).unwrap() {
+                if self.reset_flags.get(neighbor_node as usize).unwrap() && !state.breaks_on_departure_at(neighbor_node, t) {
                     new_dist.push(new_dist[0]);
                     new_dist[1].reset_distance(1, state.restriction.pause_time);
-                    state.num_labels_reset += 1;
+                    state.stats.num_labels_reset += 1;
                 }
 
                 for current_new_dist in new_dist {
-                    let pot = state.potential.potential(neighbor_node);
+                    let pot = state.potential_at(neighbor_node);
                     let distance_with_potential = state.estimated_dist_with_restriction(current_new_dist, pot);
 
                     if distance_with_potential == Weight::infinity() {
                         continue;
                     }
-                    let neighbor_label_set = state.per_node_labels.get_mut(neighbor_node as usize);
-                    let mut dominated = false;
-                    neighbor_label_set.retain(|&neighbor_label| {
-                        dominated |= neighbor_label.0.distance.dominates(&current_new_dist);
-                        dominated || !current_new_dist.dominates(&neighbor_label.0.distance)
-                    });
-
-                    if !dominated {
-                        state.num_labels_propagated += 1;
-                        neighbor_label_set.push(Reverse(Label {
-                            distance_with_potential,
-                            distance: current_new_dist,
-                            prev_node: node_id,
-                            prev_label: Some(label_index),
-                        }));
-
-                        let pot = state.potential.potential(neighbor_node);
+                    let mut neighbor_label_set = state.per_node_labels.get_mut(neighbor_node as usize);
+                    if !neighbor_label_set.is_dominated(&current_new_dist) {
+                        state.stats.num_labels_propagated += 1;
+                        neighbor_label_set.push(
+                            Reverse(Label {
+                                distance_with_potential,
+                                distance: current_new_dist,
+                                prev_node: node_id,
+                                prev_label: Some(label_index),
+                            }),
+                            |neighbor_label| self.observer.label_dominated(neighbor_node, &neighbor_label.0.distance),
+                        );
+                        state.stats.track_label_set_size(neighbor_label_set.len());
+                        self.observer.label_pushed(neighbor_node, node_id, &current_new_dist);
+
+                        let pot = state.potential_at(neighbor_node);
                         let dist_with_potential = state.estimated_dist_with_restriction(current_new_dist, pot);
                         if state.queue.contains_index(neighbor_node as usize) {
                             // decrease key seems to increase key if given a larger key than existing
@@ -349,12 +527,14 @@ impl<'a> OneRestrictionDijkstra<'a> {
                                 });
                             }
                         } else {
-                            state.num_queue_pushes += 1;
+                            state.stats.num_queue_pushes += 1;
                             state.queue.push(State {
                                 distance: dist_with_potential,
                                 node: neighbor_node,
                             });
                         }
+                    } else {
+                        self.observer.label_dominated(neighbor_node, &current_new_dist);
                     }
                 }
             }
@@ -365,64 +545,90 @@ impl<'a> OneRestrictionDijkstra<'a> {
         next
     }
 
-    pub fn dist_query<P: Potential>(&self, state: &mut OneRestrictionDijkstraData<P>, t: NodeId) -> Option<Weight> {
+    pub fn dist_query<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(&self, state: &mut OneRestrictionDijkstraData<P, Q>, t: NodeId) -> Option<Weight> {
         let start = Instant::now();
         state.reset();
         state.potential.init_new_t(t);
         state.last_t = t;
 
+        let mut result = None;
         while let Some(State { distance: _, node: node_id }) = self.settle_next_label(state, t) {
-            if node_id == t {
-                state.time_elapsed = start.elapsed();
-                return state.last_distance;
+            if node_id == t && state.last_distance.is_some() {
+                result = state.last_distance;
+                break;
             }
         }
-        state.time_elapsed = start.elapsed();
-        None
+
+        state.stats.time = start.elapsed();
+        result
     }
 
-    pub fn timeout_dist_query<P: Potential>(
+    /// Like `dist_query` but stops when the budget is exhausted
+    pub fn dist_query_with_budget<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(
         &self,
-        state: &mut OneRestrictionDijkstraData<P>,
+        state: &mut OneRestrictionDijkstraData<P, Q>,
         t: NodeId,
-        timeout: Duration,
-    ) -> Result<Option<Weight>, QueryTimeoutError> {
+        budget: &SearchBudget,
+    ) -> Result<Option<Weight>, QueryAborted> {
         let start = Instant::now();
         state.reset();
         state.potential.init_new_t(t);
         state.last_t = t;
 
-        while let Some(State { distance: _, node: node_id }) = self.settle_next_label(state, t) {
-            if node_id == t {
-                state.time_elapsed = start.elapsed();
-                return Ok(state.last_distance);
+        let result = loop {
+            if let Err(reason) = budget.check(state.stats.num_settled, state.label_memory()) {
+                state.last_distance = None;
+                break Err(QueryAborted::new(reason, state.get_tentative_dist_at(t)[0]));
             }
 
-            if start.elapsed() > timeout {
-                return Err(QueryTimeoutError);
-            };
-        }
-        state.time_elapsed = start.elapsed();
-        Ok(None)
+            match self.settle_next_label(state, t) {
+                Some(State { distance: _, node: node_id }) if node_id == t && state.last_distance.is_some() => break Ok(state.last_distance),
+                Some(_) => (),
+                None => break Ok(None),
+            }
+        };
+
+        state.stats.time = start.elapsed();
+        result
     }
 
-    pub fn dist_query_propagate_all_labels<P: Potential>(&self, state: &mut OneRestrictionDijkstraData<P>, t: NodeId) -> Option<Weight> {
+    pub fn timeout_dist_query<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(
+        &self,
+        state: &mut OneRestrictionDijkstraData<P, Q>,
+        t: NodeId,
+        timeout: Duration,
+    ) -> Result<Option<Weight>, QueryTimeoutError> {
+        self.dist_query_with_budget(state, t, &SearchBudget::unlimited().with_timeout(timeout))
+            .map_err(|_| QueryTimeoutError)
+    }
+
+    pub fn dist_query_propagate_all_labels<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(
+        &self,
+        state: &mut OneRestrictionDijkstraData<P, Q>,
+        t: NodeId,
+    ) -> Option<Weight> {
         let start = Instant::now();
         state.reset();
         state.potential.init_new_t(t);
         state.last_t = t;
 
+        let mut result = None;
         while let Some(State { distance: _, node: node_id }) = self.settle_next_label_propagate_all(state, t) {
-            if node_id == t {
-                state.time_elapsed = start.elapsed();
-                return state.last_distance;
+            if node_id == t && state.last_distance.is_some() {
+                result = state.last_distance;
+                break;
             }
         }
-        state.time_elapsed = start.elapsed();
-        None
+
+        state.stats.time = start.elapsed();
+        result
     }
 
-    pub fn settle_next_label_propagate_all<P: Potential>(&self, state: &mut OneRestrictionDijkstraData<P>, t: NodeId) -> Option<State<Weight>> {
+    pub fn settle_next_label_propagate_all<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(
+        &self,
+        state: &mut OneRestrictionDijkstraData<P, Q>,
+        t: NodeId,
+    ) -> Option<State<Weight>> {
         let next = state.queue.pop();
 
         if let Some(State {
@@ -430,59 +636,73 @@ impl<'a> OneRestrictionDijkstra<'a> {
             node: node_id,
         }) = next
         {
-            state.num_settled += 1;
+            state.stats.num_settled += 1;
 
             if node_id == t {
-                state.last_distance = state.per_node_labels.get(node_id as usize).peek().map(|label| label.0.distance[0]);
+                state.last_distance = state
+                    .per_node_labels
+                    .get(node_id as usize)
+                    .peek()
+                    .filter(|label| state.meets_requirement_at_target(&label.0))
+                    .map(|label| label.0.distance[0]);
             }
             while let Some(label_index) = state.per_node_labels.get_mut(node_id as usize).peek_index() {
                 let label = state.per_node_labels.get_mut(node_id as usize).pop().unwrap();
                 let tentative_dist_without_pot = label.0.distance;
+                self.observer.label_settled(node_id, &tentative_dist_without_pot);
 
                 // with hopping reduction
-                for (&edge_weight, &neighbor_node) in self.graph.outgoing_edge_iter(node_id).filter(|&s| *(s.1) != node_id) {
+                let departures = state.departures_from(node_id, t, tentative_dist_without_pot, self.reset_flags.get(node_id as usize).unwrap());
+                for (tentative_dist_without_pot, (&edge_weight, &neighbor_node)) in departures.into_iter().flat_map(|departure| {
+                    self.graph
+                        .outgoing_edge_iter(node_id)
+                        .filter(move |&s| *(s.1) != node_id)
+                        .map(move |edge| (departure, edge))
+                }) {
                     {
                         // [new_dist without, new_dist with parking]
                         let mut new_dist = Vec::with_capacity(2);
                         new_dist.push(tentative_dist_without_pot.link(edge_weight));
 
                         // constraint and target pruning
-                        if new_dist[0][1] >= state.restriction.max_driving_time
-                            || state.per_node_labels.get(t as usize).iter().any(|&s| s.0.distance.dominates(&new_dist[0]))
-                        {
+                        if new_dist[0][1] >= state.restriction.max_driving_time {
+                            self.observer.pruned_by_constraint(neighbor_node, &new_dist[0]);
+                            continue;
+                        }
+                        if state.is_dominated_at_target(t, &new_dist[0]) {
+                            self.observer.pruned_by_target(neighbor_node, &new_dist[0]);
                             continue;
                         }
 
-                        if self.reset_flags.get(neighbor_node as usize).unwrap() {
+                        if self.reset_flags.get(neighbor_node as usize).unwrap() && !state.breaks_on_departure_at(neighbor_node, t) {
                             new_dist.push(new_dist[0]);
                             new_dist[1].reset_distance(1, state.restriction.pause_time);
-                            state.num_labels_reset += 1;
+                            state.stats.num_labels_reset += 1;
                         }
 
                         for current_new_dist in new_dist {
-                            let pot = state.potential.potential(neighbor_node);
+                            let pot = state.potential_at(neighbor_node);
                             let distance_with_potential = state.estimated_dist_with_restriction(current_new_dist, pot);
 
                             if distance_with_potential == Weight::infinity() {
                                 continue;
                             }
-                            let neighbor_label_set = state.per_node_labels.get_mut(neighbor_node as usize);
-                            let mut dominated = false;
-                            neighbor_label_set.retain(|&neighbor_label| {
-                                dominated |= neighbor_label.0.distance.dominates(&current_new_dist);
-                                dominated || !current_new_dist.dominates(&neighbor_label.0.distance)
-                            });
-
-                            if !dominated {
-                                state.num_labels_propagated += 1;
-                                neighbor_label_set.push(Reverse(Label {
-                                    distance_with_potential,
-                                    distance: current_new_dist,
-                                    prev_node: node_id,
-                                    prev_label: Some(label_index),
-                                }));
-
-                                let pot = state.potential.potential(neighbor_node);
+                            let mut neighbor_label_set = state.per_node_labels.get_mut(neighbor_node as usize);
+                            if !neighbor_label_set.is_dominated(&current_new_dist) {
+                                state.stats.num_labels_propagated += 1;
+                                neighbor_label_set.push(
+                                    Reverse(Label {
+                                        distance_with_potential,
+                                        distance: current_new_dist,
+                                        prev_node: node_id,
+                                        prev_label: Some(label_index),
+                                    }),
+                                    |neighbor_label| self.observer.label_dominated(neighbor_node, &neighbor_label.0.distance),
+                                );
+                                state.stats.track_label_set_size(neighbor_label_set.len());
+                                self.observer.label_pushed(neighbor_node, node_id, &current_new_dist);
+
+                                let pot = state.potential_at(neighbor_node);
                                 let dist_with_potential = state.estimated_dist_with_restriction(current_new_dist, pot);
 
                                 if dist_with_potential == INFINITY {
@@ -498,12 +718,14 @@ impl<'a> OneRestrictionDijkstra<'a> {
                                         });
                                     }
                                 } else {
-                                    state.num_queue_pushes += 1;
+                                    state.stats.num_queue_pushes += 1;
                                     state.queue.push(State {
                                         distance: dist_with_potential,
                                         node: neighbor_node,
                                     });
                                 }
+                            } else {
+                                self.observer.label_dominated(neighbor_node, &current_new_dist);
                             }
                         }
                     };
@@ -516,10 +738,10 @@ impl<'a> OneRestrictionDijkstra<'a> {
         next
     }
 
-    pub fn settle_next_label_prune_bw_lower_bound<P: Potential>(
+    pub fn settle_next_label_prune_bw_lower_bound<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(
         &self,
-        state: &mut OneRestrictionDijkstraData<P>,
-        bw_state: &mut OneRestrictionDijkstraData<P>,
+        state: &mut OneRestrictionDijkstraData<P, Q>,
+        bw_state: &mut OneRestrictionDijkstraData<P, Q>,
         tentative_distance: Weight,
         t: NodeId,
     ) -> Option<State<Weight>> {
@@ -530,19 +752,25 @@ impl<'a> OneRestrictionDijkstra<'a> {
             node: node_id,
         }) = next
         {
-            state.num_settled += 1;
+            state.stats.num_settled += 1;
 
             if node_id == t {
-                state.last_distance = state.per_node_labels.get(node_id as usize).peek().map(|label| label.0.distance[0]);
+                state.last_distance = state
+                    .per_node_labels
+                    .get(node_id as usize)
+                    .peek()
+                    .filter(|label| state.meets_requirement_at_target(&label.0))
+                    .map(|label| label.0.distance[0]);
             }
 
             let label_index = state.per_node_labels.get_mut(node_id as usize).peek_index().unwrap();
             let label = state.per_node_labels.get_mut(node_id as usize).pop().unwrap();
             let tentative_dist_without_pot = label.0.distance;
+            self.observer.label_settled(node_id, &tentative_dist_without_pot);
 
             // check if next unsettled lable exists for node and push to queue
-            if let Some(next_best_label) = state.per_node_labels.get(node_id as usize).peek() {
-                let pot = state.potential.potential(node_id);
+            if let Some(next_best_label) = state.per_node_labels.get(node_id as usize).peek().copied() {
+                let pot = state.potential_at(node_id);
                 state.queue.push(State {
                     distance: state.estimated_dist_with_restriction(next_best_label.0.distance, pot),
                     node: node_id,
@@ -550,69 +778,80 @@ impl<'a> OneRestrictionDijkstra<'a> {
             }
 
             // with hopping reduction
-            for (&edge_weight, &neighbor_node) in self.graph.outgoing_edge_iter(node_id).filter(|&s| *(s.1) != node_id) {
+            let departures = state.departures_from(node_id, t, tentative_dist_without_pot, self.reset_flags.get(node_id as usize).unwrap());
+            for (tentative_dist_without_pot, (&edge_weight, &neighbor_node)) in departures.into_iter().flat_map(|departure| {
+                self.graph
+                    .outgoing_edge_iter(node_id)
+                    .filter(move |&s| *(s.1) != node_id)
+                    .map(move |edge| (departure, edge))
+            }) {
                 // [new_dist without, new_dist with parking]
                 let mut new_dist = Vec::with_capacity(2);
                 new_dist.push(tentative_dist_without_pot.link(edge_weight));
 
                 // constraint and target pruning
-                if new_dist[0][1] >= state.restriction.max_driving_time
-                    || state.per_node_labels.get(t as usize).iter().any(|&s| s.0.distance.dominates(&new_dist[0]))
-                {
+                if new_dist[0][1] >= state.restriction.max_driving_time {
+                    self.observer.pruned_by_constraint(neighbor_node, &new_dist[0]);
+                    continue;
+                }
+                if state.is_dominated_at_target(t, &new_dist[0]) {
+                    self.observer.pruned_by_target(neighbor_node, &new_dist[0]);
                     continue;
                 }
 
-                if self.reset_flags.get(neighbor_node as usize).unwrap() {
+                if self.reset_flags.get(neighbor_node as usize).unwrap() && !state.breaks_on_departure_at(neighbor_node, t) {
                     new_dist.push(new_dist[0]);
                     new_dist[1].reset_distance(1, state.restriction.pause_time);
-                    state.num_labels_reset += 1;
+                    state.stats.num_labels_reset += 1;
                 }
 
                 for current_new_dist in new_dist {
-                    let pot = state.potential.potential(neighbor_node);
+                    let pot = state.potential_at(neighbor_node);
                     let distance_with_potential = state.estimated_dist_with_restriction(current_new_dist, pot);
 
                     if distance_with_potential == Weight::infinity() {
                         continue;
                     }
-                    // pruning with bw lower bound
-                    if self.reset_flags.get(neighbor_node as usize).unwrap() {
+                    // pruning with bw lower bound, which does not hold for the shifted labels of a requirement at the target
+                    let has_requirement = state.required_at_target != Weight2::zero() || bw_state.required_at_target != Weight2::zero();
+                    if self.reset_flags.get(neighbor_node as usize).unwrap() && !has_requirement {
                         if bw_state.queue.contains_index(neighbor_node as usize) {
                             // bw_min_key - bw_pot(neighbor_node) as lower bound for D(neighbor_node,t)
                             let bw_pot_at_neighbor =
                                 bw_state.get_best_label_at(neighbor_node).unwrap().distance_with_potential - bw_state.get_tentative_dist_at(neighbor_node)[0];
                             let bw_min_key = bw_state.peek_queue().map(|s| s.distance).unwrap();
                             if (current_new_dist[0] + bw_min_key).saturating_sub(bw_pot_at_neighbor) >= tentative_distance {
+                                self.observer.pruned_by_bw_lower_bound(neighbor_node, &current_new_dist);
                                 continue;
                             }
                         } else {
                             // bw_min_key - bw_pot(neighbor_node) as lower bound for D(neighbor_node,t)
-                            let v_t_dist = bw_state.potential.potential(neighbor_node);
+                            let v_t_dist = bw_state.potential_at(neighbor_node);
                             let bw_pot_at_neighbor = bw_state.estimated_dist_with_restriction([0, 0], v_t_dist) + bw_state.restriction.pause_time;
                             let bw_min_key = bw_state.peek_queue().map(|s| s.distance).unwrap();
                             if (current_new_dist[0] + bw_min_key).saturating_sub(bw_pot_at_neighbor) >= tentative_distance {
+                                self.observer.pruned_by_bw_lower_bound(neighbor_node, &current_new_dist);
                                 continue;
                             }
                         }
                     }
 
-                    let neighbor_label_set = state.per_node_labels.get_mut(neighbor_node as usize);
-                    let mut dominated = false;
-                    neighbor_label_set.retain(|&neighbor_label| {
-                        dominated |= neighbor_label.0.distance.dominates(&current_new_dist);
-                        dominated || !current_new_dist.dominates(&neighbor_label.0.distance)
-                    });
-
-                    if !dominated {
-                        state.num_labels_propagated += 1;
-                        neighbor_label_set.push(Reverse(Label {
-                            distance_with_potential,
-                            distance: current_new_dist,
-                            prev_node: node_id,
-                            prev_label: Some(label_index),
-                        }));
-
-                        let pot = state.potential.potential(neighbor_node);
+                    let mut neighbor_label_set = state.per_node_labels.get_mut(neighbor_node as usize);
+                    if !neighbor_label_set.is_dominated(&current_new_dist) {
+                        state.stats.num_labels_propagated += 1;
+                        neighbor_label_set.push(
+                            Reverse(Label {
+                                distance_with_potential,
+                                distance: current_new_dist,
+                                prev_node: node_id,
+                                prev_label: Some(label_index),
+                            }),
+                            |neighbor_label| self.observer.label_dominated(neighbor_node, &neighbor_label.0.distance),
+                        );
+                        state.stats.track_label_set_size(neighbor_label_set.len());
+                        self.observer.label_pushed(neighbor_node, node_id, &current_new_dist);
+
+                        let pot = state.potential_at(neighbor_node);
                         let dist_with_potential = state.estimated_dist_with_restriction(current_new_dist, pot);
                         if state.queue.contains_index(neighbor_node as usize) {
                             // decrease key seems to increase key if given a larger key than existing
@@ -623,12 +862,14 @@ impl<'a> OneRestrictionDijkstra<'a> {
                                 });
                             }
                         } else {
-                            state.num_queue_pushes += 1;
+                            state.stats.num_queue_pushes += 1;
                             state.queue.push(State {
                                 distance: dist_with_potential,
                                 node: neighbor_node,
                             });
                         }
+                    } else {
+                        self.observer.label_dominated(neighbor_node, &current_new_dist);
                     }
                 }
             }
@@ -665,10 +906,10 @@ impl<'a> OneRestrictionDijkstra<'a> {
         reset_nodes
     }
 
-    pub fn summary<P: Potential>(&self, state: &OneRestrictionDijkstraData<P>) -> String {
+    pub fn summary<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(&self, state: &OneRestrictionDijkstraData<P, Q>) -> String {
         let mut s = "\n\nSummary for CSP\n\n".to_string();
 
-        if state.num_settled == 0 {
+        if state.stats.num_settled == 0 {
             writeln!(s, "No query").unwrap();
             return s;
         }
@@ -701,11 +942,13 @@ impl<'a> OneRestrictionDijkstra<'a> {
         writeln!(s).unwrap();
         writeln!(s, "Query:").unwrap();
         writeln!(s, "\ts: {} t: {}", state.s, state.last_t).unwrap();
-        writeln!(s, "\ttime elapsed: {:.3} ms", state.time_elapsed.as_secs_f64() * 1000.0).unwrap();
-        writeln!(s, "\tnumber of queue pushes: {}", state.num_queue_pushes).unwrap();
-        writeln!(s, "\tnumber of settled nodes: {}", state.num_settled).unwrap();
-        writeln!(s, "\tnumber of propagated labels: {}", state.num_labels_propagated).unwrap();
-        writeln!(s, "\tnumber of labels which were reset: {}", state.num_labels_reset).unwrap();
+        writeln!(s, "\ttime elapsed: {:.3} ms", state.stats.time.as_secs_f64() * 1000.0).unwrap();
+        writeln!(s, "\tnumber of queue pushes: {}", state.stats.num_queue_pushes).unwrap();
+        writeln!(s, "\tnumber of settled nodes: {}", state.stats.num_settled).unwrap();
+        writeln!(s, "\tnumber of propagated labels: {}", state.stats.num_labels_propagated).unwrap();
+        writeln!(s, "\tnumber of labels which were reset: {}", state.stats.num_labels_reset).unwrap();
+        writeln!(s, "\tnumber of potential evaluations: {}", state.stats.num_potential_evaluations).unwrap();
+        writeln!(s, "\tmaximum number of labels at a node: {}", state.stats.max_label_set_size).unwrap();
 
         writeln!(s).unwrap();
         writeln!(s, "Path:").unwrap();
@@ -768,6 +1011,13 @@ impl<'a> OneRestrictionDijkstra<'a> {
             };
             writeln!(s, "\t  -thereof mean number of labels: {:.2}", mean).unwrap();
         }
+        writeln!(
+            s,
+            "\tlabel memory: {:.2} MiB used, {:.2} MiB allocated",
+            state.label_memory() as f64 / 1_048_576.0,
+            state.allocated_label_memory() as f64 / 1_048_576.0
+        )
+        .unwrap();
 
         s
     }
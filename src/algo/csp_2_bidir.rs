@@ -1,5 +1,11 @@
-use super::csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData};
-use crate::types::*;
+use super::{
+    astar::NoPotential,
+    budget::{QueryAborted, SearchBudget},
+    csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+    search_space::SearchSpace,
+    stats::QueryStats,
+};
+use crate::{index_heap::IndexdMinHeap, priority_queue::IndexedPriorityQueue, types::*};
 use bit_vec::BitVec;
 use num::Integer;
 use std::{
@@ -7,18 +13,20 @@ use std::{
     time::{Duration, Instant},
 };
 
-pub struct CSP2BidirQuery<'a> {
+pub struct CSP2BidirQuery<'a, Q = IndexdMinHeap<State<Weight>>> {
     fw_graph: BorrowedGraph<'a>,
     bw_graph: BorrowedGraph<'a>,
     is_reset_node: &'a BitVec,
-    fw_state: TwoRestrictionDijkstraData,
-    bw_state: TwoRestrictionDijkstraData,
+    fw_state: TwoRestrictionDijkstraData<NoPotential, Q>,
+    bw_state: TwoRestrictionDijkstraData<NoPotential, Q>,
     restriction_long: DrivingTimeRestriction,
     restriction_short: DrivingTimeRestriction,
     fw_finished: bool,
     bw_finished: bool,
     s: NodeId,
     t: NodeId,
+    driver_state: DriverState,
+    required_at_target: RemainingDrivingTime,
     pub last_dist: Option<Weight>,
     last_middle_node: Option<NodeId>,
     last_time_elapsed: Duration,
@@ -26,13 +34,20 @@ pub struct CSP2BidirQuery<'a> {
 
 impl<'a> CSP2BidirQuery<'a> {
     pub fn new(fw_graph: BorrowedGraph<'a>, bw_graph: BorrowedGraph<'a>, is_reset_node: &'a BitVec) -> Self {
+        Self::with_queue(fw_graph, bw_graph, is_reset_node)
+    }
+}
+
+impl<'a, Q: IndexedPriorityQueue<State<Weight>>> CSP2BidirQuery<'a, Q> {
+    /// Uses `Q` as priority queue of both searches
+    pub fn with_queue(fw_graph: BorrowedGraph<'a>, bw_graph: BorrowedGraph<'a>, is_reset_node: &'a BitVec) -> Self {
         let node_count = fw_graph.num_nodes();
         CSP2BidirQuery {
             fw_graph,
             bw_graph,
             is_reset_node,
-            fw_state: TwoRestrictionDijkstraData::new(node_count),
-            bw_state: TwoRestrictionDijkstraData::new(node_count),
+            fw_state: TwoRestrictionDijkstraData::with_queue(node_count, NoPotential {}),
+            bw_state: TwoRestrictionDijkstraData::with_queue(node_count, NoPotential {}),
             restriction_long: DrivingTimeRestriction {
                 pause_time: 0,
                 max_driving_time: Weight::infinity(),
@@ -45,6 +60,8 @@ impl<'a> CSP2BidirQuery<'a> {
             bw_finished: false,
             s: node_count as NodeId,
             t: node_count as NodeId,
+            driver_state: DriverState::default(),
+            required_at_target: RemainingDrivingTime::default(),
             last_dist: None,
             last_middle_node: None,
             last_time_elapsed: Duration::ZERO,
@@ -55,6 +72,17 @@ impl<'a> CSP2BidirQuery<'a> {
         self.s = s;
     }
 
+    /// Driving times at departure from `s`, kept for all following queries
+    pub fn set_driver_state(&mut self, driver_state: DriverState) {
+        self.driver_state = driver_state;
+    }
+
+    /// Driving time which must be left at `t`, kept for all following queries
+    pub fn set_required_remaining_driving_time(&mut self, remaining: RemainingDrivingTime) {
+        self.required_at_target = remaining;
+        self.fw_state.set_required_remaining_driving_time(&remaining);
+    }
+
     pub fn init_new_t(&mut self, t: NodeId) {
         self.t = t;
     }
@@ -90,11 +118,12 @@ impl<'a> CSP2BidirQuery<'a> {
     }
     pub fn reset(&mut self) {
         if self.s != self.fw_graph.num_nodes() as NodeId {
-            self.fw_state.init_new_s(self.s);
+            self.fw_state.init_new_s_with_driver_state(self.s, &self.driver_state);
         }
 
         if self.t != self.fw_graph.num_nodes() as NodeId {
-            self.bw_state.init_new_s(self.t);
+            // the bw search starts with the driving time which must be left at t
+            self.bw_state.init_new_s_with_labels(self.t, &[self.required_at_target.weight3()]);
         }
 
         self.fw_finished = false;
@@ -114,16 +143,22 @@ impl<'a> CSP2BidirQuery<'a> {
         node: NodeId,
         restriction_short: &DrivingTimeRestriction,
         restriction_long: &DrivingTimeRestriction,
-        fw_label_dist: &Weight3,
-        bw_state: &mut TwoRestrictionDijkstraData,
+        fw_label: &Label<Weight3>,
+        fw_label_ends_with_break: bool,
+        bw_state: &mut TwoRestrictionDijkstraData<NoPotential, Q>,
     ) -> Weight {
         let v_to_t = bw_state.get_settled_labels_at(node);
 
-        let mut current_bw = v_to_t.rev().map(|r| r.0);
+        let mut current_bw = v_to_t.map(|r| r.0);
 
         let mut best_distance = Weight::infinity();
         while let Some(bw_label) = current_bw.next() {
-            let total_dist = fw_label_dist.add(bw_label.distance);
+            // no break right before departure from s or arrival at t, where one of the labels is a start label
+            if (fw_label_ends_with_break && bw_label.prev_label.is_none()) || (fw_label.prev_label.is_none() && bw_state.ends_with_break(&bw_label)) {
+                continue;
+            }
+
+            let total_dist = fw_label.distance.add(bw_label.distance);
 
             // check if restrictions allows combination of those labels/subpaths
             if total_dist[1] < restriction_short.max_driving_time && total_dist[2] < restriction_long.max_driving_time {
@@ -136,129 +171,12 @@ impl<'a> CSP2BidirQuery<'a> {
     }
 
     pub fn run_query(&mut self) -> Option<Weight> {
-        let start = Instant::now();
-        if self.s == self.fw_graph.num_nodes() as NodeId || self.t == self.fw_graph.num_nodes() as NodeId {
-            return None;
-        }
-
-        self.reset();
-
-        let mut tentative_distance = Weight::infinity();
-
-        let mut settled_fw = BitVec::from_elem(self.fw_graph.num_nodes(), false);
-        let mut settled_bw = BitVec::from_elem(self.fw_graph.num_nodes(), false);
-        let mut fw_next = true;
-
-        let fw_search = TwoRestrictionDijkstra::new(self.fw_graph, &self.is_reset_node);
-        let bw_search = TwoRestrictionDijkstra::new(self.bw_graph, &self.is_reset_node);
-
-        while !self.fw_finished || !self.bw_finished {
-            if !self.fw_finished && (self.bw_finished || fw_next) {
-                if let Some(State {
-                    distance: _dist_from_queue_at_v,
-                    node,
-                }) = if tentative_distance < Weight::infinity() && self.bw_state.min_key().is_some() {
-                    fw_search.settle_next_label_prune_bw_lower_bound(&mut self.fw_state, &mut self.bw_state, tentative_distance, self.t)
-                } else {
-                    fw_search.settle_next_label(&mut self.fw_state, self.t)
-                } {
-                    settled_fw.set(node as usize, true);
-
-                    // fw search found t -> done here
-                    if node == self.t {
-                        tentative_distance = self.fw_state.get_settled_labels_at(node).last().unwrap().0.distance[0]; // dist_from_queue_at_v[0];
-                        self.fw_finished = true;
-                        self.bw_finished = true;
-                        self.last_middle_node = None;
-
-                        break;
-                    }
-
-                    if settled_bw.get(node as usize).unwrap() {
-                        let tent_dist_at_v = Self::calculate_distance_with_break_at(
-                            node,
-                            &self.restriction_short,
-                            &self.restriction_long,
-                            &self.fw_state.get_best_label_at(node).unwrap().distance,
-                            &mut self.bw_state,
-                        );
-
-                        if tentative_distance > tent_dist_at_v {
-                            tentative_distance = tent_dist_at_v;
-                            self.last_middle_node = Some(node);
-                        }
-                    }
-
-                    if self.fw_state.min_key().is_none() {
-                        self.fw_finished = true;
-                        self.bw_finished = true;
-                    } else if self.fw_state.min_key().unwrap() + self.bw_state.min_key().unwrap() >= tentative_distance {
-                        self.fw_finished = true;
-                        self.bw_finished = true;
-                    }
-
-                    fw_next = false;
-                }
-            } else if let Some(State {
-                distance: _dist_from_queue_at_v,
-                node,
-            }) = if tentative_distance < Weight::infinity() && self.fw_state.min_key().is_some() {
-                bw_search.settle_next_label_prune_bw_lower_bound(&mut self.bw_state, &mut self.fw_state, tentative_distance, self.s)
-            } else {
-                bw_search.settle_next_label(&mut self.bw_state, self.s)
-            } {
-                settled_bw.set(node as usize, true);
-
-                // bw search found s -> done here
-                if node == self.s {
-                    tentative_distance = self.bw_state.get_settled_labels_at(node).last().unwrap().0.distance[0]; // dist_from_queue_at_v[0];
-
-                    self.fw_finished = true;
-                    self.bw_finished = true;
-                    self.last_middle_node = None;
-
-                    break;
-                }
-
-                if settled_fw.get(node as usize).unwrap() {
-                    let tent_dist_at_v = Self::calculate_distance_with_break_at(
-                        node,
-                        &self.restriction_short,
-                        &self.restriction_long,
-                        &self.bw_state.get_best_label_at(node).unwrap().distance,
-                        &mut self.fw_state,
-                    );
-
-                    if tentative_distance > tent_dist_at_v {
-                        tentative_distance = tent_dist_at_v;
-                        self.last_middle_node = Some(node);
-                    }
-                }
-
-                if self.bw_state.min_key().is_none() {
-                    self.fw_finished = true;
-                    self.bw_finished = true;
-                } else if self.fw_state.min_key().unwrap() + self.bw_state.min_key().unwrap() >= tentative_distance {
-                    self.fw_finished = true;
-                    self.bw_finished = true;
-                }
-
-                fw_next = true;
-            }
-        }
-
-        self.last_time_elapsed = start.elapsed();
-
-        if tentative_distance == Weight::infinity() {
-            self.last_dist = None;
-            return None;
-        }
-
-        self.last_dist = Some(tentative_distance);
-        self.last_dist
+        self.run_query_with_budget(&SearchBudget::unlimited())
+            .expect("an unlimited budget never aborts")
     }
 
-    pub fn timeout_run_query(&mut self, timeout: Duration) -> Result<Option<Weight>, QueryTimeoutError> {
+    /// Like `run_query` but stops when the budget is exhausted
+    pub fn run_query_with_budget(&mut self, budget: &SearchBudget) -> Result<Option<Weight>, QueryAborted> {
         let start = Instant::now();
         if self.s == self.fw_graph.num_nodes() as NodeId || self.t == self.fw_graph.num_nodes() as NodeId {
             return Ok(None);
@@ -276,8 +194,13 @@ impl<'a> CSP2BidirQuery<'a> {
         let bw_search = TwoRestrictionDijkstra::new(self.bw_graph, &self.is_reset_node);
 
         while !self.fw_finished || !self.bw_finished {
-            if start.elapsed() > timeout {
-                return Err(QueryTimeoutError);
+            if let Err(reason) = budget.check(
+                self.fw_state.stats.num_settled + self.bw_state.stats.num_settled,
+                self.fw_state.label_memory() + self.bw_state.label_memory(),
+            ) {
+                self.last_time_elapsed = start.elapsed();
+                self.last_dist = None;
+                return Err(QueryAborted::new(reason, tentative_distance));
             }
 
             if !self.fw_finished && (self.bw_finished || fw_next) {
@@ -292,8 +215,8 @@ impl<'a> CSP2BidirQuery<'a> {
                     settled_fw.set(node as usize, true);
 
                     // fw search found t -> done here
-                    if node == self.t {
-                        tentative_distance = self.fw_state.get_settled_labels_at(node).last().unwrap().0.distance[0]; // dist_from_queue_at_v[0];
+                    if let (true, Some(last_distance)) = (node == self.t, self.fw_state.last_distance) {
+                        tentative_distance = last_distance;
                         self.fw_finished = true;
                         self.bw_finished = true;
                         self.last_middle_node = None;
@@ -301,14 +224,32 @@ impl<'a> CSP2BidirQuery<'a> {
                         break;
                     }
 
-                    if settled_bw.get(node as usize).unwrap() {
-                        let tent_dist_at_v = Self::calculate_distance_with_break_at(
-                            node,
-                            &self.restriction_short,
-                            &self.restriction_long,
-                            &self.fw_state.get_best_label_at(node).unwrap().distance,
-                            &mut self.bw_state,
-                        );
+                    // with a driver state the start label must not be continued by a break at s
+                    if node == self.s && self.driver_state != DriverState::default() {
+                        if settled_bw.get(node as usize).unwrap() {
+                            let tent_dist_at_s = self.bw_state.best_distance_from_driver_state_at(node, &self.driver_state, &self.fw_state);
+                            if tentative_distance > tent_dist_at_s {
+                                tentative_distance = tent_dist_at_s;
+                                self.last_middle_node = None;
+                            }
+                        }
+                    } else if settled_bw.get(node as usize).unwrap() {
+                        // every settled label at v may be the one connecting to the other search
+                        let tent_dist_at_v = self
+                            .fw_state
+                            .get_settled_labels_at(node)
+                            .map(|label| {
+                                Self::calculate_distance_with_break_at(
+                                    node,
+                                    &self.restriction_short,
+                                    &self.restriction_long,
+                                    &label.0,
+                                    self.fw_state.ends_with_break(&label.0),
+                                    &mut self.bw_state,
+                                )
+                            })
+                            .min()
+                            .unwrap_or_else(Weight::infinity);
 
                         if tentative_distance > tent_dist_at_v {
                             tentative_distance = tent_dist_at_v;
@@ -319,9 +260,8 @@ impl<'a> CSP2BidirQuery<'a> {
                     if self.fw_state.min_key().is_none() {
                         self.fw_finished = true;
                         self.bw_finished = true;
-                    } else if self.fw_state.min_key().unwrap() + self.bw_state.min_key().unwrap() >= tentative_distance {
+                    } else if self.fw_state.min_key().unwrap() >= tentative_distance {
                         self.fw_finished = true;
-                        self.bw_finished = true;
                     }
 
                     fw_next = false;
@@ -336,9 +276,15 @@ impl<'a> CSP2BidirQuery<'a> {
             } {
                 settled_bw.set(node as usize, true);
 
-                // bw search found s -> done here
-                if node == self.s {
-                    tentative_distance = self.bw_state.get_settled_labels_at(node).last().unwrap().0.distance[0]; // dist_from_queue_at_v[0];
+                // bw search found s -> done here unless the driver has already been driving at departure
+                if node == self.s && self.driver_state != DriverState::default() {
+                    let tent_dist_at_s = self.bw_state.best_distance_from_driver_state_at(node, &self.driver_state, &self.fw_state);
+                    if tentative_distance > tent_dist_at_s {
+                        tentative_distance = tent_dist_at_s;
+                        self.last_middle_node = None;
+                    }
+                } else if node == self.s {
+                    tentative_distance = self.bw_state.get_settled_labels_at(node).next().unwrap().0.distance[0]; // dist_from_queue_at_v[0];
 
                     self.fw_finished = true;
                     self.bw_finished = true;
@@ -347,14 +293,23 @@ impl<'a> CSP2BidirQuery<'a> {
                     break;
                 }
 
-                if settled_fw.get(node as usize).unwrap() {
-                    let tent_dist_at_v = Self::calculate_distance_with_break_at(
-                        node,
-                        &self.restriction_short,
-                        &self.restriction_long,
-                        &self.bw_state.get_best_label_at(node).unwrap().distance,
-                        &mut self.fw_state,
-                    );
+                if settled_fw.get(node as usize).unwrap() && (node != self.s || self.driver_state == DriverState::default()) {
+                    // every settled label at v may be the one connecting to the other search
+                    let tent_dist_at_v = self
+                        .bw_state
+                        .get_settled_labels_at(node)
+                        .map(|label| {
+                            Self::calculate_distance_with_break_at(
+                                node,
+                                &self.restriction_short,
+                                &self.restriction_long,
+                                &label.0,
+                                self.bw_state.ends_with_break(&label.0),
+                                &mut self.fw_state,
+                            )
+                        })
+                        .min()
+                        .unwrap_or_else(Weight::infinity);
 
                     if tentative_distance > tent_dist_at_v {
                         tentative_distance = tent_dist_at_v;
@@ -362,11 +317,11 @@ impl<'a> CSP2BidirQuery<'a> {
                     }
                 }
 
-                if self.bw_state.min_key().is_none() {
+                // the bw search only covers all paths if it starts at the state of the fw search
+                if self.bw_state.min_key().is_none() && self.driver_state == DriverState::default() {
                     self.fw_finished = true;
                     self.bw_finished = true;
-                } else if self.fw_state.min_key().unwrap() + self.bw_state.min_key().unwrap() >= tentative_distance {
-                    self.fw_finished = true;
+                } else if self.bw_state.min_key().is_none_or(|min_key| min_key >= tentative_distance) {
                     self.bw_finished = true;
                 }
 
@@ -385,13 +340,34 @@ impl<'a> CSP2BidirQuery<'a> {
         Ok(self.last_dist)
     }
 
+    pub fn timeout_run_query(&mut self, timeout: Duration) -> Result<Option<Weight>, QueryTimeoutError> {
+        self.run_query_with_budget(&SearchBudget::unlimited().with_timeout(timeout))
+            .map_err(|_| QueryTimeoutError)
+    }
+
+    pub fn stats(&self) -> QueryStats {
+        QueryStats {
+            distance: self.last_dist,
+            time: self.last_time_elapsed,
+            num_nodes_searched: self.fw_state.get_number_of_visited_nodes() + self.bw_state.get_number_of_visited_nodes(),
+            forward: self.fw_state.stats,
+            backward: self.bw_state.stats,
+            ..QueryStats::default()
+        }
+    }
+
+    /// Labels of both searches, the route is unknown as the query does not unpack its path
+    pub fn search_space(&self) -> SearchSpace {
+        SearchSpace::bidirectional(&self.fw_state, &self.bw_state)
+    }
+
     pub fn summary(&self) -> String {
         let mut s = "\n\nSummary for Bidirectional CSP\n\n".to_string();
 
         if self.s == self.fw_graph.num_nodes() as NodeId
             || self.t == self.bw_graph.num_nodes() as NodeId
-            || self.fw_state.num_settled == 0
-            || self.bw_state.num_settled == 0
+            || self.fw_state.stats.num_settled == 0
+            || self.bw_state.stats.num_settled == 0
         {
             writeln!(s, "No query").unwrap();
             return s;
@@ -435,25 +411,25 @@ impl<'a> CSP2BidirQuery<'a> {
         writeln!(
             s,
             "\tnumber of queue pushes (fw/bw): {}/{}",
-            self.fw_state.num_queue_pushes, self.bw_state.num_queue_pushes
+            self.fw_state.stats.num_queue_pushes, self.bw_state.stats.num_queue_pushes
         )
         .unwrap();
         writeln!(
             s,
             "\tnumber of settled nodes (fw/bw): {}/{}",
-            self.fw_state.num_settled, self.bw_state.num_settled
+            self.fw_state.stats.num_settled, self.bw_state.stats.num_settled
         )
         .unwrap();
         writeln!(
             s,
             "\tnumber of propagated labels (fw/bw): {}/{}",
-            self.fw_state.num_labels_propagated, self.bw_state.num_labels_propagated
+            self.fw_state.stats.num_labels_propagated, self.bw_state.stats.num_labels_propagated
         )
         .unwrap();
         writeln!(
             s,
             "\tnumber of labels which were reset (fw/bw): {}/{}",
-            self.fw_state.num_labels_reset, self.bw_state.num_labels_reset
+            self.fw_state.stats.num_labels_reset, self.bw_state.stats.num_labels_reset
         )
         .unwrap();
 
@@ -551,6 +527,13 @@ impl<'a> CSP2BidirQuery<'a> {
             };
             writeln!(s, "\t  -thereof mean number of labels: {:.2}", mean).unwrap();
         }
+        writeln!(
+            s,
+            "\tlabel memory: {:.2} MiB used, {:.2} MiB allocated",
+            self.fw_state.label_memory() as f64 / 1_048_576.0,
+            self.fw_state.allocated_label_memory() as f64 / 1_048_576.0
+        )
+        .unwrap();
 
         let mut label_sizes_settled = Vec::with_capacity(self.bw_state.per_node_labels.len());
         let mut label_sizes_unsettled = Vec::with_capacity(self.bw_state.per_node_labels.len());
@@ -596,6 +579,13 @@ impl<'a> CSP2BidirQuery<'a> {
             };
             writeln!(s, "\t  -thereof mean number of labels: {:.2}", mean).unwrap();
         }
+        writeln!(
+            s,
+            "\tlabel memory: {:.2} MiB used, {:.2} MiB allocated",
+            self.bw_state.label_memory() as f64 / 1_048_576.0,
+            self.bw_state.allocated_label_memory() as f64 / 1_048_576.0
+        )
+        .unwrap();
         writeln!(s).unwrap();
 
         s
@@ -1,43 +1,63 @@
-use std::rc::Rc;
+use std::{
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
-use crate::{algo::ch_potential::CHPotential, types::*};
+use crate::{algo::ch_potential::CHPotential, index_heap::IndexdMinHeap, priority_queue::IndexedPriorityQueue, types::*, validation::ValidationError};
 use bit_vec::BitVec;
 
 use super::{
     astar::Potential,
+    budget::{QueryAborted, SearchBudget},
     ch::BorrowedContractionHierarchy,
     core_ch::BorrowedCoreContractionHierarchy,
     csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+    search_space::SearchSpace,
+    stats::{QueryStats, SearchStats},
 };
 
-pub struct CSP2AstarCoreCHQuery<'a> {
+pub struct CSP2AstarCoreCHQuery<'a, Q = IndexdMinHeap<State<Weight>>> {
     core_ch: BorrowedCoreContractionHierarchy<'a>,
-    pub fw_state: TwoRestrictionDijkstraData<CHPotential<'a>>,
-    pub bw_state: TwoRestrictionDijkstraData<CHPotential<'a>>,
+    pub fw_state: TwoRestrictionDijkstraData<CHPotential<'a>, Q>,
+    pub bw_state: TwoRestrictionDijkstraData<CHPotential<'a>, Q>,
     fw_finished: bool,
     bw_finished: bool,
     s: NodeId,
     t: NodeId,
+    driver_state: DriverState,
+    required_at_target: RemainingDrivingTime,
     pub restriction_short: DrivingTimeRestriction,
     pub restriction_long: DrivingTimeRestriction,
     is_reset_node: Rc<BitVec>,
     pub last_dist: Option<Weight>,
+    last_time_elapsed: Duration,
+    fw_core_stats: SearchStats,
+    bw_core_stats: SearchStats,
 }
 
 impl<'a> CSP2AstarCoreCHQuery<'a> {
     pub fn new(core_ch: BorrowedCoreContractionHierarchy<'a>, ch: BorrowedContractionHierarchy<'a>) -> Self {
+        Self::with_queue(core_ch, ch)
+    }
+}
+
+impl<'a, Q: IndexedPriorityQueue<State<Weight>>> CSP2AstarCoreCHQuery<'a, Q> {
+    /// Uses `Q` as priority queue of both searches
+    pub fn with_queue(core_ch: BorrowedCoreContractionHierarchy<'a>, ch: BorrowedContractionHierarchy<'a>) -> Self {
         let node_count = core_ch.rank().len();
 
         let node_mapping = core_ch.order().to_owned();
         let is_reset_node = core_ch.is_core().clone();
         CSP2AstarCoreCHQuery {
             core_ch,
-            fw_state: TwoRestrictionDijkstraData::new_with_potential(node_count, CHPotential::from_ch_with_node_mapping(ch, node_mapping.clone())),
-            bw_state: TwoRestrictionDijkstraData::new_with_potential(node_count, CHPotential::from_ch_with_node_mapping_backwards(ch, node_mapping)),
+            fw_state: TwoRestrictionDijkstraData::with_queue(node_count, CHPotential::from_ch_with_node_mapping(ch, node_mapping.clone())),
+            bw_state: TwoRestrictionDijkstraData::with_queue(node_count, CHPotential::from_ch_with_node_mapping_backwards(ch, node_mapping)),
             fw_finished: false,
             bw_finished: false,
             s: node_count as NodeId,
             t: node_count as NodeId,
+            driver_state: DriverState::default(),
+            required_at_target: RemainingDrivingTime::default(),
             restriction_short: DrivingTimeRestriction {
                 pause_time: 0,
                 max_driving_time: Weight::infinity(),
@@ -48,6 +68,9 @@ impl<'a> CSP2AstarCoreCHQuery<'a> {
             },
             is_reset_node,
             last_dist: None,
+            last_time_elapsed: Duration::ZERO,
+            fw_core_stats: SearchStats::default(),
+            bw_core_stats: SearchStats::default(),
         }
     }
 
@@ -97,48 +120,46 @@ impl<'a> CSP2AstarCoreCHQuery<'a> {
     }
 
     /// Equivalent to routingkit's check_contraction_hierarchy_for_errors except the check for only up edges
-    pub fn check(&self) {
-        let node_count = self.core_ch.forward().num_nodes();
-        assert_eq!(self.core_ch.forward().first_out().len(), node_count + 1);
-        assert_eq!(self.core_ch.backward().first_out().len(), node_count + 1);
-
-        let forward_arc_count = *self.core_ch.forward().first_out().last().unwrap();
-
-        assert_eq!(*self.core_ch.forward().first_out().first().unwrap(), 0);
-        assert!(self.core_ch.forward().first_out().is_sorted_by(|l, r| Some(l.cmp(r))));
-        assert_eq!(self.core_ch.forward().head().len(), forward_arc_count as usize);
-        assert_eq!(self.core_ch.forward().weights().len(), forward_arc_count as usize);
-        assert!(!self.core_ch.forward().head().is_empty() && *self.core_ch.forward().head().iter().max().unwrap() < node_count as NodeId);
-
-        let backward_arc_count = *self.core_ch.backward().first_out().last().unwrap();
-        assert_eq!(*self.core_ch.backward().first_out().first().unwrap(), 0);
-        assert!(self.core_ch.backward().first_out().is_sorted_by(|l, r| Some(l.cmp(r))));
-        assert_eq!(self.core_ch.backward().head().len(), backward_arc_count as usize);
-        assert_eq!(self.core_ch.backward().weights().len(), backward_arc_count as usize);
-        assert!(!self.core_ch.backward().head().is_empty() && *self.core_ch.backward().head().iter().max().unwrap() < node_count as NodeId);
+    pub fn check(&self) -> Result<(), ValidationError> {
+        self.core_ch.check()
     }
 
     pub fn init_new_s(&mut self, ext_s: NodeId) {
         self.s = self.core_ch.rank()[ext_s as usize] as NodeId;
     }
 
+    /// Driving times at departure from `s`, kept for all following queries
+    pub fn set_driver_state(&mut self, driver_state: DriverState) {
+        self.driver_state = driver_state;
+    }
+
+    /// Driving time which must be left at `t`, kept for all following queries
+    pub fn set_required_remaining_driving_time(&mut self, remaining: RemainingDrivingTime) {
+        self.required_at_target = remaining;
+        self.fw_state.set_required_remaining_driving_time(&remaining);
+    }
+
     pub fn init_new_t(&mut self, ext_t: NodeId) {
         self.t = self.core_ch.rank()[ext_t as usize] as NodeId;
     }
 
     pub fn reset(&mut self) {
         if self.s != self.core_ch.rank().len() as NodeId {
-            self.fw_state.init_new_s(self.s);
+            self.fw_state.init_new_s_with_driver_state(self.s, &self.driver_state);
             self.bw_state.potential.init_new_t(self.s);
         }
 
         if self.t != self.core_ch.rank().len() as NodeId {
-            self.bw_state.init_new_s(self.t);
+            // the bw search starts with the driving time which must be left at t
+            self.bw_state.init_new_s_with_labels(self.t, &[self.required_at_target.weight3()]);
             self.fw_state.potential.init_new_t(self.t);
         }
 
         self.fw_finished = false;
         self.bw_finished = false;
+        self.last_time_elapsed = Duration::ZERO;
+        self.fw_core_stats = SearchStats::default();
+        self.bw_core_stats = SearchStats::default();
     }
 
     pub fn clean(&mut self) {
@@ -151,16 +172,22 @@ impl<'a> CSP2AstarCoreCHQuery<'a> {
         node: NodeId,
         restriction_short: &DrivingTimeRestriction,
         restriction_long: &DrivingTimeRestriction,
-        fw_label_dist: &Weight3,
-        bw_state: &mut TwoRestrictionDijkstraData<CHPotential>,
+        fw_label: &Label<Weight3>,
+        fw_label_ends_with_break: bool,
+        bw_state: &mut TwoRestrictionDijkstraData<CHPotential, Q>,
     ) -> Weight {
         let v_to_t = bw_state.get_settled_labels_at(node);
 
-        let mut current_bw = v_to_t.rev().map(|r| r.0);
+        let mut current_bw = v_to_t.map(|r| r.0);
 
         let mut best_distance = Weight::infinity();
         while let Some(bw_label) = current_bw.next() {
-            let total_dist = fw_label_dist.add(bw_label.distance);
+            // no break right before departure from s or arrival at t, where one of the labels is a start label
+            if (fw_label_ends_with_break && bw_label.prev_label.is_none()) || (fw_label.prev_label.is_none() && bw_state.ends_with_break(&bw_label)) {
+                continue;
+            }
+
+            let total_dist = fw_label.distance.add(bw_label.distance);
 
             // check if restrictions allows combination of those labels/subpaths
             if total_dist[1] < restriction_short.max_driving_time && total_dist[2] < restriction_long.max_driving_time {
@@ -173,8 +200,15 @@ impl<'a> CSP2AstarCoreCHQuery<'a> {
     }
 
     pub fn run_query(&mut self) -> Option<Weight> {
+        self.run_query_with_budget(&SearchBudget::unlimited())
+            .expect("an unlimited budget never aborts")
+    }
+
+    /// Like `run_query` but stops when the budget is exhausted
+    pub fn run_query_with_budget(&mut self, budget: &SearchBudget) -> Result<Option<Weight>, QueryAborted> {
+        let start = Instant::now();
         if self.s == self.core_ch.rank().len() as NodeId || self.t == self.core_ch.rank().len() as NodeId {
-            return None;
+            return Ok(None);
         }
 
         self.reset();
@@ -194,7 +228,20 @@ impl<'a> CSP2AstarCoreCHQuery<'a> {
             self.bw_finished = true;
         }
 
+        let is_core = self.core_ch.is_core();
+
         while !self.fw_finished || !self.bw_finished {
+            if let Err(reason) = budget.check(
+                self.fw_state.stats.num_settled + self.bw_state.stats.num_settled,
+                self.fw_state.label_memory() + self.bw_state.label_memory(),
+            ) {
+                self.last_time_elapsed = start.elapsed();
+                self.last_dist = None;
+                return Err(QueryAborted::new(reason, tentative_distance));
+            }
+
+            let (fw_before, bw_before) = (self.fw_state.stats, self.bw_state.stats);
+
             if !self.fw_finished && (self.bw_finished || fw_next) {
                 if let Some(State {
                     distance: _dist_from_queue_at_v,
@@ -205,21 +252,41 @@ impl<'a> CSP2AstarCoreCHQuery<'a> {
                     fw_search.settle_next_label(&mut self.fw_state, self.t)
                 } {
                     settled_fw.set(node as usize, true);
+                    if is_core.get(node as usize).unwrap() {
+                        self.fw_core_stats += self.fw_state.stats - fw_before;
+                    }
 
                     // fw search found t -> done here
-                    if node == self.t {
-                        tentative_distance = self.fw_state.get_settled_labels_at(node).last().unwrap().0.distance[0]; // dist_from_queue_at_v[0];
+                    if let (true, Some(last_distance)) = (node == self.t, self.fw_state.last_distance) {
+                        tentative_distance = last_distance;
                         self.fw_finished = true;
                     }
 
-                    if settled_bw.get(node as usize).unwrap() {
-                        let tent_dist_at_v = Self::calculate_distance_with_break_at(
-                            node,
-                            &self.restriction_short,
-                            &self.restriction_long,
-                            &self.fw_state.get_best_label_at(node).unwrap().distance,
-                            &mut self.bw_state,
-                        );
+                    // with a driver state the start label must not be continued by a break at s
+                    if node == self.s && self.driver_state != DriverState::default() {
+                        if settled_bw.get(node as usize).unwrap() {
+                            let tent_dist_at_s = self.bw_state.best_distance_from_driver_state_at(node, &self.driver_state, &self.fw_state);
+                            if tentative_distance > tent_dist_at_s {
+                                tentative_distance = tent_dist_at_s;
+                            }
+                        }
+                    } else if settled_bw.get(node as usize).unwrap() {
+                        // every settled label at v may be the one connecting to the other search
+                        let tent_dist_at_v = self
+                            .fw_state
+                            .get_settled_labels_at(node)
+                            .map(|label| {
+                                Self::calculate_distance_with_break_at(
+                                    node,
+                                    &self.restriction_short,
+                                    &self.restriction_long,
+                                    &label.0,
+                                    self.fw_state.ends_with_break(&label.0),
+                                    &mut self.bw_state,
+                                )
+                            })
+                            .min()
+                            .unwrap_or_else(Weight::infinity);
 
                         if tentative_distance > tent_dist_at_v {
                             tentative_distance = tent_dist_at_v;
@@ -242,21 +309,38 @@ impl<'a> CSP2AstarCoreCHQuery<'a> {
                 bw_search.settle_next_label(&mut self.bw_state, self.s)
             } {
                 settled_bw.set(node as usize, true);
+                if is_core.get(node as usize).unwrap() {
+                    self.bw_core_stats += self.bw_state.stats - bw_before;
+                }
 
-                // bw search found s -> done here
-                if node == self.s {
-                    tentative_distance = self.bw_state.get_settled_labels_at(node).last().unwrap().0.distance[0]; // dist_from_queue_at_v[0];
+                // bw search found s -> done here unless the driver has already been driving at departure
+                if node == self.s && self.driver_state != DriverState::default() {
+                    let tent_dist_at_s = self.bw_state.best_distance_from_driver_state_at(node, &self.driver_state, &self.fw_state);
+                    if tentative_distance > tent_dist_at_s {
+                        tentative_distance = tent_dist_at_s;
+                    }
+                } else if node == self.s {
+                    tentative_distance = self.bw_state.get_settled_labels_at(node).next().unwrap().0.distance[0]; // dist_from_queue_at_v[0];
                     self.bw_finished = true;
                 }
 
-                if settled_fw.get(node as usize).unwrap() {
-                    let tent_dist_at_v = Self::calculate_distance_with_break_at(
-                        node,
-                        &self.restriction_short,
-                        &self.restriction_long,
-                        &self.bw_state.get_best_label_at(node).unwrap().distance,
-                        &mut self.fw_state,
-                    );
+                if settled_fw.get(node as usize).unwrap() && (node != self.s || self.driver_state == DriverState::default()) {
+                    // every settled label at v may be the one connecting to the other search
+                    let tent_dist_at_v = self
+                        .bw_state
+                        .get_settled_labels_at(node)
+                        .map(|label| {
+                            Self::calculate_distance_with_break_at(
+                                node,
+                                &self.restriction_short,
+                                &self.restriction_long,
+                                &label.0,
+                                self.bw_state.ends_with_break(&label.0),
+                                &mut self.fw_state,
+                            )
+                        })
+                        .min()
+                        .unwrap_or_else(Weight::infinity);
 
                     if tentative_distance > tent_dist_at_v {
                         tentative_distance = tent_dist_at_v;
@@ -272,12 +356,32 @@ impl<'a> CSP2AstarCoreCHQuery<'a> {
             }
         }
 
+        self.last_time_elapsed = start.elapsed();
+
         if tentative_distance == Weight::infinity() {
             self.last_dist = None;
-            return None;
+            return Ok(None);
         }
 
         self.last_dist = Some(tentative_distance);
-        self.last_dist
+        Ok(self.last_dist)
+    }
+
+    /// The work of both searches on core nodes is reported as the core phase
+    pub fn stats(&self) -> QueryStats {
+        QueryStats {
+            distance: self.last_dist,
+            time: self.last_time_elapsed,
+            num_nodes_searched: self.fw_state.get_number_of_visited_nodes() + self.bw_state.get_number_of_visited_nodes(),
+            forward: self.fw_state.stats - self.fw_core_stats,
+            backward: self.bw_state.stats - self.bw_core_stats,
+            core: self.fw_core_stats + self.bw_core_stats,
+            path: None,
+        }
+    }
+
+    /// Labels of both searches mapped to node ids, the route is unknown as the query does not unpack its path
+    pub fn search_space(&self) -> SearchSpace {
+        SearchSpace::bidirectional(&self.fw_state, &self.bw_state).with_order(self.core_ch.order())
     }
 }
@@ -0,0 +1,122 @@
+//! Exhaustive reference solver for driving time restricted shortest paths.
+//!
+//! Searches all states `(node, driving times since the last break of each kind)` in order of their total
+//! travel time without any dominance pruning, so its result does not depend on the label management of the
+//! optimized algorithms. The number of states grows with the maximum driving times and the arc weights,
+//! only use it on tiny graphs, e.g. to validate the other algorithms in tests.
+//!
+//! The model is the one of `OneRestrictionDijkstra` and `TwoRestrictionDijkstra`: a break can be taken
+//! whenever a parking node is reached, the driving time since the last break must stay below the maximum
+//! driving time and a break of one kind also counts as a break of all kinds with shorter maximum driving times.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+};
+
+use bit_vec::BitVec;
+
+use crate::types::*;
+
+/// Restrictions are ordered by increasing maximum driving time, e.g. `[short, long]` for the EU rules.
+/// Without restrictions this is a plain Dijkstra search.
+pub fn brute_force_dist(graph: BorrowedGraph, is_parking_node: &BitVec, restrictions: &[DrivingTimeRestriction], s: NodeId, t: NodeId) -> Option<Weight> {
+    brute_force_dist_with_driver_state(graph, is_parking_node, restrictions, s, t, &DriverState::default())
+}
+
+/// Like `brute_force_dist` but the driver departs with the given driving times, the first restriction starts with
+/// the driving time since the last break and the second one with the driving time since the last rest
+pub fn brute_force_dist_with_driver_state(
+    graph: BorrowedGraph,
+    is_parking_node: &BitVec,
+    restrictions: &[DrivingTimeRestriction],
+    s: NodeId,
+    t: NodeId,
+    driver_state: &DriverState,
+) -> Option<Weight> {
+    brute_force_dist_with_remaining_driving_time(graph, is_parking_node, restrictions, s, t, driver_state, &RemainingDrivingTime::default())
+}
+
+/// Like `brute_force_dist_with_driver_state` but the driver must reach `t` without a break there and with at least
+/// `remaining` driving time left for each restriction. A break at `t` is only useful to depart from `t` again.
+pub fn brute_force_dist_with_remaining_driving_time(
+    graph: BorrowedGraph,
+    is_parking_node: &BitVec,
+    restrictions: &[DrivingTimeRestriction],
+    s: NodeId,
+    t: NodeId,
+    driver_state: &DriverState,
+    remaining: &RemainingDrivingTime,
+) -> Option<Weight> {
+    debug_assert!(restrictions.is_sorted_by_key(|r| r.max_driving_time));
+
+    let required = [remaining.until_break, remaining.until_rest];
+    let leaves_remaining = |driving_times: &[Weight]| {
+        driving_times
+            .iter()
+            .zip(required)
+            .zip(restrictions)
+            .all(|((&driven, required), r)| driven + required < r.max_driving_time)
+    };
+
+    // states are (distance, node, driving times, whether the state is an arrival at t)
+    let mut queue = BinaryHeap::new();
+    let mut seen = HashSet::new();
+    let initial_driving_times = [driver_state.driving_time_since_break, driver_state.driving_time_since_rest];
+    let driving_times: Vec<Weight> = restrictions
+        .iter()
+        .zip(initial_driving_times)
+        .map(|(r, driven)| if r.max_driving_time == Weight::infinity() { 0 } else { driven })
+        .collect();
+    if s == t && leaves_remaining(&driving_times) {
+        queue.push(Reverse((driver_state.departure_offset, s, driving_times.clone(), true)));
+    }
+    // with a requirement, a break at t is taken before departing from t again, which includes the departure from s = t
+    if s == t && *remaining != RemainingDrivingTime::default() && is_parking_node.get(s as usize).unwrap_or(false) {
+        for (i, restriction) in restrictions.iter().enumerate() {
+            let mut after_break = driving_times.clone();
+            after_break[..=i].iter_mut().for_each(|d| *d = 0);
+            queue.push(Reverse((driver_state.departure_offset + restriction.pause_time, s, after_break, false)));
+        }
+    }
+    queue.push(Reverse((driver_state.departure_offset, s, driving_times, false)));
+
+    while let Some(Reverse((distance, node, driving_times, is_arrival))) = queue.pop() {
+        if is_arrival {
+            return Some(distance);
+        }
+        if !seen.insert((node, driving_times.clone())) {
+            continue;
+        }
+
+        for (&weight, &head) in graph.outgoing_edge_iter(node).filter(|&(_, &head)| head != node) {
+            // counters of unbounded restrictions stay at zero so that the number of states remains finite
+            let driven: Vec<Weight> = driving_times
+                .iter()
+                .zip(restrictions)
+                .map(|(&driven, r)| if r.max_driving_time == Weight::infinity() { 0 } else { driven + weight })
+                .collect();
+
+            if driven.iter().zip(restrictions).any(|(&driven, r)| driven >= r.max_driving_time) {
+                continue;
+            }
+
+            // a break at t does not count for the remaining driving time
+            if head == t && leaves_remaining(&driven) {
+                queue.push(Reverse((distance + weight, head, driven.clone(), true)));
+            }
+
+            if is_parking_node.get(head as usize).unwrap_or(false) {
+                for (i, restriction) in restrictions.iter().enumerate() {
+                    let mut after_break = driven.clone();
+                    after_break[..=i].iter_mut().for_each(|d| *d = 0);
+                    queue.push(Reverse((distance + weight + restriction.pause_time, head, after_break, false)));
+                }
+            }
+
+            queue.push(Reverse((distance + weight, head, driven, false)));
+        }
+    }
+
+    None
+}
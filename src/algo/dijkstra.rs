@@ -1,14 +1,25 @@
-use crate::{algo::astar::*, index_heap::*, timestamped_vector::TimestampedVector, types::*};
+use std::{mem, time::Instant};
+
+use crate::{
+    algo::{
+        astar::*,
+        budget::{QueryAborted, SearchBudget},
+        observer::{NoObserver, SearchObserver},
+        stats::{QueryStats, SearchStats},
+    },
+    index_heap::*,
+    priority_queue::IndexedPriorityQueue,
+    timestamped_vector::TimestampedVector,
+    types::*,
+};
 
 #[derive(Debug, Clone)]
-pub struct DijkstraData<P = NoPotential> {
-    pub queue: IndexdMinHeap<State<Weight>>,
+pub struct DijkstraData<P = NoPotential, Q = IndexdMinHeap<State<Weight>>> {
+    pub queue: Q,
     pub pred: TimestampedVector<NodeId>,
     pub dist: TimestampedVector<Weight>,
     potential: P,
-    pub num_queue_pushes: u32,
-    pub num_settled: u32,
-    pub num_labels_propagated: u32,
+    pub stats: SearchStats,
     pub settled_nodes_vec: Vec<(NodeId, Weight)>,
     num_nodes: usize,
     s: NodeId,
@@ -16,34 +27,29 @@ pub struct DijkstraData<P = NoPotential> {
 
 impl DijkstraData<NoPotential> {
     pub fn new(num_nodes: usize) -> Self {
-        Self {
-            queue: IndexdMinHeap::new(num_nodes),
-            pred: TimestampedVector::with_size(num_nodes),
-            dist: TimestampedVector::with_size(num_nodes),
-            potential: NoPotential {},
-            num_queue_pushes: 0,
-            num_settled: 0,
-            num_labels_propagated: 0,
-            settled_nodes_vec: Vec::new(),
-            num_nodes,
-            s: num_nodes as NodeId,
-        }
+        Self::new_with_potential(num_nodes, NoPotential {})
     }
 }
 
-impl<P> DijkstraData<P>
+impl<P: Potential> DijkstraData<P> {
+    pub fn new_with_potential(num_nodes: usize, potential: P) -> Self {
+        Self::with_queue(num_nodes, potential)
+    }
+}
+
+impl<P, Q> DijkstraData<P, Q>
 where
     P: Potential,
+    Q: IndexedPriorityQueue<State<Weight>>,
 {
-    pub fn new_with_potential(num_nodes: usize, potential: P) -> Self {
+    /// Uses `Q` as priority queue
+    pub fn with_queue(num_nodes: usize, potential: P) -> Self {
         Self {
-            queue: IndexdMinHeap::new(num_nodes),
+            queue: Q::with_max_index(num_nodes),
             pred: TimestampedVector::with_size(num_nodes),
             dist: TimestampedVector::with_size(num_nodes),
             potential,
-            num_queue_pushes: 0,
-            num_settled: 0,
-            num_labels_propagated: 0,
+            stats: SearchStats::default(),
             settled_nodes_vec: Vec::new(),
             num_nodes,
             s: num_nodes as NodeId,
@@ -71,9 +77,9 @@ where
     }
 
     pub fn reset(&mut self) {
-        self.num_settled = 0;
-        self.num_labels_propagated = 0;
-        self.num_queue_pushes = 0;
+        self.stats = SearchStats::default();
+        self.stats.num_queue_pushes += 1;
+        self.stats.num_potential_evaluations += 1;
         self.settled_nodes_vec.clear();
         self.dist.reset();
         self.pred.reset();
@@ -99,18 +105,53 @@ where
     pub fn min_key(&self) -> Option<Weight> {
         self.queue.peek().map(|s| s.distance)
     }
+
+    /// Estimated bytes of all tentative distances and predecessors set since the last reset
+    pub fn label_memory(&self) -> usize {
+        (self.stats.num_labels_propagated as usize + 1) * (mem::size_of::<Weight>() + mem::size_of::<NodeId>())
+    }
+
+    pub fn get_number_of_visited_nodes(&self) -> usize {
+        self.settled_nodes_vec.len()
+    }
+
+    /// Statistics of the last `dist_query`, all work is done by the forward search
+    pub fn query_stats(&self, distance: Option<Weight>) -> QueryStats {
+        QueryStats {
+            distance,
+            time: self.stats.time,
+            num_nodes_searched: self.get_number_of_visited_nodes(),
+            forward: self.stats,
+            ..QueryStats::default()
+        }
+    }
 }
 
-pub struct Dijkstra<'a> {
+pub struct Dijkstra<'a, O = NoObserver> {
     graph: BorrowedGraph<'a>,
+    observer: O,
 }
 
 impl<'a> Dijkstra<'a> {
     pub fn new(graph: BorrowedGraph<'a>) -> Self {
-        Self { graph }
+        Self::with_observer(graph, NoObserver {})
+    }
+}
+
+impl<'a, O> Dijkstra<'a, O>
+where
+    O: SearchObserver,
+{
+    /// Reports every step of the searches to `observer`
+    pub fn with_observer(graph: BorrowedGraph<'a>, observer: O) -> Self {
+        Self { graph, observer }
     }
 
-    pub fn ranks_only_exponentials<P: Potential>(&self, state: &mut DijkstraData<P>) -> Vec<NodeId> {
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn ranks_only_exponentials<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(&self, state: &mut DijkstraData<P, Q>) -> Vec<NodeId> {
         state.reset();
 
         let log_num_nodes = (state.num_nodes as f32).log2() as usize;
@@ -134,120 +175,175 @@ impl<'a> Dijkstra<'a> {
         rank_order
     }
 
-    pub fn settle_next_node_not_exceeding<P: Potential>(&self, state: &mut DijkstraData<P>, distance_limit: Weight) -> Option<State<Weight>> {
+    pub fn settle_next_node_not_exceeding<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(
+        &self,
+        state: &mut DijkstraData<P, Q>,
+        distance_limit: Weight,
+    ) -> Option<State<Weight>> {
         let dist = &mut state.dist;
         let pred = &mut state.pred;
         let queue = &mut state.queue;
         let pot = &mut state.potential;
 
-        if let Some(next) = queue.pop() {
+        let next = queue.pop();
+        if let Some(next) = next {
             state.settled_nodes_vec.push((next.node, next.distance));
-            state.num_settled += 1;
+            state.stats.num_settled += 1;
             let node_id = next.node;
+            self.observer.label_settled(node_id, &[*dist.get(node_id as usize)]);
 
             for (&edge_weight, &neighbor_node) in self.graph.outgoing_edge_iter(node_id) {
                 let new_dist = dist.get(node_id as usize).link(edge_weight);
 
                 if new_dist >= distance_limit {
+                    self.observer.pruned_by_constraint(neighbor_node, &[new_dist]);
                     continue;
                 }
 
                 if !dist.is_set(neighbor_node as usize) {
-                    state.num_queue_pushes += 1;
+                    state.stats.num_queue_pushes += 1;
+                    state.stats.num_potential_evaluations += 1;
                     queue.push(State {
                         distance: new_dist.link(pot.potential(neighbor_node)),
                         node: neighbor_node,
                     });
-                    state.num_labels_propagated += 1;
+                    state.stats.num_labels_propagated += 1;
                     dist.set(neighbor_node as usize, new_dist);
                     pred.set(neighbor_node as usize, node_id);
+                    self.observer.label_pushed(neighbor_node, node_id, &[new_dist]);
                 } else if new_dist < *dist.get(neighbor_node as usize) {
                     if !queue.contains_index(neighbor_node as usize) {
-                        state.num_queue_pushes += 1;
+                        state.stats.num_queue_pushes += 1;
+                        state.stats.num_potential_evaluations += 1;
                         queue.push(State {
                             distance: new_dist.link(pot.potential(neighbor_node)),
                             node: neighbor_node,
                         });
                     } else {
+                        state.stats.num_potential_evaluations += 1;
                         queue.decrease_key(State {
                             distance: new_dist.link(pot.potential(neighbor_node)),
                             node: neighbor_node,
                         });
                     }
-                    state.num_labels_propagated += 1;
+                    state.stats.num_labels_propagated += 1;
                     dist.set(neighbor_node as usize, new_dist);
                     pred.set(neighbor_node as usize, node_id);
+                    self.observer.label_pushed(neighbor_node, node_id, &[new_dist]);
+                } else {
+                    self.observer.label_dominated(neighbor_node, &[new_dist]);
                 }
             }
-            Some(next)
-        } else {
-            None
         }
+
+        next
     }
 
-    pub fn settle_next_node<P: Potential>(&self, state: &mut DijkstraData<P>) -> Option<State<Weight>> {
+    pub fn settle_next_node<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(&self, state: &mut DijkstraData<P, Q>) -> Option<State<Weight>> {
         let dist = &mut state.dist;
         let pred = &mut state.pred;
         let queue = &mut state.queue;
         let pot = &mut state.potential;
 
-        if let Some(next) = queue.pop() {
+        let next = queue.pop();
+        if let Some(next) = next {
             state.settled_nodes_vec.push((next.node, next.distance));
-            state.num_settled += 1;
+            state.stats.num_settled += 1;
             let node_id = next.node;
+            self.observer.label_settled(node_id, &[*dist.get(node_id as usize)]);
 
             for (&edge_weight, &neighbor_node) in self.graph.outgoing_edge_iter(node_id) {
                 let new_dist = dist.get(node_id as usize).link(edge_weight);
 
                 if !dist.is_set(neighbor_node as usize) {
-                    state.num_queue_pushes += 1;
+                    state.stats.num_queue_pushes += 1;
+                    state.stats.num_potential_evaluations += 1;
                     queue.push(State {
                         distance: new_dist.link(pot.potential(neighbor_node)),
                         node: neighbor_node,
                     });
-                    state.num_labels_propagated += 1;
+                    state.stats.num_labels_propagated += 1;
                     dist.set(neighbor_node as usize, new_dist);
                     pred.set(neighbor_node as usize, node_id);
+                    self.observer.label_pushed(neighbor_node, node_id, &[new_dist]);
                 } else if new_dist < *dist.get(neighbor_node as usize) {
                     if !queue.contains_index(neighbor_node as usize) {
-                        state.num_queue_pushes += 1;
+                        state.stats.num_queue_pushes += 1;
+                        state.stats.num_potential_evaluations += 1;
                         queue.push(State {
                             distance: new_dist.link(pot.potential(neighbor_node)),
                             node: neighbor_node,
                         });
                     } else {
+                        state.stats.num_potential_evaluations += 1;
                         queue.decrease_key(State {
                             distance: new_dist.link(pot.potential(neighbor_node)),
                             node: neighbor_node,
                         });
                     }
-                    state.num_labels_propagated += 1;
+                    state.stats.num_labels_propagated += 1;
                     dist.set(neighbor_node as usize, new_dist);
                     pred.set(neighbor_node as usize, node_id);
+                    self.observer.label_pushed(neighbor_node, node_id, &[new_dist]);
+                } else {
+                    self.observer.label_dominated(neighbor_node, &[new_dist]);
                 }
             }
-            Some(next)
-        } else {
-            None
         }
+
+        next
     }
 
-    pub fn dist_query<P: Potential>(&self, state: &mut DijkstraData<P>, t: NodeId) -> Option<Weight> {
+    pub fn dist_query<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(&self, state: &mut DijkstraData<P, Q>, t: NodeId) -> Option<Weight> {
+        let start = Instant::now();
         state.reset();
         state.potential.init_new_t(t);
 
+        let mut result = None;
         while let Some(State { distance: _, node }) = self.settle_next_node(state) {
             if node == t {
-                return Some(*state.dist.get(node as usize));
+                result = Some(*state.dist.get(node as usize));
+                break;
             }
         }
 
-        None
+        state.stats.time = start.elapsed();
+        result
     }
 
-    pub fn to_all<P: Potential>(&self, state: &mut DijkstraData<P>) {
+    /// Like `dist_query` but stops when the budget is exhausted
+    pub fn dist_query_with_budget<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(
+        &self,
+        state: &mut DijkstraData<P, Q>,
+        t: NodeId,
+        budget: &SearchBudget,
+    ) -> Result<Option<Weight>, QueryAborted> {
+        let start = Instant::now();
+        state.reset();
+        state.potential.init_new_t(t);
+
+        let result = loop {
+            if let Err(reason) = budget.check(state.stats.num_settled, state.label_memory()) {
+                break Err(QueryAborted::new(reason, state.tentative_distance_at(t)));
+            }
+
+            match self.settle_next_node(state) {
+                Some(State { distance: _, node }) if node == t => break Ok(Some(*state.dist.get(node as usize))),
+                Some(_) => (),
+                None => break Ok(None),
+            }
+        };
+
+        state.stats.time = start.elapsed();
+        result
+    }
+
+    pub fn to_all<P: Potential, Q: IndexedPriorityQueue<State<Weight>>>(&self, state: &mut DijkstraData<P, Q>) {
+        let start = Instant::now();
         state.reset();
 
         while self.settle_next_node(state).is_some() {}
+
+        state.stats.time = start.elapsed();
     }
 }
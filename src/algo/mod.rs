@@ -1,4 +1,6 @@
+pub mod alternatives;
 pub mod astar;
+pub mod budget;
 pub mod ch;
 pub mod ch_potential;
 pub mod core_ch;
@@ -11,7 +13,102 @@ pub mod csp_2_core_ch_chpot;
 pub mod csp_2_core_ch_chpot_no_bw;
 pub mod csp_bidir;
 pub mod csp_bidir_chpot;
+pub mod csp_brute_force;
 pub mod csp_core_ch;
 pub mod csp_core_ch_chpot;
 pub mod csp_core_ch_chpot_no_bw;
 pub mod dijkstra;
+pub mod multi_stop;
+pub mod observer;
+pub mod search_space;
+pub mod stats;
+pub mod vrptw;
+
+use crate::{
+    algo::{
+        budget::{QueryAborted, SearchBudget},
+        search_space::SearchSpace,
+        stats::QueryStats,
+    },
+    osm_id_mapper::{OSMIDMapper, OSMNodeId, UnknownOSMIDError},
+    priority_queue::IndexedPriorityQueue,
+    types::*,
+};
+
+/// The common interface of all point to point queries. Source and target are node ids of the original graph.
+pub trait PointToPointQuery {
+    fn init_new_s(&mut self, s: NodeId);
+    fn init_new_t(&mut self, t: NodeId);
+    fn run_query(&mut self) -> Option<Weight>;
+    /// Like `run_query` but stops when the budget is exhausted, `stats` and `search_space` then describe the partial search
+    fn run_query_with_budget(&mut self, budget: &SearchBudget) -> Result<Option<Weight>, QueryAborted>;
+    /// Statistics of the last `run_query`
+    fn stats(&self) -> QueryStats;
+    /// Labels of the last `run_query` in node ids of the original graph
+    fn search_space(&self) -> SearchSpace;
+
+    /// Like `init_new_s` but with the OSM id of the source
+    fn init_new_s_osm<M: OSMIDMapper>(&mut self, mapper: &M, s: OSMNodeId) -> Result<(), UnknownOSMIDError>
+    where
+        Self: Sized,
+    {
+        self.init_new_s(mapper.try_to_local(s)?);
+        Ok(())
+    }
+
+    /// Like `init_new_t` but with the OSM id of the target
+    fn init_new_t_osm<M: OSMIDMapper>(&mut self, mapper: &M, t: OSMNodeId) -> Result<(), UnknownOSMIDError>
+    where
+        Self: Sized,
+    {
+        self.init_new_t(mapper.try_to_local(t)?);
+        Ok(())
+    }
+}
+
+macro_rules! impl_point_to_point_query {
+    ($($query:ty),*) => {
+        $(
+            impl<'a, Q: IndexedPriorityQueue<State<Weight>>> PointToPointQuery for $query {
+                fn init_new_s(&mut self, s: NodeId) {
+                    <$query>::init_new_s(self, s)
+                }
+
+                fn init_new_t(&mut self, t: NodeId) {
+                    <$query>::init_new_t(self, t)
+                }
+
+                fn run_query(&mut self) -> Option<Weight> {
+                    <$query>::run_query(self)
+                }
+
+                fn run_query_with_budget(&mut self, budget: &SearchBudget) -> Result<Option<Weight>, QueryAborted> {
+                    <$query>::run_query_with_budget(self, budget)
+                }
+
+                fn stats(&self) -> QueryStats {
+                    <$query>::stats(self)
+                }
+
+                fn search_space(&self) -> SearchSpace {
+                    <$query>::search_space(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_point_to_point_query!(
+    ch::ContractionHierarchyQuery<'a, Q>,
+    core_ch::CoreContractionHierarchyQuery<'a, Q>,
+    csp_bidir::CSPBidirQuery<'a, Q>,
+    csp_bidir_chpot::CSPBidirAstarCHPotQuery<'a, Q>,
+    csp_core_ch::CSPCoreCHQuery<'a, Q>,
+    csp_core_ch_chpot::CSPAstarCoreCHQuery<'a, Q>,
+    csp_core_ch_chpot_no_bw::CSPAstarCoreCHQueryNoBwNoPrune<'a, Q>,
+    csp_2_bidir::CSP2BidirQuery<'a, Q>,
+    csp_2_bidir_chpot::CSP2BidirAstarCHPotQuery<'a, Q>,
+    csp_2_core_ch::CSP2CoreCHQuery<'a, Q>,
+    csp_2_core_ch_chpot::CSP2AstarCoreCHQuery<'a, Q>,
+    csp_2_core_ch_chpot_no_bw::CSP2AstarCoreCHQueryNoBwNoPrune<'a, Q>
+);
@@ -0,0 +1,536 @@
+//! Trips along a fixed sequence of stops whose driving times carry over from one leg to the next.
+//!
+//! The legs are searched one after another. The search of a leg starts with all labels which reached the previous
+//! stop, delayed by its service time, so the breaks are not placed per leg but for the whole trip. At an intermediate
+//! stop which is a parking node labels are collected until the queue passes the best of them by the longest pause
+//! time, later labels are dominated by taking a break at the stop. At any other intermediate stop no break makes up
+//! for more driving time, so the search of the leg runs until its queue is empty without target pruning and all
+//! Pareto optimal labels at the stop are kept. The break placement is therefore optimal for the whole trip.
+//!
+//! Service times count as travel time but not as driving time and do not replace a break.
+//!
+//! `MultiStopCoreCHQuery` searches the legs on a core CH. Its forward search runs on the upward and core arcs and
+//! continues the labels to the next stop on the shortest downward path, on which no break can be taken because all
+//! parking nodes have to be in the core. Like the core CH queries it does not unpack paths, so only the travel time of the trip is
+//! known.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use rust_truck_router::{algo::multi_stop::*, query::RoutingData, types::*};
+//! # fn example(data: &RoutingData) {
+//! let mut query = MultiStopQuery::new(data.graph.borrow(), &data.is_parking_node);
+//! query.set_restrictions(&[EU_SHORT]);
+//! let stops = [Stop::new(0), Stop { node: 17, service_time: 1_800_000 }, Stop::new(42)];
+//! if let Some(trip) = query.run(&stops) {
+//!     for visit in &trip.stops {
+//!         println!("stop {} reached after {} ms", visit.node, visit.arrival);
+//!     }
+//! }
+//! # }
+//! ```
+
+use bit_vec::BitVec;
+
+use crate::types::*;
+
+use super::{
+    alternatives::{planned_break, PlannedBreak},
+    astar::Potential,
+    core_ch::BorrowedCoreContractionHierarchy,
+    csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+    csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+    dijkstra::{Dijkstra, DijkstraData},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stop {
+    pub node: NodeId,
+    /// Time spent at the stop, e.g. for unloading
+    pub service_time: Weight,
+}
+
+impl Stop {
+    /// A stop without service time
+    pub fn new(node: NodeId) -> Self {
+        Self { node, service_time: 0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StopVisit {
+    pub node: NodeId,
+    /// Position of the stop in the path of the trip
+    pub path_index: usize,
+    /// Time since departure when the stop is reached
+    pub arrival: Weight,
+    /// Time since departure when the stop is left, after a break there and the service time
+    pub departure: Weight,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiStopTrip {
+    pub path: Vec<NodeId>,
+    /// Travel time including breaks and service times
+    pub distance: Weight,
+    pub driving_time: Weight,
+    pub breaks: Vec<PlannedBreak>,
+    /// One visit per stop, in the order of the stops
+    pub stops: Vec<StopVisit>,
+}
+
+/// A label at the end of a leg
+struct LegLabel {
+    /// Index of the label at the start of the leg it was reached from
+    start: usize,
+    path: Vec<NodeId>,
+    /// Distances of the labels along the path, `[distance, driving time since the break of every restriction...]`
+    times: Vec<Vec<Weight>>,
+}
+
+/// The labels at the end of a leg with which the trip continues
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeptLabels {
+    /// The best label, at the last stop
+    Best,
+    /// The Pareto optimal labels which are at most the given slack longer than the best one. At a parking node later
+    /// labels are dominated by the best one followed by a break.
+    WithinSlack(Weight),
+    /// All Pareto optimal labels, at stops without parking where no break can make up for more driving time.
+    /// The leg is searched without target pruning until its queue is empty, so it settles the labels of every node
+    /// reachable from the previous stop and is by far the most expensive kind of leg.
+    All,
+}
+
+/// The labels kept at the end of every leg of the trip along `stops`
+fn kept_labels(stops: &[Stop], is_parking_stop: impl Fn(NodeId) -> bool, restrictions: &[DrivingTimeRestriction]) -> Vec<KeptLabels> {
+    let slack = restrictions.iter().map(|r| r.pause_time).max().unwrap_or(0);
+    stops[1..]
+        .iter()
+        .enumerate()
+        .map(|(i, stop)| {
+            if i + 2 == stops.len() {
+                KeptLabels::Best
+            } else if is_parking_stop(stop.node) {
+                KeptLabels::WithinSlack(slack)
+            } else {
+                KeptLabels::All
+            }
+        })
+        .collect()
+}
+
+/// Runs the legs one after another, each starting with the labels at the end of the previous one delayed by the
+/// service time. `None` if a stop can not be reached.
+fn run_legs(
+    stops: &[Stop],
+    kept: &[KeptLabels],
+    mut run_leg: impl FnMut(NodeId, NodeId, &[Vec<Weight>], KeptLabels) -> Vec<LegLabel>,
+) -> Option<Vec<Vec<LegLabel>>> {
+    let mut legs: Vec<Vec<LegLabel>> = Vec::with_capacity(stops.len() - 1);
+    for (leg, &kept) in stops.windows(2).zip(kept) {
+        let mut starts: Vec<Vec<Weight>> = match legs.last() {
+            Some(labels) => labels.iter().map(|label| label.times.last().unwrap().clone()).collect(),
+            None => vec![vec![0; 3]],
+        };
+        for start in &mut starts {
+            start[0] += leg[0].service_time;
+        }
+
+        let labels = run_leg(leg[0].node, leg[1].node, &starts, kept);
+        if labels.is_empty() {
+            return None;
+        }
+        legs.push(labels);
+    }
+    Some(legs)
+}
+
+/// The label setting search of a leg, implemented by the search data of one and of two restrictions
+trait LegSearch {
+    /// Starts a search from `starts` at `s`, `[distance, driving time since the break of every restriction...]`.
+    /// Target pruning at `t` is only enabled if the trip does not continue with labels dominated at `t`.
+    fn init_leg(&mut self, s: NodeId, t: NodeId, starts: &[Vec<Weight>], restrictions: &[DrivingTimeRestriction], prune_at_target: bool);
+    fn settle_next_label(&mut self, graph: BorrowedGraph, is_parking_node: &BitVec, t: NodeId) -> Option<NodeId>;
+    fn min_key(&self) -> Option<Weight>;
+    /// Distances of the settled labels at `node`, best first
+    fn settled_labels_at(&self, node: NodeId) -> Vec<Vec<Weight>>;
+    /// Path from a start label to the settled label at `node` with the given index and the distances along it
+    fn path_to_settled_label(&self, node: NodeId, index: usize) -> (Vec<NodeId>, Vec<Vec<Weight>>);
+}
+
+impl<P: Potential> LegSearch for OneRestrictionDijkstraData<P> {
+    fn init_leg(&mut self, s: NodeId, t: NodeId, starts: &[Vec<Weight>], restrictions: &[DrivingTimeRestriction], prune_at_target: bool) {
+        match restrictions.first() {
+            Some(r) => self.set_restriction(r.max_driving_time, r.pause_time),
+            None => self.clear_restriction(),
+        };
+        self.set_target_pruning(prune_at_target);
+        self.potential.init_new_t(t);
+        self.last_t = t;
+        let starts: Vec<Weight2> = starts.iter().map(|d| [d[0], d[1]]).collect();
+        self.init_new_s_with_labels(s, &starts);
+    }
+
+    fn settle_next_label(&mut self, graph: BorrowedGraph, is_parking_node: &BitVec, t: NodeId) -> Option<NodeId> {
+        OneRestrictionDijkstra::new(graph, is_parking_node)
+            .settle_next_label(self, t)
+            .map(|state| state.node)
+    }
+
+    fn min_key(&self) -> Option<Weight> {
+        OneRestrictionDijkstraData::min_key(self)
+    }
+
+    fn settled_labels_at(&self, node: NodeId) -> Vec<Vec<Weight>> {
+        self.get_settled_labels_at(node).map(|label| label.0.distance.to_vec()).collect()
+    }
+
+    fn path_to_settled_label(&self, node: NodeId, index: usize) -> (Vec<NodeId>, Vec<Vec<Weight>>) {
+        let label = self.get_settled_labels_at(node).nth(index).unwrap();
+        let (path, times) = self.path_to_label(node, label, true);
+        (path, times.iter().map(|time| time.to_vec()).collect())
+    }
+}
+
+impl<P: Potential> LegSearch for TwoRestrictionDijkstraData<P> {
+    fn init_leg(&mut self, s: NodeId, t: NodeId, starts: &[Vec<Weight>], restrictions: &[DrivingTimeRestriction], prune_at_target: bool) {
+        let (short, long) = (restrictions[0], restrictions[1]);
+        self.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+        self.set_target_pruning(prune_at_target);
+        self.potential.init_new_t(t);
+        self.last_t = t;
+        let starts: Vec<Weight3> = starts.iter().map(|d| [d[0], d[1], d[2]]).collect();
+        self.init_new_s_with_labels(s, &starts);
+    }
+
+    fn settle_next_label(&mut self, graph: BorrowedGraph, is_parking_node: &BitVec, t: NodeId) -> Option<NodeId> {
+        TwoRestrictionDijkstra::new(graph, is_parking_node)
+            .settle_next_label(self, t)
+            .map(|state| state.node)
+    }
+
+    fn min_key(&self) -> Option<Weight> {
+        TwoRestrictionDijkstraData::min_key(self)
+    }
+
+    fn settled_labels_at(&self, node: NodeId) -> Vec<Vec<Weight>> {
+        self.get_settled_labels_at(node).map(|label| label.0.distance.to_vec()).collect()
+    }
+
+    fn path_to_settled_label(&self, node: NodeId, index: usize) -> (Vec<NodeId>, Vec<Vec<Weight>>) {
+        let label = self.get_settled_labels_at(node).nth(index).unwrap();
+        let (path, times) = self.path_to_label(node, label, true);
+        (path, times.iter().map(|time| time.to_vec()).collect())
+    }
+}
+
+/// A leg from `s` to `t` which starts with the labels `starts`
+struct Leg<'s> {
+    s: NodeId,
+    t: NodeId,
+    starts: &'s [Vec<Weight>],
+    kept: KeptLabels,
+}
+
+fn dominates(a: &[Weight], b: &[Weight]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x <= y)
+}
+
+/// Runs the search of `leg` on `state`. `to_t` is the distance from a node to `t` on which no break can be taken,
+/// `Some(0)` at `t`. The labels settled at those nodes are continued to `t`, paths end there with a last arc to `t`.
+fn leg_labels<S: LegSearch>(
+    state: &mut S,
+    graph: BorrowedGraph,
+    is_parking_node: &BitVec,
+    restrictions: &[DrivingTimeRestriction],
+    leg: Leg,
+    to_t: impl Fn(NodeId) -> Option<Weight>,
+) -> Vec<LegLabel> {
+    let Leg { s, t, starts, kept } = leg;
+    state.init_leg(s, t, starts, restrictions, kept != KeptLabels::All);
+
+    // the label continued to `t`, unless it exceeds a maximum driving time on the way
+    let arrival = |times: &[Weight], to_t: Weight| -> Option<Vec<Weight>> {
+        let arrival: Vec<Weight> = times.iter().map(|time| time + to_t).collect();
+        restrictions
+            .iter()
+            .zip(&arrival[1..])
+            .all(|(r, &driving_time)| driving_time < r.max_driving_time)
+            .then_some(arrival)
+    };
+
+    let mut reached = Vec::new();
+    let mut best_at_t: Option<Weight> = None;
+    while let Some(node) = state.settle_next_label(graph, is_parking_node, t) {
+        if let Some(to_t) = to_t(node) {
+            reached.push(node);
+            best_at_t = state
+                .settled_labels_at(node)
+                .iter()
+                .filter_map(|times| arrival(times, to_t))
+                .map(|times| times[0])
+                .chain(best_at_t)
+                .min();
+        }
+        let finished = match (best_at_t, kept) {
+            (Some(best), KeptLabels::Best) => state.min_key().is_none_or(|key| key >= best),
+            (Some(best), KeptLabels::WithinSlack(slack)) => state.min_key().is_none_or(|key| key > best + slack),
+            _ => false,
+        };
+        if finished {
+            break;
+        }
+    }
+
+    reached.sort_unstable();
+    reached.dedup();
+    let candidates: Vec<(NodeId, usize, Vec<Weight>)> = reached
+        .iter()
+        .flat_map(|&node| {
+            let to_t = to_t(node).unwrap();
+            state
+                .settled_labels_at(node)
+                .into_iter()
+                .enumerate()
+                .filter_map(move |(index, times)| arrival(&times, to_t).map(|times| (node, index, times)))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let targets: Vec<&(NodeId, usize, Vec<Weight>)> = if kept == KeptLabels::Best {
+        candidates.iter().min_by_key(|(_, _, times)| times[0]).into_iter().collect()
+    } else {
+        candidates
+            .iter()
+            .enumerate()
+            .filter(|&(i, (_, _, times))| {
+                !candidates
+                    .iter()
+                    .enumerate()
+                    .any(|(j, (_, _, other))| dominates(other, times) && (other != times || j < i))
+            })
+            .map(|(_, candidate)| candidate)
+            .collect()
+    };
+
+    targets
+        .into_iter()
+        .map(|(node, index, arrival)| {
+            let (mut path, mut times) = state.path_to_settled_label(*node, *index);
+            if *node != t {
+                path.push(t);
+                times.push(arrival.clone());
+            }
+            LegLabel {
+                start: starts.iter().position(|start| start.starts_with(&times[0])).unwrap(),
+                path,
+                times,
+            }
+        })
+        .collect()
+}
+
+pub struct MultiStopQuery<'a> {
+    graph: BorrowedGraph<'a>,
+    is_parking_node: &'a BitVec,
+    restrictions: Vec<DrivingTimeRestriction>,
+    csp_state: OneRestrictionDijkstraData,
+    csp_2_state: TwoRestrictionDijkstraData,
+}
+
+impl<'a> MultiStopQuery<'a> {
+    pub fn new(graph: BorrowedGraph<'a>, is_parking_node: &'a BitVec) -> Self {
+        let n = graph.num_nodes();
+        Self {
+            graph,
+            is_parking_node,
+            restrictions: Vec::new(),
+            csp_state: OneRestrictionDijkstraData::new(n),
+            csp_2_state: TwoRestrictionDijkstraData::new(n),
+        }
+    }
+
+    /// At most two restrictions ordered by increasing maximum driving time, e.g. `[short, long]` for the EU rules
+    pub fn set_restrictions(&mut self, restrictions: &[DrivingTimeRestriction]) {
+        assert!(restrictions.len() <= 2, "at most two restrictions are supported");
+        assert!(restrictions.is_sorted_by_key(|r| r.max_driving_time));
+        self.restrictions = restrictions.to_vec();
+    }
+
+    pub fn clear_restrictions(&mut self) {
+        self.restrictions.clear();
+    }
+
+    /// The fastest trip visiting the stops in the given order, `None` if a stop can not be reached
+    pub fn run(&mut self, stops: &[Stop]) -> Option<MultiStopTrip> {
+        assert!(stops.len() >= 2, "a trip needs at least two stops");
+        let kept = kept_labels(stops, |node| self.is_parking_node[node as usize], &self.restrictions);
+        let legs = run_legs(stops, &kept, |s, t, starts, kept| self.run_leg(Leg { s, t, starts, kept }))?;
+
+        // the last leg ends with the best label only
+        let mut chosen = Vec::with_capacity(legs.len());
+        let mut index = 0;
+        for labels in legs.iter().rev() {
+            chosen.push(&labels[index]);
+            index = labels[index].start;
+        }
+        chosen.reverse();
+
+        let mut path = vec![stops[0].node];
+        let mut times = vec![chosen[0].times[0].clone()];
+        let mut visits = vec![StopVisit {
+            node: stops[0].node,
+            path_index: 0,
+            arrival: 0,
+            departure: stops[0].service_time,
+        }];
+        for (leg, stop) in chosen.iter().zip(&stops[1..]) {
+            path.extend_from_slice(&leg.path[1..]);
+            times.extend_from_slice(&leg.times[1..]);
+
+            let time = times.last().unwrap();
+            let break_at_stop = if self.is_parking_node[stop.node as usize] {
+                planned_break(stop.node, time, &self.restrictions)
+            } else {
+                None
+            };
+            visits.push(StopVisit {
+                node: stop.node,
+                path_index: path.len() - 1,
+                arrival: time[0] - break_at_stop.map_or(0, |b| b.duration),
+                departure: time[0] + stop.service_time,
+            });
+        }
+
+        let breaks: Vec<PlannedBreak> = (1..path.len())
+            .filter(|&i| self.is_parking_node[path[i] as usize])
+            .filter_map(|i| planned_break(path[i], &times[i], &self.restrictions))
+            .collect();
+        let distance = visits.last().unwrap().departure;
+        let driving_time = distance - breaks.iter().map(|b| b.duration).sum::<Weight>() - stops.iter().map(|s| s.service_time).sum::<Weight>();
+
+        Some(MultiStopTrip {
+            path,
+            distance,
+            driving_time,
+            breaks,
+            stops: visits,
+        })
+    }
+
+    fn run_leg(&mut self, leg: Leg) -> Vec<LegLabel> {
+        let t = leg.t;
+        let to_t = |node| (node == t).then_some(0);
+        if self.restrictions.len() < 2 {
+            leg_labels(&mut self.csp_state, self.graph, self.is_parking_node, &self.restrictions, leg, to_t)
+        } else {
+            leg_labels(&mut self.csp_2_state, self.graph, self.is_parking_node, &self.restrictions, leg, to_t)
+        }
+    }
+}
+
+/// Like `MultiStopQuery` but the legs are searched on a core CH, see the module documentation
+pub struct MultiStopCoreCHQuery<'a> {
+    core_ch: BorrowedCoreContractionHierarchy<'a>,
+    /// Parking nodes by rank
+    is_parking_node: BitVec,
+    /// The backward graph without the arcs leaving core nodes, its search from `t` finds the downward paths to `t`
+    down_to_t: OwnedGraph,
+    down_state: DijkstraData,
+    restrictions: Vec<DrivingTimeRestriction>,
+    csp_state: OneRestrictionDijkstraData,
+    csp_2_state: TwoRestrictionDijkstraData,
+}
+
+impl<'a> MultiStopCoreCHQuery<'a> {
+    /// Panics if a parking node is not a core node, breaks could then be needed on the downward paths
+    pub fn new(core_ch: BorrowedCoreContractionHierarchy<'a>, is_parking_node: &BitVec) -> Self {
+        let n = core_ch.rank().len();
+        let is_core = core_ch.is_core();
+        let mut ranked_parking_node = BitVec::from_elem(n, false);
+        for (node, _) in is_parking_node.iter().enumerate().filter(|(_, b)| *b) {
+            let rank = core_ch.rank()[node] as usize;
+            assert!(is_core[rank], "parking node {} is not a core node", node);
+            ranked_parking_node.set(rank, true);
+        }
+
+        let backward = core_ch.backward();
+        let mut first_out = vec![0];
+        let (mut head, mut weight) = (Vec::new(), Vec::new());
+        for node in 0..n as NodeId {
+            if !is_core[node as usize] {
+                for (&w, &h) in backward.outgoing_edge_iter(node) {
+                    head.push(h);
+                    weight.push(w);
+                }
+            }
+            first_out.push(head.len() as EdgeId);
+        }
+
+        Self {
+            core_ch,
+            is_parking_node: ranked_parking_node,
+            down_to_t: OwnedGraph::new(first_out, head, weight),
+            down_state: DijkstraData::new(n),
+            restrictions: Vec::new(),
+            csp_state: OneRestrictionDijkstraData::new(n),
+            csp_2_state: TwoRestrictionDijkstraData::new(n),
+        }
+    }
+
+    /// At most two restrictions ordered by increasing maximum driving time, e.g. `[short, long]` for the EU rules
+    pub fn set_restrictions(&mut self, restrictions: &[DrivingTimeRestriction]) {
+        assert!(restrictions.len() <= 2, "at most two restrictions are supported");
+        assert!(restrictions.is_sorted_by_key(|r| r.max_driving_time));
+        self.restrictions = restrictions.to_vec();
+    }
+
+    pub fn clear_restrictions(&mut self) {
+        self.restrictions.clear();
+    }
+
+    /// Travel time of the fastest trip visiting the stops in the given order including breaks and service times,
+    /// `None` if a stop can not be reached
+    pub fn run(&mut self, stops: &[Stop]) -> Option<Weight> {
+        assert!(stops.len() >= 2, "a trip needs at least two stops");
+        let stops: Vec<Stop> = stops
+            .iter()
+            .map(|stop| Stop {
+                node: self.core_ch.rank()[stop.node as usize],
+                ..*stop
+            })
+            .collect();
+        let kept = kept_labels(&stops, |node| self.is_parking_node[node as usize], &self.restrictions);
+        let legs = run_legs(&stops, &kept, |s, t, starts, kept| self.run_leg(Leg { s, t, starts, kept }))?;
+
+        Some(legs.last().unwrap()[0].times.last().unwrap()[0] + stops.last().unwrap().service_time)
+    }
+
+    fn run_leg(&mut self, leg: Leg) -> Vec<LegLabel> {
+        self.down_state.init_new_s(leg.t);
+        Dijkstra::new(self.down_to_t.borrow()).to_all(&mut self.down_state);
+        let down_state = &self.down_state;
+        let to_t = |node: NodeId| down_state.dist.is_set(node as usize).then(|| down_state.tentative_distance_at(node));
+
+        if self.restrictions.len() < 2 {
+            leg_labels(
+                &mut self.csp_state,
+                self.core_ch.forward(),
+                &self.is_parking_node,
+                &self.restrictions,
+                leg,
+                to_t,
+            )
+        } else {
+            leg_labels(
+                &mut self.csp_2_state,
+                self.core_ch.forward(),
+                &self.is_parking_node,
+                &self.restrictions,
+                leg,
+                to_t,
+            )
+        }
+    }
+}
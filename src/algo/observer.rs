@@ -0,0 +1,184 @@
+//! Hooks into the searches for instrumentation and debugging.
+//!
+//! `Dijkstra`, `OneRestrictionDijkstra` and `TwoRestrictionDijkstra` report every step to a `SearchObserver` given to
+//! `with_observer`. Their `new` uses `NoObserver` whose empty callbacks are optimized away.
+//!
+//! Distances are passed with all their components: `[distance]` for `Dijkstra`, `[distance, driving time since the
+//! last break]` for `OneRestrictionDijkstra` and additionally the driving time since the last long break for
+//! `TwoRestrictionDijkstra`.
+
+use std::cell::{Ref, RefCell};
+
+use crate::types::*;
+
+/// Callbacks of a search, all of them do nothing by default.
+///
+/// The searches are shared between queries and only borrowed while settling, so the callbacks take `&self`.
+/// Observers keeping a record use a `Cell` or `RefCell`, see `SearchLog`.
+pub trait SearchObserver {
+    /// A label at `node` was taken from the queue
+    #[inline(always)]
+    fn label_settled(&self, _node: NodeId, _distance: &[Weight]) {}
+
+    /// A new label at `node` reached from `prev_node` was added to the labels of `node`
+    #[inline(always)]
+    fn label_pushed(&self, _node: NodeId, _prev_node: NodeId, _distance: &[Weight]) {}
+
+    /// A label at `node` was discarded, either a new label dominated by an existing one or an existing one dominated by a new label
+    #[inline(always)]
+    fn label_dominated(&self, _node: NodeId, _distance: &[Weight]) {}
+
+    /// A new label at `node` was discarded as it exceeds a maximum driving time or the distance limit of `Dijkstra`
+    #[inline(always)]
+    fn pruned_by_constraint(&self, _node: NodeId, _distance: &[Weight]) {}
+
+    /// A new label at `node` was discarded as it is dominated by a label at the target
+    #[inline(always)]
+    fn pruned_by_target(&self, _node: NodeId, _distance: &[Weight]) {}
+
+    /// A new label at `node` was discarded as the lower bound of the backward search shows it cannot improve the tentative distance
+    #[inline(always)]
+    fn pruned_by_bw_lower_bound(&self, _node: NodeId, _distance: &[Weight]) {}
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoObserver {}
+
+impl SearchObserver for NoObserver {}
+
+/// Lets the caller keep the observer and look at it after the search
+impl<O: SearchObserver + ?Sized> SearchObserver for &O {
+    #[inline(always)]
+    fn label_settled(&self, node: NodeId, distance: &[Weight]) {
+        (**self).label_settled(node, distance)
+    }
+
+    #[inline(always)]
+    fn label_pushed(&self, node: NodeId, prev_node: NodeId, distance: &[Weight]) {
+        (**self).label_pushed(node, prev_node, distance)
+    }
+
+    #[inline(always)]
+    fn label_dominated(&self, node: NodeId, distance: &[Weight]) {
+        (**self).label_dominated(node, distance)
+    }
+
+    #[inline(always)]
+    fn pruned_by_constraint(&self, node: NodeId, distance: &[Weight]) {
+        (**self).pruned_by_constraint(node, distance)
+    }
+
+    #[inline(always)]
+    fn pruned_by_target(&self, node: NodeId, distance: &[Weight]) {
+        (**self).pruned_by_target(node, distance)
+    }
+
+    #[inline(always)]
+    fn pruned_by_bw_lower_bound(&self, node: NodeId, distance: &[Weight]) {
+        (**self).pruned_by_bw_lower_bound(node, distance)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchEvent {
+    LabelSettled { node: NodeId, distance: Vec<Weight> },
+    LabelPushed { node: NodeId, prev_node: NodeId, distance: Vec<Weight> },
+    LabelDominated { node: NodeId, distance: Vec<Weight> },
+    PrunedByConstraint { node: NodeId, distance: Vec<Weight> },
+    PrunedByTarget { node: NodeId, distance: Vec<Weight> },
+    PrunedByBwLowerBound { node: NodeId, distance: Vec<Weight> },
+}
+
+impl SearchEvent {
+    pub fn node(&self) -> NodeId {
+        match self {
+            SearchEvent::LabelSettled { node, .. }
+            | SearchEvent::LabelPushed { node, .. }
+            | SearchEvent::LabelDominated { node, .. }
+            | SearchEvent::PrunedByConstraint { node, .. }
+            | SearchEvent::PrunedByTarget { node, .. }
+            | SearchEvent::PrunedByBwLowerBound { node, .. } => *node,
+        }
+    }
+
+    pub fn distance(&self) -> &[Weight] {
+        match self {
+            SearchEvent::LabelSettled { distance, .. }
+            | SearchEvent::LabelPushed { distance, .. }
+            | SearchEvent::LabelDominated { distance, .. }
+            | SearchEvent::PrunedByConstraint { distance, .. }
+            | SearchEvent::PrunedByTarget { distance, .. }
+            | SearchEvent::PrunedByBwLowerBound { distance, .. } => distance,
+        }
+    }
+}
+
+/// Records every event in order, meant for debugging small searches
+#[derive(Debug, Default)]
+pub struct SearchLog {
+    events: RefCell<Vec<SearchEvent>>,
+}
+
+impl SearchLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> Ref<'_, Vec<SearchEvent>> {
+        self.events.borrow()
+    }
+
+    /// Returns the events recorded so far and starts a new log
+    pub fn take_events(&self) -> Vec<SearchEvent> {
+        self.events.take()
+    }
+
+    fn record(&self, event: SearchEvent) {
+        self.events.borrow_mut().push(event);
+    }
+}
+
+impl SearchObserver for SearchLog {
+    fn label_settled(&self, node: NodeId, distance: &[Weight]) {
+        self.record(SearchEvent::LabelSettled {
+            node,
+            distance: distance.to_vec(),
+        });
+    }
+
+    fn label_pushed(&self, node: NodeId, prev_node: NodeId, distance: &[Weight]) {
+        self.record(SearchEvent::LabelPushed {
+            node,
+            prev_node,
+            distance: distance.to_vec(),
+        });
+    }
+
+    fn label_dominated(&self, node: NodeId, distance: &[Weight]) {
+        self.record(SearchEvent::LabelDominated {
+            node,
+            distance: distance.to_vec(),
+        });
+    }
+
+    fn pruned_by_constraint(&self, node: NodeId, distance: &[Weight]) {
+        self.record(SearchEvent::PrunedByConstraint {
+            node,
+            distance: distance.to_vec(),
+        });
+    }
+
+    fn pruned_by_target(&self, node: NodeId, distance: &[Weight]) {
+        self.record(SearchEvent::PrunedByTarget {
+            node,
+            distance: distance.to_vec(),
+        });
+    }
+
+    fn pruned_by_bw_lower_bound(&self, node: NodeId, distance: &[Weight]) {
+        self.record(SearchEvent::PrunedByBwLowerBound {
+            node,
+            distance: distance.to_vec(),
+        });
+    }
+}
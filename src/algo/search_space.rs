@@ -0,0 +1,252 @@
+//! Export of the search space of a query to see where the labels piled up.
+//!
+//! A `SearchSpace` holds the number of labels and settled labels of the forward and backward search at every node
+//! reached by a query, together with the route if it is known. It is written as CSV with one row per node or as
+//! GeoJSON with a point per node and a line string for the route, which can be opened in tools like QGIS or geojson.io.
+//!
+//! ```no_run
+//! # use rust_truck_router::{algo::csp_bidir::CSPBidirQuery, query::RoutingData};
+//! # fn example(data: &RoutingData) -> std::io::Result<()> {
+//! let mut query = CSPBidirQuery::new(data.graph.borrow(), data.bw_graph.borrow(), &data.is_parking_node);
+//! query.init_new_s(0);
+//! query.init_new_t(42);
+//! query.run_query();
+//! query.search_space().write_to("search_space.geojson", data.coordinates.as_deref())?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use serde_json::json;
+
+use crate::{
+    algo::{astar::Potential, csp::OneRestrictionDijkstraData, csp_2::TwoRestrictionDijkstraData, dijkstra::DijkstraData},
+    priority_queue::IndexedPriorityQueue,
+    types::*,
+};
+
+/// Label counts of a search state
+pub trait LabelCounts {
+    /// Number of settled and unsettled labels at every node
+    fn labels_per_node(&self) -> Vec<usize>;
+    /// Number of settled labels at every node
+    fn settled_labels_per_node(&self) -> Vec<usize>;
+}
+
+impl<P: Potential, Q: IndexedPriorityQueue<State<Weight>>> LabelCounts for OneRestrictionDijkstraData<P, Q> {
+    fn labels_per_node(&self) -> Vec<usize> {
+        self.get_per_node_number_of_labels()
+    }
+
+    fn settled_labels_per_node(&self) -> Vec<usize> {
+        self.get_per_node_number_of_settled_labels()
+    }
+}
+
+impl<P: Potential, Q: IndexedPriorityQueue<State<Weight>>> LabelCounts for TwoRestrictionDijkstraData<P, Q> {
+    fn labels_per_node(&self) -> Vec<usize> {
+        self.get_per_node_number_of_labels()
+    }
+
+    fn settled_labels_per_node(&self) -> Vec<usize> {
+        self.get_per_node_number_of_settled_labels()
+    }
+}
+
+/// Without restrictions every reached node has exactly one label
+impl<P: Potential, Q: IndexedPriorityQueue<State<Weight>>> LabelCounts for DijkstraData<P, Q> {
+    fn labels_per_node(&self) -> Vec<usize> {
+        (0..self.dist.len()).map(|i| self.dist.is_set(i) as usize).collect()
+    }
+
+    fn settled_labels_per_node(&self) -> Vec<usize> {
+        let mut settled = vec![0; self.dist.len()];
+        for &(node, _) in &self.settled_nodes_vec {
+            settled[node as usize] = 1;
+        }
+        settled
+    }
+}
+
+/// The labels of both searches at one node
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeLabels {
+    pub fw_labels: usize,
+    pub fw_settled_labels: usize,
+    pub bw_labels: usize,
+    pub bw_settled_labels: usize,
+}
+
+impl NodeLabels {
+    pub fn direction(&self) -> &'static str {
+        match (self.fw_labels > 0, self.bw_labels > 0) {
+            (true, true) => "both",
+            (true, false) => "forward",
+            (false, true) => "backward",
+            (false, false) => "none",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchSpace {
+    /// Nodes with at least one label ordered by node id
+    pub nodes: Vec<(NodeId, NodeLabels)>,
+    /// Node path of the route, empty if unknown
+    pub route: Vec<NodeId>,
+}
+
+impl SearchSpace {
+    /// Search space of a unidirectional search
+    pub fn forward<S: LabelCounts>(fw_state: &S) -> Self {
+        Self::collect(fw_state.labels_per_node(), fw_state.settled_labels_per_node(), Vec::new(), Vec::new())
+    }
+
+    /// Search space of a bidirectional search, both states have to use the same node ids
+    pub fn bidirectional<F: LabelCounts, B: LabelCounts>(fw_state: &F, bw_state: &B) -> Self {
+        Self::collect(
+            fw_state.labels_per_node(),
+            fw_state.settled_labels_per_node(),
+            bw_state.labels_per_node(),
+            bw_state.settled_labels_per_node(),
+        )
+    }
+
+    fn collect(fw_labels: Vec<usize>, fw_settled_labels: Vec<usize>, bw_labels: Vec<usize>, bw_settled_labels: Vec<usize>) -> Self {
+        let at = |v: &Vec<usize>, i: usize| v.get(i).copied().unwrap_or(0);
+        let num_nodes = fw_labels.len().max(bw_labels.len());
+
+        let nodes = (0..num_nodes)
+            .map(|i| {
+                (
+                    i as NodeId,
+                    NodeLabels {
+                        fw_labels: at(&fw_labels, i),
+                        fw_settled_labels: at(&fw_settled_labels, i),
+                        bw_labels: at(&bw_labels, i),
+                        bw_settled_labels: at(&bw_settled_labels, i),
+                    },
+                )
+            })
+            .filter(|(_, labels)| labels.fw_labels + labels.bw_labels > 0)
+            .collect();
+
+        Self { nodes, route: Vec::new() }
+    }
+
+    /// Maps the nodes from ranks to node ids for searches on a (core) contraction hierarchy.
+    /// `order` maps ranks to node ids like `ContractionHierarchy::order`.
+    pub fn with_order(mut self, order: &[NodeId]) -> Self {
+        for (node, _) in &mut self.nodes {
+            *node = order[*node as usize];
+        }
+        self.nodes.sort_unstable_by_key(|&(node, _)| node);
+        self
+    }
+
+    pub fn with_route(mut self, route: Vec<NodeId>) -> Self {
+        self.route = route;
+        self
+    }
+
+    pub fn labels_at(&self, node: NodeId) -> Option<NodeLabels> {
+        self.nodes.binary_search_by_key(&node, |&(n, _)| n).ok().map(|i| self.nodes[i].1)
+    }
+
+    /// Number of settled labels of both searches
+    pub fn num_settled_labels(&self) -> usize {
+        self.nodes.iter().map(|(_, l)| l.fw_settled_labels + l.bw_settled_labels).sum()
+    }
+
+    /// One row per node with labels or on the route. Coordinates and the position on the route are NaN if unknown.
+    pub fn write_csv<W: Write>(&self, mut writer: W, coordinates: Option<&[(f64, f64)]>) -> io::Result<()> {
+        writeln!(
+            writer,
+            "node,latitude,longitude,fw_labels,fw_settled_labels,bw_labels,bw_settled_labels,route_index"
+        )?;
+
+        let mut route_nodes: Vec<(NodeId, usize)> = self.route.iter().enumerate().map(|(i, &node)| (node, i)).collect();
+        route_nodes.sort_unstable();
+        let mut nodes: Vec<NodeId> = self.nodes.iter().map(|&(node, _)| node).chain(self.route.iter().copied()).collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+
+        for node in nodes {
+            let (lat, lon) = coordinates.map_or((f64::NAN, f64::NAN), |c| c[node as usize]);
+            let labels = self.labels_at(node).unwrap_or_default();
+            // the first visit if the route passes a node twice
+            let first_on_route = route_nodes.get(route_nodes.partition_point(|&(n, _)| n < node)).filter(|&&(n, _)| n == node);
+            let route_index = first_on_route.map_or_else(|| "NaN".to_owned(), |&(_, i)| i.to_string());
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{}",
+                node, lat, lon, labels.fw_labels, labels.fw_settled_labels, labels.bw_labels, labels.bw_settled_labels, route_index
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// A feature collection with a point per node with labels and a line string for the route
+    pub fn to_geojson(&self, coordinates: &[(f64, f64)]) -> serde_json::Value {
+        let position = |node: NodeId| {
+            let (lat, lon) = coordinates[node as usize];
+            json!([lon, lat])
+        };
+
+        let mut features: Vec<serde_json::Value> = self
+            .nodes
+            .iter()
+            .map(|&(node, labels)| {
+                json!({
+                    "type": "Feature",
+                    "geometry": { "type": "Point", "coordinates": position(node) },
+                    "properties": {
+                        "node": node,
+                        "direction": labels.direction(),
+                        "fw_labels": labels.fw_labels,
+                        "fw_settled_labels": labels.fw_settled_labels,
+                        "bw_labels": labels.bw_labels,
+                        "bw_settled_labels": labels.bw_settled_labels,
+                    },
+                })
+            })
+            .collect();
+
+        if !self.route.is_empty() {
+            features.push(json!({
+                "type": "Feature",
+                "geometry": { "type": "LineString", "coordinates": self.route.iter().map(|&node| position(node)).collect::<Vec<_>>() },
+                "properties": { "route": true, "num_nodes": self.route.len() },
+            }));
+        }
+
+        json!({ "type": "FeatureCollection", "features": features })
+    }
+
+    pub fn write_geojson<W: Write>(&self, writer: W, coordinates: &[(f64, f64)]) -> io::Result<()> {
+        serde_json::to_writer(writer, &self.to_geojson(coordinates))?;
+        Ok(())
+    }
+
+    /// Writes GeoJSON for the extensions `geojson` and `json`, otherwise CSV. GeoJSON needs coordinates.
+    pub fn write_to<P: AsRef<Path>>(&self, path: P, coordinates: Option<&[(f64, f64)]>) -> io::Result<()> {
+        let path = path.as_ref();
+        let geojson = matches!(path.extension().and_then(|e| e.to_str()), Some("geojson") | Some("json"));
+        if geojson && coordinates.is_none() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "GeoJSON export needs node coordinates"));
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        match coordinates {
+            Some(coordinates) if geojson => self.write_geojson(&mut writer, coordinates)?,
+            _ => self.write_csv(&mut writer, coordinates)?,
+        }
+        writer.flush()
+    }
+}
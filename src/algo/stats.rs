@@ -0,0 +1,178 @@
+//! Statistics collected by the searches and queries, exported as JSON or CSV.
+//!
+//! Every search state (`DijkstraData`, `OneRestrictionDijkstraData`, `TwoRestrictionDijkstraData`) counts its
+//! work in a `SearchStats`. Queries combine the statistics of their searches into a `QueryStats` with separate
+//! phases: the forward and backward searches and, for core CH queries, the work of both searches inside the core.
+//! Time is measured once per query instead of once per settled label, so the phases of the bidirectional and core CH
+//! queries carry no time of their own.
+
+use std::{
+    fmt::Write,
+    ops::{Add, AddAssign, Sub},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{experiments::measurement::MeasurementResult, types::*};
+
+/// Durations are exported in milliseconds like the times in the experiment results
+mod duration_ms {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Ok(Duration::from_secs_f64(f64::deserialize(deserializer)?.max(0.0) / 1000.0))
+    }
+}
+
+/// The work of one search or one phase of a query
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchStats {
+    pub num_queue_pushes: u32,
+    /// Number of labels taken from the queue, equal to the number of settled nodes without restrictions
+    pub num_settled: u32,
+    pub num_labels_propagated: u32,
+    /// Number of labels which got an additional label with a break at a parking node
+    pub num_labels_reset: u32,
+    pub num_potential_evaluations: u32,
+    /// Most labels at one node at any time, tracked by the searches with driving time restrictions
+    pub max_label_set_size: usize,
+    /// Time of the last `dist_query` of this search. Zero when a query drives the search label by label,
+    /// the query then only measures its total time.
+    #[serde(with = "duration_ms")]
+    pub time: Duration,
+}
+
+impl SearchStats {
+    pub const CSV_HEADER: &'static str =
+        "num_queue_pushes,num_settled,num_labels_propagated,num_labels_reset,num_potential_evaluations,max_label_set_size,time_ms";
+
+    pub fn as_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.num_queue_pushes,
+            self.num_settled,
+            self.num_labels_propagated,
+            self.num_labels_reset,
+            self.num_potential_evaluations,
+            self.max_label_set_size,
+            self.time.as_secs_f64() * 1000.0
+        )
+    }
+
+    pub fn track_label_set_size(&mut self, size: usize) {
+        self.max_label_set_size = self.max_label_set_size.max(size);
+    }
+}
+
+impl Add for SearchStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            num_queue_pushes: self.num_queue_pushes + other.num_queue_pushes,
+            num_settled: self.num_settled + other.num_settled,
+            num_labels_propagated: self.num_labels_propagated + other.num_labels_propagated,
+            num_labels_reset: self.num_labels_reset + other.num_labels_reset,
+            num_potential_evaluations: self.num_potential_evaluations + other.num_potential_evaluations,
+            max_label_set_size: self.max_label_set_size.max(other.max_label_set_size),
+            time: self.time + other.time,
+        }
+    }
+}
+
+impl AddAssign for SearchStats {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+/// The work done between two snapshots of the same search. The label set size is kept from `self`.
+impl Sub for SearchStats {
+    type Output = Self;
+
+    fn sub(self, earlier: Self) -> Self {
+        Self {
+            num_queue_pushes: self.num_queue_pushes.saturating_sub(earlier.num_queue_pushes),
+            num_settled: self.num_settled.saturating_sub(earlier.num_settled),
+            num_labels_propagated: self.num_labels_propagated.saturating_sub(earlier.num_labels_propagated),
+            num_labels_reset: self.num_labels_reset.saturating_sub(earlier.num_labels_reset),
+            num_potential_evaluations: self.num_potential_evaluations.saturating_sub(earlier.num_potential_evaluations),
+            max_label_set_size: self.max_label_set_size,
+            time: self.time.saturating_sub(earlier.time),
+        }
+    }
+}
+
+/// Properties of the path found by a query, filled in by the caller as not every query can unpack its path
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathStats {
+    pub num_nodes: usize,
+    pub num_flagged_nodes: usize,
+    pub num_short_pauses: usize,
+    pub num_long_pauses: usize,
+}
+
+/// Statistics of the last query
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryStats {
+    pub distance: Option<Weight>,
+    /// Total time of the query including the work between the phases
+    #[serde(with = "duration_ms")]
+    pub time: Duration,
+    /// Number of nodes with at least one settled label, summed over the searches
+    pub num_nodes_searched: usize,
+    pub forward: SearchStats,
+    pub backward: SearchStats,
+    /// Work of both searches of a core CH query on core nodes, not included in `forward` and `backward`
+    pub core: SearchStats,
+    pub path: Option<PathStats>,
+}
+
+impl QueryStats {
+    /// The work of all phases
+    pub fn total(&self) -> SearchStats {
+        self.forward + self.backward + self.core
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("query statistics are always serializable")
+    }
+}
+
+impl MeasurementResult for QueryStats {
+    const OWN_HEADER: &'static str = "distance,time_ms,num_nodes_searched";
+
+    fn get_header() -> String {
+        let mut header = Self::OWN_HEADER.to_owned();
+        for phase in ["fw", "bw", "core"] {
+            for column in SearchStats::CSV_HEADER.split(',') {
+                write!(header, ",{}_{}", phase, column).unwrap();
+            }
+        }
+        header + ",path_number_nodes,path_number_flagged_nodes,path_number_short_pauses,path_number_long_pauses"
+    }
+
+    fn as_csv(&self) -> String {
+        let path = self.path.map_or_else(
+            || "NaN,NaN,NaN,NaN".to_owned(),
+            |p| format!("{},{},{},{}", p.num_nodes, p.num_flagged_nodes, p.num_short_pauses, p.num_long_pauses),
+        );
+
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.distance.map_or_else(|| "NaN".to_owned(), |d| d.to_string()),
+            self.time.as_secs_f64() * 1000.0,
+            self.num_nodes_searched,
+            self.forward.as_csv(),
+            self.backward.as_csv(),
+            self.core.as_csv(),
+            path
+        )
+    }
+}
@@ -0,0 +1,532 @@
+//! Vehicle routing with time windows whose tours respect the driving time restrictions.
+//!
+//! Every vehicle starts and ends its tour at its depot and serves customers with a demand, a service time and a time
+//! window in which the service has to start. Vehicles which arrive early wait. All times are relative to the start of
+//! the planning horizon, like the departure offset of a `DriverState`.
+//!
+//! The tours are built by cheapest insertion and then improved by a local search which relocates single customers
+//! and exchanges customers between tours, until no move shortens the total duration of all tours or the maximum
+//! number of rounds is reached. Customers which no tour could take are tried again after the local search.
+//!
+//! Every candidate tour is planned leg by leg with the CSP search, which is goal-directed if the solver is created
+//! with a potential like `CHPotential`. A leg starts with the driving times at the end of the previous one, so the
+//! breaks of the whole tour follow the restrictions. The legs are planned one after another,
+//! unlike in `multi_stop` the break placement is therefore not optimal for the whole tour. The CSP distance of a
+//! rested driver between two stops is a lower bound of every leg between them. It is searched when a tour first needs
+//! it and rejects tours which miss a time window before the remaining legs are searched. The legs and lower bounds are
+//! cached for one call of `solve`, the legs by their stops and driving times at departure, so tours which share a
+//! prefix reuse its legs.
+//!
+//! Waiting and service times count as travel time but not as driving time and do not replace a break.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use rust_truck_router::{algo::{ch_potential::CHPotential, multi_stop::Stop, vrptw::*}, query::RoutingData, types::*};
+//! # fn example(data: &RoutingData) {
+//! let mut solver = VrptwSolver::new_with_potential(data.graph.borrow(), &data.is_parking_node, CHPotential::from_ch(data.ch.borrow()));
+//! solver.set_restrictions(&[EU_SHORT]);
+//! let vehicles = [Vehicle {
+//!     depot: 0,
+//!     capacity: 10,
+//!     driver_state: DriverState::default(),
+//!     latest_return: 36_000_000,
+//! }];
+//! let customers = [Customer {
+//!     stop: Stop { node: 17, service_time: 1_800_000 },
+//!     demand: 4,
+//!     time_window: TimeWindow { earliest: 3_600_000, latest: 7_200_000 },
+//! }];
+//! let solution = solver.solve(&vehicles, &customers);
+//! for tour in &solution.tours {
+//!     println!("vehicle {} back after {} ms with {} breaks", tour.vehicle, tour.duration(), tour.breaks.len());
+//! }
+//! # }
+//! ```
+
+use std::{collections::HashMap, rc::Rc};
+
+use bit_vec::BitVec;
+
+use crate::types::*;
+
+use super::{
+    alternatives::{planned_break, PlannedBreak},
+    astar::{NoPotential, Potential},
+    csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+    csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+    multi_stop::Stop,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub earliest: Weight,
+    pub latest: Weight,
+}
+
+impl TimeWindow {
+    /// A time window which allows the service at any time
+    pub fn unbounded() -> Self {
+        Self { earliest: 0, latest: INFINITY }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Customer {
+    pub stop: Stop,
+    pub demand: Weight,
+    /// The service has to start within the time window
+    pub time_window: TimeWindow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vehicle {
+    pub depot: NodeId,
+    pub capacity: Weight,
+    /// The driver departs from the depot at `departure_offset` with the given driving times
+    pub driver_state: DriverState,
+    /// Latest arrival back at the depot
+    pub latest_return: Weight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CustomerVisit {
+    /// Index of the customer in the instance
+    pub customer: usize,
+    /// Position of the customer in the path of the tour
+    pub path_index: usize,
+    pub arrival: Weight,
+    /// Start of the service after waiting for the time window
+    pub service_start: Weight,
+    pub departure: Weight,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tour {
+    /// Index of the vehicle in the instance
+    pub vehicle: usize,
+    pub path: Vec<NodeId>,
+    pub departure: Weight,
+    /// Arrival back at the depot
+    pub arrival: Weight,
+    pub driving_time: Weight,
+    pub waiting_time: Weight,
+    pub breaks: Vec<PlannedBreak>,
+    /// One visit per customer, in the order of the tour
+    pub visits: Vec<CustomerVisit>,
+}
+
+impl Tour {
+    /// Time from the departure at the depot until the return, including breaks, waiting and service times
+    pub fn duration(&self) -> Weight {
+        self.arrival - self.departure
+    }
+
+    /// Indices of the customers in the order of the tour
+    pub fn customers(&self) -> impl Iterator<Item = usize> + '_ {
+        self.visits.iter().map(|visit| visit.customer)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VrptwSolution {
+    /// Tours of the vehicles which serve at least one customer, ordered by vehicle
+    pub tours: Vec<Tour>,
+    /// Customers which fit into no tour, e.g. because their time window can not be reached
+    pub unserved: Vec<usize>,
+}
+
+impl VrptwSolution {
+    pub fn total_duration(&self) -> Weight {
+        self.tours.iter().map(Tour::duration).sum()
+    }
+}
+
+/// The path between two stops, the distances are relative to the departure
+struct Leg {
+    path: Vec<NodeId>,
+    /// Distances of the labels along the path, `[distance, driving time since the break of every restriction...]`
+    times: Vec<Vec<Weight>>,
+}
+
+struct Instance<'i> {
+    vehicles: &'i [Vehicle],
+    customers: &'i [Customer],
+}
+
+/// The customers of every vehicle and their tour, `None` for vehicles without customers
+struct Plan {
+    routes: Vec<Vec<usize>>,
+    tours: Vec<Option<Tour>>,
+}
+
+impl Plan {
+    fn duration(&self, vehicle: usize) -> Weight {
+        self.tours[vehicle].as_ref().map_or(0, Tour::duration)
+    }
+
+    fn set(&mut self, vehicle: usize, route: Vec<usize>, tour: Option<Tour>) {
+        self.routes[vehicle] = route;
+        self.tours[vehicle] = tour;
+    }
+}
+
+/// Number of cached legs after which the cache is cleared during a `solve`
+const MAX_CACHED_LEGS: usize = 1 << 16;
+
+pub struct VrptwSolver<'a, P = NoPotential>
+where
+    P: Potential,
+{
+    graph: BorrowedGraph<'a>,
+    is_parking_node: &'a BitVec,
+    restrictions: Vec<DrivingTimeRestriction>,
+    max_local_search_rounds: usize,
+    csp_state: OneRestrictionDijkstraData<P>,
+    csp_2_state: TwoRestrictionDijkstraData<P>,
+    /// Legs of the current `solve` by their stops and the driving times at departure, `None` if the end can not be
+    /// reached
+    legs: HashMap<(NodeId, NodeId, Vec<Weight>), Option<Rc<Leg>>>,
+    /// CSP distances of a rested driver between the stops of the current `solve`
+    lower_bounds: HashMap<(NodeId, NodeId), Weight>,
+}
+
+impl<'a> VrptwSolver<'a> {
+    pub fn new(graph: BorrowedGraph<'a>, is_parking_node: &'a BitVec) -> Self {
+        Self::new_with_potential(graph, is_parking_node, NoPotential {})
+    }
+}
+
+impl<'a, P: Potential + Clone> VrptwSolver<'a, P> {
+    /// The legs are searched goal-directed with `potential`
+    pub fn new_with_potential(graph: BorrowedGraph<'a>, is_parking_node: &'a BitVec, potential: P) -> Self {
+        let n = graph.num_nodes();
+        Self {
+            graph,
+            is_parking_node,
+            restrictions: Vec::new(),
+            max_local_search_rounds: 1000,
+            csp_state: OneRestrictionDijkstraData::new_with_potential(n, potential.clone()),
+            csp_2_state: TwoRestrictionDijkstraData::new_with_potential(n, potential),
+            legs: HashMap::new(),
+            lower_bounds: HashMap::new(),
+        }
+    }
+}
+
+impl<'a, P: Potential> VrptwSolver<'a, P> {
+    /// At most two restrictions ordered by increasing maximum driving time, e.g. `[short, long]` for the EU rules
+    pub fn set_restrictions(&mut self, restrictions: &[DrivingTimeRestriction]) {
+        assert!(restrictions.len() <= 2, "at most two restrictions are supported");
+        assert!(restrictions.is_sorted_by_key(|r| r.max_driving_time));
+        self.restrictions = restrictions.to_vec();
+    }
+
+    pub fn clear_restrictions(&mut self) {
+        self.restrictions.clear();
+    }
+
+    /// Each round applies one improving move, zero only keeps the tours of the cheapest insertion
+    pub fn set_max_local_search_rounds(&mut self, rounds: usize) {
+        self.max_local_search_rounds = rounds;
+    }
+
+    /// Tours for the vehicles which serve as many customers as the heuristic can fit in
+    pub fn solve(&mut self, vehicles: &[Vehicle], customers: &[Customer]) -> VrptwSolution {
+        self.legs.clear();
+        self.lower_bounds.clear();
+        // the restrictions stay the same for all legs of this call
+        match self.restrictions[..] {
+            [] => {
+                self.csp_state.clear_restriction();
+            }
+            [r] => {
+                self.csp_state.set_restriction(r.max_driving_time, r.pause_time);
+            }
+            [short, long] => {
+                self.csp_2_state
+                    .set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+            }
+            _ => unreachable!("at most two restrictions are supported"),
+        }
+        let instance = Instance { vehicles, customers };
+
+        let mut plan = Plan {
+            routes: vec![Vec::new(); vehicles.len()],
+            tours: vec![None; vehicles.len()],
+        };
+        let mut unserved: Vec<usize> = (0..customers.len()).collect();
+        self.insert_cheapest(&instance, &mut plan, &mut unserved);
+        if self.improve(&instance, &mut plan) {
+            self.insert_cheapest(&instance, &mut plan, &mut unserved);
+        }
+
+        unserved.sort_unstable();
+        VrptwSolution {
+            tours: plan.tours.into_iter().flatten().collect(),
+            unserved,
+        }
+    }
+
+    /// Inserts the customer whose best feasible position lengthens the tours the least until no customer fits
+    fn insert_cheapest(&mut self, instance: &Instance, plan: &mut Plan, unserved: &mut Vec<usize>) {
+        loop {
+            // (additional duration, index in `unserved`, vehicle, route, tour)
+            let mut best: Option<(i64, usize, usize, Vec<usize>, Tour)> = None;
+            for (i, &customer) in unserved.iter().enumerate() {
+                for vehicle in 0..plan.routes.len() {
+                    for position in 0..=plan.routes[vehicle].len() {
+                        // the tour up to the previous stop does not change
+                        let (previous_node, previous_departure) = match position {
+                            0 => (instance.vehicles[vehicle].depot, instance.vehicles[vehicle].driver_state.departure_offset),
+                            _ => {
+                                let previous = plan.tours[vehicle].as_ref().unwrap().visits[position - 1];
+                                (instance.customers[previous.customer].stop.node, previous.departure)
+                            }
+                        };
+                        let lower_bound = self.lower_bound(previous_node, instance.customers[customer].stop.node);
+                        if previous_departure + lower_bound > instance.customers[customer].time_window.latest {
+                            continue;
+                        }
+
+                        let mut route = plan.routes[vehicle].clone();
+                        route.insert(position, customer);
+                        if let Some(tour) = self.evaluate(instance, vehicle, &route) {
+                            let additional = tour.duration() as i64 - plan.duration(vehicle) as i64;
+                            if best.as_ref().is_none_or(|b| additional < b.0) {
+                                best = Some((additional, i, vehicle, route, tour));
+                            }
+                        }
+                    }
+                }
+            }
+
+            match best {
+                Some((_, i, vehicle, route, tour)) => {
+                    unserved.remove(i);
+                    plan.set(vehicle, route, Some(tour));
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Local search with the first improving move of each round, returns whether any move was applied
+    fn improve(&mut self, instance: &Instance, plan: &mut Plan) -> bool {
+        let mut improved = false;
+        for _ in 0..self.max_local_search_rounds {
+            if !self.relocate(instance, plan) && !self.exchange(instance, plan) {
+                break;
+            }
+            improved = true;
+        }
+        improved
+    }
+
+    /// Moves one customer to another position of its tour or into another tour
+    fn relocate(&mut self, instance: &Instance, plan: &mut Plan) -> bool {
+        for from in 0..plan.routes.len() {
+            for i in 0..plan.routes[from].len() {
+                let customer = plan.routes[from][i];
+                let mut shortened = plan.routes[from].clone();
+                shortened.remove(i);
+                let shortened_tour = match self.evaluate_route(instance, from, &shortened) {
+                    Some(tour) => tour,
+                    None => continue,
+                };
+
+                for to in 0..plan.routes.len() {
+                    let (target, before) = if to == from {
+                        (&shortened, plan.duration(from))
+                    } else {
+                        (&plan.routes[to], plan.duration(from) + plan.duration(to))
+                    };
+
+                    for position in 0..=target.len() {
+                        if to == from && position == i {
+                            continue;
+                        }
+                        let mut route = target.clone();
+                        route.insert(position, customer);
+                        let tour = match self.evaluate(instance, to, &route) {
+                            Some(tour) => tour,
+                            None => continue,
+                        };
+
+                        let after = tour.duration() + if to == from { 0 } else { shortened_tour.as_ref().map_or(0, Tour::duration) };
+                        if after < before {
+                            if to != from {
+                                plan.set(from, shortened, shortened_tour);
+                            }
+                            plan.set(to, route, Some(tour));
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Swaps two customers of different tours
+    fn exchange(&mut self, instance: &Instance, plan: &mut Plan) -> bool {
+        for a in 0..plan.routes.len() {
+            for b in a + 1..plan.routes.len() {
+                let before = plan.duration(a) + plan.duration(b);
+                for i in 0..plan.routes[a].len() {
+                    for j in 0..plan.routes[b].len() {
+                        let mut route_a = plan.routes[a].clone();
+                        let mut route_b = plan.routes[b].clone();
+                        std::mem::swap(&mut route_a[i], &mut route_b[j]);
+
+                        let tour_a = match self.evaluate(instance, a, &route_a) {
+                            Some(tour) => tour,
+                            None => continue,
+                        };
+                        let tour_b = match self.evaluate(instance, b, &route_b) {
+                            Some(tour) => tour,
+                            None => continue,
+                        };
+                        if tour_a.duration() + tour_b.duration() < before {
+                            plan.set(a, route_a, Some(tour_a));
+                            plan.set(b, route_b, Some(tour_b));
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Like `evaluate` but an empty route is a feasible tour without customers, which is `Some(None)`
+    fn evaluate_route(&mut self, instance: &Instance, vehicle: usize, route: &[usize]) -> Option<Option<Tour>> {
+        if route.is_empty() {
+            Some(None)
+        } else {
+            self.evaluate(instance, vehicle, route).map(Some)
+        }
+    }
+
+    /// Plans the tour of `vehicle` through the customers of `route` in the given order, `None` if it exceeds the
+    /// capacity, misses a time window or returns too late
+    fn evaluate(&mut self, instance: &Instance, vehicle: usize, route: &[usize]) -> Option<Tour> {
+        let v = &instance.vehicles[vehicle];
+        if route.iter().map(|&c| instance.customers[c].demand).sum::<Weight>() > v.capacity {
+            return None;
+        }
+
+        let state = &v.driver_state;
+        let mut time = [state.departure_offset, state.driving_time_since_break, state.driving_time_since_rest][..1 + self.restrictions.len()].to_vec();
+        let mut path = vec![v.depot];
+        let mut breaks = Vec::new();
+        let mut visits: Vec<CustomerVisit> = Vec::with_capacity(route.len());
+        let mut waiting_time = 0;
+
+        for stop in route.iter().map(Some).chain(std::iter::once(None)) {
+            let (node, latest) = match stop {
+                Some(&c) => (instance.customers[c].stop.node, instance.customers[c].time_window.latest),
+                None => (v.depot, v.latest_return),
+            };
+            let previous_node = *path.last().unwrap();
+            if time[0] + self.lower_bound(previous_node, node) > latest {
+                return None;
+            }
+
+            let leg = self.leg(previous_node, node, &time[1..])?;
+            let departure = time[0];
+            for (&node, leg_time) in leg.path.iter().zip(&leg.times).skip(1) {
+                time.copy_from_slice(leg_time);
+                time[0] += departure;
+                path.push(node);
+                if self.is_parking_node[node as usize] {
+                    breaks.extend(planned_break(node, &time, &self.restrictions));
+                }
+            }
+
+            match stop {
+                Some(&c) => {
+                    let customer = &instance.customers[c];
+                    let arrival = time[0];
+                    let service_start = arrival.max(customer.time_window.earliest);
+                    if service_start > customer.time_window.latest {
+                        return None;
+                    }
+                    waiting_time += service_start - arrival;
+                    time[0] = service_start + customer.stop.service_time;
+                    visits.push(CustomerVisit {
+                        customer: c,
+                        path_index: path.len() - 1,
+                        arrival,
+                        service_start,
+                        departure: time[0],
+                    });
+                }
+                None if time[0] > latest => return None,
+                None => (),
+            }
+        }
+
+        let service_time: Weight = visits.iter().map(|visit| visit.departure - visit.service_start).sum();
+        let break_time: Weight = breaks.iter().map(|b| b.duration).sum();
+        Some(Tour {
+            vehicle,
+            path,
+            departure: state.departure_offset,
+            arrival: time[0],
+            driving_time: time[0] - state.departure_offset - waiting_time - service_time - break_time,
+            waiting_time,
+            breaks,
+            visits,
+        })
+    }
+
+    /// The travel time of a rested driver from `s` to `t`, which no leg between them undercuts, infinity if `t` can
+    /// not be reached
+    fn lower_bound(&mut self, s: NodeId, t: NodeId) -> Weight {
+        if let Some(&bound) = self.lower_bounds.get(&(s, t)) {
+            return bound;
+        }
+        let rested = vec![0; self.restrictions.len()];
+        let bound = self.leg(s, t, &rested).map_or(INFINITY, |leg| leg.times.last().unwrap()[0]);
+        self.lower_bounds.insert((s, t), bound);
+        bound
+    }
+
+    /// The best path from `s` to `t` for a driver departing with `driving_times` since the break of every
+    /// restriction, the restrictions of the searches are set by `solve`
+    fn leg(&mut self, s: NodeId, t: NodeId, driving_times: &[Weight]) -> Option<Rc<Leg>> {
+        let key = (s, t, driving_times.to_vec());
+        if let Some(leg) = self.legs.get(&key) {
+            return leg.clone();
+        }
+
+        let num_restrictions = self.restrictions.len();
+        let leg = if num_restrictions < 2 {
+            self.csp_state.init_new_s_with_labels(s, &[[0, driving_times.first().copied().unwrap_or(0)]]);
+            OneRestrictionDijkstra::new(self.graph, self.is_parking_node)
+                .dist_query(&mut self.csp_state, t)
+                .and_then(|_| self.csp_state.current_best_path_to(t, true))
+                .map(|(path, distances)| Leg {
+                    path,
+                    times: distances.iter().map(|d| d[..1 + num_restrictions].to_vec()).collect(),
+                })
+        } else {
+            self.csp_2_state.init_new_s_with_labels(s, &[[0, driving_times[0], driving_times[1]]]);
+            TwoRestrictionDijkstra::new(self.graph, self.is_parking_node)
+                .dist_query(&mut self.csp_2_state, t)
+                .and_then(|_| self.csp_2_state.current_best_path_to(t, true))
+                .map(|(path, distances)| Leg {
+                    path,
+                    times: distances.iter().map(|d| d.to_vec()).collect(),
+                })
+        }
+        .map(Rc::new);
+
+        if self.legs.len() >= MAX_CACHED_LEGS {
+            self.legs.clear();
+        }
+        self.legs.insert(key, leg.clone());
+        leg
+    }
+}
@@ -0,0 +1,19 @@
+use rust_truck_router::{cli::CliErr, io::dataset::Dataset, time::report_time};
+use std::{env, error::Error};
+
+/// Packs a RoutingKit style directory including `ch` and `core_ch` into a single dataset file
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let input = args.next().ok_or(CliErr("No input directory given"))?;
+    let output = args.next().ok_or(CliErr("No output file given"))?;
+
+    let dataset = report_time("reading directory", || Dataset::from_routingkit_dir(&input))?;
+    println!("Graph with {} nodes and {} arcs", dataset.num_nodes(), dataset.num_arcs());
+    for name in dataset.section_names() {
+        println!("{}", name);
+    }
+
+    report_time("writing dataset", || dataset.write_to(&output))?;
+
+    Ok(())
+}
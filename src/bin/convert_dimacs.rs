@@ -0,0 +1,45 @@
+use bit_vec::BitVec;
+use rust_truck_router::{
+    cli::CliErr,
+    io::{dimacs::*, *},
+    types::Graph,
+};
+use std::{env, error::Error, fs, path::Path};
+
+/// Converts a DIMACS `.gr` file and optionally `.co` and `.flg` files into a RoutingKit style directory
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let graph_file = args.next().ok_or(CliErr("No .gr file given"))?;
+    let output = args.next().ok_or(CliErr("No output directory given"))?;
+    let coordinate_file = args.next();
+    let flag_file = args.next();
+
+    let output = Path::new(&output);
+    fs::create_dir_all(output)?;
+
+    let graph = read_dimacs_graph(graph_file)?;
+    println!("Graph with {} nodes and {} arcs", graph.num_nodes(), graph.num_arcs());
+    graph.first_out().write_to(&output.join("first_out"))?;
+    graph.head().write_to(&output.join("head"))?;
+    graph.weights().write_to(&output.join("travel_time"))?;
+
+    if let Some(coordinate_file) = coordinate_file {
+        let (latitude, longitude) = read_dimacs_coordinates(coordinate_file)?;
+        if latitude.len() != graph.num_nodes() {
+            return Err(Box::new(CliErr("Number of coordinates does not match number of nodes")));
+        }
+        latitude.write_to(&output.join("latitude"))?;
+        longitude.write_to(&output.join("longitude"))?;
+    }
+
+    let flags = match flag_file {
+        Some(flag_file) => read_dimacs_flags(flag_file)?,
+        None => BitVec::from_elem(graph.num_nodes(), false),
+    };
+    if flags.len() != graph.num_nodes() {
+        return Err(Box::new(CliErr("Number of flags does not match number of nodes")));
+    }
+    store_routingkit_bitvector(&flags, output.join("routing_parking_flags"))?;
+
+    Ok(())
+}
@@ -41,7 +41,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     core_ch_query.set_custom_reset_nodes(parking_rc.clone());
     // core_ch_query.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
     core_ch_query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
-    core_ch_query.check();
+    core_ch_query.check()?;
 
     core_ch_query.init_new_s(s);
     core_ch_query.init_new_t(t);
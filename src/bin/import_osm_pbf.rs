@@ -0,0 +1,31 @@
+use rust_truck_router::{
+    cli::CliErr,
+    osm_import::{OsmImportConfig, OsmRoutingGraph},
+    time::report_time,
+    types::Graph,
+};
+use std::{env, error::Error, path::Path};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let input = args.next().ok_or(CliErr("No input .osm.pbf file given"))?;
+    let output = args.next().ok_or(CliErr("No output directory given"))?;
+
+    let mut config = OsmImportConfig::default();
+    if let Some(speed) = args.next() {
+        config.max_truck_speed = speed.parse().map_err(|_| CliErr("Invalid maximum truck speed"))?;
+    }
+
+    let extract = report_time("importing OSM data", || OsmRoutingGraph::import_pbf(Path::new(&input), &config))?;
+
+    println!(
+        "Graph with {} nodes, {} arcs and {} parking nodes",
+        extract.graph.num_nodes(),
+        extract.graph.num_arcs(),
+        extract.is_parking_node.iter().filter(|b| *b).count()
+    );
+
+    extract.write_to_routingkit_dir(Path::new(&output))?;
+
+    Ok(())
+}
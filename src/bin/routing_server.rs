@@ -0,0 +1,22 @@
+use rust_truck_router::{cli::CliErr, io::dataset::Dataset, query::RoutingData, server::RoutingServer, time::report_time};
+use std::{env, error::Error, net::TcpListener};
+
+/// Serves routing requests on localhost, see the `server` module for the API
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let input = args.next().ok_or(CliErr("No dataset file or directory given"))?;
+    let port: u16 = match args.next() {
+        Some(port) => port.parse().map_err(|_| CliErr("Invalid port"))?,
+        None => 8080,
+    };
+
+    let dataset = report_time("loading dataset", || Dataset::open(&input))?;
+    let data = report_time("preparing routing data", || RoutingData::from_dataset(&dataset))?;
+    drop(dataset);
+
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("Listening on {}", listener.local_addr()?);
+    RoutingServer::new(&data).serve(listener)?;
+
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use rust_truck_router::{
+    cli::CliErr,
+    experiments::runner::{run_experiment, ExperimentConfig},
+    time::report_time,
+};
+use std::{env, error::Error};
+
+/// Runs the experiment described by a config file, see `experiments::runner`.
+/// Optional further arguments override the dataset and the output file of the config.
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let config_path = args.next().ok_or(CliErr("No config file given"))?;
+
+    let mut config = ExperimentConfig::load_from(&config_path)?;
+    if let Some(dataset) = args.next() {
+        config.dataset = dataset.into();
+    }
+    if let Some(output) = args.next() {
+        config.output = output.into();
+    }
+
+    report_time("running experiment", || run_experiment(&config))?;
+
+    Ok(())
+}
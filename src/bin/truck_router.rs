@@ -0,0 +1,250 @@
+use rust_truck_router::{
+    algo::PointToPointQuery,
+    cli::CliErr,
+    io::dataset::*,
+    osm_id_mapper::{OSMIDMapper, OSMNodeId},
+    query::{new_query, Algorithm, RestrictionPreset, RoutingData},
+    time::report_time,
+    types::*,
+};
+use std::{
+    env,
+    error::Error,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    time::Instant,
+};
+
+const USAGE: &str = "Usage:
+    truck_router query <dataset> <source> <target> [options]
+    truck_router batch <dataset> <queries.csv> <results.csv> [options]
+    truck_router stats <dataset>
+
+<dataset> is a dataset file or a RoutingKit directory.
+The queries csv starts with the columns source,target and has an optional header line,\nquery sets saved by the experiments module can be used directly.
+
+Options:
+    --algorithm <dijkstra|astar|bidir|bidir_astar|core_ch|core_ch_astar>  (default core_ch_astar)
+    --restriction <none|eu_short|eu_long>                                (default eu_long)
+    --osm                                                                source and target are osm ids
+    --stats                                                              print the query statistics as json
+    --search-space <file>                                                write the search space as csv, as geojson for .geojson files,
+                                                                         the route is only included for dijkstra and astar";
+
+struct Options {
+    algorithm: Algorithm,
+    restriction: RestrictionPreset,
+    osm: bool,
+    stats: bool,
+    search_space: Option<String>,
+}
+
+/// Splits the arguments into positional arguments and options
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<(Vec<String>, Options), CliErr> {
+    let mut positional = Vec::new();
+    let mut options = Options {
+        algorithm: Algorithm::default(),
+        restriction: RestrictionPreset::default(),
+        osm: false,
+        stats: false,
+        search_space: None,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--algorithm" => {
+                options.algorithm = args
+                    .next()
+                    .ok_or(CliErr("No algorithm given"))?
+                    .parse()
+                    .map_err(|_| CliErr("Unknown algorithm"))?
+            }
+            "--restriction" => {
+                options.restriction = args
+                    .next()
+                    .ok_or(CliErr("No restriction given"))?
+                    .parse()
+                    .map_err(|_| CliErr("Unknown restriction"))?
+            }
+            "--osm" => options.osm = true,
+            "--stats" => options.stats = true,
+            "--search-space" => options.search_space = Some(args.next().ok_or(CliErr("No search space file given"))?),
+            _ if arg.starts_with("--") => return Err(CliErr("Unknown option")),
+            _ => positional.push(arg),
+        }
+    }
+
+    Ok((positional, options))
+}
+
+fn resolve(data: &RoutingData, node: &str, osm: bool) -> Result<NodeId, Box<dyn Error>> {
+    if osm {
+        let mapper = data.osm_ids.as_ref().ok_or(CliErr("The dataset contains no osm ids"))?;
+        return Ok(mapper.try_to_local(node.parse::<OSMNodeId>().map_err(|_| CliErr("Invalid osm id"))?)?);
+    }
+
+    let node = node.parse::<NodeId>().map_err(|_| CliErr("Invalid node id"))?;
+    if node as usize >= data.num_nodes() {
+        return Err(Box::new(CliErr("Node id out of range")));
+    }
+    Ok(node)
+}
+
+fn load_data(path: &str) -> Result<RoutingData, Box<dyn Error>> {
+    let dataset = report_time("loading dataset", || Dataset::open(path))?;
+    Ok(report_time("preparing routing data", || RoutingData::from_dataset(&dataset))?)
+}
+
+fn run_query(query: &mut dyn PointToPointQuery, s: NodeId, t: NodeId) -> (Option<Weight>, f64) {
+    query.init_new_s(s);
+    query.init_new_t(t);
+    let start = Instant::now();
+    let dist = query.run_query();
+    (dist, start.elapsed().as_secs_f64() * 1000.0)
+}
+
+fn query(args: &[String], options: &Options) -> Result<(), Box<dyn Error>> {
+    let (dataset, s, t) = match args {
+        [dataset, s, t] => (dataset, s, t),
+        _ => return Err(Box::new(CliErr("Expected <dataset> <source> <target>"))),
+    };
+
+    let data = load_data(dataset)?;
+    let s = resolve(&data, s, options.osm)?;
+    let t = resolve(&data, t, options.osm)?;
+
+    let mut query = new_query(&data, options.algorithm, options.restriction);
+    let (dist, time_ms) = run_query(query.as_mut(), s, t);
+
+    println!("Algorithm {} with restriction {}", options.algorithm, options.restriction);
+    match dist {
+        Some(dist) => println!("From {} to {}: {} ({:.3} h)", s, t, dist, dist as f64 / 3_600_000.0),
+        None => println!("No path found from {} to {}", s, t),
+    }
+    println!("Took {} ms", time_ms);
+    if options.stats {
+        println!("{}", query.stats().to_json());
+    }
+    if let Some(path) = &options.search_space {
+        let search_space = query.search_space();
+        search_space.write_to(path, data.coordinates.as_deref())?;
+        println!("Wrote the search space with {} nodes to {}", search_space.nodes.len(), path);
+        if search_space.route.is_empty() && dist.is_some() {
+            // the route of another algorithm would not match this search space
+            println!("No route written, only dijkstra and astar unpack their path");
+        }
+    }
+
+    Ok(())
+}
+
+fn batch(args: &[String], options: &Options) -> Result<(), Box<dyn Error>> {
+    let (dataset, input, output) = match args {
+        [dataset, input, output] => (dataset, input, output),
+        _ => return Err(Box::new(CliErr("Expected <dataset> <queries.csv> <results.csv>"))),
+    };
+
+    let data = load_data(dataset)?;
+    let mut queries = Vec::new();
+    for (i, line) in BufReader::new(File::open(input)?).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || (i == 0 && line.starts_with("source")) {
+            continue;
+        }
+
+        // further columns such as the rank exponent of saved query sets are ignored
+        let mut columns = line.split(',').map(str::trim);
+        let (s, t) = columns
+            .next()
+            .zip(columns.next())
+            .ok_or(CliErr("Expected at least two columns in queries csv"))?;
+        queries.push((resolve(&data, s, options.osm)?, resolve(&data, t, options.osm)?));
+    }
+
+    let mut query = new_query(&data, options.algorithm, options.restriction);
+    let mut file = BufWriter::new(File::create(output)?);
+    writeln!(file, "source,target,algorithm,restriction,distance,time_ms")?;
+
+    report_time(&format!("running {} queries", queries.len()), || -> Result<(), Box<dyn Error>> {
+        for &(s, t) in &queries {
+            let (dist, time_ms) = run_query(query.as_mut(), s, t);
+            let dist = dist.map_or_else(|| "NaN".to_owned(), |d| d.to_string());
+            writeln!(file, "{},{},{},{},{},{}", s, t, options.algorithm, options.restriction, dist, time_ms)?;
+        }
+        Ok(())
+    })?;
+    file.flush()?;
+
+    Ok(())
+}
+
+fn stats(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let dataset = match args {
+        [dataset] => dataset,
+        _ => return Err(Box::new(CliErr("Expected <dataset>"))),
+    };
+
+    let dataset = report_time("loading dataset", || Dataset::open(dataset))?;
+    let graph = dataset.get_graph(GRAPH)?;
+    println!("nodes: {}", graph.num_nodes());
+    println!("arcs: {}", graph.num_arcs());
+
+    let max_degree = (0..graph.num_nodes() as NodeId).map(|node| graph.degree(node)).max().unwrap_or(0);
+    println!("average degree: {:.3}", graph.num_arcs() as f64 / graph.num_nodes().max(1) as f64);
+    println!("max degree: {}", max_degree);
+
+    let weights = graph.weights();
+    if !weights.is_empty() {
+        let total: u64 = weights.iter().map(|&w| w as u64).sum();
+        println!("min arc travel time: {} ms", weights.iter().min().unwrap());
+        println!("max arc travel time: {} ms", weights.iter().max().unwrap());
+        println!("average arc travel time: {:.3} ms", total as f64 / weights.len() as f64);
+    }
+
+    if dataset.contains(PARKING_FLAGS) {
+        let is_parking_node = dataset.get_node_flags(PARKING_FLAGS)?;
+        println!("parking nodes: {}", is_parking_node.iter().take(graph.num_nodes()).filter(|b| *b).count());
+    }
+
+    if dataset.contains(CH) {
+        let ch = dataset.get_ch(CH)?;
+        println!("ch arcs: {} forward, {} backward", ch.forward().num_arcs(), ch.backward().num_arcs());
+    }
+
+    if dataset.contains(CORE_CH) {
+        let core_ch = dataset.get_core_ch(CORE_CH)?;
+        println!("core nodes: {}", core_ch.is_core().iter().filter(|b| *b).count());
+        println!(
+            "core ch arcs: {} forward, {} backward",
+            core_ch.forward().num_arcs(),
+            core_ch.backward().num_arcs()
+        );
+    }
+
+    println!("sections: {}", dataset.section_names().collect::<Vec<_>>().join(", "));
+
+    Ok(())
+}
+
+/// Runs single queries, batches of queries from a csv file or prints statistics of a dataset
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let command = args.next().ok_or_else(|| {
+        eprintln!("{}", USAGE);
+        CliErr("No command given")
+    })?;
+    let (positional, options) = parse_args(args)?;
+
+    let result = match command.as_str() {
+        "query" => query(&positional, &options),
+        "batch" => batch(&positional, &options),
+        "stats" => stats(&positional),
+        _ => Err(Box::new(CliErr("Unknown command")) as Box<dyn Error>),
+    };
+
+    if result.is_err() {
+        eprintln!("{}", USAGE);
+    }
+    result
+}
@@ -0,0 +1,211 @@
+//! A bucket queue for elements with integer keys.
+//!
+//! Keys are grouped into buckets of a fixed width. A circular array of buckets covers a window of keys
+//! starting at the bucket of the last popped element, elements beyond the window wait in an overflow list
+//! until the window moves over their key or is empty and jumps to the smallest overflow key. Popping scans forward to the first non
+//! empty bucket and takes its smallest element, so with monotone keys and a bucket width in the order of the
+//! edge weights most operations are `O(1)`.
+//!
+//! Keys smaller than the last popped key are allowed and are put into the current bucket.
+//!
+//! # Example
+//!
+//! ```
+//! use rust_truck_router::{bucket_queue::BucketQueue, priority_queue::IndexedPriorityQueue, types::*};
+//!
+//! let mut queue = BucketQueue::with_buckets(3, 10, 4);
+//! queue.push(State { node: 0, distance: 42 as Weight });
+//! queue.push(State { node: 1, distance: 23 });
+//! queue.push(State { node: 2, distance: 50000 });
+//! assert_eq!(queue.pop(), Some(State { node: 1, distance: 23 }));
+//! queue.decrease_key(State { node: 2, distance: 30 });
+//! assert_eq!(queue.pop(), Some(State { node: 2, distance: 30 }));
+//! assert_eq!(queue.pop(), Some(State { node: 0, distance: 42 }));
+//! assert!(queue.is_empty());
+//! ```
+
+use std::mem::{self, size_of};
+
+use crate::{
+    index_heap::Indexing,
+    priority_queue::{min_position, IndexedPriorityQueue, IntegerKey},
+};
+
+/// One second
+pub const DEFAULT_BUCKET_WIDTH: u64 = 1000;
+/// Covers a bit more than an hour with the default width
+pub const DEFAULT_NUM_BUCKETS: usize = 4096;
+
+const INVALID_BUCKET: u32 = u32::MAX;
+const OVERFLOW_BUCKET: u32 = u32::MAX - 1;
+
+#[derive(Debug, Clone)]
+pub struct BucketQueue<T> {
+    buckets: Vec<Vec<T>>,
+    overflow: Vec<T>,
+    /// Bucket and position in the bucket of every index
+    positions: Vec<(u32, u32)>,
+    bucket_width: u64,
+    /// The window covers the buckets `[first_bucket, window_end)`
+    first_bucket: u64,
+    window_end: u64,
+    /// Smallest bucket of the overflow elements, may be smaller once elements left the overflow list
+    overflow_min_bucket: u64,
+    in_window: usize,
+    len: usize,
+}
+
+impl<T: Ord + Indexing + IntegerKey> BucketQueue<T> {
+    /// A queue with `num_buckets` buckets of `bucket_width` keys each
+    pub fn with_buckets(max_index: usize, bucket_width: u64, num_buckets: usize) -> Self {
+        assert!(bucket_width > 0 && num_buckets > 0);
+        BucketQueue {
+            buckets: (0..num_buckets).map(|_| Vec::new()).collect(),
+            overflow: Vec::new(),
+            positions: vec![(INVALID_BUCKET, 0); max_index],
+            bucket_width,
+            first_bucket: 0,
+            window_end: num_buckets as u64,
+            overflow_min_bucket: u64::MAX,
+            in_window: 0,
+            len: 0,
+        }
+    }
+
+    fn insert(&mut self, element: T) {
+        let bucket = (element.integer_key() / self.bucket_width).max(self.first_bucket);
+        let index = element.as_index();
+        if bucket < self.window_end {
+            let slot = (bucket % self.buckets.len() as u64) as usize;
+            self.positions[index] = (slot as u32, self.buckets[slot].len() as u32);
+            self.buckets[slot].push(element);
+            self.in_window += 1;
+        } else {
+            self.positions[index] = (OVERFLOW_BUCKET, self.overflow.len() as u32);
+            self.overflow.push(element);
+            self.overflow_min_bucket = self.overflow_min_bucket.min(bucket);
+        }
+    }
+
+    fn remove_at(&mut self, bucket: u32, position: usize) -> T {
+        let list = if bucket == OVERFLOW_BUCKET {
+            &mut self.overflow
+        } else {
+            self.in_window -= 1;
+            &mut self.buckets[bucket as usize]
+        };
+
+        let element = list.swap_remove(position);
+        if let Some(moved) = list.get(position) {
+            self.positions[moved.as_index()].1 = position as u32;
+        }
+        element
+    }
+
+    /// Moves the window to the smallest overflow key
+    fn refill(&mut self) {
+        let min_key = self.overflow.iter().map(|e| e.integer_key()).min().unwrap();
+        self.first_bucket = min_key / self.bucket_width;
+        self.window_end = self.first_bucket + self.buckets.len() as u64;
+        self.take_overflow();
+    }
+
+    /// Moves the overflow elements which are in the window to their buckets
+    fn take_overflow(&mut self) {
+        self.overflow_min_bucket = u64::MAX;
+        for element in mem::take(&mut self.overflow) {
+            self.insert(element);
+        }
+    }
+
+    /// Slot of the first non empty bucket of the window
+    fn first_non_empty_slot(&self) -> Option<usize> {
+        (self.first_bucket..self.window_end)
+            .map(|b| (b % self.buckets.len() as u64) as usize)
+            .find(|&slot| !self.buckets[slot].is_empty())
+    }
+}
+
+impl<T: Ord + Indexing + IntegerKey> IndexedPriorityQueue<T> for BucketQueue<T> {
+    fn with_max_index(max_index: usize) -> Self {
+        Self::with_buckets(max_index, DEFAULT_BUCKET_WIDTH, DEFAULT_NUM_BUCKETS)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn contains_index(&self, id: usize) -> bool {
+        self.positions[id].0 != INVALID_BUCKET
+    }
+
+    fn clear(&mut self) {
+        for list in self.buckets.iter_mut().chain(std::iter::once(&mut self.overflow)) {
+            for element in list.iter() {
+                self.positions[element.as_index()] = (INVALID_BUCKET, 0);
+            }
+            list.clear();
+        }
+        self.first_bucket = 0;
+        self.window_end = self.buckets.len() as u64;
+        self.overflow_min_bucket = u64::MAX;
+        self.in_window = 0;
+        self.len = 0;
+    }
+
+    fn peek(&self) -> Option<&T> {
+        if self.in_window == 0 {
+            return self.overflow.iter().min();
+        }
+        self.first_non_empty_slot().map(|slot| &self.buckets[slot][min_position(&self.buckets[slot])])
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        if self.in_window == 0 {
+            self.refill();
+        }
+
+        // the window keeps its size, the slot of an empty first bucket becomes the new last bucket
+        while self.buckets[(self.first_bucket % self.buckets.len() as u64) as usize].is_empty() {
+            self.first_bucket += 1;
+            self.window_end += 1;
+            if self.window_end > self.overflow_min_bucket {
+                self.take_overflow();
+            }
+        }
+        let slot = (self.first_bucket % self.buckets.len() as u64) as usize;
+        let element = self.remove_at(slot as u32, min_position(&self.buckets[slot]));
+        self.positions[element.as_index()] = (INVALID_BUCKET, 0);
+        self.len -= 1;
+        Some(element)
+    }
+
+    fn push(&mut self, element: T) {
+        assert!(!self.contains_index(element.as_index()));
+        self.insert(element);
+        self.len += 1;
+    }
+
+    fn decrease_key(&mut self, element: T) {
+        let (bucket, position) = self.positions[element.as_index()];
+        self.remove_at(bucket, position as usize);
+        self.insert(element);
+    }
+
+    fn get_key_by_index(&self, id: usize) -> Option<&T> {
+        let (bucket, position) = self.positions[id];
+        match bucket {
+            INVALID_BUCKET => None,
+            OVERFLOW_BUCKET => Some(&self.overflow[position as usize]),
+            _ => Some(&self.buckets[bucket as usize][position as usize]),
+        }
+    }
+
+    fn memory_consumption(&self) -> usize {
+        self.positions.capacity() * size_of::<(u32, u32)>()
+            + (self.overflow.capacity() + self.buckets.iter().map(|b| b.capacity()).sum::<usize>()) * size_of::<T>()
+    }
+}
@@ -0,0 +1,185 @@
+//! Differential validation: runs a query set through several algorithms and reports every query on which
+//! they do not agree with the first one, the reference.
+//!
+//! Queries which led to a disagreement can be collected in a regression corpus. The corpus is a query set
+//! as written by `experiments::queries::save_queries`, so it can be replayed with `load_queries`,
+//! `{ "type": "file", "path": ... }` in experiment configs or `truck_router batch`.
+
+use std::{
+    fmt::{self, Display},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    algo::{ch::ContractionHierarchyQuery, dijkstra::*, PointToPointQuery},
+    experiments::queries::{load_queries, save_queries, Query},
+    query::{new_query, Algorithm, DijkstraQuery, RestrictionPreset, RoutingData},
+    types::*,
+};
+
+/// An algorithm taking part in a differential run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Candidate {
+    /// `Dijkstra` without driving time restrictions
+    Dijkstra,
+    /// `ContractionHierarchyQuery` without driving time restrictions
+    Ch,
+    /// A query created by `query::new_query`, i.e. `OneRestrictionDijkstra` or `TwoRestrictionDijkstra` based.
+    /// The plain `CoreContractionHierarchyQuery` is no candidate as it does not search the core.
+    Restricted(Algorithm, RestrictionPreset),
+}
+
+impl Candidate {
+    /// All algorithms with the given restriction, including those without restrictions if it is `RestrictionPreset::None`
+    pub fn all_with(restriction: RestrictionPreset) -> Vec<Candidate> {
+        let mut candidates = if restriction == RestrictionPreset::None {
+            vec![Candidate::Dijkstra, Candidate::Ch]
+        } else {
+            Vec::new()
+        };
+        candidates.extend(Algorithm::ALL.iter().map(|&algorithm| Candidate::Restricted(algorithm, restriction)));
+        candidates
+    }
+
+    pub fn new_query<'a>(&self, data: &'a RoutingData) -> Box<dyn PointToPointQuery + 'a> {
+        match *self {
+            Candidate::Dijkstra => Box::new(DijkstraQuery::new(Dijkstra::new(data.graph.borrow()), DijkstraData::new(data.num_nodes()))),
+            Candidate::Ch => Box::new(ContractionHierarchyQuery::new(data.ch.borrow())),
+            Candidate::Restricted(algorithm, restriction) => new_query(data, algorithm, restriction),
+        }
+    }
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Candidate::Dijkstra => f.write_str("dijkstra"),
+            Candidate::Ch => f.write_str("ch"),
+            Candidate::Restricted(algorithm, restriction) => write!(f, "{}/{}", algorithm, restriction),
+        }
+    }
+}
+
+/// A query on which a candidate found a different distance than the reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disagreement {
+    pub query: Query,
+    pub reference: Candidate,
+    pub expected: Option<Weight>,
+    pub candidate: Candidate,
+    pub found: Option<Weight>,
+}
+
+impl Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "query from {} to {}: {} found {:?} but {} found {:?}",
+            self.query.s, self.query.t, self.candidate, self.found, self.reference, self.expected
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DifferentialReport {
+    pub num_queries: usize,
+    pub disagreements: Vec<Disagreement>,
+}
+
+impl DifferentialReport {
+    pub fn all_agree(&self) -> bool {
+        self.disagreements.is_empty()
+    }
+
+    /// The queries with at least one disagreement, each only once and in the order they were run
+    pub fn failing_queries(&self) -> Vec<Query> {
+        let mut queries: Vec<Query> = Vec::new();
+        for disagreement in &self.disagreements {
+            if !queries.contains(&disagreement.query) {
+                queries.push(disagreement.query);
+            }
+        }
+        queries
+    }
+
+    /// Writes one line per disagreement with the columns `source,target,reference,expected,candidate,found`
+    pub fn write_disagreements<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "source,target,reference,expected,candidate,found")?;
+        for d in &self.disagreements {
+            let expected = d.expected.map_or_else(|| "NaN".to_owned(), |w| w.to_string());
+            let found = d.found.map_or_else(|| "NaN".to_owned(), |w| w.to_string());
+            writeln!(file, "{},{},{},{},{},{}", d.query.s, d.query.t, d.reference, expected, d.candidate, found)?;
+        }
+        file.flush()
+    }
+
+    /// Adds the failing queries to the corpus at `path`, creating it if necessary.
+    /// Returns the number of queries which were not yet contained in the corpus.
+    pub fn update_corpus<P: AsRef<Path>>(&self, path: P) -> io::Result<usize> {
+        let path = path.as_ref();
+        let mut corpus = if path.exists() { load_queries(path)? } else { Vec::new() };
+        let num_before = corpus.len();
+
+        for query in self.failing_queries() {
+            if !corpus.iter().any(|q| q.s == query.s && q.t == query.t) {
+                corpus.push(query);
+            }
+        }
+
+        save_queries(path, &corpus)?;
+        Ok(corpus.len() - num_before)
+    }
+}
+
+impl Display for DifferentialReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} disagreements in {} queries", self.disagreements.len(), self.num_queries)?;
+        for disagreement in &self.disagreements {
+            writeln!(f, "{}", disagreement)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs all queries with all candidates and compares the distances with those of the first candidate.
+/// Queries with node ids out of range are not skipped, validate externally provided query sets before.
+pub fn run_differential(data: &RoutingData, candidates: &[Candidate], queries: &[Query]) -> DifferentialReport {
+    let mut report = DifferentialReport {
+        num_queries: queries.len(),
+        disagreements: Vec::new(),
+    };
+
+    let (reference, others) = match candidates.split_first() {
+        Some(split) => split,
+        None => return report,
+    };
+    let mut reference_query = reference.new_query(data);
+    let mut other_queries: Vec<_> = others.iter().map(|candidate| (*candidate, candidate.new_query(data))).collect();
+
+    let run = |query: &mut dyn PointToPointQuery, q: &Query| {
+        query.init_new_s(q.s);
+        query.init_new_t(q.t);
+        query.run_query()
+    };
+
+    for q in queries {
+        let expected = run(reference_query.as_mut(), q);
+        for (candidate, query) in &mut other_queries {
+            let found = run(query.as_mut(), q);
+            if found != expected {
+                report.disagreements.push(Disagreement {
+                    query: *q,
+                    reference: *reference,
+                    expected,
+                    candidate: *candidate,
+                    found,
+                });
+            }
+        }
+    }
+
+    report
+}
@@ -1 +1,5 @@
+pub mod differential;
 pub mod measurement;
+pub mod queries;
+pub mod runner;
+pub mod thesis;
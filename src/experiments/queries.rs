@@ -0,0 +1,201 @@
+//! Seeded generation of query sets for experiments and tests. The same seed always yields the same queries.
+//!
+//! Query sets are stored as CSV files with the columns `source,target,dijkstra_rank_exponent` so that they can be
+//! shared between experiments, tests and the `truck_router batch` command. Missing rank exponents are written as `NaN`.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, LineWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use bit_vec::BitVec;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algo::dijkstra::{Dijkstra, DijkstraData},
+    types::*,
+};
+
+pub const QUERY_SET_HEADER: &str = "source,target,dijkstra_rank_exponent";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Query {
+    pub s: NodeId,
+    pub t: NodeId,
+    /// `t` is the `2^i`th node settled by a Dijkstra search from `s`
+    pub dijkstra_rank_exponent: Option<usize>,
+}
+
+/// The kind of queries to generate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QueryGenerator {
+    /// Source and target chosen uniformly at random
+    Random { count: usize, seed: u64 },
+    /// For `count` random sources all targets with Dijkstra ranks `2^0, 2^1, ..., 2^(log n - 1)`
+    DijkstraRank { count: usize, seed: u64 },
+    /// Targets with a travel time without breaks in `[min_distance, max_distance)` ms from a random source
+    DistanceBand {
+        count: usize,
+        seed: u64,
+        min_distance: Weight,
+        max_distance: Weight,
+    },
+    /// Source and target chosen uniformly at random among the parking nodes
+    ParkingToParking { count: usize, seed: u64 },
+    /// A query set saved with `save_queries`
+    File { path: PathBuf },
+}
+
+impl QueryGenerator {
+    pub fn generate(&self, graph: BorrowedGraph, is_parking_node: &BitVec) -> io::Result<Vec<Query>> {
+        Ok(match *self {
+            QueryGenerator::Random { count, seed } => random_queries(graph.num_nodes(), count, seed),
+            QueryGenerator::DijkstraRank { count, seed } => dijkstra_rank_queries(graph, count, seed),
+            QueryGenerator::DistanceBand {
+                count,
+                seed,
+                min_distance,
+                max_distance,
+            } => distance_band_queries(graph, count, seed, min_distance, max_distance),
+            QueryGenerator::ParkingToParking { count, seed } => parking_to_parking_queries(is_parking_node, graph.num_nodes(), count, seed),
+            QueryGenerator::File { ref path } => load_queries(path)?,
+        })
+    }
+}
+
+pub fn random_queries(num_nodes: usize, count: usize, seed: u64) -> Vec<Query> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| Query {
+            s: rng.gen_range(0..num_nodes as NodeId),
+            t: rng.gen_range(0..num_nodes as NodeId),
+            dijkstra_rank_exponent: None,
+        })
+        .collect()
+}
+
+/// Sources which do not reach `log n` nodes are skipped, just like in the thesis experiments.
+/// The sources are drawn up front so that skipping does not change which sources follow.
+pub fn dijkstra_rank_queries(graph: BorrowedGraph, count: usize, seed: u64) -> Vec<Query> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let num_nodes = graph.num_nodes();
+    let num_rank_targets = (num_nodes as f32).log2() as usize;
+    let sources: Vec<NodeId> = (0..count).map(|_| rng.gen_range(0..num_nodes as NodeId)).collect();
+
+    let dijkstra = Dijkstra::new(graph);
+    let mut state = DijkstraData::new(num_nodes);
+    let mut queries = Vec::with_capacity(count * num_rank_targets);
+
+    for s in sources {
+        state.init_new_s(s);
+        let rank_targets = dijkstra.ranks_only_exponentials(&mut state);
+        if rank_targets.len() != num_rank_targets {
+            continue;
+        }
+
+        queries.extend(rank_targets.into_iter().enumerate().map(|(i, t)| Query {
+            s,
+            t,
+            dijkstra_rank_exponent: Some(i),
+        }));
+    }
+
+    queries
+}
+
+/// Gives up after `100 * count` sources so that empty bands terminate, the result may then contain fewer queries
+pub fn distance_band_queries(graph: BorrowedGraph, count: usize, seed: u64, min_distance: Weight, max_distance: Weight) -> Vec<Query> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let num_nodes = graph.num_nodes();
+    let dijkstra = Dijkstra::new(graph);
+    let mut state = DijkstraData::new(num_nodes);
+    let mut queries = Vec::with_capacity(count);
+    let mut candidates = Vec::new();
+
+    for _ in 0..100 * count {
+        if queries.len() == count || num_nodes == 0 {
+            break;
+        }
+
+        let s = rng.gen_range(0..num_nodes as NodeId);
+        state.init_new_s(s);
+        candidates.clear();
+        while let Some(State { distance, node }) = dijkstra.settle_next_node_not_exceeding(&mut state, max_distance) {
+            if distance >= min_distance {
+                candidates.push(node);
+            }
+        }
+
+        if let Some(&t) = candidates.choose(&mut rng) {
+            queries.push(Query {
+                s,
+                t,
+                dijkstra_rank_exponent: None,
+            });
+        }
+    }
+
+    queries
+}
+
+/// Returns no queries if there are no parking nodes. Padding bits beyond `num_nodes` are ignored.
+pub fn parking_to_parking_queries(is_parking_node: &BitVec, num_nodes: usize, count: usize, seed: u64) -> Vec<Query> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let parking_nodes: Vec<NodeId> = (0..num_nodes).filter(|&i| is_parking_node[i]).map(|i| i as NodeId).collect();
+    if parking_nodes.is_empty() {
+        return Vec::new();
+    }
+
+    (0..count)
+        .map(|_| Query {
+            s: *parking_nodes.choose(&mut rng).unwrap(),
+            t: *parking_nodes.choose(&mut rng).unwrap(),
+            dijkstra_rank_exponent: None,
+        })
+        .collect()
+}
+
+pub fn save_queries<P: AsRef<Path>>(path: P, queries: &[Query]) -> io::Result<()> {
+    let mut file = LineWriter::new(File::create(path)?);
+    writeln!(file, "{}", QUERY_SET_HEADER)?;
+    for query in queries {
+        match query.dijkstra_rank_exponent {
+            Some(exponent) => writeln!(file, "{},{},{}", query.s, query.t, exponent)?,
+            None => writeln!(file, "{},{},NaN", query.s, query.t)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a query set. The header and the rank column are optional, so plain `source,target` files work as well.
+pub fn load_queries<P: AsRef<Path>>(path: P) -> io::Result<Vec<Query>> {
+    let invalid = |line: usize| io::Error::new(io::ErrorKind::InvalidData, format!("invalid query in line {}", line + 1));
+    let mut queries = Vec::new();
+
+    for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || (i == 0 && line.starts_with("source")) {
+            continue;
+        }
+
+        let mut columns = line.split(',').map(str::trim);
+        let s = columns.next().and_then(|s| s.parse().ok()).ok_or_else(|| invalid(i))?;
+        let t = columns.next().and_then(|t| t.parse().ok()).ok_or_else(|| invalid(i))?;
+        let dijkstra_rank_exponent = match columns.next() {
+            None | Some("NaN") | Some("") => None,
+            Some(exponent) => Some(exponent.parse().map_err(|_| invalid(i))?),
+        };
+        if columns.next().is_some() {
+            return Err(invalid(i));
+        }
+
+        queries.push(Query { s, t, dijkstra_rank_exponent });
+    }
+
+    Ok(queries)
+}
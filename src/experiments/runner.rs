@@ -0,0 +1,337 @@
+//! Runs experiments described by a JSON config instead of a dedicated binary per experiment.
+//!
+//! ```json
+//! {
+//!     "dataset": "/data/germany",
+//!     "algorithms": ["astar", "core_ch_astar"],
+//!     "restrictions": ["eu_short", "eu_long"],
+//!     "queries": { "type": "dijkstra_rank", "count": 1000, "seed": 42 },
+//!     "repetitions": 1,
+//!     "output": "rank_times.csv"
+//! }
+//! ```
+//!
+//! The query types are described in `experiments::queries`, `{ "type": "file", "path": "queries.csv" }` reuses a saved query set.
+//!
+//! One CSV file is written per restriction. With several restrictions the restriction name is appended to the file name,
+//! e.g. `rank_times-eu_short.csv`. The columns are `algo`, `dijkstra_rank_exponent` if any query has a rank and the
+//! columns of `QueryStats`.
+//!
+//! The `algo` column holds the `Algorithm` names, the thesis plots formerly called `astar` and `core_ch_astar`
+//! `astar_chpot` and `core_ch_chpot`.
+//!
+//! The time and search space columns describe each algorithm's own query, the time includes setting source and target.
+//! The path columns describe the path of the A* reference search with CH potentials as not every algorithm can unpack
+//! its path. Every distance is checked against the reference.
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs::File,
+    io::{self, stdout, LineWriter, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algo::{
+        ch_potential::CHPotential,
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+        stats::{PathStats, QueryStats},
+    },
+    experiments::{
+        measurement::MeasurementResult,
+        queries::{Query, QueryGenerator},
+    },
+    io::dataset::{Dataset, DatasetError},
+    query::{new_query, Algorithm, RestrictionPreset, RoutingData},
+    types::*,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    /// A dataset file or a RoutingKit directory
+    pub dataset: PathBuf,
+    #[serde(default = "all_algorithms")]
+    pub algorithms: Vec<Algorithm>,
+    #[serde(default = "default_restrictions")]
+    pub restrictions: Vec<RestrictionPreset>,
+    pub queries: QueryGenerator,
+    /// How often each query is timed per algorithm, every repetition yields a row
+    #[serde(default = "one")]
+    pub repetitions: usize,
+    pub output: PathBuf,
+}
+
+fn all_algorithms() -> Vec<Algorithm> {
+    Algorithm::ALL.to_vec()
+}
+
+fn default_restrictions() -> Vec<RestrictionPreset> {
+    vec![RestrictionPreset::EuShort]
+}
+
+fn one() -> usize {
+    1
+}
+
+impl ExperimentConfig {
+    pub fn load_from<P: AsRef<Path>>(path: P) -> Result<Self, ExperimentError> {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    /// The file the results for the given restriction are written to
+    pub fn output_for(&self, restriction: RestrictionPreset) -> PathBuf {
+        if self.restrictions.len() <= 1 {
+            return self.output.clone();
+        }
+
+        let stem = self.output.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let file_name = match self.output.extension().and_then(|e| e.to_str()) {
+            Some(extension) => format!("{}-{}.{}", stem, restriction, extension),
+            None => format!("{}-{}", stem, restriction),
+        };
+        self.output.with_file_name(file_name)
+    }
+}
+
+#[derive(Debug)]
+pub enum ExperimentError {
+    Io(io::Error),
+    Config(serde_json::Error),
+    Dataset(DatasetError),
+    /// An algorithm disagrees with the reference search, the results would be meaningless
+    DistanceMismatch {
+        algorithm: Algorithm,
+        restriction: RestrictionPreset,
+        query: Query,
+        expected: Option<Weight>,
+        found: Option<Weight>,
+    },
+}
+
+impl Display for ExperimentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExperimentError::Io(e) => write!(f, "io error: {}", e),
+            ExperimentError::Config(e) => write!(f, "invalid config: {}", e),
+            ExperimentError::Dataset(e) => write!(f, "invalid dataset: {}", e),
+            ExperimentError::DistanceMismatch {
+                algorithm,
+                restriction,
+                query,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} with restriction {} found {:?} instead of {:?} from {} to {}",
+                algorithm, restriction, found, expected, query.s, query.t
+            ),
+        }
+    }
+}
+
+impl Error for ExperimentError {}
+
+impl From<io::Error> for ExperimentError {
+    fn from(e: io::Error) -> Self {
+        ExperimentError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ExperimentError {
+    fn from(e: serde_json::Error) -> Self {
+        ExperimentError::Config(e)
+    }
+}
+
+impl From<DatasetError> for ExperimentError {
+    fn from(e: DatasetError) -> Self {
+        ExperimentError::Dataset(e)
+    }
+}
+
+struct ExperimentRow<M> {
+    algorithm: Algorithm,
+    dijkstra_rank_exponent: Option<usize>,
+    result: M,
+}
+
+/// Loads the dataset, generates the queries and writes the results of all configured restrictions
+pub fn run_experiment(config: &ExperimentConfig) -> Result<(), ExperimentError> {
+    let dataset = Dataset::open(&config.dataset)?;
+    let data = RoutingData::from_dataset(&dataset)?;
+    drop(dataset);
+
+    let queries = config.queries.generate(data.graph.borrow(), &data.is_parking_node)?;
+    if let Some(query) = queries.iter().find(|q| q.s as usize >= data.num_nodes() || q.t as usize >= data.num_nodes()) {
+        return Err(ExperimentError::Io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("query from {} to {} exceeds the {} nodes of the graph", query.s, query.t, data.num_nodes()),
+        )));
+    }
+    let with_rank = queries.iter().any(|q| q.dijkstra_rank_exponent.is_some());
+
+    for &restriction in &config.restrictions {
+        let output = config.output_for(restriction);
+
+        let references = if restriction == RestrictionPreset::EuLong {
+            reference_csp_2(&data, &queries)?
+        } else {
+            reference_csp(&data, restriction, &queries)?
+        };
+        let rows = run_algorithms(config, &data, restriction, &queries, &references)?;
+        write_rows(&output, with_rank, &rows)?;
+    }
+
+    Ok(())
+}
+
+/// Distances and paths of the A* reference search with one restriction, or none for `RestrictionPreset::None`
+fn reference_csp(data: &RoutingData, restriction: RestrictionPreset, queries: &[Query]) -> io::Result<Vec<(Option<Weight>, Option<PathStats>)>> {
+    let astar = OneRestrictionDijkstra::new(data.graph.borrow(), &data.is_parking_node);
+    let mut astar_state = OneRestrictionDijkstraData::new_with_potential(data.num_nodes(), CHPotential::from_ch(data.ch.borrow()));
+    if restriction == RestrictionPreset::None {
+        astar_state.clear_restriction();
+    } else {
+        astar_state.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    }
+
+    let mut references = Vec::with_capacity(queries.len());
+    for (i, query) in queries.iter().enumerate() {
+        print!(
+            "\rProgress {}/{} from {} to {} - reference {}\t\t\t",
+            i,
+            queries.len(),
+            query.s,
+            query.t,
+            restriction
+        );
+        stdout().flush()?;
+
+        astar_state.init_new_s(query.s);
+        let dist = astar.dist_query(&mut astar_state, query.t);
+
+        let path = dist.and_then(|_| astar_state.current_best_path_to(query.t, true)).map(|p| PathStats {
+            num_nodes: p.0.len(),
+            num_flagged_nodes: astar.flagged_nodes_on_path(&p).len(),
+            num_short_pauses: astar.reset_nodes_on_path(&p).len(),
+            num_long_pauses: 0,
+        });
+        references.push((dist, path));
+
+        astar_state.clean();
+    }
+
+    Ok(references)
+}
+
+/// Distances and paths of the A* reference search with the EU short and long restrictions
+fn reference_csp_2(data: &RoutingData, queries: &[Query]) -> io::Result<Vec<(Option<Weight>, Option<PathStats>)>> {
+    let astar = TwoRestrictionDijkstra::new(data.graph.borrow(), &data.is_parking_node);
+    let mut astar_state = TwoRestrictionDijkstraData::new_with_potential(data.num_nodes(), CHPotential::from_ch(data.ch.borrow()));
+    astar_state.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+
+    let mut references = Vec::with_capacity(queries.len());
+    for (i, query) in queries.iter().enumerate() {
+        print!("\rProgress {}/{} from {} to {} - reference eu_long\t\t\t", i, queries.len(), query.s, query.t);
+        stdout().flush()?;
+
+        astar_state.init_new_s(query.s);
+        let dist = astar.dist_query(&mut astar_state, query.t);
+
+        let path = dist.and_then(|_| astar_state.current_best_path_to(query.t, true)).map(|p| {
+            let (short_pauses, long_pauses) = astar.reset_nodes_on_path(&p);
+            PathStats {
+                num_nodes: p.0.len(),
+                num_flagged_nodes: astar.flagged_nodes_on_path(&p).len(),
+                num_short_pauses: short_pauses.len(),
+                num_long_pauses: long_pauses.len(),
+            }
+        });
+        references.push((dist, path));
+
+        astar_state.clean();
+    }
+
+    Ok(references)
+}
+
+/// Runs every algorithm on every query and checks its distance against the reference
+fn run_algorithms(
+    config: &ExperimentConfig,
+    data: &RoutingData,
+    restriction: RestrictionPreset,
+    queries: &[Query],
+    references: &[(Option<Weight>, Option<PathStats>)],
+) -> Result<Vec<ExperimentRow<QueryStats>>, ExperimentError> {
+    let mut rows = Vec::with_capacity(config.algorithms.len() * config.repetitions * queries.len());
+
+    for &algorithm in &config.algorithms {
+        let mut query = new_query(data, algorithm, restriction);
+
+        for _ in 0..config.repetitions {
+            for (i, (q, &(expected, path))) in queries.iter().zip(references).enumerate() {
+                print!(
+                    "\rProgress {}/{} from {} to {} - {} {}\t\t\t",
+                    i,
+                    queries.len(),
+                    q.s,
+                    q.t,
+                    algorithm,
+                    restriction
+                );
+                stdout().flush()?;
+
+                let start = Instant::now();
+                query.init_new_s(q.s);
+                query.init_new_t(q.t);
+                let dist = query.run_query();
+                let time = start.elapsed();
+
+                if dist != expected {
+                    return Err(ExperimentError::DistanceMismatch {
+                        algorithm,
+                        restriction,
+                        query: *q,
+                        expected,
+                        found: dist,
+                    });
+                }
+
+                rows.push(ExperimentRow {
+                    algorithm,
+                    dijkstra_rank_exponent: q.dijkstra_rank_exponent,
+                    result: QueryStats { time, path, ..query.stats() },
+                });
+            }
+        }
+    }
+    println!("\rProgress {}/{}", queries.len(), queries.len());
+
+    Ok(rows)
+}
+
+fn write_rows<M: MeasurementResult>(path: &Path, with_rank: bool, rows: &[ExperimentRow<M>]) -> io::Result<()> {
+    let mut file = LineWriter::new(File::create(path)?);
+
+    if with_rank {
+        writeln!(file, "algo,dijkstra_rank_exponent,{}", M::get_header())?;
+    } else {
+        writeln!(file, "algo,{}", M::get_header())?;
+    }
+
+    for row in rows {
+        if with_rank {
+            let exponent = row.dijkstra_rank_exponent.map_or_else(|| "NaN".to_owned(), |e| e.to_string());
+            writeln!(file, "{},{},{}", row.algorithm, exponent, row.result.as_csv())?;
+        } else {
+            writeln!(file, "{},{}", row.algorithm, row.result.as_csv())?;
+        }
+    }
+
+    Ok(())
+}
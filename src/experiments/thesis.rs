@@ -0,0 +1,334 @@
+//! The thesis experiments which vary a parameter, e.g. the maximum driving time or the core size, and can therefore not
+//! be described by an `ExperimentConfig`. The experiments with fixed parameters are configs in `experiment_configs`.
+//!
+//! Every parameter value is timed on the same random queries. The results are written to
+//! `thesis_<experiment>-<problem>-<dataset>.txt` in the working directory where `eval/plotting/make_plots.py` expects
+//! them, the problem is `csp` for one and `csp_2` for two restrictions. The columns are `algo`, the parameter columns,
+//! `time_ms` and `path_distance`.
+
+use std::{
+    fs,
+    io::{self, stdout, LineWriter, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Instant,
+};
+
+use bit_vec::BitVec;
+
+use crate::{
+    algo::{
+        ch::OwnedContractionHierarchy, core_ch::OwnedCoreContractionHierarchy, csp_2_core_ch_chpot::CSP2AstarCoreCHQuery,
+        csp_2_core_ch_chpot_no_bw::CSP2AstarCoreCHQueryNoBwNoPrune, csp_core_ch::CSPCoreCHQuery, csp_core_ch_chpot::CSPAstarCoreCHQuery,
+        csp_core_ch_chpot_no_bw::CSPAstarCoreCHQueryNoBwNoPrune, PointToPointQuery,
+    },
+    experiments::{
+        measurement::{EXPERIMENTS_BASE_N, EXPERIMENTS_SEED},
+        queries::{random_queries, Query},
+        runner::ExperimentError,
+    },
+    io::dataset::*,
+    query::{Algorithm, RestrictionPreset},
+    types::*,
+};
+
+/// Timed queries of one experiment, one row per query and parameter value
+pub struct Sweep {
+    parameter_header: &'static str,
+    rows: Vec<String>,
+}
+
+impl Sweep {
+    /// `parameter_header` names the comma separated parameter columns and may be empty
+    pub fn new(parameter_header: &'static str) -> Self {
+        Self {
+            parameter_header,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Times every query including setting source and target and records it with the given parameter columns
+    pub fn measure(&mut self, algo: &str, parameters: &str, query: &mut dyn PointToPointQuery, queries: &[Query]) -> io::Result<()> {
+        for (i, q) in queries.iter().enumerate() {
+            print!("\rProgress {}/{} from {} to {} - {} {}\t\t\t", i, queries.len(), q.s, q.t, algo, parameters);
+            stdout().flush()?;
+
+            let start = Instant::now();
+            query.init_new_s(q.s);
+            query.init_new_t(q.t);
+            let dist = query.run_query();
+            let time = start.elapsed();
+
+            let mut row = algo.to_owned();
+            if !self.parameter_header.is_empty() {
+                row = row + "," + parameters;
+            }
+            self.rows.push(format!(
+                "{},{},{}",
+                row,
+                time.as_secs_f64() * 1000.0,
+                dist.map_or("NaN".to_owned(), |d| d.to_string())
+            ));
+        }
+        println!("\rProgress {}/{} - {} {}", queries.len(), queries.len(), algo, parameters);
+
+        Ok(())
+    }
+
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = LineWriter::new(fs::File::create(path)?);
+        if self.parameter_header.is_empty() {
+            writeln!(file, "algo,time_ms,path_distance")?;
+        } else {
+            writeln!(file, "algo,{},time_ms,path_distance", self.parameter_header)?;
+        }
+        for row in &self.rows {
+            writeln!(file, "{}", row)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `csp` for one restriction and `csp_2` for two, like the suffixes of the thesis binaries
+pub fn problem_name(restriction: RestrictionPreset) -> &'static str {
+    match restriction {
+        RestrictionPreset::None => "none",
+        RestrictionPreset::EuShort => "csp",
+        RestrictionPreset::EuLong => "csp_2",
+    }
+}
+
+/// `thesis_<experiment>-<problem>-<dataset directory name>.txt`
+pub fn output_file(experiment: &str, restriction: RestrictionPreset, dataset: &Path) -> PathBuf {
+    let dataset_name = dataset.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    PathBuf::from(format!("thesis_{}-{}-{}.txt", experiment, problem_name(restriction), dataset_name))
+}
+
+fn invalid_data(message: String) -> ExperimentError {
+    ExperimentError::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+/// The core CH A* query with CH potentials, the reset nodes default to the core nodes
+fn core_ch_astar_query<'a>(
+    core_ch: &'a OwnedCoreContractionHierarchy,
+    ch: &'a OwnedContractionHierarchy,
+    reset_nodes: Option<Rc<BitVec>>,
+    restriction: RestrictionPreset,
+) -> Box<dyn PointToPointQuery + 'a> {
+    if restriction == RestrictionPreset::EuLong {
+        let mut query = CSP2AstarCoreCHQuery::new(core_ch.borrow(), ch.borrow());
+        if let Some(reset_nodes) = reset_nodes {
+            query.set_custom_reset_nodes(reset_nodes);
+        }
+        query.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+        Box::new(query)
+    } else {
+        let mut query = CSPAstarCoreCHQuery::new(core_ch.borrow(), ch.borrow());
+        if let Some(reset_nodes) = reset_nodes {
+            query.set_custom_reset_nodes(reset_nodes);
+        }
+        if restriction == RestrictionPreset::None {
+            query.clear_restriction();
+        } else {
+            query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+        }
+        Box::new(query)
+    }
+}
+
+/// The core CH A* query with and without the backward search and pruning on `10 * EXPERIMENTS_BASE_N` random queries
+pub fn avg_opt(dataset: &Path, restriction: RestrictionPreset) -> Result<(), ExperimentError> {
+    let data = Dataset::open(dataset)?;
+    let ch = data.get_ch(CH)?;
+    let core_ch = data.get_core_ch(CORE_CH)?;
+    let queries = random_queries(data.num_nodes(), EXPERIMENTS_BASE_N * 10, EXPERIMENTS_SEED);
+
+    let mut no_bw_no_prune: Box<dyn PointToPointQuery> = if restriction == RestrictionPreset::EuLong {
+        let mut query = CSP2AstarCoreCHQueryNoBwNoPrune::new(core_ch.borrow(), ch.borrow());
+        query.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+        Box::new(query)
+    } else {
+        let mut query = CSPAstarCoreCHQueryNoBwNoPrune::new(core_ch.borrow(), ch.borrow());
+        query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+        Box::new(query)
+    };
+
+    let mut sweep = Sweep::new("");
+    sweep.measure(
+        Algorithm::CoreChAstar.name(),
+        "",
+        core_ch_astar_query(&core_ch, &ch, None, restriction).as_mut(),
+        &queries,
+    )?;
+    sweep.measure("core_ch_astar_no_bw_no_prune", "", no_bw_no_prune.as_mut(), &queries)?;
+    sweep.write_to(output_file("avg_opt", restriction, dataset))?;
+
+    Ok(())
+}
+
+/// Core CH with and without CH potentials for one restriction with each of the given `(max_driving_time, pause_time)`
+fn one_restriction_sweep(
+    dataset: &Path,
+    experiment: &str,
+    parameter_header: &'static str,
+    restrictions: impl Iterator<Item = (Weight, (Weight, Weight))>,
+) -> Result<(), ExperimentError> {
+    let data = Dataset::open(dataset)?;
+    let ch = data.get_ch(CH)?;
+    let core_ch = data.get_core_ch(CORE_CH)?;
+    let queries = random_queries(data.num_nodes(), EXPERIMENTS_BASE_N, EXPERIMENTS_SEED);
+
+    let mut core_ch_query = CSPCoreCHQuery::new(core_ch.borrow());
+    let mut core_ch_astar_query = CSPAstarCoreCHQuery::new(core_ch.borrow(), ch.borrow());
+
+    let mut sweep = Sweep::new(parameter_header);
+    for (parameter, (max_driving_time, pause_time)) in restrictions {
+        core_ch_query.set_restriction(max_driving_time, pause_time);
+        core_ch_astar_query.set_restriction(max_driving_time, pause_time);
+
+        sweep.measure(Algorithm::CoreCh.name(), &parameter.to_string(), &mut core_ch_query, &queries)?;
+        sweep.measure(Algorithm::CoreChAstar.name(), &parameter.to_string(), &mut core_ch_astar_query, &queries)?;
+    }
+    sweep.write_to(output_file(experiment, RestrictionPreset::EuShort, dataset))?;
+
+    Ok(())
+}
+
+/// The EU short restriction with maximum driving times from 0 to 20h in steps of 1000s
+pub fn driving_times(dataset: &Path) -> Result<(), ExperimentError> {
+    one_restriction_sweep(
+        dataset,
+        "driving_times",
+        "max_driving_time",
+        (0..=72_000_000)
+            .step_by(1_000_000)
+            .map(|driving_time| (driving_time, (driving_time, EU_SHORT_PAUSE_TIME))),
+    )
+}
+
+/// The EU short restriction with pause times from 0 to 20h in steps of 1000s
+pub fn break_times(dataset: &Path) -> Result<(), ExperimentError> {
+    one_restriction_sweep(
+        dataset,
+        "break_times",
+        "max_break_time",
+        (0..=72_000_000)
+            .step_by(1_000_000)
+            .map(|pause_time| (pause_time, (EU_SHORT_DRIVING_TIME, pause_time))),
+    )
+}
+
+/// The core CH A* query with the cores listed in the `core_experiment.log` of the dataset directory.
+/// The log is copied next to the results as `thesis_core_sizes-construction-<dataset>.txt`.
+pub fn core_sizes(dataset: &Path, restriction: RestrictionPreset) -> Result<(), ExperimentError> {
+    let data = Dataset::open(dataset)?;
+    let ch = data.get_ch(CH)?;
+    let is_parking_node = Rc::new(data.get_node_flags(PARKING_FLAGS)?);
+    let queries = random_queries(data.num_nodes(), EXPERIMENTS_BASE_N, EXPERIMENTS_SEED);
+    drop(data);
+
+    let log = fs::read_to_string(dataset.join("core_experiment.log"))?;
+    let dataset_name = dataset.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    fs::copy(
+        dataset.join("core_experiment.log"),
+        format!("thesis_core_sizes-construction-{}.txt", dataset_name),
+    )?;
+
+    // columns: relative core size, absolute core size, construction time, core ch directory
+    let mut cores: Vec<(f64, usize, PathBuf)> = Vec::new();
+    for (i, line) in log.lines().enumerate().skip(1) {
+        let columns: Vec<_> = line.split(',').map(str::trim).collect();
+        let invalid = || invalid_data(format!("invalid core in line {} of the core experiment log", i + 1));
+        if columns.len() < 4 {
+            return Err(invalid());
+        }
+
+        let core = (
+            columns[0].parse().map_err(|_| invalid())?,
+            columns[1].parse().map_err(|_| invalid())?,
+            dataset.join(columns[3]),
+        );
+        if !cores.contains(&core) {
+            cores.push(core);
+        }
+    }
+
+    let mut sweep = Sweep::new("rel_core_size,abs_core_size");
+    for (rel_core_size, abs_core_size, core_ch_dir) in cores {
+        let core_ch = OwnedCoreContractionHierarchy::load_from_routingkit_dir(core_ch_dir)?;
+        core_ch.check().map_err(|e| invalid_data(e.to_string()))?;
+
+        let mut query = core_ch_astar_query(&core_ch, &ch, Some(is_parking_node.clone()), restriction);
+        sweep.measure(
+            Algorithm::CoreChAstar.name(),
+            &format!("{},{}", rel_core_size, abs_core_size),
+            query.as_mut(),
+            &queries,
+        )?;
+    }
+    sweep.write_to(output_file("core_sizes", restriction, dataset))?;
+
+    Ok(())
+}
+
+/// Times the core CH A* query on `10 * EXPERIMENTS_BASE_N` random queries on every dataset in a subdirectory whose name
+/// starts with `prefix`. The parameter columns are computed from the rest of the name and the core CH.
+fn dataset_sweep(
+    parent_dir: &Path,
+    experiment: &str,
+    restriction: RestrictionPreset,
+    prefix: &str,
+    parameter_header: &'static str,
+    parameters: impl Fn(&str, &OwnedCoreContractionHierarchy) -> Result<String, ExperimentError>,
+) -> Result<(), ExperimentError> {
+    let mut subdirs: Vec<_> = fs::read_dir(parent_dir)?
+        .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+        .collect::<io::Result<_>>()?;
+    subdirs.sort_unstable();
+
+    let mut sweep = Sweep::new(parameter_header);
+    for subdir in subdirs {
+        let Some(name) = subdir.strip_prefix(prefix) else {
+            continue;
+        };
+
+        let data = Dataset::open(parent_dir.join(&subdir))?;
+        let ch = data.get_ch(CH)?;
+        let core_ch = data.get_core_ch(CORE_CH)?;
+        let parameters = parameters(name, &core_ch)?;
+        let queries = random_queries(data.num_nodes(), EXPERIMENTS_BASE_N * 10, EXPERIMENTS_SEED);
+        drop(data);
+
+        let mut query = core_ch_astar_query(&core_ch, &ch, None, restriction);
+        sweep.measure(Algorithm::CoreChAstar.name(), &parameters, query.as_mut(), &queries)?;
+    }
+    sweep.write_to(output_file(experiment, restriction, parent_dir))?;
+
+    Ok(())
+}
+
+/// The graphs with capped speeds in the `speed_cap_<km/h>` subdirectories
+pub fn speed_caps(parent_dir: &Path, restriction: RestrictionPreset) -> Result<(), ExperimentError> {
+    dataset_sweep(parent_dir, "speed_cap", restriction, "speed_cap_", "speed_cap_kmh", |name, _| {
+        name.parse::<Weight>()
+            .map(|speed_cap| speed_cap.to_string())
+            .map_err(|_| invalid_data(format!("invalid speed cap '{}'", name)))
+    })
+}
+
+/// The cores of different parking sets in the `<parent dir name>_<parking set type>` subdirectories
+pub fn parking_sets(parent_dir: &Path, restriction: RestrictionPreset) -> Result<(), ExperimentError> {
+    let prefix = format!("{}_", parent_dir.file_name().and_then(|name| name.to_str()).unwrap_or_default());
+    dataset_sweep(
+        parent_dir,
+        "parking_set",
+        restriction,
+        &prefix,
+        "parking_set_type,parking_set_size",
+        |name, core_ch| {
+            let core_size = core_ch.is_core().iter().filter(|is_core| *is_core).count();
+            Ok(format!("{},{}", name, core_size))
+        },
+    )
+}
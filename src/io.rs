@@ -24,6 +24,10 @@ use std::{
 
 use bit_vec::BitVec;
 
+pub mod dataset;
+pub mod dimacs;
+pub mod mmap;
+
 /// A trait which allows accessing the data of an object as a slice of bytes.
 /// The bytes should represent a serialization of the object and allow
 /// recreating it when reading these bytes again from the disk.
@@ -119,3 +123,12 @@ pub fn load_routingkit_bitvector<P: AsRef<Path>>(path: P) -> Result<BitVec> {
             .data_bytes(),
     ))
 }
+
+/// Stores a `BitVec` in the format of RoutingKit so that it can be read with `load_routingkit_bitvector`.
+/// The number of bits is stored as a 64 bit header followed by the bits in 64 bit words.
+pub fn store_routingkit_bitvector<P: AsRef<Path>>(bit_vector: &BitVec, path: P) -> Result<()> {
+    let mut bytes = (bit_vector.len() as u64).to_le_bytes().to_vec();
+    bytes.extend(bit_vector.to_bytes().iter().map(|b| b.reverse_bits()));
+    bytes.resize(8 + bit_vector.len().div_ceil(64) * 8, 0);
+    bytes.write_to(&path)
+}
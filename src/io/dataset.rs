@@ -0,0 +1,602 @@
+//! A self-describing single-file container for all data of one extraction.
+//!
+//! Each section stores one vector under a name like `ch/forward/head`, mirroring the RoutingKit directory layout,
+//! together with its element type, length and a checksum. The file header records the number of nodes and arcs
+//! of the graph so that sections from different extractions can not be mixed up unnoticed.
+//! All loaders return a `DatasetError` for corrupted or mismatching data instead of panicking.
+//!
+//! File layout (all integers little endian):
+//!
+//! - header: magic `RTRDSET\0`, version (u32), number of sections (u32), number of nodes (u64), number of arcs (u64)
+//! - per section: name length (u32), name (utf-8), element type (u32), number of elements (u64),
+//!   number of bytes (u64), FNV-1a checksum of the bytes (u64), the bytes
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use rust_truck_router::io::dataset::*;
+//!
+//! let dataset = Dataset::from_routingkit_dir("graph_dir")?;
+//! dataset.write_to("graph.rtr")?;
+//!
+//! let dataset = Dataset::load_from("graph.rtr")?;
+//! let graph = dataset.get_graph(GRAPH)?;
+//! let is_parking_node = dataset.get_node_flags(PARKING_FLAGS)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs::{metadata, File},
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    mem,
+    path::Path,
+};
+
+use bit_vec::BitVec;
+
+use super::{load_routingkit_bitvector, DataBytes, DataBytesMut, Load};
+use crate::{
+    algo::{
+        ch::{ContractionHierarchy, OwnedContractionHierarchy},
+        core_ch::{CoreContractionHierarchy, OwnedCoreContractionHierarchy},
+    },
+    types::*,
+    validation::*,
+};
+
+const MAGIC: &[u8; 8] = b"RTRDSET\0";
+pub const VERSION: u32 = 1;
+
+/// Section name prefix of the road graph
+pub const GRAPH: &str = "graph";
+/// Section name of the parking flags
+pub const PARKING_FLAGS: &str = "routing_parking_flags";
+/// Section name prefix of the contraction hierarchy
+pub const CH: &str = "ch";
+/// Section name prefix of the core contraction hierarchy
+pub const CORE_CH: &str = "core_ch";
+/// Section names of the optional node attributes
+pub const LATITUDE: &str = "latitude";
+pub const LONGITUDE: &str = "longitude";
+pub const OSM_NODE_ID: &str = "osm_node_id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementType {
+    U8,
+    U32,
+    U64,
+    F32,
+    Bits,
+}
+
+impl ElementType {
+    fn from_tag(tag: u32) -> Option<Self> {
+        match tag {
+            0 => Some(ElementType::U8),
+            1 => Some(ElementType::U32),
+            2 => Some(ElementType::U64),
+            3 => Some(ElementType::F32),
+            4 => Some(ElementType::Bits),
+            _ => None,
+        }
+    }
+
+    fn tag(self) -> u32 {
+        self as u32
+    }
+
+    /// `None` if the size does not fit into 64 bits
+    fn num_bytes(self, len: u64) -> Option<u64> {
+        match self {
+            ElementType::U8 => Some(len),
+            ElementType::U32 | ElementType::F32 => len.checked_mul(4),
+            ElementType::U64 => len.checked_mul(8),
+            ElementType::Bits => Some(len.div_ceil(8)),
+        }
+    }
+}
+
+/// Types which can be stored as section elements
+pub trait DatasetElement: Copy + Default {
+    const ELEMENT_TYPE: ElementType;
+}
+
+impl DatasetElement for u8 {
+    const ELEMENT_TYPE: ElementType = ElementType::U8;
+}
+
+impl DatasetElement for u32 {
+    const ELEMENT_TYPE: ElementType = ElementType::U32;
+}
+
+impl DatasetElement for u64 {
+    const ELEMENT_TYPE: ElementType = ElementType::U64;
+}
+
+impl DatasetElement for f32 {
+    const ELEMENT_TYPE: ElementType = ElementType::F32;
+}
+
+#[derive(Debug)]
+pub enum DatasetError {
+    Io(io::Error),
+    /// The file does not start with the dataset magic bytes
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    /// The file ended in the middle of a header or section
+    Truncated,
+    InvalidSectionHeader(String),
+    ChecksumMismatch(String),
+    MissingSection(String),
+    TypeMismatch {
+        section: String,
+        expected: ElementType,
+        found: ElementType,
+    },
+    /// The length of a section does not fit to the header or to other sections
+    LengthMismatch {
+        section: String,
+        expected: usize,
+        found: usize,
+    },
+    /// The data of the sections violates an invariant of the stored structure
+    Invalid {
+        section: String,
+        error: ValidationError,
+    },
+}
+
+impl Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DatasetError::Io(e) => write!(f, "{}", e),
+            DatasetError::InvalidMagic => f.write_str("not a dataset file"),
+            DatasetError::UnsupportedVersion(v) => write!(f, "unsupported dataset version {}", v),
+            DatasetError::Truncated => f.write_str("dataset file is truncated"),
+            DatasetError::InvalidSectionHeader(name) => write!(f, "invalid header of section '{}'", name),
+            DatasetError::ChecksumMismatch(name) => write!(f, "checksum mismatch in section '{}'", name),
+            DatasetError::MissingSection(name) => write!(f, "missing section '{}'", name),
+            DatasetError::TypeMismatch { section, expected, found } => {
+                write!(f, "section '{}' has element type {:?} but {:?} was expected", section, found, expected)
+            }
+            DatasetError::LengthMismatch { section, expected, found } => {
+                write!(f, "section '{}' has {} elements but {} were expected", section, found, expected)
+            }
+            DatasetError::Invalid { section, error } => write!(f, "invalid section '{}': {}", section, error),
+        }
+    }
+}
+
+impl Error for DatasetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DatasetError::Io(e) => Some(e),
+            DatasetError::Invalid { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DatasetError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == ErrorKind::UnexpectedEof {
+            DatasetError::Truncated
+        } else {
+            DatasetError::Io(e)
+        }
+    }
+}
+
+/// 64 bit FNV-1a hash
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+#[derive(Debug, Clone)]
+struct Section {
+    name: String,
+    element_type: ElementType,
+    len: u64,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    num_nodes: usize,
+    num_arcs: usize,
+    sections: Vec<Section>,
+}
+
+impl Dataset {
+    /// Creates an empty dataset for a graph with the given number of nodes and arcs
+    pub fn new(num_nodes: usize, num_arcs: usize) -> Self {
+        Self {
+            num_nodes,
+            num_arcs,
+            sections: Vec::new(),
+        }
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    pub fn num_arcs(&self) -> usize {
+        self.num_arcs
+    }
+
+    pub fn section_names(&self) -> impl Iterator<Item = &str> {
+        self.sections.iter().map(|s| s.name.as_str())
+    }
+
+    /// Whether there is a section or a structure of several sections such as a graph or CH with this name
+    pub fn contains(&self, name: &str) -> bool {
+        self.sections
+            .iter()
+            .any(|s| s.name == name || s.name.strip_prefix(name).is_some_and(|rest| rest.starts_with('/')))
+    }
+
+    fn insert_section(&mut self, section: Section) {
+        match self.sections.iter_mut().find(|s| s.name == section.name) {
+            Some(s) => *s = section,
+            None => self.sections.push(section),
+        }
+    }
+
+    fn section(&self, name: &str, element_type: ElementType) -> Result<&Section, DatasetError> {
+        let section = self
+            .sections
+            .iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| DatasetError::MissingSection(name.to_owned()))?;
+
+        if section.element_type != element_type {
+            return Err(DatasetError::TypeMismatch {
+                section: name.to_owned(),
+                expected: element_type,
+                found: section.element_type,
+            });
+        }
+
+        Ok(section)
+    }
+
+    /// Inserts a vector, replacing any section with the same name
+    pub fn insert<T: DatasetElement>(&mut self, name: &str, data: &[T]) {
+        self.insert_section(Section {
+            name: name.to_owned(),
+            element_type: T::ELEMENT_TYPE,
+            len: data.len() as u64,
+            data: data.data_bytes().to_vec(),
+        });
+    }
+
+    pub fn get<T: DatasetElement>(&self, name: &str) -> Result<Vec<T>, DatasetError> {
+        let section = self.section(name, T::ELEMENT_TYPE)?;
+        debug_assert_eq!(section.data.len(), section.len as usize * mem::size_of::<T>());
+
+        let mut data = Vec::<T>::new_with_bytes(section.data.len());
+        data.data_bytes_mut().copy_from_slice(&section.data);
+        Ok(data)
+    }
+
+    /// Inserts a bit vector, replacing any section with the same name
+    pub fn insert_bitvector(&mut self, name: &str, bit_vector: &BitVec) {
+        self.insert_section(Section {
+            name: name.to_owned(),
+            element_type: ElementType::Bits,
+            len: bit_vector.len() as u64,
+            data: bit_vector.to_bytes(),
+        });
+    }
+
+    pub fn get_bitvector(&self, name: &str) -> Result<BitVec, DatasetError> {
+        let section = self.section(name, ElementType::Bits)?;
+        let mut bit_vector = BitVec::from_bytes(&section.data);
+        bit_vector.truncate(section.len as usize);
+        Ok(bit_vector)
+    }
+
+    /// Like `get` but checks that there is one element for every node
+    pub fn get_node_data<T: DatasetElement>(&self, name: &str) -> Result<Vec<T>, DatasetError> {
+        let data = self.get::<T>(name)?;
+        Self::check_len(name.to_owned(), self.num_nodes, data.len())?;
+        Ok(data)
+    }
+
+    /// Like `get_bitvector` but checks that there is a flag for every node
+    pub fn get_node_flags(&self, name: &str) -> Result<BitVec, DatasetError> {
+        let flags = self.get_bitvector(name)?;
+        if flags.len() < self.num_nodes {
+            return Err(DatasetError::LengthMismatch {
+                section: name.to_owned(),
+                expected: self.num_nodes,
+                found: flags.len(),
+            });
+        }
+        Ok(flags)
+    }
+
+    /// Inserts the sections `<name>/first_out`, `<name>/head` and `<name>/travel_time`
+    pub fn insert_graph<FirstOutContainer, HeadContainer, WeightsContainer>(
+        &mut self,
+        name: &str,
+        graph: &FirstOutGraph<FirstOutContainer, HeadContainer, WeightsContainer>,
+    ) where
+        FirstOutContainer: AsRef<[EdgeId]>,
+        HeadContainer: AsRef<[NodeId]>,
+        WeightsContainer: AsRef<[Weight]>,
+    {
+        self.insert(&format!("{}/first_out", name), graph.first_out());
+        self.insert(&format!("{}/head", name), graph.head());
+        self.insert(&format!("{}/travel_time", name), graph.weights());
+    }
+
+    fn check_len(section: String, expected: usize, found: usize) -> Result<(), DatasetError> {
+        if expected != found {
+            return Err(DatasetError::LengthMismatch { section, expected, found });
+        }
+        Ok(())
+    }
+
+    /// Loads a graph with `num_nodes` nodes and checks that all arrays fit together
+    fn get_graph_with_nodes(&self, name: &str) -> Result<OwnedGraph, DatasetError> {
+        let first_out = self.get::<EdgeId>(&format!("{}/first_out", name))?;
+        let head = self.get::<NodeId>(&format!("{}/head", name))?;
+        let weights = self.get::<Weight>(&format!("{}/travel_time", name))?;
+
+        Self::check_len(format!("{}/first_out", name), self.num_nodes + 1, first_out.len())?;
+        let graph = OwnedGraph::new(first_out, head, weights);
+        check_graph("graph", &graph).map_err(|error| DatasetError::Invalid {
+            section: name.to_owned(),
+            error,
+        })?;
+
+        Ok(graph)
+    }
+
+    /// Loads the graph stored under `name` which must have the number of nodes and arcs of the dataset
+    pub fn get_graph(&self, name: &str) -> Result<OwnedGraph, DatasetError> {
+        let graph = self.get_graph_with_nodes(name)?;
+        Self::check_len(format!("{}/head", name), self.num_arcs, graph.num_arcs())?;
+        Ok(graph)
+    }
+
+    /// Inserts the sections of a contraction hierarchy below `name`
+    pub fn insert_ch<RankOrderContainer, FirstOutContainer, HeadContainer, WeightsContainer>(
+        &mut self,
+        name: &str,
+        ch: &ContractionHierarchy<RankOrderContainer, FirstOutContainer, HeadContainer, WeightsContainer>,
+    ) where
+        RankOrderContainer: AsRef<[NodeId]>,
+        FirstOutContainer: AsRef<[EdgeId]>,
+        HeadContainer: AsRef<[NodeId]>,
+        WeightsContainer: AsRef<[Weight]>,
+    {
+        self.insert(&format!("{}/rank", name), ch.rank());
+        self.insert(&format!("{}/order", name), ch.order());
+        self.insert_graph(&format!("{}/forward", name), &ch.forward());
+        self.insert_graph(&format!("{}/backward", name), &ch.backward());
+    }
+
+    pub fn get_ch(&self, name: &str) -> Result<OwnedContractionHierarchy, DatasetError> {
+        let (rank, order) = self.get_rank_order(name)?;
+        let ch = ContractionHierarchy::new(
+            rank,
+            order,
+            self.get_graph_with_nodes(&format!("{}/forward", name))?,
+            self.get_graph_with_nodes(&format!("{}/backward", name))?,
+        );
+        ch.check().map_err(|error| DatasetError::Invalid {
+            section: name.to_owned(),
+            error,
+        })?;
+
+        Ok(ch)
+    }
+
+    fn get_rank_order(&self, name: &str) -> Result<(Vec<NodeId>, Vec<NodeId>), DatasetError> {
+        let rank = self.get::<NodeId>(&format!("{}/rank", name))?;
+        let order = self.get::<NodeId>(&format!("{}/order", name))?;
+        Self::check_len(format!("{}/rank", name), self.num_nodes, rank.len())?;
+        Self::check_len(format!("{}/order", name), self.num_nodes, order.len())?;
+        check_rank_order(&rank, &order).map_err(|error| DatasetError::Invalid {
+            section: name.to_owned(),
+            error,
+        })?;
+        Ok((rank, order))
+    }
+
+    /// Inserts the sections of a core contraction hierarchy below `name`
+    pub fn insert_core_ch<RankOrderContainer, FirstOutContainer, HeadContainer, WeightsContainer>(
+        &mut self,
+        name: &str,
+        core_ch: &CoreContractionHierarchy<RankOrderContainer, FirstOutContainer, HeadContainer, WeightsContainer>,
+    ) where
+        RankOrderContainer: AsRef<[NodeId]>,
+        FirstOutContainer: AsRef<[EdgeId]>,
+        HeadContainer: AsRef<[NodeId]>,
+        WeightsContainer: AsRef<[Weight]>,
+    {
+        // is_core is indexed by rank
+        let is_core = core_ch.is_core();
+        let core: Vec<NodeId> = core_ch
+            .order()
+            .iter()
+            .enumerate()
+            .filter(|&(rank, _)| is_core.get(rank).unwrap_or(false))
+            .map(|(_, &node)| node)
+            .collect();
+
+        self.insert(&format!("{}/rank", name), core_ch.rank());
+        self.insert(&format!("{}/order", name), core_ch.order());
+        self.insert(&format!("{}/core", name), &core);
+        self.insert_graph(&format!("{}/forward", name), &core_ch.forward());
+        self.insert_graph(&format!("{}/backward", name), &core_ch.backward());
+    }
+
+    pub fn get_core_ch(&self, name: &str) -> Result<OwnedCoreContractionHierarchy, DatasetError> {
+        let (rank, order) = self.get_rank_order(name)?;
+        let core = self.get::<NodeId>(&format!("{}/core", name))?;
+        if let Some(&node) = core.iter().find(|&&node| node as usize >= self.num_nodes) {
+            return Err(DatasetError::LengthMismatch {
+                section: format!("{}/core", name),
+                expected: self.num_nodes,
+                found: node as usize + 1,
+            });
+        }
+
+        let invalid = |error| DatasetError::Invalid {
+            section: name.to_owned(),
+            error,
+        };
+        let core_ch = CoreContractionHierarchy::try_new(
+            rank,
+            order,
+            core,
+            self.get_graph_with_nodes(&format!("{}/forward", name))?,
+            self.get_graph_with_nodes(&format!("{}/backward", name))?,
+        )
+        .map_err(invalid)?;
+        core_ch.check().map_err(invalid)?;
+
+        Ok(core_ch)
+    }
+
+    /// Collects the graph and if present the parking flags, coordinates, osm ids and the `ch` and `core_ch` directories of a RoutingKit directory
+    pub fn from_routingkit_dir<P: AsRef<Path>>(path: P) -> Result<Self, DatasetError> {
+        let path = path.as_ref();
+        let graph = OwnedGraph::load_from_routingkit_dir(path)?;
+        let mut dataset = Dataset::new(graph.num_nodes(), graph.num_arcs());
+        dataset.insert_graph(GRAPH, &graph);
+
+        if path.join(PARKING_FLAGS).exists() {
+            let mut flags = load_routingkit_bitvector(path.join(PARKING_FLAGS))?;
+            flags.truncate(graph.num_nodes());
+            dataset.insert_bitvector(PARKING_FLAGS, &flags);
+        }
+
+        for name in [LATITUDE, LONGITUDE] {
+            if path.join(name).exists() {
+                dataset.insert(name, &Vec::<f32>::load_from(path.join(name))?);
+            }
+        }
+
+        if path.join(OSM_NODE_ID).exists() {
+            dataset.insert(OSM_NODE_ID, &Vec::<u64>::load_from(path.join(OSM_NODE_ID))?);
+        }
+
+        if path.join(CH).exists() {
+            dataset.insert_ch(CH, &OwnedContractionHierarchy::load_from_routingkit_dir(path.join(CH))?);
+        }
+
+        if path.join(CORE_CH).exists() {
+            dataset.insert_core_ch(CORE_CH, &OwnedCoreContractionHierarchy::load_from_routingkit_dir(path.join(CORE_CH))?);
+        }
+
+        Ok(dataset)
+    }
+
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.sections.len() as u32).to_le_bytes())?;
+        writer.write_all(&(self.num_nodes as u64).to_le_bytes())?;
+        writer.write_all(&(self.num_arcs as u64).to_le_bytes())?;
+
+        for section in &self.sections {
+            writer.write_all(&(section.name.len() as u32).to_le_bytes())?;
+            writer.write_all(section.name.as_bytes())?;
+            writer.write_all(&section.element_type.tag().to_le_bytes())?;
+            writer.write_all(&section.len.to_le_bytes())?;
+            writer.write_all(&(section.data.len() as u64).to_le_bytes())?;
+            writer.write_all(&checksum(&section.data).to_le_bytes())?;
+            writer.write_all(&section.data)?;
+        }
+
+        writer.flush()
+    }
+
+    /// Collects a RoutingKit directory or reads a dataset file depending on what the path points to
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DatasetError> {
+        if path.as_ref().is_dir() {
+            Self::from_routingkit_dir(path)
+        } else {
+            Self::load_from(path)
+        }
+    }
+
+    /// Reads a dataset file and verifies the checksums of all sections
+    pub fn load_from<P: AsRef<Path>>(path: P) -> Result<Self, DatasetError> {
+        let file_len = metadata(path.as_ref())?.len();
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(DatasetError::InvalidMagic);
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(DatasetError::UnsupportedVersion(version));
+        }
+
+        let num_sections = read_u32(&mut reader)?;
+        let mut dataset = Dataset::new(read_u64(&mut reader)? as usize, read_u64(&mut reader)? as usize);
+        let mut position = 32;
+
+        for _ in 0..num_sections {
+            let name_len = read_u32(&mut reader)? as u64;
+            if position + 4 + name_len > file_len {
+                return Err(DatasetError::Truncated);
+            }
+            let mut name = vec![0u8; name_len as usize];
+            reader.read_exact(&mut name)?;
+            let name = String::from_utf8(name).map_err(|_| DatasetError::InvalidSectionHeader(String::new()))?;
+
+            let element_type = ElementType::from_tag(read_u32(&mut reader)?).ok_or_else(|| DatasetError::InvalidSectionHeader(name.clone()))?;
+            let len = read_u64(&mut reader)?;
+            let num_bytes = read_u64(&mut reader)?;
+            let expected_checksum = read_u64(&mut reader)?;
+            position += 4 + name_len + 28;
+
+            if element_type.num_bytes(len) != Some(num_bytes) {
+                return Err(DatasetError::InvalidSectionHeader(name));
+            }
+            if position.checked_add(num_bytes).is_none_or(|end| end > file_len) {
+                return Err(DatasetError::Truncated);
+            }
+
+            let mut data = vec![0u8; num_bytes as usize];
+            reader.read_exact(&mut data)?;
+            position += num_bytes;
+
+            if checksum(&data) != expected_checksum {
+                return Err(DatasetError::ChecksumMismatch(name));
+            }
+
+            dataset.insert_section(Section { name, element_type, len, data });
+        }
+
+        Ok(dataset)
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
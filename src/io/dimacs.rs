@@ -0,0 +1,248 @@
+//! Reading and writing graphs in the text formats of the 9th DIMACS implementation challenge on shortest paths.
+//!
+//! - `.gr` files contain the arcs: `p sp <n> <m>` followed by `a <tail> <head> <weight>` lines
+//! - `.co` files contain coordinates: `p aux sp co <n>` followed by `v <id> <lon * 10^6> <lat * 10^6>` lines
+//! - `.flg` files are a companion format for reset node flags: `p aux sp flg <n>` followed by one `f <id>` line per flagged node
+//!
+//! Node ids are 1-based in all files and lines starting with `c` are comments.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use rust_truck_router::io::dimacs::*;
+//!
+//! let graph = read_dimacs_graph("USA-road-t.NY.gr")?;
+//! let (latitude, longitude) = read_dimacs_coordinates("USA-road-d.NY.co")?;
+//! write_dimacs_graph(&graph, "copy.gr")?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Result, Write},
+    path::Path,
+};
+
+use bit_vec::BitVec;
+
+use crate::types::*;
+
+/// DIMACS coordinates are integers in millionths of a degree
+const COORDINATE_FACTOR: f64 = 1_000_000.0;
+
+fn parse_error(line_number: usize, msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("line {}: {}", line_number, msg))
+}
+
+/// Iterates over the non-comment lines of a file as `(line_number, tokens)`
+fn for_each_line<F: FnMut(usize, &[&str]) -> Result<()>>(path: &Path, mut f: F) -> Result<()> {
+    for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        if tokens.is_empty() || tokens[0] == "c" {
+            continue;
+        }
+
+        f(i + 1, &tokens)?;
+    }
+
+    Ok(())
+}
+
+fn parse_token<T: std::str::FromStr>(line_number: usize, token: Option<&&str>) -> Result<T> {
+    token
+        .ok_or_else(|| parse_error(line_number, "missing value"))?
+        .parse()
+        .map_err(|_| parse_error(line_number, "invalid number"))
+}
+
+/// Converts a 1-based id into a 0-based `NodeId` and checks it against the number of nodes
+fn parse_node_id(line_number: usize, token: Option<&&str>, num_nodes: usize) -> Result<NodeId> {
+    let id: usize = parse_token(line_number, token)?;
+
+    if id == 0 || id > num_nodes {
+        return Err(parse_error(line_number, "node id out of range"));
+    }
+
+    Ok((id - 1) as NodeId)
+}
+
+fn expect_problem_line(line_number: usize, num_nodes: Option<usize>) -> Result<usize> {
+    num_nodes.ok_or_else(|| parse_error(line_number, "data before problem line"))
+}
+
+/// Reads a `.gr` file. Arcs do not need to be sorted by their tail.
+pub fn read_dimacs_graph<P: AsRef<Path>>(path: P) -> Result<OwnedGraph> {
+    let mut num_nodes = None;
+    let mut num_arcs = 0;
+    // (tail, head, weight)
+    let mut arcs: Vec<(NodeId, NodeId, Weight)> = Vec::new();
+
+    for_each_line(path.as_ref(), |line_number, tokens| {
+        match tokens[0] {
+            "p" => {
+                if tokens.get(1) != Some(&"sp") {
+                    return Err(parse_error(line_number, "expected problem line 'p sp <n> <m>'"));
+                }
+                num_nodes = Some(parse_token(line_number, tokens.get(2))?);
+                num_arcs = parse_token(line_number, tokens.get(3))?;
+                arcs.reserve(num_arcs);
+            }
+            "a" => {
+                let n = expect_problem_line(line_number, num_nodes)?;
+                arcs.push((
+                    parse_node_id(line_number, tokens.get(1), n)?,
+                    parse_node_id(line_number, tokens.get(2), n)?,
+                    parse_token(line_number, tokens.get(3))?,
+                ));
+            }
+            _ => return Err(parse_error(line_number, "unknown line type")),
+        }
+
+        Ok(())
+    })?;
+
+    let num_nodes = num_nodes.ok_or_else(|| Error::new(ErrorKind::InvalidData, "missing problem line"))?;
+    if arcs.len() != num_arcs {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("expected {} arcs but found {}", num_arcs, arcs.len()),
+        ));
+    }
+
+    // stable to keep the order of parallel arcs
+    arcs.sort_by_key(|&(tail, _, _)| tail);
+
+    let mut first_out = vec![0 as EdgeId; num_nodes + 1];
+    for &(tail, _, _) in &arcs {
+        first_out[tail as usize + 1] += 1;
+    }
+    for i in 1..first_out.len() {
+        first_out[i] += first_out[i - 1];
+    }
+
+    Ok(OwnedGraph::new(
+        first_out,
+        arcs.iter().map(|a| a.1).collect(),
+        arcs.iter().map(|a| a.2).collect(),
+    ))
+}
+
+/// Writes a graph as a `.gr` file
+pub fn write_dimacs_graph<G: OutgoingEdgeIterable, P: AsRef<Path>>(graph: &G, path: P) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writeln!(writer, "p sp {} {}", graph.num_nodes(), graph.num_arcs())?;
+    for node in 0..graph.num_nodes() as NodeId {
+        for (weight, head) in graph.outgoing_edge_iter(node) {
+            writeln!(writer, "a {} {} {}", node + 1, head + 1, weight)?;
+        }
+    }
+
+    writer.flush()
+}
+
+/// Reads a `.co` file and returns the latitude and longitude vectors in degrees
+pub fn read_dimacs_coordinates<P: AsRef<Path>>(path: P) -> Result<(Vec<f32>, Vec<f32>)> {
+    let mut num_nodes = None;
+    let mut latitude = Vec::new();
+    let mut longitude = Vec::new();
+    let mut is_set = BitVec::new();
+
+    for_each_line(path.as_ref(), |line_number, tokens| {
+        match tokens[0] {
+            "p" => {
+                if tokens[1..].len() < 4 || tokens[1..4] != ["aux", "sp", "co"] {
+                    return Err(parse_error(line_number, "expected problem line 'p aux sp co <n>'"));
+                }
+                let n = parse_token(line_number, tokens.get(4))?;
+                num_nodes = Some(n);
+                latitude = vec![0.0; n];
+                longitude = vec![0.0; n];
+                is_set = BitVec::from_elem(n, false);
+            }
+            "v" => {
+                let n = expect_problem_line(line_number, num_nodes)?;
+                let node = parse_node_id(line_number, tokens.get(1), n)? as usize;
+                let x: i64 = parse_token(line_number, tokens.get(2))?;
+                let y: i64 = parse_token(line_number, tokens.get(3))?;
+
+                longitude[node] = (x as f64 / COORDINATE_FACTOR) as f32;
+                latitude[node] = (y as f64 / COORDINATE_FACTOR) as f32;
+                is_set.set(node, true);
+            }
+            _ => return Err(parse_error(line_number, "unknown line type")),
+        }
+
+        Ok(())
+    })?;
+
+    if num_nodes.is_none() {
+        return Err(Error::new(ErrorKind::InvalidData, "missing problem line"));
+    }
+
+    if let Some(missing) = is_set.iter().position(|b| !b) {
+        return Err(Error::new(ErrorKind::InvalidData, format!("missing coordinates for node {}", missing + 1)));
+    }
+
+    Ok((latitude, longitude))
+}
+
+/// Writes latitude and longitude vectors in degrees as a `.co` file
+pub fn write_dimacs_coordinates<P: AsRef<Path>>(latitude: &[f32], longitude: &[f32], path: P) -> Result<()> {
+    assert_eq!(latitude.len(), longitude.len());
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writeln!(writer, "p aux sp co {}", latitude.len())?;
+    for (i, (&lat, &lon)) in latitude.iter().zip(longitude).enumerate() {
+        writeln!(
+            writer,
+            "v {} {} {}",
+            i + 1,
+            (lon as f64 * COORDINATE_FACTOR).round() as i64,
+            (lat as f64 * COORDINATE_FACTOR).round() as i64
+        )?;
+    }
+
+    writer.flush()
+}
+
+/// Reads a `.flg` file with reset node flags
+pub fn read_dimacs_flags<P: AsRef<Path>>(path: P) -> Result<BitVec> {
+    let mut flags = None;
+
+    for_each_line(path.as_ref(), |line_number, tokens| {
+        match tokens[0] {
+            "p" => {
+                if tokens[1..].len() < 4 || tokens[1..4] != ["aux", "sp", "flg"] {
+                    return Err(parse_error(line_number, "expected problem line 'p aux sp flg <n>'"));
+                }
+                flags = Some(BitVec::from_elem(parse_token(line_number, tokens.get(4))?, false));
+            }
+            "f" => {
+                let flags = flags.as_mut().ok_or_else(|| parse_error(line_number, "data before problem line"))?;
+                let node = parse_node_id(line_number, tokens.get(1), flags.len())?;
+                flags.set(node as usize, true);
+            }
+            _ => return Err(parse_error(line_number, "unknown line type")),
+        }
+
+        Ok(())
+    })?;
+
+    flags.ok_or_else(|| Error::new(ErrorKind::InvalidData, "missing problem line"))
+}
+
+/// Writes reset node flags as a `.flg` file. Only the number of nodes given is written,
+/// padding bits of loaded RoutingKit bit vectors are not part of the output.
+pub fn write_dimacs_flags<P: AsRef<Path>>(flags: &BitVec, num_nodes: usize, path: P) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writeln!(writer, "p aux sp flg {}", num_nodes)?;
+    for node in flags.iter().take(num_nodes).enumerate().filter(|(_, b)| *b).map(|(i, _)| i) {
+        writeln!(writer, "f {}", node + 1)?;
+    }
+
+    writer.flush()
+}
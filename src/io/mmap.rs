@@ -0,0 +1,114 @@
+//! Zero-copy loading of serialized vectors by memory-mapping the files.
+//!
+//! A `MappedVec<T>` can be used in place of a `Vec<T>` anywhere a container only needs `AsRef<[T]>`. The element
+//! types are restricted to plain old data by the sealed `MappedElement` trait.
+//! The graph and hierarchy types provide `map_routingkit_dir` loaders building on this,
+//! whose `borrow()` views can be passed to all queries.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use rust_truck_router::{algo::ch::*, types::*};
+//!
+//! let graph = MappedGraph::map_routingkit_dir("graph_dir")?;
+//! let ch = MappedContractionHierarchy::map_routingkit_dir("graph_dir/ch")?;
+//! let mut query = ContractionHierarchyQuery::new(ch.borrow());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Files must not be modified while they are mapped.
+
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Result},
+    marker::PhantomData,
+    mem,
+    path::Path,
+    slice,
+};
+
+use memmap2::Mmap;
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for u8 {}
+    impl Sealed for u32 {}
+    impl Sealed for u64 {}
+    impl Sealed for f32 {}
+}
+
+/// Plain old data types for which every bit pattern is a valid value, so the mapped bytes can be viewed as a slice
+/// of them. Sealed, implemented for `u8`, `u32`, `u64` and `f32`.
+pub trait MappedElement: Copy + sealed::Sealed {}
+
+impl MappedElement for u8 {}
+impl MappedElement for u32 {}
+impl MappedElement for u64 {}
+impl MappedElement for f32 {}
+
+/// A read-only vector backed by a memory-mapped file in the same format as written by `Store`
+pub struct MappedVec<T> {
+    mmap: Mmap,
+    phantom: PhantomData<T>,
+}
+
+impl<T: MappedElement> MappedVec<T> {
+    /// Maps the file at the given path. No data is read until it is accessed.
+    pub fn map_from<P: AsRef<Path>>(path: P) -> Result<Self> {
+        if mem::size_of::<T>() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "zero-sized element types can not be mapped"));
+        }
+        let file = File::open(path.as_ref())?;
+        // safety: the file is only read and the module documentation requires that it is not modified while mapped
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        if mmap.len() % mem::size_of::<T>() != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{}: size of {} bytes is no multiple of the element size {}",
+                    path.as_ref().display(),
+                    mmap.len(),
+                    mem::size_of::<T>()
+                ),
+            ));
+        }
+
+        // mappings start at page boundaries, so this only fails on unusual platforms
+        if !mmap.is_empty() && !mmap.as_ptr().cast::<T>().is_aligned() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: mapping is not aligned to {} bytes", path.as_ref().display(), mem::align_of::<T>()),
+            ));
+        }
+
+        Ok(Self { mmap, phantom: PhantomData })
+    }
+
+    pub fn len(&self) -> usize {
+        self.mmap.len() / mem::size_of::<T>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mmap.is_empty()
+    }
+}
+
+impl<T: MappedElement> AsRef<[T]> for MappedVec<T> {
+    fn as_ref(&self) -> &[T] {
+        if self.mmap.is_empty() {
+            return &[];
+        }
+        // alignment and length were checked on creation and every bit pattern is a valid `T`
+        unsafe { slice::from_raw_parts(self.mmap.as_ptr() as *const T, self.len()) }
+    }
+}
+
+impl<T: MappedElement> std::ops::Deref for MappedVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_ref()
+    }
+}
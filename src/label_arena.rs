@@ -0,0 +1,350 @@
+//! Contiguous storage for the labels of the multi-criteria searches.
+//!
+//! All labels of a search live in one vector in the order they were created. Every node keeps a binary heap of
+//! the indices of its unsettled labels with the best label on top and the indices of its settled labels in the
+//! order they were settled. Labels are never moved or reused until the next reset, so the index of a label stays
+//! a valid predecessor reference for path reconstruction even after the label was dominated.
+//!
+//! Next to the heap every node keeps the `ParetoFront` of its labels for the dominance tests. Dominated labels
+//! are only marked as removed. They are skipped when iterating and dropped once they reach the top of the heap,
+//! so the heap takes constant time to remove a label and logarithmic time to push one. Updating the front on a push
+//! stays linear in the labels of the node, only its dominance test for two criteria is sublinear.
+//!
+//! # Example
+//!
+//! ```
+//! use rust_truck_router::{label_arena::LabelArena, types::*};
+//! use std::cmp::Reverse;
+//!
+//! let label = |distance: Weight2, prev_label| {
+//!     Reverse(Label {
+//!         distance_with_potential: distance[0],
+//!         distance,
+//!         prev_node: 0,
+//!         prev_label,
+//!     })
+//! };
+//!
+//! let mut labels: LabelArena<Weight2> = LabelArena::with_size(2);
+//! let first = labels.get_mut(0).push(label([4, 4], None), |_| ());
+//! labels.get_mut(1).push(label([7, 0], Some(first)), |_| ());
+//! labels.get_mut(1).push(label([5, 5], Some(first)), |_| ());
+//!
+//! // the best label is settled first
+//! assert_eq!(labels.get_mut(1).pop().unwrap().0.distance, [5, 5]);
+//! assert_eq!(labels.get(1).popped().count(), 1);
+//! assert_eq!(labels.get(1).iter().count(), 1);
+//!
+//! // dominated labels are removed but can still be reached through their index
+//! assert!(labels.get(0).is_dominated(&[5, 4]));
+//! labels.get_mut(0).push(label([3, 3], None), |_| ());
+//! assert_eq!(labels.get(0).len(), 1);
+//! assert_eq!(labels.label(first).0.distance, [4, 4]);
+//!
+//! labels.reset();
+//! assert_eq!(labels.num_labels(), 0);
+//! ```
+
+use std::{cmp::Reverse, mem};
+
+use crate::{
+    pareto_front::{ParetoFront, ParetoWeight},
+    timestamped_vector::TimestampedVector,
+    types::{DefaultReset, Label, Weight},
+};
+
+#[derive(Debug, Clone, Copy)]
+struct Entry<L> {
+    label: Reverse<Label<L>>,
+    removed: bool,
+}
+
+#[derive(Debug, Clone)]
+struct NodeLabels<L> {
+    /// Max heap of the unsettled labels including removed ones, `Reverse` turns the best label into the greatest
+    heap: Vec<u32>,
+    /// Settled labels, best first, including removed ones
+    settled: Vec<u32>,
+    front: ParetoFront<L>,
+}
+
+impl<L: Clone> DefaultReset for NodeLabels<L> {
+    const DEFAULT: NodeLabels<L> = NodeLabels {
+        heap: Vec::new(),
+        settled: Vec::new(),
+        front: ParetoFront::new(),
+    };
+
+    // keeps the memory of the heap and the front for the next search
+    fn reset(&mut self) {
+        self.heap.clear();
+        self.settled.clear();
+        self.front.clear();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LabelArena<L> {
+    entries: Vec<Entry<L>>,
+    nodes: TimestampedVector<NodeLabels<L>>,
+}
+
+impl<L: ParetoWeight> LabelArena<L> {
+    pub fn with_size(num_nodes: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            nodes: TimestampedVector::with_size(num_nodes),
+        }
+    }
+
+    /// Removes all labels but keeps the allocated memory for the next search
+    pub fn reset(&mut self) {
+        self.entries.clear();
+        self.nodes.reset();
+    }
+
+    /// Removes all labels and frees their memory
+    pub fn clean(&mut self) {
+        self.entries = Vec::new();
+        for node in &mut self.nodes.vector {
+            *node = NodeLabels::DEFAULT;
+        }
+    }
+
+    /// Number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get(&self, node: usize) -> LabelSet<'_, L> {
+        LabelSet {
+            entries: &self.entries,
+            labels: self.nodes.get(node),
+        }
+    }
+
+    pub fn get_mut(&mut self, node: usize) -> LabelSetMut<'_, L> {
+        LabelSetMut {
+            entries: &mut self.entries,
+            labels: self.nodes.get_mut(node),
+        }
+    }
+
+    /// The label with the index returned by `push` or `peek_index`, also if it was removed since
+    pub fn label(&self, index: usize) -> &Reverse<Label<L>> {
+        &self.entries[index].label
+    }
+
+    /// Number of labels created since the last reset, including removed ones
+    pub fn num_labels(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Bytes used by the labels created since the last reset
+    pub fn label_bytes(&self) -> usize {
+        self.entries.len() * mem::size_of::<Entry<L>>()
+    }
+
+    /// Bytes allocated for the labels, the per node heaps and the Pareto fronts
+    pub fn allocated_bytes(&self) -> usize {
+        let per_node: usize = self
+            .nodes
+            .vector
+            .iter()
+            .map(|n| (n.heap.capacity() + n.settled.capacity()) * mem::size_of::<u32>() + n.front.allocated_bytes())
+            .sum();
+        self.entries.capacity() * mem::size_of::<Entry<L>>() + self.nodes.len() * (mem::size_of::<NodeLabels<L>>() + mem::size_of::<usize>()) + per_node
+    }
+}
+
+/// The labels of one node
+pub struct LabelSet<'a, L> {
+    entries: &'a [Entry<L>],
+    labels: &'a NodeLabels<L>,
+}
+
+impl<'a, L: ParetoWeight> LabelSet<'a, L> {
+    /// Best unsettled label
+    pub fn peek(&self) -> Option<&'a Reverse<Label<L>>> {
+        self.peek_index().map(|i| &self.entries[i].label)
+    }
+
+    pub fn peek_index(&self) -> Option<usize> {
+        self.labels.heap.first().map(|&i| i as usize)
+    }
+
+    /// Unsettled labels in no particular order
+    pub fn iter(&self) -> LabelIter<'a, L> {
+        LabelIter {
+            entries: self.entries,
+            indices: self.labels.heap.iter(),
+        }
+    }
+
+    /// Settled labels, best first
+    pub fn popped(&self) -> LabelIter<'a, L> {
+        LabelIter {
+            entries: self.entries,
+            indices: self.labels.settled.iter(),
+        }
+    }
+
+    /// Whether a settled or unsettled label dominates `distance`
+    #[inline]
+    pub fn is_dominated(&self, distance: &L) -> bool {
+        self.labels.front.dominates(distance)
+    }
+
+    /// Smallest distance of all settled and unsettled labels, infinity if there are none
+    pub fn best_distance(&self) -> Weight {
+        self.labels.front.best_distance()
+    }
+
+    /// Number of settled and unsettled labels
+    pub fn len(&self) -> usize {
+        self.labels.front.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The labels of one node which can be changed
+pub struct LabelSetMut<'a, L> {
+    entries: &'a mut Vec<Entry<L>>,
+    labels: &'a mut NodeLabels<L>,
+}
+
+impl<'a, L: ParetoWeight> LabelSetMut<'a, L> {
+    pub fn peek_index(&self) -> Option<usize> {
+        self.labels.heap.first().map(|&i| i as usize)
+    }
+
+    /// Moves the best unsettled label to the settled labels
+    pub fn pop(&mut self) -> Option<Reverse<Label<L>>> {
+        let index = *self.labels.heap.first()?;
+        pop_heap(self.entries, &mut self.labels.heap);
+        drop_removed_top(self.entries, &mut self.labels.heap);
+
+        // labels are settled best first unless the potential is inconsistent
+        let label = self.entries[index as usize].label;
+        let settled = &mut self.labels.settled;
+        let position = if settled.last().is_none_or(|&last| self.entries[last as usize].label >= label) {
+            settled.len()
+        } else {
+            settled.partition_point(|&i| self.entries[i as usize].label >= label)
+        };
+        settled.insert(position, index);
+        Some(label)
+    }
+
+    /// Whether a settled or unsettled label dominates `distance`
+    #[inline]
+    pub fn is_dominated(&self, distance: &L) -> bool {
+        self.labels.front.dominates(distance)
+    }
+
+    /// Adds an unsettled label which is not dominated and returns its index. The settled and unsettled labels
+    /// dominated by the new one are removed and passed to `removed`. They stay in the arena for path reconstruction.
+    /// The heap takes logarithmic time, updating the `ParetoFront` linear time in the labels of the node.
+    pub fn push<F: FnMut(&Reverse<Label<L>>)>(&mut self, label: Reverse<Label<L>>, mut removed: F) -> usize {
+        let index = self.entries.len();
+        debug_assert!(index < u32::MAX as usize, "too many labels");
+
+        let entries = &mut *self.entries;
+        let mut any_removed = false;
+        self.labels.front.insert(label.0.distance, index as u32, |removed_index| {
+            let entry = &mut entries[removed_index as usize];
+            entry.removed = true;
+            removed(&entry.label);
+            any_removed = true;
+        });
+        if any_removed {
+            drop_removed_top(entries, &mut self.labels.heap);
+        }
+
+        entries.push(Entry { label, removed: false });
+        self.labels.heap.push(index as u32);
+        sift_up(entries, &mut self.labels.heap);
+        index
+    }
+
+    /// Number of settled and unsettled labels
+    pub fn len(&self) -> usize {
+        self.labels.front.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Pops removed labels until the best label of `heap` is one which was not removed
+fn drop_removed_top<L: Ord>(entries: &[Entry<L>], heap: &mut Vec<u32>) {
+    while heap.first().is_some_and(|&i| entries[i as usize].removed) {
+        pop_heap(entries, heap);
+    }
+}
+
+/// Moves the last element of `heap` up to its position
+fn sift_up<L: Ord>(entries: &[Entry<L>], heap: &mut [u32]) {
+    let mut pos = heap.len() - 1;
+    let element = heap[pos];
+    while pos > 0 {
+        let parent = (pos - 1) / 2;
+        if entries[element as usize].label <= entries[heap[parent] as usize].label {
+            break;
+        }
+        heap[pos] = heap[parent];
+        pos = parent;
+    }
+    heap[pos] = element;
+}
+
+/// Removes the top of `heap`
+fn pop_heap<L: Ord>(entries: &[Entry<L>], heap: &mut Vec<u32>) {
+    let last = heap.pop().expect("heap is not empty");
+    if heap.is_empty() {
+        return;
+    }
+
+    let mut pos = 0;
+    loop {
+        let mut child = 2 * pos + 1;
+        if child >= heap.len() {
+            break;
+        }
+        if child + 1 < heap.len() && entries[heap[child] as usize].label < entries[heap[child + 1] as usize].label {
+            child += 1;
+        }
+        if entries[last as usize].label >= entries[heap[child] as usize].label {
+            break;
+        }
+        heap[pos] = heap[child];
+        pos = child;
+    }
+    heap[pos] = last;
+}
+
+/// Iterates over the labels of a node which were not removed
+pub struct LabelIter<'a, L> {
+    entries: &'a [Entry<L>],
+    indices: std::slice::Iter<'a, u32>,
+}
+
+impl<'a, L> Iterator for LabelIter<'a, L> {
+    type Item = &'a Reverse<Label<L>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.indices
+            .by_ref()
+            .map(|&i| &self.entries[i as usize])
+            .find(|entry| !entry.removed)
+            .map(|entry| &entry.label)
+    }
+}
@@ -1,3 +1,4 @@
+#![feature(impl_trait_in_assoc_type)]
 #![feature(generic_associated_types)]
 #![feature(type_alias_impl_trait)]
 #![feature(binary_heap_retain)]
@@ -6,13 +7,23 @@
 #![feature(int_log)]
 
 pub mod algo;
+pub mod bucket_queue;
 pub mod cli;
 pub mod experiments;
 pub mod index_heap;
 pub mod io;
+pub mod label_arena;
+pub mod osm_import;
 pub mod osm_id_mapper;
+pub mod pareto_front;
+pub mod priority_queue;
+pub mod query;
+pub mod radix_heap;
 pub mod rrr_heap;
 pub mod rrr_indexed_heap;
+pub mod server;
+pub mod synthetic;
 pub mod time;
 pub mod timestamped_vector;
 pub mod types;
+pub mod validation;
@@ -1,4 +1,10 @@
-//! (SLOW! AND EASY) Mapping for conversion of osm ids to local ids and vice versa.
+//! Mapping for conversion of osm ids to local ids and vice versa.
+//!
+//! - `BitVec`: (SLOW! AND EASY) scans the bit vector on every call
+//! - `RankSelectMapper`: rank in O(1) and select in O(log n) with about 6% memory overhead over the bit vector
+//! - `SortedIDMapper`: an explicit table of the osm ids of all local nodes, O(log n) to local and O(1) to osm
+//!
+//! Both fast mappers can be loaded from the files written by RoutingKit or the OSM import.
 //!
 //! # Example
 //!
@@ -7,35 +13,228 @@
 //! use rust_truck_router::osm_id_mapper::*;
 //!
 //! let bv = BitVec::from_fn(5, |i| i == 1 || i == 2 || i == 4);
+//! let rank_select = RankSelectMapper::new(&bv);
+//! let sorted = SortedIDMapper::new(vec![1, 2, 4]).unwrap();
 //!
 //! for i in [1,2,4] {
 //!     assert_eq!(bv.to_osm(bv.to_local(i).unwrap()),i);
+//!     assert_eq!(rank_select.to_osm(rank_select.to_local(i).unwrap()),i);
+//!     assert_eq!(sorted.to_local(i), bv.to_local(i));
 //! }
 //!
 //! for i in [0,3] {
 //!     assert_eq!(bv.to_local(i), None);
+//!     assert_eq!(rank_select.to_local(i), None);
+//!     assert_eq!(sorted.to_local(i), None);
 //! }
 //!
 //! ```
 
-use crate::types::NodeId;
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+    path::Path,
+};
+
+use crate::{
+    io::{load_routingkit_bitvector, Load},
+    types::NodeId,
+};
 use bit_vec::BitVec;
 
+/// OSM ids have long exceeded the range of 32 bit integers
+pub type OSMNodeId = u64;
+
 pub trait OSMIDMapper {
-    fn to_local(&self, osm_id: NodeId) -> Option<NodeId>;
-    fn to_osm(&self, local_id: NodeId) -> NodeId;
+    fn to_local(&self, osm_id: OSMNodeId) -> Option<NodeId>;
+    fn to_osm(&self, local_id: NodeId) -> OSMNodeId;
+
+    /// Like `to_local` but returns an error which names the unknown id
+    fn try_to_local(&self, osm_id: OSMNodeId) -> Result<NodeId, UnknownOSMIDError> {
+        self.to_local(osm_id).ok_or(UnknownOSMIDError(osm_id))
+    }
+
+    fn path_to_osm(&self, path: &[NodeId]) -> Vec<OSMNodeId> {
+        path.iter().map(|&node| self.to_osm(node)).collect()
+    }
 }
 
+/// The osm id does not belong to a node of the routing graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownOSMIDError(pub OSMNodeId);
+
+impl Display for UnknownOSMIDError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "osm node {} is not part of the routing graph", self.0)
+    }
+}
+
+impl Error for UnknownOSMIDError {}
+
 impl OSMIDMapper for BitVec {
-    fn to_local(&self, osm_id: NodeId) -> Option<NodeId> {
-        if !self[osm_id as usize] {
+    fn to_local(&self, osm_id: OSMNodeId) -> Option<NodeId> {
+        if !self.get(osm_id as usize)? {
             return None;
         }
 
         Some(self.iter().enumerate().filter(|(_, e)| *e).take_while(|(i, _)| *i < osm_id as usize).count() as NodeId)
     }
 
-    fn to_osm(&self, local_id: NodeId) -> NodeId {
-        self.iter().enumerate().filter(|(_, e)| *e).nth(local_id as usize).unwrap().0 as NodeId
+    fn to_osm(&self, local_id: NodeId) -> OSMNodeId {
+        self.iter().enumerate().filter(|(_, e)| *e).nth(local_id as usize).unwrap().0 as OSMNodeId
+    }
+}
+
+const WORDS_PER_BLOCK: usize = 8;
+const BITS_PER_BLOCK: usize = 64 * WORDS_PER_BLOCK;
+
+/// A bit vector over the osm id space with precomputed ranks for every block of 512 bits.
+/// The local id of a set bit is its rank, i.e. the number of set bits before it.
+#[derive(Debug, Clone)]
+pub struct RankSelectMapper {
+    words: Vec<u64>,
+    len: usize,
+    // number of set bits before each block, with an additional entry for the total number
+    block_ranks: Vec<u32>,
+}
+
+impl RankSelectMapper {
+    pub fn new(bit_vector: &BitVec) -> Self {
+        let len = bit_vector.len();
+        let mut words = vec![0u64; len.div_ceil(64)];
+        for (i, block) in bit_vector.storage().iter().enumerate() {
+            // bit_vec stores bit i at position i % 32 of block i / 32
+            words[i / 2] |= (*block as u64) << (32 * (i % 2));
+        }
+        if len % 64 != 0 {
+            *words.last_mut().unwrap() &= (1 << (len % 64)) - 1;
+        }
+
+        let mut block_ranks = Vec::with_capacity(words.len() / WORDS_PER_BLOCK + 2);
+        let mut rank = 0;
+        for block in words.chunks(WORDS_PER_BLOCK) {
+            block_ranks.push(rank);
+            rank += block.iter().map(|w| w.count_ones()).sum::<u32>();
+        }
+        block_ranks.push(rank);
+
+        Self { words, len, block_ranks }
+    }
+
+    /// Loads a RoutingKit bit vector file such as the routing node flags over all osm ids
+    pub fn load_from_routingkit_bitvector<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(&load_routingkit_bitvector(path)?))
+    }
+
+    /// The number of bits, i.e. the size of the osm id space
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of set bits, i.e. the number of local nodes
+    pub fn num_ones(&self) -> usize {
+        *self.block_ranks.last().unwrap() as usize
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        i < self.len && self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// The number of set bits before position `i`
+    pub fn rank(&self, i: usize) -> usize {
+        let i = i.min(self.len);
+        let word = i / 64;
+        let block = i / BITS_PER_BLOCK;
+
+        let mut rank = self.block_ranks[block] as usize;
+        for w in &self.words[block * WORDS_PER_BLOCK..word] {
+            rank += w.count_ones() as usize;
+        }
+        if !i.is_multiple_of(64) {
+            rank += (self.words[word] & ((1 << (i % 64)) - 1)).count_ones() as usize;
+        }
+
+        rank
+    }
+
+    /// The position of the set bit with rank `k`
+    pub fn select(&self, k: usize) -> Option<usize> {
+        if k >= self.num_ones() {
+            return None;
+        }
+
+        // last block with less than k + 1 set bits before it
+        let block = self.block_ranks.partition_point(|&r| r as usize <= k) - 1;
+        let mut remaining = k - self.block_ranks[block] as usize;
+
+        for (word_index, &w) in self.words.iter().enumerate().skip(block * WORDS_PER_BLOCK) {
+            let ones = w.count_ones() as usize;
+            if remaining < ones {
+                let mut w = w;
+                for _ in 0..remaining {
+                    // clear lowest set bit
+                    w &= w - 1;
+                }
+                return Some(word_index * 64 + w.trailing_zeros() as usize);
+            }
+            remaining -= ones;
+        }
+
+        unreachable!("block ranks are inconsistent with the bits")
+    }
+}
+
+impl OSMIDMapper for RankSelectMapper {
+    fn to_local(&self, osm_id: OSMNodeId) -> Option<NodeId> {
+        if !self.get(osm_id as usize) {
+            return None;
+        }
+
+        Some(self.rank(osm_id as usize) as NodeId)
+    }
+
+    fn to_osm(&self, local_id: NodeId) -> OSMNodeId {
+        self.select(local_id as usize).unwrap() as OSMNodeId
+    }
+}
+
+/// The osm ids of all local nodes in ascending order, the local id of a node is its index
+#[derive(Debug, Clone)]
+pub struct SortedIDMapper {
+    osm_ids: Vec<OSMNodeId>,
+}
+
+impl SortedIDMapper {
+    /// Returns `None` if the ids are not strictly increasing
+    pub fn new(osm_ids: Vec<OSMNodeId>) -> Option<Self> {
+        if !osm_ids.windows(2).all(|w| w[0] < w[1]) {
+            return None;
+        }
+
+        Some(Self { osm_ids })
+    }
+
+    /// Loads a vector of 64 bit osm ids like the `osm_node_id` file of the OSM import
+    pub fn load_from<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(Vec::<OSMNodeId>::load_from(path)?).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "osm ids are not sorted"))
+    }
+
+    pub fn osm_ids(&self) -> &[OSMNodeId] {
+        &self.osm_ids
+    }
+}
+
+impl OSMIDMapper for SortedIDMapper {
+    fn to_local(&self, osm_id: OSMNodeId) -> Option<NodeId> {
+        self.osm_ids.binary_search(&osm_id).ok().map(|i| i as NodeId)
+    }
+
+    fn to_osm(&self, local_id: NodeId) -> OSMNodeId {
+        self.osm_ids[local_id as usize]
     }
 }
@@ -0,0 +1,410 @@
+//! Extraction of truck routing graphs from OpenStreetMap PBF files.
+//!
+//! The extracted graph is written in the same directory layout as the RoutingKit based tool
+//! used for the thesis so that it can be read with `OwnedGraph::load_from_routingkit_dir`
+//! and `load_routingkit_bitvector`. Routing nodes are ordered by their OSM id.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rust_truck_router::osm_import::*;
+//!
+//! let extract = OsmRoutingGraph::import_pbf("karlsruhe.osm.pbf", &OsmImportConfig::default())?;
+//! extract.write_to_routingkit_dir("parking_ka_hgv")?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub mod pbf;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Result,
+    path::Path,
+};
+
+use bit_vec::BitVec;
+
+use crate::{
+    io::{store_routingkit_bitvector, Store},
+    types::*,
+};
+use pbf::{find_tag, Element, PbfReader};
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct OsmImportConfig {
+    /// Maximum speed of trucks in km/h which caps the speed of every road
+    pub max_truck_speed: u32,
+    /// Parking areas which are farther away from the nearest routing node (in meters) are dropped
+    pub max_parking_snap_distance: f64,
+}
+
+impl Default for OsmImportConfig {
+    fn default() -> Self {
+        Self {
+            max_truck_speed: 80,
+            max_parking_snap_distance: 500.0,
+        }
+    }
+}
+
+/// A truck routing graph with travel times in ms together with node coordinates and parking flags
+#[derive(Debug, Clone)]
+pub struct OsmRoutingGraph {
+    pub graph: OwnedGraph,
+    /// Length of each arc in meters
+    pub geo_distance: Vec<Weight>,
+    pub latitude: Vec<f32>,
+    pub longitude: Vec<f32>,
+    pub osm_node_ids: Vec<u64>,
+    pub is_parking_node: BitVec,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Forward,
+    Backward,
+    Both,
+}
+
+struct RoutingWay {
+    refs: Vec<i64>,
+    speed: u32,
+    direction: Direction,
+}
+
+impl OsmRoutingGraph {
+    pub fn import_pbf<P: AsRef<Path>>(path: P, config: &OsmImportConfig) -> Result<Self> {
+        // first pass: ways which are part of the graph and parking areas modelled as ways
+        let mut ways = Vec::new();
+        let mut parking_areas = Vec::new();
+        let mut node_usage: HashMap<i64, u32> = HashMap::new();
+
+        PbfReader::open(path.as_ref())?.for_each_element(|element| {
+            if let Element::Way(way) = element {
+                if way.refs.len() < 2 {
+                    return;
+                }
+
+                if is_truck_parking(&way.tags) {
+                    parking_areas.push(way.refs.clone());
+                }
+
+                if let Some((speed, direction)) = truck_way_speed_and_direction(&way.tags, config.max_truck_speed) {
+                    for (i, &node) in way.refs.iter().enumerate() {
+                        // end points always become routing nodes
+                        let uses = if i == 0 || i == way.refs.len() - 1 { 2 } else { 1 };
+                        *node_usage.entry(node).or_insert(0) += uses;
+                    }
+
+                    ways.push(RoutingWay {
+                        refs: way.refs,
+                        speed,
+                        direction,
+                    });
+                }
+            }
+        })?;
+
+        let parking_area_nodes: HashSet<i64> = parking_areas.iter().flatten().copied().collect();
+
+        // second pass: coordinates of all relevant nodes and parking nodes
+        let mut coordinates: HashMap<i64, (f64, f64)> = HashMap::with_capacity(node_usage.len());
+        let mut parking_points = Vec::new();
+        let mut parking_road_nodes = Vec::new();
+
+        PbfReader::open(path.as_ref())?.for_each_element(|element| {
+            if let Element::Node(node) = element {
+                let is_parking = is_truck_parking(&node.tags);
+
+                if node_usage.contains_key(&node.id) || parking_area_nodes.contains(&node.id) {
+                    coordinates.insert(node.id, (node.lat, node.lon));
+                }
+
+                if is_parking {
+                    match node_usage.get_mut(&node.id) {
+                        // parking on the road itself becomes a routing node
+                        Some(uses) => {
+                            *uses = (*uses).max(2);
+                            parking_road_nodes.push(node.id);
+                        }
+                        None => parking_points.push((node.lat, node.lon)),
+                    }
+                }
+            }
+        })?;
+
+        for area in &parking_areas {
+            let area_coordinates: Vec<_> = area.iter().filter_map(|id| coordinates.get(id)).collect();
+
+            if !area_coordinates.is_empty() {
+                let n = area_coordinates.len() as f64;
+                parking_points.push((
+                    area_coordinates.iter().map(|c| c.0).sum::<f64>() / n,
+                    area_coordinates.iter().map(|c| c.1).sum::<f64>() / n,
+                ));
+            }
+        }
+
+        let mut osm_node_ids: Vec<i64> = node_usage
+            .iter()
+            .filter(|(id, &uses)| uses >= 2 && coordinates.contains_key(id))
+            .map(|(&id, _)| id)
+            .collect();
+        osm_node_ids.sort_unstable();
+
+        let local_id: HashMap<i64, NodeId> = osm_node_ids.iter().enumerate().map(|(i, &id)| (id, i as NodeId)).collect();
+
+        // split ways into arcs between routing nodes
+        // (tail, head, travel time, geo distance)
+        let mut arcs: Vec<(NodeId, NodeId, Weight, Weight)> = Vec::new();
+
+        for way in &ways {
+            let mut segment_start = None;
+            let mut segment_length = 0.0;
+            let mut last_coordinate = None;
+
+            for node in &way.refs {
+                let coordinate = match coordinates.get(node) {
+                    Some(&c) => c,
+                    None => {
+                        // node is missing in the extract, the way is interrupted here
+                        segment_start = None;
+                        last_coordinate = None;
+                        continue;
+                    }
+                };
+
+                if let Some(last) = last_coordinate {
+                    segment_length += haversine_distance(last, coordinate);
+                }
+                last_coordinate = Some(coordinate);
+
+                if let Some(&current) = local_id.get(node) {
+                    if let Some(start) = segment_start {
+                        if start != current {
+                            let travel_time = ((segment_length * 3600.0 / way.speed as f64).round() as Weight).max(1);
+                            let geo_distance = segment_length.round() as Weight;
+
+                            if way.direction != Direction::Backward {
+                                arcs.push((start, current, travel_time, geo_distance));
+                            }
+                            if way.direction != Direction::Forward {
+                                arcs.push((current, start, travel_time, geo_distance));
+                            }
+                        }
+                    }
+
+                    segment_start = Some(current);
+                    segment_length = 0.0;
+                }
+            }
+        }
+
+        arcs.sort_unstable();
+
+        let num_nodes = osm_node_ids.len();
+        let mut first_out = vec![0 as EdgeId; num_nodes + 1];
+        for &(tail, _, _, _) in &arcs {
+            first_out[tail as usize + 1] += 1;
+        }
+        for i in 1..first_out.len() {
+            first_out[i] += first_out[i - 1];
+        }
+
+        let head = arcs.iter().map(|a| a.1).collect();
+        let travel_time = arcs.iter().map(|a| a.2).collect();
+        let geo_distance = arcs.iter().map(|a| a.3).collect();
+
+        let node_coordinates: Vec<(f64, f64)> = osm_node_ids.iter().map(|id| coordinates[id]).collect();
+
+        let mut is_parking_node = BitVec::from_elem(num_nodes, false);
+        for id in &parking_road_nodes {
+            if let Some(&node) = local_id.get(id) {
+                is_parking_node.set(node as usize, true);
+            }
+        }
+
+        let snapping = NearestNodeGrid::new(&node_coordinates, config.max_parking_snap_distance);
+        for &point in &parking_points {
+            if let Some(node) = snapping.nearest(&node_coordinates, point, config.max_parking_snap_distance) {
+                is_parking_node.set(node as usize, true);
+            }
+        }
+
+        Ok(Self {
+            graph: OwnedGraph::new(first_out, head, travel_time),
+            geo_distance,
+            latitude: node_coordinates.iter().map(|c| c.0 as f32).collect(),
+            longitude: node_coordinates.iter().map(|c| c.1 as f32).collect(),
+            osm_node_ids: osm_node_ids.into_iter().map(|id| id as u64).collect(),
+            is_parking_node,
+        })
+    }
+
+    /// Writes `first_out`, `head`, `travel_time`, `geo_distance`, `latitude`, `longitude`, `osm_node_id`
+    /// and `routing_parking_flags` into the given directory which is created if it does not exist.
+    pub fn write_to_routingkit_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        fs::create_dir_all(path)?;
+
+        self.graph.first_out().write_to(&path.join("first_out"))?;
+        self.graph.head().write_to(&path.join("head"))?;
+        self.graph.weights().write_to(&path.join("travel_time"))?;
+        self.geo_distance.write_to(&path.join("geo_distance"))?;
+        self.latitude.write_to(&path.join("latitude"))?;
+        self.longitude.write_to(&path.join("longitude"))?;
+        self.osm_node_ids.write_to(&path.join("osm_node_id"))?;
+        store_routingkit_bitvector(&self.is_parking_node, path.join("routing_parking_flags"))
+    }
+}
+
+/// Truck stops, rest areas and parking lots which explicitly allow trucks
+fn is_truck_parking(tags: &[(&str, &str)]) -> bool {
+    match find_tag(tags, "highway") {
+        Some("rest_area") | Some("services") => return true,
+        _ => {}
+    }
+
+    find_tag(tags, "amenity") == Some("parking") && matches!(find_tag(tags, "hgv"), Some("yes") | Some("designated"))
+}
+
+/// Default speeds in km/h of the road categories which are accessible by trucks
+fn default_speed(highway: &str) -> Option<u32> {
+    Some(match highway {
+        "motorway" => 90,
+        "motorway_link" => 45,
+        "trunk" => 85,
+        "trunk_link" => 40,
+        "primary" => 65,
+        "primary_link" => 30,
+        "secondary" => 55,
+        "secondary_link" => 25,
+        "tertiary" => 40,
+        "tertiary_link" => 20,
+        "unclassified" | "residential" | "road" => 25,
+        "living_street" => 10,
+        "service" => 8,
+        _ => return None,
+    })
+}
+
+fn is_truck_accessible(tags: &[(&str, &str)]) -> bool {
+    // the most specific access tag decides
+    for key in ["hgv", "goods", "motor_vehicle", "vehicle", "access"] {
+        if let Some(value) = find_tag(tags, key) {
+            return !matches!(value, "no" | "private" | "agricultural" | "forestry" | "emergency");
+        }
+    }
+
+    true
+}
+
+/// Parses values like "50", "30 mph" or "none". Other values like "walk" or "signals" are ignored.
+fn parse_maxspeed(value: &str) -> Option<u32> {
+    let value = value.trim();
+
+    if value == "none" {
+        return Some(u32::MAX);
+    }
+
+    if let Some(mph) = value.strip_suffix("mph") {
+        return mph.trim().parse::<f64>().ok().map(|s| (s * 1.609_344).round() as u32);
+    }
+
+    value.strip_suffix("km/h").unwrap_or(value).trim().parse::<f64>().ok().map(|s| s.round() as u32)
+}
+
+fn truck_way_speed_and_direction(tags: &[(&str, &str)], max_truck_speed: u32) -> Option<(u32, Direction)> {
+    let highway = find_tag(tags, "highway")?;
+    let default = default_speed(highway)?;
+
+    if !is_truck_accessible(tags) || find_tag(tags, "area") == Some("yes") {
+        return None;
+    }
+
+    let speed = find_tag(tags, "maxspeed:hgv")
+        .and_then(parse_maxspeed)
+        .or_else(|| find_tag(tags, "maxspeed").and_then(parse_maxspeed))
+        .unwrap_or(default)
+        .min(max_truck_speed)
+        .max(1);
+
+    let direction = match find_tag(tags, "oneway") {
+        Some("yes") | Some("true") | Some("1") => Direction::Forward,
+        Some("-1") | Some("reverse") => Direction::Backward,
+        Some("no") | Some("false") | Some("0") => Direction::Both,
+        _ => {
+            if highway == "motorway" || highway == "motorway_link" || matches!(find_tag(tags, "junction"), Some("roundabout") | Some("circular")) {
+                Direction::Forward
+            } else {
+                Direction::Both
+            }
+        }
+    };
+
+    Some((speed, direction))
+}
+
+/// Great circle distance in meters between two (lat, lon) coordinates in degrees
+pub fn haversine_distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat_from, lat_to) = (from.0.to_radians(), to.0.to_radians());
+    let delta_lat = lat_to - lat_from;
+    let delta_lon = (to.1 - from.1).to_radians();
+
+    let a = (delta_lat / 2.0).sin().powi(2) + lat_from.cos() * lat_to.cos() * (delta_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+/// Uniform grid over node coordinates for nearest neighbor queries within a maximum radius.
+/// Coordinates are `(latitude, longitude)` pairs in degrees.
+pub struct NearestNodeGrid {
+    cell_size: f64,
+    cells: HashMap<(i64, i64), Vec<NodeId>>,
+}
+
+impl NearestNodeGrid {
+    pub fn new(coordinates: &[(f64, f64)], radius: f64) -> Self {
+        // one degree latitude is roughly 111km
+        let cell_size = (radius / 111_000.0).max(1e-4);
+        let mut cells: HashMap<(i64, i64), Vec<NodeId>> = HashMap::new();
+
+        for (i, &(lat, lon)) in coordinates.iter().enumerate() {
+            cells
+                .entry(((lat / cell_size).floor() as i64, (lon / cell_size).floor() as i64))
+                .or_default()
+                .push(i as NodeId);
+        }
+
+        Self { cell_size, cells }
+    }
+
+    /// The closest node within `radius` meters, `coordinates` must be the ones the grid was built from
+    pub fn nearest(&self, coordinates: &[(f64, f64)], point: (f64, f64), radius: f64) -> Option<NodeId> {
+        let lat_cell = (point.0 / self.cell_size).floor() as i64;
+        let lon_cell = (point.1 / self.cell_size).floor() as i64;
+        // longitude degrees get shorter towards the poles
+        let lon_range = (1.0 / point.0.to_radians().cos().max(0.01)).ceil() as i64;
+
+        let mut best = None;
+        let mut best_distance = radius;
+
+        for lat_offset in -1..=1 {
+            for lon_offset in -lon_range..=lon_range {
+                if let Some(nodes) = self.cells.get(&(lat_cell + lat_offset, lon_cell + lon_offset)) {
+                    for &node in nodes {
+                        let distance = haversine_distance(point, coordinates[node as usize]);
+                        if distance <= best_distance {
+                            best_distance = distance;
+                            best = Some(node);
+                        }
+                    }
+                }
+            }
+        }
+
+        best
+    }
+}
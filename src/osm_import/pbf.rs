@@ -0,0 +1,474 @@
+//! A minimal reader for the OpenStreetMap PBF format.
+//!
+//! Only the parts needed for the extraction of routing graphs are decoded: nodes (plain and dense)
+//! and ways including their tags. Relations, metadata and history information are skipped.
+//! Blobs may be stored uncompressed or zlib compressed.
+
+use std::{
+    fs::File,
+    io::{BufReader, Error, ErrorKind, Read, Result},
+    path::Path,
+    str,
+};
+
+use flate2::read::ZlibDecoder;
+
+/// Features which are declared in the file header and can be handled by this reader
+const SUPPORTED_FEATURES: [&str; 2] = ["OsmSchema-V0.6", "DenseNodes"];
+
+/// Upper bounds from the format specification, used to reject corrupted files early
+const MAX_BLOB_HEADER_SIZE: usize = 64 * 1024;
+const MAX_BLOB_SIZE: usize = 32 * 1024 * 1024;
+
+/// An OSM node with its coordinates in degrees
+#[derive(Debug, Clone)]
+pub struct Node<'a> {
+    pub id: i64,
+    pub lat: f64,
+    pub lon: f64,
+    pub tags: Vec<(&'a str, &'a str)>,
+}
+
+/// An OSM way with the ids of the nodes it consists of
+#[derive(Debug, Clone)]
+pub struct Way<'a> {
+    pub id: i64,
+    pub refs: Vec<i64>,
+    pub tags: Vec<(&'a str, &'a str)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Element<'a> {
+    Node(Node<'a>),
+    Way(Way<'a>),
+}
+
+impl<'a> Node<'a> {
+    pub fn tag(&self, key: &str) -> Option<&'a str> {
+        find_tag(&self.tags, key)
+    }
+}
+
+impl<'a> Way<'a> {
+    pub fn tag(&self, key: &str) -> Option<&'a str> {
+        find_tag(&self.tags, key)
+    }
+}
+
+/// The value of the first tag with `key`
+pub fn find_tag<'a>(tags: &[(&'a str, &'a str)], key: &str) -> Option<&'a str> {
+    tags.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+fn invalid_data(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Reads the blobs of a PBF file one after another and hands the decoded elements to a callback.
+pub struct PbfReader<R: Read> {
+    reader: R,
+}
+
+impl PbfReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read> PbfReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// Decodes the whole file and calls `f` for every node and way in file order.
+    pub fn for_each_element<F: FnMut(Element<'_>)>(&mut self, mut f: F) -> Result<()> {
+        while let Some((blob_type, data)) = self.next_blob()? {
+            match blob_type.as_str() {
+                "OSMHeader" => check_header_block(&data)?,
+                "OSMData" => decode_primitive_block(&data, &mut f)?,
+                // unknown blob types must be skipped according to the specification
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the type and the decompressed content of the next blob or `None` at the end of the file
+    fn next_blob(&mut self) -> Result<Option<(String, Vec<u8>)>> {
+        let mut size_bytes = [0u8; 4];
+        match self.reader.read_exact(&mut size_bytes) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let header_size = u32::from_be_bytes(size_bytes) as usize;
+        if header_size > MAX_BLOB_HEADER_SIZE {
+            return Err(invalid_data("blob header exceeds maximum size"));
+        }
+
+        let mut header = vec![0u8; header_size];
+        self.reader.read_exact(&mut header)?;
+
+        let mut blob_type = None;
+        let mut data_size = None;
+        let mut fields = ProtoReader::new(&header);
+        while let Some((field, value)) = fields.next_field()? {
+            match (field, value) {
+                (1, Value::Bytes(b)) => blob_type = Some(str::from_utf8(b).map_err(|_| invalid_data("blob type is not valid utf-8"))?.to_owned()),
+                (3, Value::Varint(v)) => data_size = Some(v as usize),
+                _ => {}
+            }
+        }
+
+        let blob_type = blob_type.ok_or_else(|| invalid_data("blob header without type"))?;
+        let data_size = data_size.ok_or_else(|| invalid_data("blob header without data size"))?;
+        if data_size > MAX_BLOB_SIZE {
+            return Err(invalid_data("blob exceeds maximum size"));
+        }
+
+        let mut blob = vec![0u8; data_size];
+        self.reader.read_exact(&mut blob)?;
+
+        Ok(Some((blob_type, decode_blob(&blob)?)))
+    }
+}
+
+fn decode_blob(blob: &[u8]) -> Result<Vec<u8>> {
+    let mut raw_size = None;
+    let mut fields = ProtoReader::new(blob);
+
+    while let Some((field, value)) = fields.next_field()? {
+        match (field, value) {
+            (1, Value::Bytes(raw)) => return Ok(raw.to_vec()),
+            (2, Value::Varint(v)) => raw_size = Some(v as usize),
+            (3, Value::Bytes(compressed)) => {
+                // never inflate beyond the declared size, a small blob may expand without bound
+                let limit = raw_size.unwrap_or(MAX_BLOB_SIZE).min(MAX_BLOB_SIZE);
+                let mut data = Vec::with_capacity(raw_size.unwrap_or(compressed.len() * 4).min(MAX_BLOB_SIZE));
+                ZlibDecoder::new(compressed).take(limit as u64 + 1).read_to_end(&mut data)?;
+                if data.len() > limit {
+                    return Err(invalid_data("decompressed blob is larger than its declared size"));
+                }
+                return Ok(data);
+            }
+            (4..=7, Value::Bytes(_)) => return Err(Error::new(ErrorKind::Unsupported, "only raw and zlib compressed blobs are supported")),
+            _ => {}
+        }
+    }
+
+    Err(invalid_data("blob without data"))
+}
+
+fn check_header_block(data: &[u8]) -> Result<()> {
+    let mut fields = ProtoReader::new(data);
+
+    while let Some((field, value)) = fields.next_field()? {
+        if let (4, Value::Bytes(feature)) = (field, value) {
+            let feature = str::from_utf8(feature).map_err(|_| invalid_data("required feature is not valid utf-8"))?;
+            if !SUPPORTED_FEATURES.contains(&feature) {
+                return Err(Error::new(ErrorKind::Unsupported, format!("unsupported required feature {}", feature)));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Coordinate transformation parameters of a primitive block
+struct BlockGeometry {
+    granularity: i64,
+    lat_offset: i64,
+    lon_offset: i64,
+}
+
+impl BlockGeometry {
+    fn lat(&self, lat: i64) -> f64 {
+        1e-9 * (self.lat_offset + self.granularity * lat) as f64
+    }
+
+    fn lon(&self, lon: i64) -> f64 {
+        1e-9 * (self.lon_offset + self.granularity * lon) as f64
+    }
+}
+
+fn decode_primitive_block<F: FnMut(Element<'_>)>(data: &[u8], f: &mut F) -> Result<()> {
+    let mut string_table = Vec::new();
+    let mut groups = Vec::new();
+    let mut geometry = BlockGeometry {
+        granularity: 100,
+        lat_offset: 0,
+        lon_offset: 0,
+    };
+
+    // the string table has to be known before the groups can be decoded
+    let mut fields = ProtoReader::new(data);
+    while let Some((field, value)) = fields.next_field()? {
+        match (field, value) {
+            (1, Value::Bytes(table)) => {
+                let mut table_fields = ProtoReader::new(table);
+                while let Some((field, value)) = table_fields.next_field()? {
+                    if let (1, Value::Bytes(s)) = (field, value) {
+                        string_table.push(str::from_utf8(s).map_err(|_| invalid_data("string table entry is not valid utf-8"))?);
+                    }
+                }
+            }
+            (2, Value::Bytes(group)) => groups.push(group),
+            (17, Value::Varint(v)) => geometry.granularity = v as i64,
+            (19, Value::Varint(v)) => geometry.lat_offset = v as i64,
+            (20, Value::Varint(v)) => geometry.lon_offset = v as i64,
+            _ => {}
+        }
+    }
+
+    let string_at = |i: u64| string_table.get(i as usize).copied().ok_or_else(|| invalid_data("string table index out of bounds"));
+
+    for group in groups {
+        let mut group_fields = ProtoReader::new(group);
+        while let Some((field, value)) = group_fields.next_field()? {
+            match (field, value) {
+                (1, Value::Bytes(node)) => f(Element::Node(decode_node(node, &geometry, &string_at)?)),
+                (2, Value::Bytes(dense)) => decode_dense_nodes(dense, &geometry, &string_at, f)?,
+                (3, Value::Bytes(way)) => f(Element::Way(decode_way(way, &string_at)?)),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn decode_tags<'a, S: Fn(u64) -> Result<&'a str>>(keys: &[u64], vals: &[u64], string_at: &S) -> Result<Vec<(&'a str, &'a str)>> {
+    if keys.len() != vals.len() {
+        return Err(invalid_data("number of tag keys and values differ"));
+    }
+
+    keys.iter().zip(vals).map(|(&k, &v)| Ok((string_at(k)?, string_at(v)?))).collect()
+}
+
+fn decode_node<'a, S: Fn(u64) -> Result<&'a str>>(data: &[u8], geometry: &BlockGeometry, string_at: &S) -> Result<Node<'a>> {
+    let mut id = 0;
+    let mut lat = 0;
+    let mut lon = 0;
+    let mut keys = Vec::new();
+    let mut vals = Vec::new();
+
+    let mut fields = ProtoReader::new(data);
+    while let Some((field, value)) = fields.next_field()? {
+        match field {
+            1 => id = zigzag(value.as_varint()?),
+            2 => value.append_repeated(&mut keys)?,
+            3 => value.append_repeated(&mut vals)?,
+            8 => lat = zigzag(value.as_varint()?),
+            9 => lon = zigzag(value.as_varint()?),
+            _ => {}
+        }
+    }
+
+    Ok(Node {
+        id,
+        lat: geometry.lat(lat),
+        lon: geometry.lon(lon),
+        tags: decode_tags(&keys, &vals, string_at)?,
+    })
+}
+
+fn decode_dense_nodes<'a, S: Fn(u64) -> Result<&'a str>, F: FnMut(Element<'_>)>(
+    data: &[u8],
+    geometry: &BlockGeometry,
+    string_at: &S,
+    f: &mut F,
+) -> Result<()> {
+    let mut ids = Vec::new();
+    let mut lats = Vec::new();
+    let mut lons = Vec::new();
+    let mut keys_vals = Vec::new();
+
+    let mut fields = ProtoReader::new(data);
+    while let Some((field, value)) = fields.next_field()? {
+        match field {
+            1 => value.append_repeated(&mut ids)?,
+            8 => value.append_repeated(&mut lats)?,
+            9 => value.append_repeated(&mut lons)?,
+            10 => value.append_repeated(&mut keys_vals)?,
+            _ => {}
+        }
+    }
+
+    if ids.len() != lats.len() || ids.len() != lons.len() {
+        return Err(invalid_data("dense nodes with differing number of ids and coordinates"));
+    }
+
+    let mut id = 0;
+    let mut lat = 0;
+    let mut lon = 0;
+    let mut keys_vals = keys_vals.into_iter();
+
+    for i in 0..ids.len() {
+        // delta coded
+        id += zigzag(ids[i]);
+        lat += zigzag(lats[i]);
+        lon += zigzag(lons[i]);
+
+        // keys and values are stored as pairs and each node is terminated by a 0
+        let mut tags = Vec::new();
+        while let Some(k) = keys_vals.next() {
+            if k == 0 {
+                break;
+            }
+            let v = keys_vals.next().ok_or_else(|| invalid_data("dense node key without value"))?;
+            tags.push((string_at(k)?, string_at(v)?));
+        }
+
+        f(Element::Node(Node {
+            id,
+            lat: geometry.lat(lat),
+            lon: geometry.lon(lon),
+            tags,
+        }));
+    }
+
+    Ok(())
+}
+
+fn decode_way<'a, S: Fn(u64) -> Result<&'a str>>(data: &[u8], string_at: &S) -> Result<Way<'a>> {
+    let mut id = 0;
+    let mut keys = Vec::new();
+    let mut vals = Vec::new();
+    let mut delta_refs = Vec::new();
+
+    let mut fields = ProtoReader::new(data);
+    while let Some((field, value)) = fields.next_field()? {
+        match field {
+            1 => id = value.as_varint()? as i64,
+            2 => value.append_repeated(&mut keys)?,
+            3 => value.append_repeated(&mut vals)?,
+            8 => value.append_repeated(&mut delta_refs)?,
+            _ => {}
+        }
+    }
+
+    let mut current = 0;
+    let refs = delta_refs
+        .into_iter()
+        .map(|d| {
+            current += zigzag(d);
+            current
+        })
+        .collect();
+
+    Ok(Way {
+        id,
+        refs,
+        tags: decode_tags(&keys, &vals, string_at)?,
+    })
+}
+
+#[inline(always)]
+fn zigzag(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    /// 32 and 64 bit fixed size fields are not used by the format and only skipped
+    Fixed,
+}
+
+impl<'a> Value<'a> {
+    fn as_varint(self) -> Result<u64> {
+        match self {
+            Value::Varint(v) => Ok(v),
+            _ => Err(invalid_data("expected varint field")),
+        }
+    }
+
+    /// Repeated scalar fields may be encoded packed or as one field per element
+    fn append_repeated(self, out: &mut Vec<u64>) -> Result<()> {
+        match self {
+            Value::Varint(v) => out.push(v),
+            Value::Bytes(packed) => {
+                let mut reader = ProtoReader::new(packed);
+                while !reader.is_at_end() {
+                    out.push(reader.read_varint()?);
+                }
+            }
+            _ => return Err(invalid_data("expected varint or packed field")),
+        }
+
+        Ok(())
+    }
+}
+
+/// Decoder for the protocol buffer wire format
+struct ProtoReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn read_varint(&mut self) -> Result<u64> {
+        let mut result = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = *self.data.get(self.pos).ok_or_else(|| invalid_data("truncated varint"))?;
+            self.pos += 1;
+            result |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+
+        Err(invalid_data("varint too long"))
+    }
+
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() - self.pos < len {
+            return Err(invalid_data("truncated field"));
+        }
+
+        let slice = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn next_field(&mut self) -> Result<Option<(u32, Value<'a>)>> {
+        if self.is_at_end() {
+            return Ok(None);
+        }
+
+        let key = self.read_varint()?;
+        let field = (key >> 3) as u32;
+
+        let value = match key & 0x7 {
+            0 => Value::Varint(self.read_varint()?),
+            1 => {
+                self.read_slice(8)?;
+                Value::Fixed
+            }
+            2 => {
+                let len = self.read_varint()? as usize;
+                Value::Bytes(self.read_slice(len)?)
+            }
+            5 => {
+                self.read_slice(4)?;
+                Value::Fixed
+            }
+            _ => return Err(invalid_data("unsupported wire type")),
+        };
+
+        Ok(Some((field, value)))
+    }
+}
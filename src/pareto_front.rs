@@ -0,0 +1,181 @@
+//! Pareto fronts of the labels at one node, ordered by the driving time components.
+//!
+//! A label is only added to a node if no label there dominates it and all labels it dominates are removed, so the
+//! labels of a node always form a Pareto front. For two criteria (distance and driving time) the front sorted by
+//! driving time has strictly decreasing distances. Testing dominance is then a binary search for the last label
+//! with a driving time not greater than the new one, and the labels dominated by a new label form one range of the
+//! front. For three criteria the binary search on the first driving time component only limits the labels to check,
+//! the labels before that point are scanned one by one.
+//!
+//! The front also keeps the smallest distance of its labels. No label with a smaller distance can be dominated,
+//! so most dominance tests at the target are answered with a single comparison.
+//!
+//! Only the dominance test for two criteria is sublinear. For three criteria it stays linear in the size of the
+//! front. The front is a sorted vector, so inserting a label and removing the labels it dominates shift the labels
+//! behind them, and for three criteria finding the dominated labels scans all labels with at least the first driving
+//! time component of the new one. Both stay linear in the size of the front for any number of criteria.
+//!
+//! # Example
+//!
+//! ```
+//! use rust_truck_router::{pareto_front::ParetoFront, types::*};
+//!
+//! let mut front: ParetoFront<Weight2> = ParetoFront::new();
+//! front.insert([10, 5], 0, |_| ());
+//! front.insert([8, 7], 1, |_| ());
+//! assert!(front.dominates(&[11, 6]));
+//! assert!(!front.dominates(&[9, 5]));
+//!
+//! let mut removed = Vec::new();
+//! front.insert([7, 5], 2, |index| removed.push(index));
+//! assert_eq!(removed, [0, 1]);
+//! assert_eq!(front.best_distance(), 7);
+//! ```
+
+use crate::types::*;
+
+/// Distance vectors which can be kept in a `ParetoFront`
+pub trait ParetoWeight: WeightOps {
+    /// The driving time components followed by the distance
+    type Key: Ord;
+
+    fn pareto_key(&self) -> Self::Key;
+    fn distance(&self) -> Weight;
+    /// Whether a label of `front`, sorted by `pareto_key`, dominates `weight`
+    fn front_dominates(front: &[(Self, u32)], weight: &Self) -> bool;
+    /// Removes the labels of `front` dominated by `weight` and calls `removed` with their indices
+    fn remove_dominated<F: FnMut(u32)>(front: &mut Vec<(Self, u32)>, weight: &Self, removed: F);
+}
+
+impl ParetoWeight for Weight2 {
+    type Key = (Weight, Weight);
+
+    #[inline(always)]
+    fn pareto_key(&self) -> Self::Key {
+        (self[1], self[0])
+    }
+
+    #[inline(always)]
+    fn distance(&self) -> Weight {
+        self[0]
+    }
+
+    fn front_dominates(front: &[(Self, u32)], weight: &Self) -> bool {
+        // the last label with at most the driving time of `weight` has the smallest distance of those
+        let end = front.partition_point(|(w, _)| w[1] <= weight[1]);
+        end > 0 && front[end - 1].0[0] <= weight[0]
+    }
+
+    fn remove_dominated<F: FnMut(u32)>(front: &mut Vec<(Self, u32)>, weight: &Self, mut removed: F) {
+        let start = front.partition_point(|(w, _)| w[1] < weight[1]);
+        let end = start + front[start..].partition_point(|(w, _)| w[0] >= weight[0]);
+        for (_, index) in front.drain(start..end) {
+            removed(index);
+        }
+    }
+}
+
+impl ParetoWeight for Weight3 {
+    type Key = (Weight, Weight, Weight);
+
+    #[inline(always)]
+    fn pareto_key(&self) -> Self::Key {
+        (self[1], self[2], self[0])
+    }
+
+    #[inline(always)]
+    fn distance(&self) -> Weight {
+        self[0]
+    }
+
+    fn front_dominates(front: &[(Self, u32)], weight: &Self) -> bool {
+        // the labels with at most the first driving time of `weight` are not sorted by the second one
+        let end = front.partition_point(|(w, _)| w[1] <= weight[1]);
+        front[..end].iter().any(|(w, _)| w.dominates(weight))
+    }
+
+    fn remove_dominated<F: FnMut(u32)>(front: &mut Vec<(Self, u32)>, weight: &Self, mut removed: F) {
+        let start = front.partition_point(|(w, _)| w[1] < weight[1]);
+        let mut kept = start;
+        for i in start..front.len() {
+            if weight.dominates(&front[i].0) {
+                removed(front[i].1);
+            } else {
+                front.swap(kept, i);
+                kept += 1;
+            }
+        }
+        front.truncate(kept);
+    }
+}
+
+/// The distances of the labels at one node together with their index in the `LabelArena`
+#[derive(Debug, Clone)]
+pub struct ParetoFront<W> {
+    labels: Vec<(W, u32)>,
+    best_distance: Weight,
+}
+
+impl<W> ParetoFront<W> {
+    pub const fn new() -> Self {
+        Self {
+            labels: Vec::new(),
+            best_distance: INFINITY,
+        }
+    }
+
+    /// Removes all labels but keeps the memory
+    pub fn clear(&mut self) {
+        self.labels.clear();
+        self.best_distance = INFINITY;
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Smallest distance of all labels, infinity if there are none
+    pub fn best_distance(&self) -> Weight {
+        self.best_distance
+    }
+
+    /// Labels sorted by driving time
+    pub fn iter(&self) -> impl Iterator<Item = &(W, u32)> + '_ {
+        self.labels.iter()
+    }
+
+    pub fn allocated_bytes(&self) -> usize {
+        self.labels.capacity() * std::mem::size_of::<(W, u32)>()
+    }
+}
+
+impl<W: ParetoWeight> ParetoFront<W> {
+    /// Whether a label of the front dominates `weight`. Takes logarithmic time for `Weight2` and linear time in
+    /// the size of the front for `Weight3`.
+    #[inline]
+    pub fn dominates(&self, weight: &W) -> bool {
+        weight.distance() >= self.best_distance && W::front_dominates(&self.labels, weight)
+    }
+
+    /// Adds a label which no label of the front dominates. Labels dominated by the new one are removed and
+    /// `removed` is called with their index. Takes linear time in the size of the front.
+    pub fn insert<F: FnMut(u32)>(&mut self, weight: W, index: u32, removed: F) {
+        debug_assert!(!self.dominates(&weight));
+        W::remove_dominated(&mut self.labels, &weight, removed);
+        let key = weight.pareto_key();
+        let position = self.labels.partition_point(|(w, _)| w.pareto_key() < key);
+        self.labels.insert(position, (weight, index));
+        // removed labels had a distance of at least the new one
+        self.best_distance = self.best_distance.min(weight.distance());
+    }
+}
+
+impl<W> Default for ParetoFront<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,119 @@
+//! The operations the searches need from their priority queue.
+//!
+//! `DijkstraData` and the CSP data structs are generic over an `IndexedPriorityQueue` and use the 4-ary
+//! `IndexdMinHeap` by default. The `RadixHeap` and the `BucketQueue` exploit that the keys are integer
+//! milliseconds which, with consistent potentials, never fall below the last popped key.
+//!
+//! # Example
+//!
+//! ```
+//! use rust_truck_router::{
+//!     algo::dijkstra::{Dijkstra, DijkstraData},
+//!     algo::astar::NoPotential,
+//!     priority_queue::IndexedPriorityQueue,
+//!     radix_heap::RadixHeap,
+//!     types::*,
+//! };
+//!
+//! let graph = OwnedGraph::new(vec![0, 1, 2, 2], vec![1, 2], vec![5, 7]);
+//! let dijkstra = Dijkstra::new(graph.borrow());
+//! let mut state: DijkstraData<NoPotential, RadixHeap<State<Weight>>> = DijkstraData::with_queue(graph.num_nodes(), NoPotential {});
+//! state.init_new_s(0);
+//! assert_eq!(dijkstra.dist_query(&mut state, 2), Some(12));
+//! assert!(state.queue.is_empty());
+//! ```
+
+use crate::index_heap::{IndexdMinHeap, Indexing};
+
+/// Queue elements with a non negative integer key, their order has to agree with the order of the keys
+pub trait IntegerKey {
+    fn integer_key(&self) -> u64;
+}
+
+/// A min priority queue whose elements are identified by an index in `[0, max_index)`
+pub trait IndexedPriorityQueue<T: Ord + Indexing> {
+    fn with_max_index(max_index: usize) -> Self;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Checks if the queue contains an element mapped to the given index
+    fn contains_index(&self, id: usize) -> bool;
+
+    /// Drops all elements, keeps the memory
+    fn clear(&mut self);
+
+    /// The smallest element
+    fn peek(&self) -> Option<&T>;
+
+    /// Removes the smallest element
+    fn pop(&mut self) -> Option<T>;
+
+    /// Panics if an element with the same index already exists
+    fn push(&mut self, element: T);
+
+    /// Replaces the element with the same index by one with a smaller key.
+    /// Panics if the element is not part of the queue.
+    fn decrease_key(&mut self, element: T);
+
+    fn get_key_by_index(&self, id: usize) -> Option<&T>;
+
+    /// Allocated bytes
+    fn memory_consumption(&self) -> usize;
+}
+
+impl<T: Ord + Indexing> IndexedPriorityQueue<T> for IndexdMinHeap<T> {
+    fn with_max_index(max_index: usize) -> Self {
+        IndexdMinHeap::new(max_index)
+    }
+
+    fn len(&self) -> usize {
+        IndexdMinHeap::len(self)
+    }
+
+    fn contains_index(&self, id: usize) -> bool {
+        IndexdMinHeap::contains_index(self, id)
+    }
+
+    fn clear(&mut self) {
+        IndexdMinHeap::clear(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        IndexdMinHeap::peek(self)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        IndexdMinHeap::pop(self)
+    }
+
+    fn push(&mut self, element: T) {
+        IndexdMinHeap::push(self, element)
+    }
+
+    fn decrease_key(&mut self, element: T) {
+        IndexdMinHeap::decrease_key(self, element)
+    }
+
+    fn get_key_by_index(&self, id: usize) -> Option<&T> {
+        IndexdMinHeap::get_key_by_index(self, id)
+    }
+
+    fn memory_consumption(&self) -> usize {
+        IndexdMinHeap::memory_consumption(self)
+    }
+}
+
+/// Position of the smallest element of a non empty bucket
+pub(crate) fn min_position<T: Ord>(bucket: &[T]) -> usize {
+    let mut best = 0;
+    for (i, element) in bucket.iter().enumerate().skip(1) {
+        if *element < bucket[best] {
+            best = i;
+        }
+    }
+    best
+}
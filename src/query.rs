@@ -0,0 +1,441 @@
+//! Selection of algorithms and driving time restrictions at runtime, e.g. from command line arguments or requests.
+//!
+//! All queries are created from a `RoutingData` and returned behind the `PointToPointQuery` trait
+//! with the restriction already set, so callers only need to set source and target.
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+    rc::Rc,
+    str::FromStr,
+};
+
+use bit_vec::BitVec;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algo::{
+        astar::Potential,
+        budget::{QueryAborted, SearchBudget},
+        ch::OwnedContractionHierarchy,
+        ch_potential::CHPotential,
+        core_ch::OwnedCoreContractionHierarchy,
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+        csp_2_bidir::CSP2BidirQuery,
+        csp_2_bidir_chpot::CSP2BidirAstarCHPotQuery,
+        csp_2_core_ch::CSP2CoreCHQuery,
+        csp_2_core_ch_chpot::CSP2AstarCoreCHQuery,
+        csp_bidir::CSPBidirQuery,
+        csp_bidir_chpot::CSPBidirAstarCHPotQuery,
+        csp_core_ch::CSPCoreCHQuery,
+        csp_core_ch_chpot::CSPAstarCoreCHQuery,
+        dijkstra::{Dijkstra, DijkstraData},
+        search_space::SearchSpace,
+        stats::QueryStats,
+        PointToPointQuery,
+    },
+    index_heap::IndexdMinHeap,
+    io::dataset::*,
+    osm_id_mapper::{OSMNodeId, SortedIDMapper},
+    priority_queue::IndexedPriorityQueue,
+    types::*,
+};
+
+/// The name of an algorithm or preset could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownNameError(pub String);
+
+impl Display for UnknownNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown name '{}'", self.0)
+    }
+}
+
+impl Error for UnknownNameError {}
+
+/// The driving time restrictions of a query
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestrictionPreset {
+    /// Shortest travel time without breaks
+    None,
+    /// A short break of 45min after at most 4.5h of driving
+    EuShort,
+    /// Additionally a long rest of 11h after at most 9h of driving
+    #[default]
+    EuLong,
+}
+
+impl RestrictionPreset {
+    pub const ALL: [RestrictionPreset; 3] = [RestrictionPreset::None, RestrictionPreset::EuShort, RestrictionPreset::EuLong];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RestrictionPreset::None => "none",
+            RestrictionPreset::EuShort => "eu_short",
+            RestrictionPreset::EuLong => "eu_long",
+        }
+    }
+
+    /// The restrictions of the preset ordered by increasing maximum driving time
+    pub fn restrictions(&self) -> &'static [DrivingTimeRestriction] {
+        match self {
+            RestrictionPreset::None => &[],
+            RestrictionPreset::EuShort => &[EU_SHORT],
+            RestrictionPreset::EuLong => &[EU_SHORT, EU_LONG],
+        }
+    }
+}
+
+impl FromStr for RestrictionPreset {
+    type Err = UnknownNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RestrictionPreset::ALL
+            .into_iter()
+            .find(|preset| preset.name() == s)
+            .ok_or_else(|| UnknownNameError(s.to_owned()))
+    }
+}
+
+impl Display for RestrictionPreset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The algorithms which support driving time restrictions. All of them are exact.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    Dijkstra,
+    /// Dijkstra with CH potentials
+    Astar,
+    Bidir,
+    /// Bidirectional Dijkstra with CH potentials
+    BidirAstar,
+    CoreCh,
+    /// Core CH with CH potentials in the core
+    #[default]
+    CoreChAstar,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 6] = [
+        Algorithm::Dijkstra,
+        Algorithm::Astar,
+        Algorithm::Bidir,
+        Algorithm::BidirAstar,
+        Algorithm::CoreCh,
+        Algorithm::CoreChAstar,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Dijkstra => "dijkstra",
+            Algorithm::Astar => "astar",
+            Algorithm::Bidir => "bidir",
+            Algorithm::BidirAstar => "bidir_astar",
+            Algorithm::CoreCh => "core_ch",
+            Algorithm::CoreChAstar => "core_ch_astar",
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = UnknownNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Algorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == s)
+            .ok_or_else(|| UnknownNameError(s.to_owned()))
+    }
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Everything the queries need, loaded once at startup
+pub struct RoutingData {
+    pub graph: OwnedGraph,
+    pub bw_graph: OwnedGraph,
+    pub is_parking_node: BitVec,
+    pub ch: OwnedContractionHierarchy,
+    pub core_ch: OwnedCoreContractionHierarchy,
+    /// `(latitude, longitude)` of all nodes if available
+    pub coordinates: Option<Vec<(f64, f64)>>,
+    pub osm_ids: Option<SortedIDMapper>,
+}
+
+impl RoutingData {
+    /// Loads and validates all sections needed for running queries
+    pub fn from_dataset(dataset: &Dataset) -> Result<Self, DatasetError> {
+        let coordinates = if dataset.contains(LATITUDE) && dataset.contains(LONGITUDE) {
+            let latitude = dataset.get_node_data::<f32>(LATITUDE)?;
+            let longitude = dataset.get_node_data::<f32>(LONGITUDE)?;
+            Some(latitude.iter().zip(&longitude).map(|(&lat, &lon)| (lat as f64, lon as f64)).collect())
+        } else {
+            None
+        };
+
+        let osm_ids = if dataset.contains(OSM_NODE_ID) {
+            Some(
+                SortedIDMapper::new(dataset.get_node_data::<OSMNodeId>(OSM_NODE_ID)?)
+                    .ok_or_else(|| DatasetError::Io(io::Error::new(io::ErrorKind::InvalidData, "osm node ids are not sorted")))?,
+            )
+        } else {
+            None
+        };
+
+        let graph = dataset.get_graph(GRAPH)?;
+        Ok(Self {
+            bw_graph: OwnedGraph::reverse(graph.borrow()),
+            graph,
+            is_parking_node: dataset.get_node_flags(PARKING_FLAGS)?,
+            ch: dataset.get_ch(CH)?,
+            core_ch: dataset.get_core_ch(CORE_CH)?,
+            coordinates,
+            osm_ids,
+        })
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.graph.num_nodes()
+    }
+}
+
+/// Dijkstra without driving time restrictions, packing algorithm and state into one query
+pub struct DijkstraQuery<'a, P: Potential, Q = IndexdMinHeap<State<Weight>>> {
+    pub dijkstra: Dijkstra<'a>,
+    pub state: DijkstraData<P, Q>,
+    t: NodeId,
+    last_dist: Option<Weight>,
+}
+
+impl<'a, P: Potential, Q: IndexedPriorityQueue<State<Weight>>> DijkstraQuery<'a, P, Q> {
+    pub fn new(dijkstra: Dijkstra<'a>, state: DijkstraData<P, Q>) -> Self {
+        Self {
+            dijkstra,
+            state,
+            t: 0,
+            last_dist: None,
+        }
+    }
+}
+
+impl<'a, P: Potential, Q: IndexedPriorityQueue<State<Weight>>> PointToPointQuery for DijkstraQuery<'a, P, Q> {
+    fn init_new_s(&mut self, s: NodeId) {
+        self.state.init_new_s(s);
+    }
+
+    fn init_new_t(&mut self, t: NodeId) {
+        self.t = t;
+    }
+
+    fn run_query(&mut self) -> Option<Weight> {
+        self.last_dist = self.dijkstra.dist_query(&mut self.state, self.t);
+        self.last_dist
+    }
+
+    fn run_query_with_budget(&mut self, budget: &SearchBudget) -> Result<Option<Weight>, QueryAborted> {
+        self.last_dist = None;
+        self.last_dist = self.dijkstra.dist_query_with_budget(&mut self.state, self.t, budget)?;
+        Ok(self.last_dist)
+    }
+
+    fn stats(&self) -> QueryStats {
+        self.state.query_stats(self.last_dist)
+    }
+
+    fn search_space(&self) -> SearchSpace {
+        SearchSpace::forward(&self.state).with_route(self.state.current_node_path_to(self.t).unwrap_or_default())
+    }
+}
+
+/// Unidirectional Dijkstra with one restriction, packing algorithm and state into one query
+pub struct CSPDijkstraQuery<'a, P: Potential, Q = IndexdMinHeap<State<Weight>>> {
+    pub dijkstra: OneRestrictionDijkstra<'a>,
+    pub state: OneRestrictionDijkstraData<P, Q>,
+    t: NodeId,
+}
+
+impl<'a, P: Potential, Q: IndexedPriorityQueue<State<Weight>>> CSPDijkstraQuery<'a, P, Q> {
+    pub fn new(dijkstra: OneRestrictionDijkstra<'a>, state: OneRestrictionDijkstraData<P, Q>) -> Self {
+        Self { dijkstra, state, t: 0 }
+    }
+}
+
+impl<'a, P: Potential, Q: IndexedPriorityQueue<State<Weight>>> PointToPointQuery for CSPDijkstraQuery<'a, P, Q> {
+    fn init_new_s(&mut self, s: NodeId) {
+        self.state.init_new_s(s);
+    }
+
+    fn init_new_t(&mut self, t: NodeId) {
+        self.t = t;
+    }
+
+    fn run_query(&mut self) -> Option<Weight> {
+        self.dijkstra.dist_query(&mut self.state, self.t)
+    }
+
+    fn run_query_with_budget(&mut self, budget: &SearchBudget) -> Result<Option<Weight>, QueryAborted> {
+        self.dijkstra.dist_query_with_budget(&mut self.state, self.t, budget)
+    }
+
+    fn stats(&self) -> QueryStats {
+        self.state.query_stats()
+    }
+
+    fn search_space(&self) -> SearchSpace {
+        SearchSpace::forward(&self.state).with_route(self.state.current_best_node_path_to(self.t).unwrap_or_default())
+    }
+}
+
+/// Unidirectional Dijkstra with two restrictions, packing algorithm and state into one query
+pub struct CSP2DijkstraQuery<'a, P: Potential, Q = IndexdMinHeap<State<Weight>>> {
+    pub dijkstra: TwoRestrictionDijkstra<'a>,
+    pub state: TwoRestrictionDijkstraData<P, Q>,
+    t: NodeId,
+}
+
+impl<'a, P: Potential, Q: IndexedPriorityQueue<State<Weight>>> CSP2DijkstraQuery<'a, P, Q> {
+    pub fn new(dijkstra: TwoRestrictionDijkstra<'a>, state: TwoRestrictionDijkstraData<P, Q>) -> Self {
+        Self { dijkstra, state, t: 0 }
+    }
+}
+
+impl<'a, P: Potential, Q: IndexedPriorityQueue<State<Weight>>> PointToPointQuery for CSP2DijkstraQuery<'a, P, Q> {
+    fn init_new_s(&mut self, s: NodeId) {
+        self.state.init_new_s(s);
+    }
+
+    fn init_new_t(&mut self, t: NodeId) {
+        self.t = t;
+    }
+
+    fn run_query(&mut self) -> Option<Weight> {
+        self.dijkstra.dist_query(&mut self.state, self.t)
+    }
+
+    fn run_query_with_budget(&mut self, budget: &SearchBudget) -> Result<Option<Weight>, QueryAborted> {
+        self.dijkstra.dist_query_with_budget(&mut self.state, self.t, budget)
+    }
+
+    fn stats(&self) -> QueryStats {
+        self.state.query_stats()
+    }
+
+    fn search_space(&self) -> SearchSpace {
+        SearchSpace::forward(&self.state).with_route(self.state.current_best_node_path_to(self.t).unwrap_or_default())
+    }
+}
+
+/// Sets the restriction on a query with one restriction, `None` clears it
+macro_rules! restrict_one {
+    ($query:expr, $restriction:expr) => {
+        if $restriction == RestrictionPreset::None {
+            $query.clear_restriction();
+        } else {
+            $query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+        }
+    };
+}
+
+/// Creates a query of the given algorithm with the restriction already set.
+/// `RestrictionPreset::EuLong` uses the two restriction variant of the algorithm.
+pub fn new_query<'a>(data: &'a RoutingData, algorithm: Algorithm, restriction: RestrictionPreset) -> Box<dyn PointToPointQuery + 'a> {
+    let num_nodes = data.num_nodes();
+    let reset_nodes = || Rc::new(data.is_parking_node.clone());
+
+    if restriction == RestrictionPreset::EuLong {
+        let eu_long = (EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+
+        return match algorithm {
+            Algorithm::Dijkstra => {
+                let mut state = TwoRestrictionDijkstraData::new(num_nodes);
+                state.set_restriction(eu_long.0, eu_long.1, eu_long.2, eu_long.3);
+                Box::new(CSP2DijkstraQuery::new(
+                    TwoRestrictionDijkstra::new(data.graph.borrow(), &data.is_parking_node),
+                    state,
+                ))
+            }
+            Algorithm::Astar => {
+                let mut state = TwoRestrictionDijkstraData::new_with_potential(num_nodes, CHPotential::from_ch(data.ch.borrow()));
+                state.set_restriction(eu_long.0, eu_long.1, eu_long.2, eu_long.3);
+                Box::new(CSP2DijkstraQuery::new(
+                    TwoRestrictionDijkstra::new(data.graph.borrow(), &data.is_parking_node),
+                    state,
+                ))
+            }
+            Algorithm::Bidir => {
+                let mut query = CSP2BidirQuery::new(data.graph.borrow(), data.bw_graph.borrow(), &data.is_parking_node);
+                query.set_restriction(eu_long.0, eu_long.1, eu_long.2, eu_long.3);
+                Box::new(query)
+            }
+            Algorithm::BidirAstar => {
+                let mut query = CSP2BidirAstarCHPotQuery::new(data.graph.borrow(), data.bw_graph.borrow(), &data.is_parking_node, data.ch.borrow());
+                query.set_restriction(eu_long.0, eu_long.1, eu_long.2, eu_long.3);
+                Box::new(query)
+            }
+            Algorithm::CoreCh => {
+                let mut query = CSP2CoreCHQuery::new(data.core_ch.borrow());
+                query.set_custom_reset_nodes(reset_nodes());
+                query.set_restriction(eu_long.0, eu_long.1, eu_long.2, eu_long.3);
+                Box::new(query)
+            }
+            Algorithm::CoreChAstar => {
+                let mut query = CSP2AstarCoreCHQuery::new(data.core_ch.borrow(), data.ch.borrow());
+                query.set_custom_reset_nodes(reset_nodes());
+                query.set_restriction(eu_long.0, eu_long.1, eu_long.2, eu_long.3);
+                Box::new(query)
+            }
+        };
+    }
+
+    match algorithm {
+        Algorithm::Dijkstra => {
+            let mut state = OneRestrictionDijkstraData::new(num_nodes);
+            restrict_one!(state, restriction);
+            Box::new(CSPDijkstraQuery::new(
+                OneRestrictionDijkstra::new(data.graph.borrow(), &data.is_parking_node),
+                state,
+            ))
+        }
+        Algorithm::Astar => {
+            let mut state = OneRestrictionDijkstraData::new_with_potential(num_nodes, CHPotential::from_ch(data.ch.borrow()));
+            restrict_one!(state, restriction);
+            Box::new(CSPDijkstraQuery::new(
+                OneRestrictionDijkstra::new(data.graph.borrow(), &data.is_parking_node),
+                state,
+            ))
+        }
+        Algorithm::Bidir => {
+            let mut query = CSPBidirQuery::new(data.graph.borrow(), data.bw_graph.borrow(), &data.is_parking_node);
+            restrict_one!(query, restriction);
+            Box::new(query)
+        }
+        Algorithm::BidirAstar => {
+            let mut query = CSPBidirAstarCHPotQuery::new(data.graph.borrow(), data.bw_graph.borrow(), &data.is_parking_node, data.ch.borrow());
+            restrict_one!(query, restriction);
+            Box::new(query)
+        }
+        Algorithm::CoreCh => {
+            let mut query = CSPCoreCHQuery::new(data.core_ch.borrow());
+            query.set_custom_reset_nodes(reset_nodes());
+            restrict_one!(query, restriction);
+            Box::new(query)
+        }
+        Algorithm::CoreChAstar => {
+            let mut query = CSPAstarCoreCHQuery::new(data.core_ch.borrow(), data.ch.borrow());
+            query.set_custom_reset_nodes(reset_nodes());
+            restrict_one!(query, restriction);
+            Box::new(query)
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! A radix heap for elements with integer keys.
+//!
+//! Bucket `i > 0` holds the elements whose key first differs from the last popped key in bit `i - 1`,
+//! bucket 0 the elements with a key not greater than it. Popping takes the smallest element of the first
+//! non empty bucket and, unless that is bucket 0, first redistributes that bucket relative to its smallest key.
+//! Every element moves to a lower bucket at most 64 times, so with monotone keys the amortized time for
+//! `pop` is `O(log C)` where `C` is the largest difference of two keys in the queue.
+//!
+//! Keys smaller than the last popped key are allowed but end up in bucket 0, which is scanned on every `pop`.
+//!
+//! # Example
+//!
+//! ```
+//! use rust_truck_router::{priority_queue::IndexedPriorityQueue, radix_heap::RadixHeap, types::*};
+//!
+//! let mut heap = RadixHeap::with_max_index(3);
+//! heap.push(State { node: 0, distance: 42 as Weight });
+//! heap.push(State { node: 1, distance: 23 });
+//! heap.push(State { node: 2, distance: 50000 });
+//! assert_eq!(heap.peek().cloned(), Some(State { node: 1, distance: 23 }));
+//! assert_eq!(heap.pop(), Some(State { node: 1, distance: 23 }));
+//! heap.decrease_key(State { node: 2, distance: 30 });
+//! assert_eq!(heap.pop(), Some(State { node: 2, distance: 30 }));
+//! assert_eq!(heap.pop(), Some(State { node: 0, distance: 42 }));
+//! assert!(heap.is_empty());
+//! ```
+
+use std::mem::{self, size_of};
+
+use crate::{
+    index_heap::Indexing,
+    priority_queue::{min_position, IndexedPriorityQueue, IntegerKey},
+};
+
+const NUM_BUCKETS: usize = 65;
+const INVALID_BUCKET: u32 = u32::MAX;
+
+#[derive(Debug, Clone)]
+pub struct RadixHeap<T> {
+    buckets: Vec<Vec<T>>,
+    /// Bucket and position in the bucket of every index
+    positions: Vec<(u32, u32)>,
+    last_key: u64,
+    len: usize,
+}
+
+impl<T: Ord + Indexing + IntegerKey> RadixHeap<T> {
+    #[inline]
+    fn bucket_of(&self, key: u64) -> usize {
+        if key <= self.last_key {
+            0
+        } else {
+            64 - (key ^ self.last_key).leading_zeros() as usize
+        }
+    }
+
+    fn insert(&mut self, element: T) {
+        let bucket = self.bucket_of(element.integer_key());
+        self.positions[element.as_index()] = (bucket as u32, self.buckets[bucket].len() as u32);
+        self.buckets[bucket].push(element);
+    }
+
+    fn remove_at(&mut self, bucket: usize, position: usize) -> T {
+        let element = self.buckets[bucket].swap_remove(position);
+        if let Some(moved) = self.buckets[bucket].get(position) {
+            self.positions[moved.as_index()].1 = position as u32;
+        }
+        element
+    }
+
+    fn first_non_empty_bucket(&self) -> Option<usize> {
+        self.buckets.iter().position(|b| !b.is_empty())
+    }
+}
+
+impl<T: Ord + Indexing + IntegerKey> IndexedPriorityQueue<T> for RadixHeap<T> {
+    fn with_max_index(max_index: usize) -> Self {
+        RadixHeap {
+            buckets: (0..NUM_BUCKETS).map(|_| Vec::new()).collect(),
+            positions: vec![(INVALID_BUCKET, 0); max_index],
+            last_key: 0,
+            len: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn contains_index(&self, id: usize) -> bool {
+        self.positions[id].0 != INVALID_BUCKET
+    }
+
+    fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            for element in bucket.iter() {
+                self.positions[element.as_index()] = (INVALID_BUCKET, 0);
+            }
+            bucket.clear();
+        }
+        self.last_key = 0;
+        self.len = 0;
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.first_non_empty_bucket().map(|b| &self.buckets[b][min_position(&self.buckets[b])])
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let bucket = self.first_non_empty_bucket()?;
+        if bucket > 0 {
+            // all elements of the bucket move to lower buckets
+            self.last_key = self.buckets[bucket][min_position(&self.buckets[bucket])].integer_key();
+            let mut elements = mem::take(&mut self.buckets[bucket]);
+            for element in elements.drain(..) {
+                self.insert(element);
+            }
+            self.buckets[bucket] = elements;
+        }
+
+        let element = self.remove_at(0, min_position(&self.buckets[0]));
+        self.positions[element.as_index()] = (INVALID_BUCKET, 0);
+        self.len -= 1;
+        Some(element)
+    }
+
+    fn push(&mut self, element: T) {
+        assert!(!self.contains_index(element.as_index()));
+        self.insert(element);
+        self.len += 1;
+    }
+
+    fn decrease_key(&mut self, element: T) {
+        let (bucket, position) = self.positions[element.as_index()];
+        self.remove_at(bucket as usize, position as usize);
+        self.insert(element);
+    }
+
+    fn get_key_by_index(&self, id: usize) -> Option<&T> {
+        if !self.contains_index(id) {
+            return None;
+        }
+
+        let (bucket, position) = self.positions[id];
+        Some(&self.buckets[bucket as usize][position as usize])
+    }
+
+    fn memory_consumption(&self) -> usize {
+        self.positions.capacity() * size_of::<(u32, u32)>() + self.buckets.iter().map(|b| b.capacity() * size_of::<T>()).sum::<usize>()
+    }
+}
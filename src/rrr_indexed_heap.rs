@@ -357,6 +357,15 @@ impl<T: Ord + Copy> AutoIndexedHeap<T> {
         }
     }
 
+    /// Number of settled and unsettled elements
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
     pub fn clear(&mut self) {
         self.data.clear();
         self.positions.clear();
@@ -0,0 +1,423 @@
+//! A minimal single-threaded HTTP server which answers routing requests with JSON.
+//!
+//! The data is loaded once and all queries use the CSP core CH queries with CH potentials.
+//! Only the standard library is used for networking so that the server runs fully offline.
+//!
+//! - `GET /health` returns `{"status":"ok","num_nodes":...}`
+//! - `POST /route` expects a `RouteRequest` and returns a `RouteResponse` or `{"error":"..."}`,
+//!   bodies larger than `MAX_REQUEST_BODY_SIZE` are rejected with 413
+//!
+//! Locations are given as `{"node": 42}`, `{"osm": 123456}` or `{"lat": 49.0, "lon": 8.4}`.
+//! The restriction is one of `"none"`, `"eu_short"` (4.5h driving, 45min break) and `"eu_long"`
+//! (additionally 9h driving, 11h rest), the default is `"eu_long"`. The optional `driver_state` gives the
+//! driving times in ms at departure, e.g. `{"driving_time_since_break": 3600000, "driving_time_since_rest": 18000000}`,
+//! which have to be below the maximum driving times of the restriction.
+//!
+//! ```text
+//! POST /route
+//! {"source": {"node": 0}, "target": {"osm": 123456}, "restriction": "eu_short", "itinerary": true}
+//! ```
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    rc::Rc,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algo::{
+        ch_potential::CHPotential,
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+        csp_2_core_ch_chpot::CSP2AstarCoreCHQuery,
+        csp_core_ch_chpot::CSPAstarCoreCHQuery,
+    },
+    osm_id_mapper::{OSMIDMapper, OSMNodeId},
+    osm_import::NearestNodeGrid,
+    query::{RestrictionPreset, RoutingData},
+    types::*,
+};
+
+/// Coordinates further away from the closest node are rejected
+pub const DEFAULT_MAX_SNAP_DISTANCE: f64 = 1000.0;
+
+/// Request bodies with a larger `Content-Length` are answered with 413 without being read
+pub const MAX_REQUEST_BODY_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Location {
+    Node { node: NodeId },
+    Osm { osm: OSMNodeId },
+    Coordinate { lat: f64, lon: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteRequest {
+    pub source: Location,
+    pub target: Location,
+    #[serde(default)]
+    pub restriction: RestrictionPreset,
+    #[serde(default)]
+    pub driver_state: DriverState,
+    /// Whether to compute the node sequence and break locations
+    #[serde(default)]
+    pub itinerary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Waypoint {
+    pub node: NodeId,
+    pub osm: Option<OSMNodeId>,
+    pub lat: Option<f32>,
+    pub lon: Option<f32>,
+    /// Time since departure including breaks in ms
+    pub time: Weight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakKind {
+    Short,
+    Long,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Break {
+    pub location: Waypoint,
+    pub kind: BreakKind,
+    pub duration: Weight,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteResponse {
+    pub source: NodeId,
+    pub target: NodeId,
+    /// Travel time including breaks in ms, `None` if the target can not be reached
+    pub distance: Option<Weight>,
+    pub itinerary: Option<Vec<Waypoint>>,
+    pub breaks: Option<Vec<Break>>,
+}
+
+/// A request which can not be answered, the message is returned to the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestError(pub String);
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for RequestError {}
+
+/// Plain Dijkstra based searches on the original graph which are only used to unpack itineraries
+struct PathSearches<'a> {
+    csp: OneRestrictionDijkstra<'a>,
+    csp_state: OneRestrictionDijkstraData<CHPotential<'a>>,
+    csp_2: TwoRestrictionDijkstra<'a>,
+    csp_2_state: TwoRestrictionDijkstraData<CHPotential<'a>>,
+}
+
+pub struct RoutingServer<'a> {
+    data: &'a RoutingData,
+    nearest_node_grid: Option<NearestNodeGrid>,
+    max_snap_distance: f64,
+    csp_query: CSPAstarCoreCHQuery<'a>,
+    csp_2_query: CSP2AstarCoreCHQuery<'a>,
+    path_searches: Option<PathSearches<'a>>,
+}
+
+impl<'a> RoutingServer<'a> {
+    pub fn new(data: &'a RoutingData) -> Self {
+        let is_parking_node = Rc::new(data.is_parking_node.clone());
+
+        let mut csp_query = CSPAstarCoreCHQuery::new(data.core_ch.borrow(), data.ch.borrow());
+        csp_query.set_custom_reset_nodes(is_parking_node.clone());
+        let mut csp_2_query = CSP2AstarCoreCHQuery::new(data.core_ch.borrow(), data.ch.borrow());
+        csp_2_query.set_custom_reset_nodes(is_parking_node);
+        csp_2_query.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+
+        Self {
+            data,
+            nearest_node_grid: data
+                .coordinates
+                .as_ref()
+                .map(|coordinates| NearestNodeGrid::new(coordinates, DEFAULT_MAX_SNAP_DISTANCE)),
+            max_snap_distance: DEFAULT_MAX_SNAP_DISTANCE,
+            csp_query,
+            csp_2_query,
+            path_searches: None,
+        }
+    }
+
+    fn resolve(&self, location: &Location) -> Result<NodeId, RequestError> {
+        match *location {
+            Location::Node { node } => {
+                if node as usize >= self.data.graph.num_nodes() {
+                    return Err(RequestError(format!("node {} does not exist", node)));
+                }
+                Ok(node)
+            }
+            Location::Osm { osm } => self
+                .data
+                .osm_ids
+                .as_ref()
+                .ok_or_else(|| RequestError("no osm ids loaded".to_owned()))?
+                .try_to_local(osm)
+                .map_err(|e| RequestError(e.to_string())),
+            Location::Coordinate { lat, lon } => {
+                let (grid, coordinates) = self
+                    .nearest_node_grid
+                    .as_ref()
+                    .zip(self.data.coordinates.as_ref())
+                    .ok_or_else(|| RequestError("no coordinates loaded".to_owned()))?;
+
+                grid.nearest(coordinates, (lat, lon), self.max_snap_distance)
+                    .ok_or_else(|| RequestError(format!("no node within {}m of ({}, {})", self.max_snap_distance, lat, lon)))
+            }
+        }
+    }
+
+    pub fn route(&mut self, request: &RouteRequest) -> Result<RouteResponse, RequestError> {
+        let s = self.resolve(&request.source)?;
+        let t = self.resolve(&request.target)?;
+
+        let driver_state = request.driver_state;
+        if driver_state.driving_time_since_break > driver_state.driving_time_since_rest {
+            return Err(RequestError(
+                "the driving time since the last break exceeds the one since the last rest".to_owned(),
+            ));
+        }
+        // a driver who has reached a maximum driving time has to take a break before departure
+        let driving_times = [
+            ("since the last break", driver_state.driving_time_since_break),
+            ("since the last rest", driver_state.driving_time_since_rest),
+        ];
+        for (restriction, (since, driving_time)) in request.restriction.restrictions().iter().zip(driving_times) {
+            if driving_time >= restriction.max_driving_time {
+                return Err(RequestError(format!(
+                    "the driving time {} of {}ms reaches the maximum driving time of {}ms",
+                    since, driving_time, restriction.max_driving_time
+                )));
+            }
+        }
+
+        let distance = match request.restriction {
+            RestrictionPreset::None | RestrictionPreset::EuShort => {
+                if request.restriction == RestrictionPreset::None {
+                    self.csp_query.clear_restriction();
+                } else {
+                    self.csp_query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+                }
+                self.csp_query.set_driver_state(driver_state);
+                self.csp_query.init_new_s(s);
+                self.csp_query.init_new_t(t);
+                self.csp_query.run_query()
+            }
+            RestrictionPreset::EuLong => {
+                self.csp_2_query.set_driver_state(driver_state);
+                self.csp_2_query.init_new_s(s);
+                self.csp_2_query.init_new_t(t);
+                self.csp_2_query.run_query()
+            }
+        }
+        // the searches measure from the start of the planning horizon
+        .map(|distance| distance - driver_state.departure_offset);
+
+        let (itinerary, breaks) = if request.itinerary && distance.is_some() {
+            let (itinerary, breaks) = self.itinerary(s, t, request.restriction, &driver_state);
+            (Some(itinerary), Some(breaks))
+        } else {
+            (None, None)
+        };
+
+        Ok(RouteResponse {
+            source: s,
+            target: t,
+            distance,
+            itinerary,
+            breaks,
+        })
+    }
+
+    fn itinerary(&mut self, s: NodeId, t: NodeId, restriction: RestrictionPreset, driver_state: &DriverState) -> (Vec<Waypoint>, Vec<Break>) {
+        let data = self.data;
+        let searches = self.path_searches.get_or_insert_with(|| PathSearches {
+            csp: OneRestrictionDijkstra::new(data.graph.borrow(), &data.is_parking_node),
+            csp_state: OneRestrictionDijkstraData::new_with_potential(data.graph.num_nodes(), CHPotential::from_ch(data.ch.borrow())),
+            csp_2: TwoRestrictionDijkstra::new(data.graph.borrow(), &data.is_parking_node),
+            csp_2_state: TwoRestrictionDijkstraData::new_with_potential(data.graph.num_nodes(), CHPotential::from_ch(data.ch.borrow())),
+        });
+
+        let mut itinerary = Vec::new();
+        let mut breaks = Vec::new();
+
+        match restriction {
+            RestrictionPreset::None | RestrictionPreset::EuShort => {
+                if restriction == RestrictionPreset::None {
+                    searches.csp_state.clear_restriction();
+                } else {
+                    searches.csp_state.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+                }
+                searches.csp_state.init_new_s_with_driver_state(s, driver_state);
+                searches.csp.dist_query(&mut searches.csp_state, t);
+
+                let (path, distances) = searches.csp_state.current_best_path_to(t, true).unwrap_or_default();
+                for (i, (&node, distance)) in path.iter().zip(&distances).enumerate() {
+                    let time = distance[0] - driver_state.departure_offset;
+                    itinerary.push(waypoint(data, node, time));
+                    if restriction == RestrictionPreset::EuShort && data.is_parking_node[node as usize] && distance[1] == 0 && i != 0 {
+                        breaks.push(Break {
+                            location: waypoint(data, node, time - EU_SHORT_PAUSE_TIME),
+                            kind: BreakKind::Short,
+                            duration: EU_SHORT_PAUSE_TIME,
+                        });
+                    }
+                }
+            }
+            RestrictionPreset::EuLong => {
+                searches.csp_2_state.init_new_s_with_driver_state(s, driver_state);
+                searches
+                    .csp_2_state
+                    .set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+                searches.csp_2.dist_query(&mut searches.csp_2_state, t);
+
+                let (path, distances) = searches.csp_2_state.current_best_path_to(t, true).unwrap_or_default();
+                for (i, (&node, distance)) in path.iter().zip(&distances).enumerate() {
+                    let time = distance[0] - driver_state.departure_offset;
+                    itinerary.push(waypoint(data, node, time));
+                    if !data.is_parking_node[node as usize] || i == 0 {
+                        continue;
+                    }
+
+                    // a long rest also resets the time until the next short break
+                    let (kind, duration) = if distance[2] == 0 {
+                        (BreakKind::Long, EU_LONG_PAUSE_TIME)
+                    } else if distance[1] == 0 {
+                        (BreakKind::Short, EU_SHORT_PAUSE_TIME)
+                    } else {
+                        continue;
+                    };
+
+                    breaks.push(Break {
+                        location: waypoint(data, node, time - duration),
+                        kind,
+                        duration,
+                    });
+                }
+            }
+        }
+
+        (itinerary, breaks)
+    }
+
+    /// Answers a request body with a status code and a JSON body
+    pub fn handle_json(&mut self, body: &[u8]) -> (u16, String) {
+        let request: RouteRequest = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(e) => return (400, error_json(&format!("invalid request: {}", e))),
+        };
+
+        match self.route(&request) {
+            Ok(response) => (200, serde_json::to_string(&response).unwrap()),
+            Err(e) => (400, error_json(&e.0)),
+        }
+    }
+
+    /// Reads one HTTP request from the stream, answers it and closes the connection
+    pub fn handle_connection(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let mut reader = BufReader::new(stream);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_owned();
+        let target = parts.next().unwrap_or_default().to_owned();
+
+        // `None` if the header can not be parsed
+        let mut content_length = Some(0);
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().ok();
+                }
+            }
+        }
+
+        let (status, body) = match (method.as_str(), target.as_str()) {
+            ("GET", "/health") => (200, serde_json::json!({ "status": "ok", "num_nodes": self.data.graph.num_nodes() }).to_string()),
+            ("POST", "/route") => match content_length {
+                None => (400, error_json("invalid content length")),
+                Some(content_length) if content_length > MAX_REQUEST_BODY_SIZE => (413, error_json("request body too large")),
+                Some(content_length) => {
+                    let mut body = vec![0; content_length];
+                    reader.read_exact(&mut body)?;
+                    self.handle_json(&body)
+                }
+            },
+            _ => (404, error_json("not found")),
+        };
+
+        let reason = match status {
+            200 => "OK",
+            400 => "Bad Request",
+            413 => "Payload Too Large",
+            _ => "Not Found",
+        };
+
+        let mut stream = reader.into_inner();
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            reason,
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+
+    /// Answers requests one after another, failed connections are logged and skipped
+    pub fn serve(&mut self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = self.handle_connection(stream) {
+                eprintln!("Failed to handle request: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn waypoint(data: &RoutingData, node: NodeId, time: Weight) -> Waypoint {
+    let coordinate = data.coordinates.as_ref().map(|c| c[node as usize]);
+    Waypoint {
+        node,
+        osm: data.osm_ids.as_ref().map(|m| m.to_osm(node)),
+        lat: coordinate.map(|c| c.0 as f32),
+        lon: coordinate.map(|c| c.1 as f32),
+        time,
+    }
+}
+
+fn error_json(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
@@ -0,0 +1,235 @@
+//! Generators for synthetic road networks with parking flags, so that tests of the driving time restricted
+//! algorithms do not depend on real world data.
+//!
+//! All generators are seeded and place their nodes on a plane around `SyntheticConfig::origin`. Travel times
+//! are derived from the euclidean lengths of the arcs like in `osm_import`, so the graphs can be written
+//! as RoutingKit directories together with coordinates and loaded as any other dataset.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rust_truck_router::synthetic::*;
+//!
+//! let config = SyntheticConfig { parking_density: 0.05, ..SyntheticConfig::default() };
+//! let corridor = motorway_corridor(50, 20_000.0, 3, 2_000.0, &config);
+//! corridor.write_to_routingkit_dir("corridor")?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{fs, io::Result, path::Path};
+
+use bit_vec::BitVec;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    io::{store_routingkit_bitvector, Store},
+    types::*,
+};
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SyntheticConfig {
+    /// Probability of a node to be a parking node
+    pub parking_density: f64,
+    pub seed: u64,
+    /// Speed in km/h on all arcs, side roads of motorway corridors use half of it
+    pub speed: u32,
+    /// `(latitude, longitude)` of the point with plane coordinates `(0, 0)`
+    pub origin: (f64, f64),
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        Self {
+            parking_density: 0.01,
+            seed: 42,
+            speed: 80,
+            origin: (49.0, 8.4),
+        }
+    }
+}
+
+/// A generated road network with travel times in ms, node coordinates and parking flags
+#[derive(Debug, Clone)]
+pub struct SyntheticGraph {
+    pub graph: OwnedGraph,
+    pub latitude: Vec<f32>,
+    pub longitude: Vec<f32>,
+    pub is_parking_node: BitVec,
+}
+
+impl SyntheticGraph {
+    /// Writes `first_out`, `head`, `travel_time`, `latitude`, `longitude` and `routing_parking_flags`
+    /// into the given directory which is created if it does not exist.
+    pub fn write_to_routingkit_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        fs::create_dir_all(path)?;
+
+        self.graph.first_out().write_to(&path.join("first_out"))?;
+        self.graph.head().write_to(&path.join("head"))?;
+        self.graph.weights().write_to(&path.join("travel_time"))?;
+        self.latitude.write_to(&path.join("latitude"))?;
+        self.longitude.write_to(&path.join("longitude"))?;
+        store_routingkit_bitvector(&self.is_parking_node, path.join("routing_parking_flags"))
+    }
+}
+
+/// Collects nodes and bidirectional roads given in plane coordinates (meters) and builds the graph
+struct Builder<'c> {
+    config: &'c SyntheticConfig,
+    rng: StdRng,
+    positions: Vec<(f64, f64)>,
+    arcs: Vec<(NodeId, NodeId, Weight)>,
+    is_parking_node: Vec<bool>,
+}
+
+impl<'c> Builder<'c> {
+    fn new(config: &'c SyntheticConfig) -> Self {
+        Self {
+            config,
+            rng: StdRng::seed_from_u64(config.seed),
+            positions: Vec::new(),
+            arcs: Vec::new(),
+            is_parking_node: Vec::new(),
+        }
+    }
+
+    fn add_node(&mut self, position: (f64, f64), may_park: bool) -> NodeId {
+        let is_parking = may_park && self.rng.gen_bool(self.config.parking_density.clamp(0.0, 1.0));
+        self.positions.push(position);
+        self.is_parking_node.push(is_parking);
+        (self.positions.len() - 1) as NodeId
+    }
+
+    fn add_road(&mut self, a: NodeId, b: NodeId, speed: u32) {
+        let (from, to) = (self.positions[a as usize], self.positions[b as usize]);
+        let length = ((from.0 - to.0).powi(2) + (from.1 - to.1).powi(2)).sqrt();
+        let travel_time = ((length * 3600.0 / speed as f64).round() as Weight).max(1);
+        self.arcs.push((a, b, travel_time));
+        self.arcs.push((b, a, travel_time));
+    }
+
+    fn build(mut self) -> SyntheticGraph {
+        self.arcs.sort_unstable();
+
+        let num_nodes = self.positions.len();
+        let mut first_out = vec![0 as EdgeId; num_nodes + 1];
+        for &(tail, _, _) in &self.arcs {
+            first_out[tail as usize + 1] += 1;
+        }
+        for i in 1..first_out.len() {
+            first_out[i] += first_out[i - 1];
+        }
+
+        let (origin_lat, origin_lon) = self.config.origin;
+        let latitude = self
+            .positions
+            .iter()
+            .map(|&(_, y)| (origin_lat + (y / EARTH_RADIUS_METERS).to_degrees()) as f32)
+            .collect();
+        let longitude = self
+            .positions
+            .iter()
+            .map(|&(x, _)| (origin_lon + (x / (EARTH_RADIUS_METERS * origin_lat.to_radians().cos())).to_degrees()) as f32)
+            .collect();
+
+        SyntheticGraph {
+            graph: OwnedGraph::new(first_out, self.arcs.iter().map(|a| a.1).collect(), self.arcs.iter().map(|a| a.2).collect()),
+            latitude,
+            longitude,
+            is_parking_node: self.is_parking_node.into_iter().collect(),
+        }
+    }
+}
+
+/// A `width` x `height` grid with bidirectional roads of length `spacing` meters between neighboring nodes.
+/// Node `y * width + x` is at column `x` and row `y`.
+pub fn grid(width: usize, height: usize, spacing: f64, config: &SyntheticConfig) -> SyntheticGraph {
+    let mut builder = Builder::new(config);
+
+    for y in 0..height {
+        for x in 0..width {
+            builder.add_node((x as f64 * spacing, y as f64 * spacing), true);
+        }
+    }
+
+    for y in 0..height {
+        for x in 0..width {
+            let node = (y * width + x) as NodeId;
+            if x + 1 < width {
+                builder.add_road(node, node + 1, config.speed);
+            }
+            if y + 1 < height {
+                builder.add_road(node, node + width as NodeId, config.speed);
+            }
+        }
+    }
+
+    builder.build()
+}
+
+/// `num_nodes` nodes placed uniformly at random in a square with sides of `side_length` meters,
+/// connected by a road if they are at most `radius` meters apart. The graph is not necessarily connected.
+pub fn random_geometric(num_nodes: usize, side_length: f64, radius: f64, config: &SyntheticConfig) -> SyntheticGraph {
+    let mut builder = Builder::new(config);
+    let mut position_rng = StdRng::seed_from_u64(config.seed.wrapping_add(1));
+
+    for _ in 0..num_nodes {
+        let position = (position_rng.gen_range(0.0..side_length), position_rng.gen_range(0.0..side_length));
+        builder.add_node(position, true);
+    }
+
+    // sweep over the nodes ordered by x so that only nodes within the radius in x direction are compared
+    let mut by_x: Vec<NodeId> = (0..num_nodes as NodeId).collect();
+    by_x.sort_unstable_by(|&a, &b| builder.positions[a as usize].0.partial_cmp(&builder.positions[b as usize].0).unwrap());
+
+    for (i, &a) in by_x.iter().enumerate() {
+        let pos_a = builder.positions[a as usize];
+        for &b in &by_x[i + 1..] {
+            let pos_b = builder.positions[b as usize];
+            if pos_b.0 - pos_a.0 > radius {
+                break;
+            }
+            if (pos_a.0 - pos_b.0).powi(2) + (pos_a.1 - pos_b.1).powi(2) <= radius * radius {
+                builder.add_road(a, b, config.speed);
+            }
+        }
+    }
+
+    builder.build()
+}
+
+/// A straight motorway of `num_junctions` junctions which are `junction_spacing` meters apart. At every junction
+/// a side road of `side_road_length` nodes, `side_road_spacing` meters apart, branches off at half the speed.
+/// Only motorway junctions can be parking nodes, like rest areas along a motorway.
+///
+/// Junction `i` has the id `i`, the side road nodes follow after all junctions.
+pub fn motorway_corridor(
+    num_junctions: usize,
+    junction_spacing: f64,
+    side_road_length: usize,
+    side_road_spacing: f64,
+    config: &SyntheticConfig,
+) -> SyntheticGraph {
+    let mut builder = Builder::new(config);
+
+    for i in 0..num_junctions {
+        builder.add_node((i as f64 * junction_spacing, 0.0), true);
+    }
+    for i in 1..num_junctions as NodeId {
+        builder.add_road(i - 1, i, config.speed);
+    }
+
+    let side_road_speed = (config.speed / 2).max(1);
+    for junction in 0..num_junctions {
+        let mut previous = junction as NodeId;
+        for j in 1..=side_road_length {
+            let node = builder.add_node((junction as f64 * junction_spacing, j as f64 * side_road_spacing), false);
+            builder.add_road(previous, node, side_road_speed);
+            previous = node;
+        }
+    }
+
+    builder.build()
+}
@@ -1,5 +1,11 @@
 //! This module contains a few basic type and constant definitions
-use crate::{index_heap::*, io::Load, rrr_indexed_heap::AutoIndexedHeap};
+use crate::{
+    index_heap::*,
+    io::{mmap::MappedVec, Load},
+    priority_queue::IntegerKey,
+    rrr_indexed_heap::AutoIndexedHeap,
+};
+use serde::{Deserialize, Serialize};
 use std::{cmp::Reverse, path::Path};
 
 /// Node ids are unsigned 32 bit integers
@@ -18,6 +24,18 @@ pub const EU_SHORT_PAUSE_TIME: Weight = 2_700_000; // 45 minutes
 pub const EU_LONG_PAUSE_TIME: Weight = 39_600_000; // 11 hours
 pub const EU_SHORT_DRIVING_TIME: Weight = 16_200_000; // 4.5 hours
 pub const EU_LONG_DRIVING_TIME: Weight = 32_400_000; // 9 hours
+pub const HOUR: Weight = 3_600_000;
+
+/// A short break of 45min after at most 4.5h of driving
+pub const EU_SHORT: DrivingTimeRestriction = DrivingTimeRestriction {
+    pause_time: EU_SHORT_PAUSE_TIME,
+    max_driving_time: EU_SHORT_DRIVING_TIME,
+};
+/// A long rest of 11h after at most 9h of driving
+pub const EU_LONG: DrivingTimeRestriction = DrivingTimeRestriction {
+    pause_time: EU_LONG_PAUSE_TIME,
+    max_driving_time: EU_LONG_DRIVING_TIME,
+};
 
 pub trait DefaultReset: Clone {
     const DEFAULT: Self;
@@ -163,6 +181,55 @@ pub struct DrivingTimeRestriction {
     pub max_driving_time: Weight,
 }
 
+/// The state of the driver at departure, the default is a rested driver departing at time zero.
+/// Searches with one restriction start with the driving time since the last short break.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DriverState {
+    /// Driving time since the last short break in ms
+    pub driving_time_since_break: Weight,
+    /// Driving time since the last long rest in ms, a rest also counts as a break so this is not less than
+    /// `driving_time_since_break`
+    pub driving_time_since_rest: Weight,
+    /// Departure time relative to the start of the planning horizon in ms, all distances of a search include it
+    pub departure_offset: Weight,
+}
+
+impl DriverState {
+    /// Distance of the start label of a search with one restriction
+    pub fn weight2(&self) -> Weight2 {
+        [self.departure_offset, self.driving_time_since_break]
+    }
+
+    /// Distance of the start label of a search with two restrictions
+    pub fn weight3(&self) -> Weight3 {
+        [self.departure_offset, self.driving_time_since_break, self.driving_time_since_rest]
+    }
+}
+
+/// Driving time the driver must have left at the target, e.g. to leave a customer without taking a break first.
+/// A break at the target does not count. The default requires nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemainingDrivingTime {
+    /// Driving time left until the next short break in ms
+    pub until_break: Weight,
+    /// Driving time left until the next long rest in ms
+    pub until_rest: Weight,
+}
+
+impl RemainingDrivingTime {
+    /// Driving times of a search with one restriction which the remaining driving time adds at the target
+    pub fn weight2(&self) -> Weight2 {
+        [0, self.until_break]
+    }
+
+    /// Driving times of a search with two restrictions which the remaining driving time adds at the target
+    pub fn weight3(&self) -> Weight3 {
+        [0, self.until_break, self.until_rest]
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Ord, PartialOrd)]
 pub struct State<T> {
     pub distance: T,
@@ -175,6 +242,13 @@ impl<T> Indexing for State<T> {
     }
 }
 
+impl IntegerKey for State<Weight> {
+    #[inline(always)]
+    fn integer_key(&self) -> u64 {
+        self.distance as u64
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FirstOutGraph<FirstOutContainer, HeadContainer, WeightsContainer> {
     first_out: FirstOutContainer,
@@ -198,6 +272,7 @@ pub trait OutgoingEdgeIterable: Graph {
 
 pub type OwnedGraph = FirstOutGraph<Vec<EdgeId>, Vec<NodeId>, Vec<Weight>>;
 pub type BorrowedGraph<'a> = FirstOutGraph<&'a [EdgeId], &'a [NodeId], &'a [Weight]>;
+pub type MappedGraph = FirstOutGraph<MappedVec<EdgeId>, MappedVec<NodeId>, MappedVec<Weight>>;
 
 impl<FirstOutContainer, HeadContainer, WeightsContainer> FirstOutGraph<FirstOutContainer, HeadContainer, WeightsContainer>
 where
@@ -293,6 +368,17 @@ impl OwnedGraph {
     }
 }
 
+impl MappedGraph {
+    /// Memory-maps the graph files of a RoutingKit directory instead of copying them into memory
+    pub fn map_routingkit_dir<P: AsRef<Path>>(path: P) -> Result<Self, std::io::Error> {
+        Ok(Self {
+            first_out: MappedVec::map_from(path.as_ref().join("first_out"))?,
+            head: MappedVec::map_from(path.as_ref().join("head"))?,
+            weights: MappedVec::map_from(path.as_ref().join("travel_time"))?,
+        })
+    }
+}
+
 impl<'a> Copy for BorrowedGraph<'a> {}
 
 impl<FirstOutContainer, HeadContainer, WeightsContainer> OutgoingEdgeIterable for FirstOutGraph<FirstOutContainer, HeadContainer, WeightsContainer>
@@ -317,6 +403,7 @@ pub struct Label<T> {
     pub distance_with_potential: Weight,
     pub distance: T,
     pub prev_node: NodeId,
+    /// Index of the predecessor label in the `LabelArena` of the search
     pub prev_label: Option<usize>,
 }
 
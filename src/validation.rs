@@ -0,0 +1,241 @@
+//! Validation of graphs and hierarchies which reports violated invariants as errors instead of panicking.
+//!
+//! The `check` methods of `ContractionHierarchy`, `CoreContractionHierarchy` and the core CH queries build on
+//! the functions in this module. All functions stop at the first violation found.
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+use bit_vec::BitVec;
+
+use crate::types::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// `first_out` needs at least the entry for the end of the last node
+    EmptyFirstOut {
+        component: &'static str,
+    },
+    FirstOutNotStartingAtZero {
+        component: &'static str,
+        found: EdgeId,
+    },
+    /// `first_out[node + 1]` is smaller than `first_out[node]`
+    FirstOutDecreasing {
+        component: &'static str,
+        node: NodeId,
+    },
+    /// The length of `head` or `weights` does not match the last entry of `first_out`
+    ArcCountMismatch {
+        component: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    HeadOutOfRange {
+        component: &'static str,
+        arc: EdgeId,
+        head: NodeId,
+    },
+    /// Two parts which describe the same nodes have different sizes
+    NodeCountMismatch {
+        component: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// A node id in a list of nodes is not smaller than the number of nodes
+    NodeOutOfRange {
+        component: &'static str,
+        node: NodeId,
+    },
+    /// `order` is not the inverse permutation of `rank`
+    RankOrderMismatch {
+        node: NodeId,
+    },
+    /// A hierarchy arc which does not lead to a node of higher rank
+    NotUpward {
+        component: &'static str,
+        arc: EdgeId,
+        tail: NodeId,
+        head: NodeId,
+    },
+    /// An arc of a graph which has no counterpart in the supposedly reversed graph
+    MissingReverseArc {
+        tail: NodeId,
+        head: NodeId,
+        weight: Weight,
+    },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::EmptyFirstOut { component } => write!(f, "{}: first_out is empty", component),
+            ValidationError::FirstOutNotStartingAtZero { component, found } => {
+                write!(f, "{}: first_out starts with {} instead of 0", component, found)
+            }
+            ValidationError::FirstOutDecreasing { component, node } => write!(f, "{}: first_out decreases after node {}", component, node),
+            ValidationError::ArcCountMismatch { component, expected, found } => {
+                write!(f, "{}: expected {} arcs according to first_out but found {}", component, expected, found)
+            }
+            ValidationError::HeadOutOfRange { component, arc, head } => write!(f, "{}: head {} of arc {} is no valid node", component, head, arc),
+            ValidationError::NodeCountMismatch { component, expected, found } => {
+                write!(f, "{}: expected {} nodes but found {}", component, expected, found)
+            }
+            ValidationError::NodeOutOfRange { component, node } => write!(f, "{}: {} is no valid node", component, node),
+            ValidationError::RankOrderMismatch { node } => write!(f, "order is not the inverse of rank for node {}", node),
+            ValidationError::NotUpward { component, arc, tail, head } => {
+                write!(f, "{}: arc {} from rank {} to rank {} does not lead upwards", component, arc, tail, head)
+            }
+            ValidationError::MissingReverseArc { tail, head, weight } => {
+                write!(f, "arc {} -> {} with weight {} is missing in the reversed graph", tail, head, weight)
+            }
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Checks that two parts of a data set agree on the number of nodes
+pub fn check_node_count(component: &'static str, expected: usize, found: usize) -> Result<(), ValidationError> {
+    if expected != found {
+        return Err(ValidationError::NodeCountMismatch { component, expected, found });
+    }
+    Ok(())
+}
+
+/// Checks the adjacency array invariants of a graph, equivalent to the graph part of routingkit's check_contraction_hierarchy_for_errors
+pub fn check_graph<FirstOutContainer, HeadContainer, WeightsContainer>(
+    component: &'static str,
+    graph: &FirstOutGraph<FirstOutContainer, HeadContainer, WeightsContainer>,
+) -> Result<(), ValidationError>
+where
+    FirstOutContainer: AsRef<[EdgeId]>,
+    HeadContainer: AsRef<[NodeId]>,
+    WeightsContainer: AsRef<[Weight]>,
+{
+    let first_out = graph.first_out();
+    let (&first, &last) = match (first_out.first(), first_out.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Err(ValidationError::EmptyFirstOut { component }),
+    };
+
+    if first != 0 {
+        return Err(ValidationError::FirstOutNotStartingAtZero { component, found: first });
+    }
+
+    if let Some(node) = first_out.windows(2).position(|w| w[0] > w[1]) {
+        return Err(ValidationError::FirstOutDecreasing {
+            component,
+            node: node as NodeId,
+        });
+    }
+
+    for found in [graph.head().len(), graph.weights().len()] {
+        if found != last as usize {
+            return Err(ValidationError::ArcCountMismatch {
+                component,
+                expected: last as usize,
+                found,
+            });
+        }
+    }
+
+    let num_nodes = first_out.len() - 1;
+    if let Some(arc) = graph.head().iter().position(|&head| head as usize >= num_nodes) {
+        return Err(ValidationError::HeadOutOfRange {
+            component,
+            arc: arc as EdgeId,
+            head: graph.head()[arc],
+        });
+    }
+
+    Ok(())
+}
+
+/// Checks that `rank` and `order` are inverse permutations of each other
+pub fn check_rank_order(rank: &[NodeId], order: &[NodeId]) -> Result<(), ValidationError> {
+    check_node_count("order", rank.len(), order.len())?;
+
+    for (node, &r) in rank.iter().enumerate() {
+        if order.get(r as usize) != Some(&(node as NodeId)) {
+            return Err(ValidationError::RankOrderMismatch { node: node as NodeId });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that all arcs of a hierarchy graph lead from lower to higher rank
+pub fn check_upward<FirstOutContainer, HeadContainer, WeightsContainer>(
+    component: &'static str,
+    graph: &FirstOutGraph<FirstOutContainer, HeadContainer, WeightsContainer>,
+) -> Result<(), ValidationError>
+where
+    FirstOutContainer: AsRef<[EdgeId]>,
+    HeadContainer: AsRef<[NodeId]>,
+    WeightsContainer: AsRef<[Weight]>,
+{
+    for tail in 0..graph.num_nodes() {
+        for arc in graph.first_out()[tail]..graph.first_out()[tail + 1] {
+            let head = graph.head()[arc as usize];
+            if head as usize <= tail {
+                return Err(ValidationError::NotUpward {
+                    component,
+                    arc,
+                    tail: tail as NodeId,
+                    head,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that `reversed` contains exactly the arcs of `graph` with flipped direction
+pub fn check_reversed<G: OutgoingEdgeIterable, R: OutgoingEdgeIterable>(graph: &G, reversed: &R) -> Result<(), ValidationError> {
+    check_node_count("reversed graph", graph.num_nodes(), reversed.num_nodes())?;
+
+    // reversing the reversed graph again yields the arcs of graph, only the order of parallel arcs may differ
+    let mut expected: Vec<(NodeId, NodeId, Weight)> = (0..graph.num_nodes() as NodeId)
+        .flat_map(|tail| graph.outgoing_edge_iter(tail).map(move |(&weight, &head)| (tail, head, weight)))
+        .collect();
+    let mut found: Vec<(NodeId, NodeId, Weight)> = (0..reversed.num_nodes() as NodeId)
+        .flat_map(|head| reversed.outgoing_edge_iter(head).map(move |(&weight, &tail)| (tail, head, weight)))
+        .collect();
+    expected.sort_unstable();
+    found.sort_unstable();
+
+    let mut found_iter = found.iter().peekable();
+    for &(tail, head, weight) in &expected {
+        // skip arcs which only exist in the reversed graph
+        while found_iter.next_if(|&&arc| arc < (tail, head, weight)).is_some() {}
+
+        if found_iter.next_if(|&&arc| arc == (tail, head, weight)).is_none() {
+            return Err(ValidationError::MissingReverseArc { tail, head, weight });
+        }
+    }
+
+    check_arc_count("reversed graph", graph.num_arcs(), reversed.num_arcs())
+}
+
+fn check_arc_count(component: &'static str, expected: usize, found: usize) -> Result<(), ValidationError> {
+    if expected != found {
+        return Err(ValidationError::ArcCountMismatch { component, expected, found });
+    }
+    Ok(())
+}
+
+/// Checks that there is a flag for every node of the graph. RoutingKit bit vectors may contain additional padding bits.
+pub fn check_node_flags<G: Graph>(graph: &G, flags: &BitVec) -> Result<(), ValidationError> {
+    if flags.len() < graph.num_nodes() {
+        return Err(ValidationError::NodeCountMismatch {
+            component: "node flags",
+            expected: graph.num_nodes(),
+            found: flags.len(),
+        });
+    }
+    Ok(())
+}
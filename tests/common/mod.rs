@@ -0,0 +1,12 @@
+//! Fixtures shared by several integration tests
+
+use rust_truck_router::synthetic::*;
+
+/// 20 motorway junctions 30km apart with short side roads, half of the junctions are parking nodes
+pub fn corridor() -> SyntheticGraph {
+    let config = SyntheticConfig {
+        parking_density: 0.5,
+        ..SyntheticConfig::default()
+    };
+    motorway_corridor(20, 30_000.0, 2, 3_000.0, &config)
+}
@@ -0,0 +1,155 @@
+use rust_truck_router::{
+    algo::{
+        alternatives::*,
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+        dijkstra::{Dijkstra, DijkstraData},
+    },
+    query::RestrictionPreset,
+    synthetic::*,
+    types::*,
+};
+
+fn network() -> SyntheticGraph {
+    // crossing the square takes about seven hours
+    let config = SyntheticConfig {
+        parking_density: 0.5,
+        speed: 20,
+        ..SyntheticConfig::default()
+    };
+    random_geometric(300, 100_000.0, 15_000.0, &config)
+}
+
+/// Node 0 and the node farthest from it
+fn far_apart(network: &SyntheticGraph) -> (NodeId, NodeId) {
+    let mut state = DijkstraData::new(network.graph.num_nodes());
+    state.init_new_s(0);
+    Dijkstra::new(network.graph.borrow()).to_all(&mut state);
+    let t = (0..network.graph.num_nodes() as NodeId)
+        .filter(|&v| state.tentative_distance_at(v) < INFINITY)
+        .max_by_key(|&v| state.tentative_distance_at(v))
+        .unwrap();
+    (0, t)
+}
+
+fn check_route(network: &SyntheticGraph, restrictions: &[DrivingTimeRestriction], route: &RouteOption, s: NodeId, t: NodeId) {
+    let graph = network.graph.borrow();
+    assert_eq!(route.path.first(), Some(&s));
+    assert_eq!(route.path.last(), Some(&t));
+
+    let mut driving_time = 0;
+    let mut arc_end_times = vec![0];
+    for w in route.path.windows(2) {
+        let (&weight, _) = graph
+            .outgoing_edge_iter(w[0])
+            .find(|&(_, &head)| head == w[1])
+            .expect("path uses existing arcs");
+        driving_time += weight;
+        arc_end_times.push(driving_time);
+    }
+    assert_eq!(route.driving_time, driving_time);
+    assert_eq!(route.distance, driving_time + route.breaks.iter().map(|b| b.duration).sum::<Weight>());
+
+    // driving time since the last break of every restriction at each node
+    let mut last_break = vec![0; restrictions.len()];
+    let mut paused = 0;
+    for (i, &node) in route.path.iter().enumerate() {
+        for r in 0..restrictions.len() {
+            assert!(arc_end_times[i] - last_break[r] <= restrictions[r].max_driving_time);
+        }
+        if let Some(b) = route.breaks.iter().find(|b| b.node == node) {
+            assert!(network.is_parking_node[node as usize]);
+            assert_eq!(b.start, arc_end_times[i] + paused);
+            assert_eq!(b.duration, restrictions[b.restriction].pause_time);
+            paused += b.duration;
+            for time in &mut last_break[..=b.restriction] {
+                *time = arc_end_times[i];
+            }
+        }
+    }
+}
+
+fn check_alternatives(network: &SyntheticGraph, restrictions: &[DrivingTimeRestriction], method: AlternativeMethod, optimum: Weight) {
+    let (s, t) = far_apart(network);
+    let graph = network.graph.borrow();
+    let bw_graph = OwnedGraph::reverse(graph);
+    let mut query = AlternativeRouteQuery::new(graph, bw_graph.borrow(), &network.is_parking_node);
+    query.set_restrictions(restrictions);
+    query.config.max_stretch = 1.5;
+
+    let routes = query.alternatives(s, t, method);
+    assert!(routes.len() >= 2, "{:?} found {} routes", method, routes.len());
+    assert!(routes.len() <= 1 + query.config.max_alternatives);
+
+    assert_eq!(routes[0].distance, optimum);
+    assert_eq!(routes[0].stretch, 1.0);
+    for (i, route) in routes.iter().enumerate() {
+        check_route(network, restrictions, route, s, t);
+        if i > 0 {
+            assert!(route.distance >= optimum);
+            assert!(route.stretch <= query.config.max_stretch);
+            assert!(route.overlap <= query.config.max_overlap);
+            assert!(routes[..i].iter().all(|other| other.path != route.path));
+        }
+    }
+}
+
+#[test]
+fn alternatives_with_one_restriction() {
+    let network = network();
+    let (s, t) = far_apart(&network);
+    let mut state = OneRestrictionDijkstraData::new(network.graph.num_nodes());
+    state.set_restriction(EU_SHORT.max_driving_time, EU_SHORT.pause_time);
+    state.init_new_s(s);
+    let optimum = OneRestrictionDijkstra::new(network.graph.borrow(), &network.is_parking_node)
+        .dist_query(&mut state, t)
+        .unwrap();
+    assert!(
+        state.current_best_path_to(t, true).unwrap().1.iter().skip(1).any(|d| d[1] == 0),
+        "the optimal route needs a break"
+    );
+
+    check_alternatives(&network, &[EU_SHORT], AlternativeMethod::ViaNode, optimum);
+    check_alternatives(&network, &[EU_SHORT], AlternativeMethod::Penalty, optimum);
+}
+
+#[test]
+fn alternatives_with_two_restrictions() {
+    let network = network();
+    let (s, t) = far_apart(&network);
+    let mut state = TwoRestrictionDijkstraData::new(network.graph.num_nodes());
+    state.set_restriction(EU_LONG.max_driving_time, EU_LONG.pause_time, EU_SHORT.max_driving_time, EU_SHORT.pause_time);
+    state.init_new_s(s);
+    let optimum = TwoRestrictionDijkstra::new(network.graph.borrow(), &network.is_parking_node)
+        .dist_query(&mut state, t)
+        .unwrap();
+
+    let restrictions = RestrictionPreset::EuLong.restrictions();
+    check_alternatives(&network, restrictions, AlternativeMethod::ViaNode, optimum);
+    check_alternatives(&network, restrictions, AlternativeMethod::Penalty, optimum);
+}
+
+#[test]
+fn alternatives_without_restrictions() {
+    let network = grid(16, 4, 30_000.0, &SyntheticConfig::default());
+    let bw_graph = OwnedGraph::reverse(network.graph.borrow());
+    let mut query = AlternativeRouteQuery::new(network.graph.borrow(), bw_graph.borrow(), &network.is_parking_node);
+
+    // 15 arcs of 22.5 minutes
+    let routes = query.alternatives(0, 15, AlternativeMethod::Penalty);
+    assert_eq!(routes[0].distance, 15 * 1_350_000);
+    assert!(routes.iter().all(|route| route.breaks.is_empty() && route.distance == route.driving_time));
+}
+
+#[test]
+fn unreachable_target_has_no_routes() {
+    let graph = OwnedGraph::new(vec![0, 1, 1, 1], vec![1], vec![1000]);
+    let bw_graph = OwnedGraph::reverse(graph.borrow());
+    let is_parking_node = bit_vec::BitVec::from_elem(3, true);
+    let mut query = AlternativeRouteQuery::new(graph.borrow(), bw_graph.borrow(), &is_parking_node);
+    query.set_restrictions(&[EU_SHORT]);
+
+    assert!(query.alternatives(0, 2, AlternativeMethod::ViaNode).is_empty());
+    assert!(query.alternatives(0, 2, AlternativeMethod::Penalty).is_empty());
+    assert_eq!(query.alternatives(0, 1, AlternativeMethod::Penalty)[0].distance, 1000);
+}
@@ -0,0 +1,197 @@
+use bit_vec::BitVec;
+use rust_truck_router::{
+    algo::{
+        budget::*,
+        ch::{ContractionHierarchy, ContractionHierarchyQuery},
+        core_ch::CoreContractionHierarchy,
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+        csp_2_bidir::CSP2BidirQuery,
+        csp_bidir::CSPBidirQuery,
+        csp_core_ch::CSPCoreCHQuery,
+        dijkstra::{Dijkstra, DijkstraData},
+        PointToPointQuery,
+    },
+    io::dataset::*,
+    query::*,
+    types::*,
+};
+use std::{
+    error::Error,
+    path::Path,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+mod common;
+
+use common::corridor;
+
+#[test]
+fn check_order() {
+    let cancellation = CancellationFlag::new();
+    let budget = SearchBudget::unlimited()
+        .with_max_settled_labels(10)
+        .with_max_label_memory(100)
+        .with_cancellation(cancellation.clone());
+
+    assert_eq!(budget.check(9, 100), Ok(()));
+    assert_eq!(budget.check(10, 200), Err(AbortReason::SettledLabels));
+    assert_eq!(budget.check(9, 101), Err(AbortReason::LabelMemory));
+    cancellation.cancel();
+    assert_eq!(budget.check(0, 0), Err(AbortReason::Cancelled));
+    cancellation.reset();
+    assert_eq!(budget.check(0, 0), Ok(()));
+
+    let expired = SearchBudget::unlimited().with_deadline(Instant::now() - Duration::from_millis(1));
+    assert_eq!(expired.check(0, 0), Err(AbortReason::Deadline));
+    assert_eq!(SearchBudget::unlimited().check(u32::MAX, usize::MAX), Ok(()));
+}
+
+#[test]
+fn unidirectional() {
+    let network = corridor();
+    let t = network.graph.num_nodes() as NodeId - 1;
+
+    let csp = OneRestrictionDijkstra::new(network.graph.borrow(), &network.is_parking_node);
+    let mut state = OneRestrictionDijkstraData::new(network.graph.num_nodes());
+    state.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    state.init_new_s(0);
+    let dist = csp.dist_query(&mut state, t);
+    assert!(dist.is_some());
+    let num_settled = state.stats.num_settled;
+
+    state.init_new_s(0);
+    assert_eq!(csp.dist_query_with_budget(&mut state, t, &SearchBudget::unlimited()), Ok(dist));
+    state.init_new_s(0);
+    let aborted = csp
+        .dist_query_with_budget(&mut state, t, &SearchBudget::unlimited().with_max_settled_labels(num_settled / 2))
+        .unwrap_err();
+    assert_eq!(aborted.reason, AbortReason::SettledLabels);
+    assert_eq!(state.stats.num_settled, num_settled / 2);
+    assert!(aborted.tentative_distance.is_none_or(|d| d >= dist.unwrap()));
+
+    let csp_2 = TwoRestrictionDijkstra::new(network.graph.borrow(), &network.is_parking_node);
+    let mut state_2 = TwoRestrictionDijkstraData::new(network.graph.num_nodes());
+    state_2.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    state_2.init_new_s(0);
+    let aborted = csp_2
+        .dist_query_with_budget(&mut state_2, t, &SearchBudget::unlimited().with_max_label_memory(0))
+        .unwrap_err();
+    assert_eq!(aborted.reason, AbortReason::LabelMemory);
+    assert_eq!(aborted.tentative_distance, None);
+
+    let dijkstra = Dijkstra::new(network.graph.borrow());
+    let mut dijkstra_state = DijkstraData::new(network.graph.num_nodes());
+    dijkstra_state.init_new_s(0);
+    let aborted = dijkstra
+        .dist_query_with_budget(&mut dijkstra_state, t, &SearchBudget::unlimited().with_timeout(Duration::ZERO))
+        .unwrap_err();
+    assert_eq!(aborted.reason, AbortReason::Deadline);
+}
+
+fn check_query<Q: PointToPointQuery>(query: &mut Q, s: NodeId, t: NodeId) {
+    query.init_new_s(s);
+    query.init_new_t(t);
+    let dist = query.run_query();
+    assert!(dist.is_some());
+    let num_settled = query.stats().total().num_settled;
+    assert_eq!(query.run_query_with_budget(&SearchBudget::unlimited()), Ok(dist));
+    assert_eq!(query.stats().total().num_settled, num_settled);
+
+    let aborted = query
+        .run_query_with_budget(&SearchBudget::unlimited().with_max_settled_labels(num_settled / 2))
+        .unwrap_err();
+    assert_eq!(aborted.reason, AbortReason::SettledLabels);
+    // the statistics describe the partial search
+    let stats = query.stats();
+    assert_eq!(stats.distance, None);
+    assert_eq!(stats.total().num_settled, num_settled / 2);
+    assert_eq!(query.search_space().num_settled_labels(), (num_settled / 2) as usize);
+    assert!(aborted.tentative_distance.is_none_or(|d| d >= dist.unwrap()));
+
+    let cancellation = CancellationFlag::new();
+    cancellation.cancel();
+    let aborted = query
+        .run_query_with_budget(&SearchBudget::unlimited().with_cancellation(cancellation))
+        .unwrap_err();
+    assert_eq!(aborted, QueryAborted::new(AbortReason::Cancelled, Weight::infinity()));
+
+    // the query is usable after an abort
+    assert_eq!(query.run_query(), dist);
+}
+
+#[test]
+fn bidirectional() {
+    let network = corridor();
+    let bw_graph = OwnedGraph::reverse(network.graph.borrow());
+    let t = network.graph.num_nodes() as NodeId - 1;
+
+    let mut query = CSPBidirQuery::new(network.graph.borrow(), bw_graph.borrow(), &network.is_parking_node);
+    query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    check_query(&mut query, 0, t);
+    assert!(query.timeout_run_query(Duration::ZERO).is_err());
+
+    let mut query = CSP2BidirQuery::new(network.graph.borrow(), bw_graph.borrow(), &network.is_parking_node);
+    query.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    check_query(&mut query, 0, t);
+}
+
+#[test]
+fn core_ch() {
+    let network = corridor();
+    let graph = &network.graph;
+    let t = graph.num_nodes() as NodeId - 1;
+    // all nodes in the core
+    let identity: Vec<NodeId> = (0..graph.num_nodes() as NodeId).collect();
+    let core_ch = CoreContractionHierarchy::new(identity.clone(), identity.clone(), identity, graph.clone(), OwnedGraph::reverse(graph.borrow()));
+
+    let mut query = CSPCoreCHQuery::new(core_ch.borrow());
+    query.set_custom_reset_nodes(Rc::new(network.is_parking_node.clone()));
+    query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    check_query(&mut query, 0, t);
+}
+
+#[test]
+fn ch() -> Result<(), Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/"));
+    let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
+    let mut query = ContractionHierarchyQuery::new(ch.borrow());
+    check_query(&mut query, 0, 4);
+    Ok(())
+}
+
+#[test]
+fn every_algorithm() -> Result<(), Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let mut dataset = Dataset::from_routingkit_dir(&path)?;
+    dataset.insert_bitvector(PARKING_FLAGS, &BitVec::from_fn(dataset.num_nodes(), |i| i == 2 || i == 3));
+    let data = RoutingData::from_dataset(&dataset)?;
+
+    for restriction in RestrictionPreset::ALL {
+        for algorithm in Algorithm::ALL {
+            let mut query = new_query(&data, algorithm, restriction);
+            query.init_new_s(0);
+            query.init_new_t(data.num_nodes() as NodeId - 1);
+            let dist = query.run_query();
+
+            let aborted = query.run_query_with_budget(&SearchBudget::unlimited().with_max_settled_labels(1));
+            assert_eq!(aborted.unwrap_err().reason, AbortReason::SettledLabels, "{} {}", algorithm, restriction);
+            assert_eq!(query.stats().distance, None, "{} {}", algorithm, restriction);
+
+            let aborted = query.run_query_with_budget(&SearchBudget::unlimited().with_timeout(Duration::ZERO));
+            assert_eq!(aborted.unwrap_err().reason, AbortReason::Deadline, "{} {}", algorithm, restriction);
+
+            assert_eq!(
+                query.run_query_with_budget(&SearchBudget::unlimited()),
+                Ok(dist),
+                "{} {}",
+                algorithm,
+                restriction
+            );
+            assert_eq!(query.stats().distance, dist, "{} {}", algorithm, restriction);
+        }
+    }
+
+    Ok(())
+}
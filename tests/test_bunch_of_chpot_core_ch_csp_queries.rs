@@ -18,7 +18,7 @@ fn test_instance_queries() -> Result<(), Box<dyn Error>> {
     let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
 
     let mut core_ch = CSPAstarCoreCHQuery::new(core_ch.borrow(), ch.borrow());
-    core_ch.check();
+    core_ch.check()?;
     let mut csp_pot_state = OneRestrictionDijkstraData::new(graph.num_nodes());
     let is_parking_node = BitVec::from_fn(5, |i| i == 2 || i == 3);
     let csp_pot = OneRestrictionDijkstra::new(graph.borrow(), &is_parking_node);
@@ -57,7 +57,7 @@ fn test_needs_two_breaks_instance() -> Result<(), Box<dyn Error>> {
     let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
     let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
     let mut core_ch = CSPAstarCoreCHQuery::new(core_ch.borrow(), ch.borrow());
-    core_ch.check();
+    core_ch.check()?;
 
     let mut csp_pot_state = OneRestrictionDijkstraData::new(graph.num_nodes());
     let is_parking_node = BitVec::from_fn(5, |i| i == 2 || i == 3);
@@ -15,7 +15,7 @@ fn test_instance_queries() -> Result<(), Box<dyn Error>> {
     let graph = OwnedGraph::load_from_routingkit_dir(path.clone())?;
     let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
     let mut core_ch = CSPCoreCHQuery::new(core_ch.borrow());
-    core_ch.check();
+    core_ch.check()?;
 
     let mut csp_pot_state = OneRestrictionDijkstraData::new(graph.num_nodes());
     let is_parking = BitVec::from_fn(5, |i| i == 2 || i == 3);
@@ -51,7 +51,7 @@ fn test_needs_two_breaks_instance() -> Result<(), Box<dyn Error>> {
     let graph = OwnedGraph::load_from_routingkit_dir(path.clone())?;
     let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
     let mut core_ch = CSPCoreCHQuery::new(core_ch.borrow());
-    core_ch.check();
+    core_ch.check()?;
 
     let mut csp_pot_state = OneRestrictionDijkstraData::new(graph.num_nodes());
     let is_parking = BitVec::from_fn(5, |i| i == 2 || i == 3);
@@ -12,7 +12,7 @@ use std::{error::Error, path::Path};
 fn load_core_ch() -> Result<(), Box<dyn Error>> {
     let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance"));
     let ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
-    ch.check();
+    ch.check()?;
     Ok(())
 }
 
@@ -21,7 +21,7 @@ fn query_does_not_reach_core() -> Result<(), Box<dyn Error>> {
     let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance"));
     let graph = OwnedGraph::load_from_routingkit_dir(path.clone())?;
     let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
-    core_ch.check();
+    core_ch.check()?;
     let mut core_ch = CoreContractionHierarchyQuery::new(core_ch.borrow());
 
     let to_test = [(0, 1)];
@@ -48,7 +48,7 @@ fn query_to_core_node() -> Result<(), Box<dyn Error>> {
     let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance"));
     let graph = OwnedGraph::load_from_routingkit_dir(path.clone())?;
     let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
-    core_ch.check();
+    core_ch.check()?;
     let mut core_ch = CoreContractionHierarchyQuery::new(core_ch.borrow());
 
     let to_test = [(0, 2), (0, 3)];
@@ -74,7 +74,7 @@ fn query_through_core() -> Result<(), Box<dyn Error>> {
     let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance"));
     let graph = OwnedGraph::load_from_routingkit_dir(path.clone())?;
     let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
-    core_ch.check();
+    core_ch.check()?;
     let mut core_ch = CoreContractionHierarchyQuery::new(core_ch.borrow());
 
     let to_test = [(0, 4), (1, 4)];
@@ -101,7 +101,7 @@ fn query_from_core_node() -> Result<(), Box<dyn Error>> {
     let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance"));
     let graph = OwnedGraph::load_from_routingkit_dir(path.clone())?;
     let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
-    core_ch.check();
+    core_ch.check()?;
     let mut core_ch = CoreContractionHierarchyQuery::new(core_ch.borrow());
 
     let to_test = [(2, 4), (3, 4)];
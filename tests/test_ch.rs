@@ -12,7 +12,7 @@ use std::path::Path;
 fn load_ch() -> Result<(), Box<dyn Error>> {
     let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/"));
     let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
-    ch.check();
+    ch.check()?;
     Ok(())
 }
 
@@ -22,7 +22,7 @@ fn some_ch_pot_queries() -> Result<(), Box<dyn Error>> {
 
     let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/"));
     let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
-    ch.check();
+    ch.check()?;
 
     let graph = OwnedGraph::new(first_out, head, travel_time);
     let mut dijkstra_state = DijkstraData::new(graph.num_nodes());
@@ -61,7 +61,7 @@ fn hundred_ka_queries() -> Result<(), Box<dyn Error>> {
     let travel_time = Vec::<Weight>::load_from(path.join("travel_time"))?;
 
     let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
-    ch.check();
+    ch.check()?;
 
     let graph = OwnedGraph::new(first_out.clone(), head.clone(), travel_time.clone());
     let mut dijkstra_state = DijkstraData::new(graph.num_nodes());
@@ -2,13 +2,21 @@ use bit_vec::BitVec;
 use rust_truck_router::{
     algo::{
         ch::ContractionHierarchy,
+        core_ch::{CoreContractionHierarchy, OwnedCoreContractionHierarchy},
+        csp_2_bidir::CSP2BidirQuery,
+        csp_2_core_ch::CSP2CoreCHQuery,
+        csp_bidir::CSPBidirQuery,
         csp_bidir_chpot::CSPBidirAstarCHPotQuery,
+        csp_brute_force::brute_force_dist,
+        csp_core_ch::CSPCoreCHQuery,
         dijkstra::{Dijkstra, DijkstraData},
+        PointToPointQuery,
     },
     types::{OwnedGraph, *},
 };
 use std::error::Error;
 use std::path::Path;
+use std::rc::Rc;
 
 #[test]
 fn some_astar_bidir_queries() -> Result<(), Box<dyn Error>> {
@@ -37,3 +45,79 @@ fn some_astar_bidir_queries() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+/// All nodes in the core, so the core CH queries run a bidirectional search on the original graph
+fn core_only_hierarchy(graph: &OwnedGraph) -> OwnedCoreContractionHierarchy {
+    let identity: Vec<NodeId> = (0..graph.num_nodes() as NodeId).collect();
+    CoreContractionHierarchy::new(identity.clone(), identity.clone(), identity, graph.clone(), OwnedGraph::reverse(graph.borrow()))
+}
+
+fn assert_all_queries_exact(
+    graph: &OwnedGraph,
+    is_parking_node: &BitVec,
+    restrictions: &[DrivingTimeRestriction],
+    queries: &mut [(&str, Box<dyn PointToPointQuery + '_>)],
+) {
+    for s in 0..graph.num_nodes() as NodeId {
+        for t in 0..graph.num_nodes() as NodeId {
+            let expected = brute_force_dist(graph.borrow(), is_parking_node, restrictions, s, t);
+            for (name, query) in queries.iter_mut() {
+                query.init_new_s(s);
+                query.init_new_t(t);
+                assert_eq!(query.run_query(), expected, "{} from {} to {}", name, s, t);
+            }
+        }
+    }
+}
+
+// In both graphs the meeting node has a settled label which is not the shortest but needs no break on the way to t
+
+#[test]
+fn one_restriction_meets_with_every_settled_label() {
+    let graph = OwnedGraph::new(vec![0, 2, 4, 4, 5, 5, 6, 9], vec![4, 6, 5, 6, 1, 0, 2, 3, 5], vec![7, 9, 8, 6, 4, 4, 4, 3, 9]);
+    let bw_graph = OwnedGraph::reverse(graph.borrow());
+    let is_parking_node = BitVec::from_fn(graph.num_nodes(), |node| node != 3);
+    let core_ch = core_only_hierarchy(&graph);
+    let short = DrivingTimeRestriction {
+        max_driving_time: 17,
+        pause_time: 13,
+    };
+
+    let mut bidir = CSPBidirQuery::new(graph.borrow(), bw_graph.borrow(), &is_parking_node);
+    bidir.set_restriction(short.max_driving_time, short.pause_time);
+    let mut core_ch_query = CSPCoreCHQuery::new(core_ch.borrow());
+    core_ch_query.set_custom_reset_nodes(Rc::new(is_parking_node.clone()));
+    core_ch_query.set_restriction(short.max_driving_time, short.pause_time);
+
+    let mut queries: Vec<(&str, Box<dyn PointToPointQuery>)> = vec![("bidir", Box::new(bidir)), ("core ch", Box::new(core_ch_query))];
+    assert_all_queries_exact(&graph, &is_parking_node, &[short], &mut queries);
+}
+
+#[test]
+fn two_restrictions_meet_with_every_settled_label() {
+    let graph = OwnedGraph::new(
+        vec![0, 2, 3, 6, 7, 14, 15],
+        vec![1, 2, 0, 1, 4, 5, 2, 0, 0, 1, 3, 3, 5, 5, 1],
+        vec![1, 6, 8, 5, 4, 8, 1, 6, 7, 3, 5, 8, 3, 8, 6],
+    );
+    let bw_graph = OwnedGraph::reverse(graph.borrow());
+    let is_parking_node = BitVec::from_fn(graph.num_nodes(), |node| node != 3 && node != 5);
+    let core_ch = core_only_hierarchy(&graph);
+    let short = DrivingTimeRestriction {
+        max_driving_time: 17,
+        pause_time: 5,
+    };
+    let long = DrivingTimeRestriction {
+        max_driving_time: 22,
+        pause_time: 20,
+    };
+
+    let mut bidir = CSP2BidirQuery::new(graph.borrow(), bw_graph.borrow(), &is_parking_node);
+    bidir.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+    let mut core_ch_query = CSP2CoreCHQuery::new(core_ch.borrow());
+    core_ch_query.set_custom_reset_nodes(Rc::new(is_parking_node.clone()));
+    core_ch_query.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+
+    let mut queries: Vec<(&str, Box<dyn PointToPointQuery>)> = vec![("bidir", Box::new(bidir)), ("core ch", Box::new(core_ch_query))];
+    assert_all_queries_exact(&graph, &is_parking_node, &[short, long], &mut queries);
+}
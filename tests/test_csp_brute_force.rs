@@ -0,0 +1,529 @@
+//! Compares all CSP variants against the exhaustive reference solver on many small random graphs.
+//! Every case is derived from its own seed which is printed on failure.
+
+use bit_vec::BitVec;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rust_truck_router::{
+    algo::{
+        ch::{ContractionHierarchy, ContractionHierarchyQuery, OwnedContractionHierarchy},
+        ch_potential::CHPotential,
+        core_ch::{CoreContractionHierarchy, OwnedCoreContractionHierarchy},
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+        csp_2_bidir::CSP2BidirQuery,
+        csp_2_bidir_chpot::CSP2BidirAstarCHPotQuery,
+        csp_2_core_ch::CSP2CoreCHQuery,
+        csp_2_core_ch_chpot::CSP2AstarCoreCHQuery,
+        csp_2_core_ch_chpot_no_bw::CSP2AstarCoreCHQueryNoBwNoPrune,
+        csp_bidir::CSPBidirQuery,
+        csp_bidir_chpot::CSPBidirAstarCHPotQuery,
+        csp_brute_force::{brute_force_dist, brute_force_dist_with_driver_state, brute_force_dist_with_remaining_driving_time},
+        csp_core_ch::CSPCoreCHQuery,
+        csp_core_ch_chpot::CSPAstarCoreCHQuery,
+        csp_core_ch_chpot_no_bw::CSPAstarCoreCHQueryNoBwNoPrune,
+        PointToPointQuery,
+    },
+    types::*,
+};
+use std::{collections::BTreeMap, rc::Rc};
+
+const NUM_CASES: u64 = 300;
+
+struct Case {
+    graph: OwnedGraph,
+    is_parking_node: BitVec,
+    short: DrivingTimeRestriction,
+    long: DrivingTimeRestriction,
+}
+
+fn random_case(seed: u64) -> Case {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let num_nodes = rng.gen_range(2..8);
+    let num_arcs = rng.gen_range(num_nodes..3 * num_nodes);
+
+    let mut arcs: Vec<(NodeId, NodeId, Weight)> = (0..num_arcs)
+        .map(|_| {
+            let tail = rng.gen_range(0..num_nodes as NodeId);
+            let head = (tail + rng.gen_range(1..num_nodes as NodeId)) % num_nodes as NodeId;
+            (tail, head, rng.gen_range(1..10))
+        })
+        .collect();
+    arcs.sort_unstable();
+
+    let mut first_out = vec![0 as EdgeId; num_nodes + 1];
+    for &(tail, _, _) in &arcs {
+        first_out[tail as usize + 1] += 1;
+    }
+    for i in 1..first_out.len() {
+        first_out[i] += first_out[i - 1];
+    }
+
+    let is_parking_node = BitVec::from_fn(num_nodes, |_| rng.gen_bool(0.4));
+    let short = DrivingTimeRestriction {
+        max_driving_time: rng.gen_range(5..25),
+        pause_time: rng.gen_range(0..15),
+    };
+    let long = DrivingTimeRestriction {
+        max_driving_time: short.max_driving_time + rng.gen_range(0..25),
+        pause_time: short.pause_time + rng.gen_range(0..30),
+    };
+
+    Case {
+        graph: OwnedGraph::new(first_out, arcs.iter().map(|a| a.1).collect(), arcs.iter().map(|a| a.2).collect()),
+        is_parking_node,
+        short,
+        long,
+    }
+}
+
+/// Driving times below the maximum driving times at departure
+fn random_driver_state(seed: u64, case: &Case) -> DriverState {
+    let mut rng = StdRng::seed_from_u64(seed + NUM_CASES);
+    let driving_time_since_break = rng.gen_range(0..case.short.max_driving_time);
+    DriverState {
+        driving_time_since_break,
+        driving_time_since_rest: rng.gen_range(driving_time_since_break..case.long.max_driving_time.max(driving_time_since_break + 1)),
+        departure_offset: rng.gen_range(0..20),
+    }
+}
+
+/// Remaining driving times below the maximum driving times at the target
+fn random_remaining_driving_time(seed: u64, case: &Case) -> RemainingDrivingTime {
+    let mut rng = StdRng::seed_from_u64(seed + 2 * NUM_CASES);
+    let until_break = rng.gen_range(0..case.short.max_driving_time);
+    RemainingDrivingTime {
+        until_break,
+        until_rest: rng.gen_range(until_break..case.long.max_driving_time.max(until_break + 1)),
+    }
+}
+
+/// All nodes in the core, so the core CH queries run a bidirectional search on the original graph
+fn core_only_hierarchy(graph: &OwnedGraph) -> OwnedCoreContractionHierarchy {
+    let identity: Vec<NodeId> = (0..graph.num_nodes() as NodeId).collect();
+    CoreContractionHierarchy::new(identity.clone(), identity.clone(), identity, graph.clone(), OwnedGraph::reverse(graph.borrow()))
+}
+
+/// Contracts the nodes in the order of their ids and inserts every shortcut without witness searches,
+/// which is exact and small enough for the random cases
+fn contract_by_id(graph: &OwnedGraph) -> OwnedContractionHierarchy {
+    let n = graph.num_nodes();
+    let mut arcs = BTreeMap::new();
+    for tail in 0..n as NodeId {
+        for (&weight, &head) in graph.borrow().outgoing_edge_iter(tail) {
+            let arc = arcs.entry((tail, head)).or_insert(weight);
+            *arc = weight.min(*arc);
+        }
+    }
+
+    for node in 0..n as NodeId {
+        let incoming: Vec<_> = arcs
+            .iter()
+            .filter(|(&(tail, head), _)| head == node && tail > node)
+            .map(|(&(tail, _), &w)| (tail, w))
+            .collect();
+        let outgoing: Vec<_> = arcs
+            .iter()
+            .filter(|(&(tail, head), _)| tail == node && head > node)
+            .map(|(&(_, head), &w)| (head, w))
+            .collect();
+        for &(tail, first) in &incoming {
+            for &(head, second) in &outgoing {
+                if tail != head {
+                    let arc = arcs.entry((tail, head)).or_insert(first + second);
+                    *arc = (first + second).min(*arc);
+                }
+            }
+        }
+    }
+
+    let upward_graph = |arcs: Vec<(NodeId, NodeId, Weight)>| {
+        let mut first_out = vec![0 as EdgeId; n + 1];
+        for &(tail, _, _) in &arcs {
+            first_out[tail as usize + 1] += 1;
+        }
+        for i in 1..first_out.len() {
+            first_out[i] += first_out[i - 1];
+        }
+        OwnedGraph::new(first_out, arcs.iter().map(|a| a.1).collect(), arcs.iter().map(|a| a.2).collect())
+    };
+    let forward = upward_graph(
+        arcs.iter()
+            .filter(|(&(tail, head), _)| tail < head)
+            .map(|(&(tail, head), &w)| (tail, head, w))
+            .collect(),
+    );
+    let mut backward: Vec<_> = arcs
+        .iter()
+        .filter(|(&(tail, head), _)| tail > head)
+        .map(|(&(tail, head), &w)| (head, tail, w))
+        .collect();
+    backward.sort_unstable();
+    let identity: Vec<NodeId> = (0..n as NodeId).collect();
+    ContractionHierarchy::new(identity.clone(), identity, forward, upward_graph(backward))
+}
+
+/// All queries with CH potentials for one restriction, starting with `driver_state` and leaving `remaining` at the target
+fn chpot_queries<'a>(
+    case: &'a Case,
+    bw_graph: &'a OwnedGraph,
+    ch: &'a OwnedContractionHierarchy,
+    core_ch: &'a OwnedCoreContractionHierarchy,
+    driver_state: DriverState,
+    remaining: RemainingDrivingTime,
+) -> Vec<(&'static str, Box<dyn PointToPointQuery + 'a>)> {
+    let (max_driving_time, pause_time) = (case.short.max_driving_time, case.short.pause_time);
+    let reset_nodes = Rc::new(case.is_parking_node.clone());
+
+    let mut bidir = CSPBidirAstarCHPotQuery::new(case.graph.borrow(), bw_graph.borrow(), &case.is_parking_node, ch.borrow());
+    bidir.set_restriction(max_driving_time, pause_time);
+    bidir.set_driver_state(driver_state);
+    bidir.set_required_remaining_driving_time(remaining);
+    let mut core_ch_query = CSPAstarCoreCHQuery::new(core_ch.borrow(), ch.borrow());
+    core_ch_query.set_custom_reset_nodes(reset_nodes.clone());
+    core_ch_query.set_restriction(max_driving_time, pause_time);
+    core_ch_query.set_driver_state(driver_state);
+    core_ch_query.set_required_remaining_driving_time(remaining);
+    let mut no_bw = CSPAstarCoreCHQueryNoBwNoPrune::new(core_ch.borrow(), ch.borrow());
+    no_bw.set_custom_reset_nodes(reset_nodes);
+    no_bw.set_restriction(max_driving_time, pause_time);
+    no_bw.set_driver_state(driver_state);
+    no_bw.set_required_remaining_driving_time(remaining);
+
+    vec![
+        ("bidir chpot", Box::new(bidir)),
+        ("core ch chpot", Box::new(core_ch_query)),
+        ("core ch chpot without backward search", Box::new(no_bw)),
+    ]
+}
+
+/// All queries with CH potentials for two restrictions, starting with `driver_state` and leaving `remaining` at the target
+fn chpot_2_queries<'a>(
+    case: &'a Case,
+    bw_graph: &'a OwnedGraph,
+    ch: &'a OwnedContractionHierarchy,
+    core_ch: &'a OwnedCoreContractionHierarchy,
+    driver_state: DriverState,
+    remaining: RemainingDrivingTime,
+) -> Vec<(&'static str, Box<dyn PointToPointQuery + 'a>)> {
+    let (short, long) = (case.short, case.long);
+    let reset_nodes = Rc::new(case.is_parking_node.clone());
+
+    let mut bidir = CSP2BidirAstarCHPotQuery::new(case.graph.borrow(), bw_graph.borrow(), &case.is_parking_node, ch.borrow());
+    bidir.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+    bidir.set_driver_state(driver_state);
+    bidir.set_required_remaining_driving_time(remaining);
+    let mut core_ch_query = CSP2AstarCoreCHQuery::new(core_ch.borrow(), ch.borrow());
+    core_ch_query.set_custom_reset_nodes(reset_nodes.clone());
+    core_ch_query.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+    core_ch_query.set_driver_state(driver_state);
+    core_ch_query.set_required_remaining_driving_time(remaining);
+    let mut no_bw = CSP2AstarCoreCHQueryNoBwNoPrune::new(core_ch.borrow(), ch.borrow());
+    no_bw.set_custom_reset_nodes(reset_nodes);
+    no_bw.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+    no_bw.set_driver_state(driver_state);
+    no_bw.set_required_remaining_driving_time(remaining);
+
+    vec![
+        ("bidir chpot", Box::new(bidir)),
+        ("core ch chpot", Box::new(core_ch_query)),
+        ("core ch chpot without backward search", Box::new(no_bw)),
+    ]
+}
+
+#[test]
+fn contraction_by_id_is_exact() {
+    for seed in 0..NUM_CASES {
+        let case = random_case(seed);
+        let ch = contract_by_id(&case.graph);
+        let mut query = ContractionHierarchyQuery::new(ch.borrow());
+        for s in 0..case.graph.num_nodes() as NodeId {
+            for t in 0..case.graph.num_nodes() as NodeId {
+                query.init_new_s(s);
+                query.init_new_t(t);
+                assert_eq!(
+                    query.run_query(),
+                    brute_force_dist(case.graph.borrow(), &case.is_parking_node, &[], s, t),
+                    "seed {} from {} to {}",
+                    seed,
+                    s,
+                    t
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn one_restriction() {
+    for seed in 0..NUM_CASES {
+        let case = random_case(seed);
+        let graph = &case.graph;
+        let n = graph.num_nodes();
+        let core_ch = core_only_hierarchy(graph);
+        let bw_graph = OwnedGraph::reverse(graph.borrow());
+        let ch = contract_by_id(graph);
+
+        let dijkstra = OneRestrictionDijkstra::new(graph.borrow(), &case.is_parking_node);
+        let mut state = OneRestrictionDijkstraData::new(n);
+        state.set_restriction(case.short.max_driving_time, case.short.pause_time);
+        let mut bidir = CSPBidirQuery::new(graph.borrow(), bw_graph.borrow(), &case.is_parking_node);
+        bidir.set_restriction(case.short.max_driving_time, case.short.pause_time);
+        let mut core_ch_query = CSPCoreCHQuery::new(core_ch.borrow());
+        core_ch_query.set_custom_reset_nodes(Rc::new(case.is_parking_node.clone()));
+        core_ch_query.set_restriction(case.short.max_driving_time, case.short.pause_time);
+        let mut astar_state = OneRestrictionDijkstraData::new_with_potential(n, CHPotential::from_ch(ch.borrow()));
+        astar_state.set_restriction(case.short.max_driving_time, case.short.pause_time);
+        let mut chpot_queries = chpot_queries(&case, &bw_graph, &ch, &core_ch, DriverState::default(), RemainingDrivingTime::default());
+
+        for s in 0..n as NodeId {
+            for t in 0..n as NodeId {
+                let expected = brute_force_dist(graph.borrow(), &case.is_parking_node, &[case.short], s, t);
+
+                state.init_new_s(s);
+                assert_eq!(dijkstra.dist_query(&mut state, t), expected, "dijkstra, seed {} from {} to {}", seed, s, t);
+                state.init_new_s(s);
+                assert_eq!(
+                    dijkstra.dist_query_propagate_all_labels(&mut state, t),
+                    expected,
+                    "dijkstra propagating all labels, seed {} from {} to {}",
+                    seed,
+                    s,
+                    t
+                );
+
+                bidir.init_new_s(s);
+                bidir.init_new_t(t);
+                assert_eq!(bidir.run_query(), expected, "bidir, seed {} from {} to {}", seed, s, t);
+
+                core_ch_query.init_new_s(s);
+                core_ch_query.init_new_t(t);
+                assert_eq!(core_ch_query.run_query(), expected, "core ch, seed {} from {} to {}", seed, s, t);
+
+                astar_state.init_new_s(s);
+                assert_eq!(dijkstra.dist_query(&mut astar_state, t), expected, "astar, seed {} from {} to {}", seed, s, t);
+                for (name, query) in &mut chpot_queries {
+                    query.init_new_s(s);
+                    query.init_new_t(t);
+                    assert_eq!(query.run_query(), expected, "{}, seed {} from {} to {}", name, seed, s, t);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn two_restrictions() {
+    for seed in 0..NUM_CASES {
+        let case = random_case(seed);
+        let graph = &case.graph;
+        let n = graph.num_nodes();
+        let core_ch = core_only_hierarchy(graph);
+        let bw_graph = OwnedGraph::reverse(graph.borrow());
+        let ch = contract_by_id(graph);
+        let (short, long) = (case.short, case.long);
+
+        let dijkstra = TwoRestrictionDijkstra::new(graph.borrow(), &case.is_parking_node);
+        let mut state = TwoRestrictionDijkstraData::new(n);
+        state.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+        let mut bidir = CSP2BidirQuery::new(graph.borrow(), bw_graph.borrow(), &case.is_parking_node);
+        bidir.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+        let mut core_ch_query = CSP2CoreCHQuery::new(core_ch.borrow());
+        core_ch_query.set_custom_reset_nodes(Rc::new(case.is_parking_node.clone()));
+        core_ch_query.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+        let mut astar_state = TwoRestrictionDijkstraData::new_with_potential(n, CHPotential::from_ch(ch.borrow()));
+        astar_state.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+        let mut chpot_queries = chpot_2_queries(&case, &bw_graph, &ch, &core_ch, DriverState::default(), RemainingDrivingTime::default());
+
+        for s in 0..n as NodeId {
+            for t in 0..n as NodeId {
+                let expected = brute_force_dist(graph.borrow(), &case.is_parking_node, &[short, long], s, t);
+
+                state.init_new_s(s);
+                assert_eq!(dijkstra.dist_query(&mut state, t), expected, "dijkstra, seed {} from {} to {}", seed, s, t);
+                state.init_new_s(s);
+                assert_eq!(
+                    dijkstra.dist_query_propagate_all_labels(&mut state, t),
+                    expected,
+                    "dijkstra propagating all labels, seed {} from {} to {}",
+                    seed,
+                    s,
+                    t
+                );
+
+                bidir.init_new_s(s);
+                bidir.init_new_t(t);
+                assert_eq!(bidir.run_query(), expected, "bidir, seed {} from {} to {}", seed, s, t);
+
+                core_ch_query.init_new_s(s);
+                core_ch_query.init_new_t(t);
+                assert_eq!(core_ch_query.run_query(), expected, "core ch, seed {} from {} to {}", seed, s, t);
+
+                astar_state.init_new_s(s);
+                assert_eq!(dijkstra.dist_query(&mut astar_state, t), expected, "astar, seed {} from {} to {}", seed, s, t);
+                for (name, query) in &mut chpot_queries {
+                    query.init_new_s(s);
+                    query.init_new_t(t);
+                    assert_eq!(query.run_query(), expected, "{}, seed {} from {} to {}", name, seed, s, t);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn without_restrictions_is_dijkstra() {
+    let case = random_case(7);
+    let unbounded = DrivingTimeRestriction {
+        max_driving_time: Weight::infinity(),
+        pause_time: 0,
+    };
+    for s in 0..case.graph.num_nodes() as NodeId {
+        for t in 0..case.graph.num_nodes() as NodeId {
+            assert_eq!(
+                brute_force_dist(case.graph.borrow(), &case.is_parking_node, &[], s, t),
+                brute_force_dist(case.graph.borrow(), &case.is_parking_node, &[unbounded], s, t)
+            );
+        }
+    }
+}
+
+/// A Dijkstra or A* search on a prepared state, from s to t
+type DistQuery<'a> = Box<dyn FnMut(NodeId, NodeId) -> Option<Weight> + 'a>;
+
+/// Compares all queries starting with a random driver state against the reference solver.
+/// Without `with_remaining_driving_time` no driving time has to be left at the target.
+fn compare_with_driver_state(num_restrictions: usize, with_remaining_driving_time: bool) {
+    for seed in 0..NUM_CASES {
+        let case = random_case(seed);
+        let driver_state = random_driver_state(seed, &case);
+        let remaining = if with_remaining_driving_time {
+            random_remaining_driving_time(seed, &case)
+        } else {
+            RemainingDrivingTime::default()
+        };
+        let graph = &case.graph;
+        let n = graph.num_nodes();
+        let core_ch = core_only_hierarchy(graph);
+        let bw_graph = OwnedGraph::reverse(graph.borrow());
+        let ch = contract_by_id(graph);
+        let (short, long) = (case.short, case.long);
+        let restrictions = &[short, long][..num_restrictions];
+
+        let mut dist_queries: Vec<(&str, DistQuery)>;
+        let mut queries: Vec<(&str, Box<dyn PointToPointQuery + '_>)>;
+        if num_restrictions == 1 {
+            let dijkstra = OneRestrictionDijkstra::new(graph.borrow(), &case.is_parking_node);
+            let mut state = OneRestrictionDijkstraData::new(n);
+            state.set_restriction(short.max_driving_time, short.pause_time);
+            state.set_required_remaining_driving_time(&remaining);
+            let astar = OneRestrictionDijkstra::new(graph.borrow(), &case.is_parking_node);
+            let mut astar_state = OneRestrictionDijkstraData::new_with_potential(n, CHPotential::from_ch(ch.borrow()));
+            astar_state.set_restriction(short.max_driving_time, short.pause_time);
+            astar_state.set_required_remaining_driving_time(&remaining);
+            dist_queries = vec![
+                (
+                    "dijkstra",
+                    Box::new(move |s, t| {
+                        state.init_new_s_with_driver_state(s, &driver_state);
+                        dijkstra.dist_query(&mut state, t)
+                    }),
+                ),
+                (
+                    "astar",
+                    Box::new(move |s, t| {
+                        astar_state.init_new_s_with_driver_state(s, &driver_state);
+                        astar.dist_query(&mut astar_state, t)
+                    }),
+                ),
+            ];
+
+            let mut bidir = CSPBidirQuery::new(graph.borrow(), bw_graph.borrow(), &case.is_parking_node);
+            bidir.set_restriction(short.max_driving_time, short.pause_time);
+            bidir.set_driver_state(driver_state);
+            bidir.set_required_remaining_driving_time(remaining);
+            let mut core_ch_query = CSPCoreCHQuery::new(core_ch.borrow());
+            core_ch_query.set_custom_reset_nodes(Rc::new(case.is_parking_node.clone()));
+            core_ch_query.set_restriction(short.max_driving_time, short.pause_time);
+            core_ch_query.set_driver_state(driver_state);
+            core_ch_query.set_required_remaining_driving_time(remaining);
+            queries = vec![("bidir", Box::new(bidir)), ("core ch", Box::new(core_ch_query))];
+            queries.extend(chpot_queries(&case, &bw_graph, &ch, &core_ch, driver_state, remaining));
+        } else {
+            let dijkstra = TwoRestrictionDijkstra::new(graph.borrow(), &case.is_parking_node);
+            let mut state = TwoRestrictionDijkstraData::new(n);
+            state.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+            state.set_required_remaining_driving_time(&remaining);
+            let astar = TwoRestrictionDijkstra::new(graph.borrow(), &case.is_parking_node);
+            let mut astar_state = TwoRestrictionDijkstraData::new_with_potential(n, CHPotential::from_ch(ch.borrow()));
+            astar_state.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+            astar_state.set_required_remaining_driving_time(&remaining);
+            dist_queries = vec![
+                (
+                    "dijkstra",
+                    Box::new(move |s, t| {
+                        state.init_new_s_with_driver_state(s, &driver_state);
+                        dijkstra.dist_query(&mut state, t)
+                    }),
+                ),
+                (
+                    "astar",
+                    Box::new(move |s, t| {
+                        astar_state.init_new_s_with_driver_state(s, &driver_state);
+                        astar.dist_query(&mut astar_state, t)
+                    }),
+                ),
+            ];
+
+            let mut bidir = CSP2BidirQuery::new(graph.borrow(), bw_graph.borrow(), &case.is_parking_node);
+            bidir.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+            bidir.set_driver_state(driver_state);
+            bidir.set_required_remaining_driving_time(remaining);
+            let mut core_ch_query = CSP2CoreCHQuery::new(core_ch.borrow());
+            core_ch_query.set_custom_reset_nodes(Rc::new(case.is_parking_node.clone()));
+            core_ch_query.set_restriction(long.max_driving_time, long.pause_time, short.max_driving_time, short.pause_time);
+            core_ch_query.set_driver_state(driver_state);
+            core_ch_query.set_required_remaining_driving_time(remaining);
+            queries = vec![("bidir", Box::new(bidir)), ("core ch", Box::new(core_ch_query))];
+            queries.extend(chpot_2_queries(&case, &bw_graph, &ch, &core_ch, driver_state, remaining));
+        }
+
+        for s in 0..n as NodeId {
+            for t in 0..n as NodeId {
+                let expected = if with_remaining_driving_time {
+                    brute_force_dist_with_remaining_driving_time(graph.borrow(), &case.is_parking_node, restrictions, s, t, &driver_state, &remaining)
+                } else {
+                    brute_force_dist_with_driver_state(graph.borrow(), &case.is_parking_node, restrictions, s, t, &driver_state)
+                };
+
+                for (name, query) in &mut dist_queries {
+                    assert_eq!(query(s, t), expected, "{}, seed {} from {} to {}", name, seed, s, t);
+                }
+                for (name, query) in &mut queries {
+                    query.init_new_s(s);
+                    query.init_new_t(t);
+                    assert_eq!(query.run_query(), expected, "{}, seed {} from {} to {}", name, seed, s, t);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn one_restriction_with_driver_state() {
+    compare_with_driver_state(1, false);
+}
+
+#[test]
+fn two_restrictions_with_driver_state() {
+    compare_with_driver_state(2, false);
+}
+
+#[test]
+fn one_restriction_with_remaining_driving_time() {
+    compare_with_driver_state(1, true);
+}
+
+#[test]
+fn two_restrictions_with_remaining_driving_time() {
+    compare_with_driver_state(2, true);
+}
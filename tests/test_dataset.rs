@@ -0,0 +1,115 @@
+use bit_vec::BitVec;
+use rust_truck_router::{
+    algo::{ch::ContractionHierarchy, core_ch::CoreContractionHierarchy},
+    io::dataset::*,
+    types::*,
+};
+use std::{error::Error, fs, path::Path};
+
+fn test_dir() -> Result<std::path::PathBuf, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join("rust_truck_router_test_dataset");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+#[test]
+fn roundtrip_routingkit_dir() -> Result<(), Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let file = test_dir()?.join("core_instance_2.rtr");
+
+    let mut dataset = Dataset::from_routingkit_dir(&path)?;
+    dataset.insert_bitvector(PARKING_FLAGS, &BitVec::from_fn(dataset.num_nodes(), |i| i % 2 == 0));
+    dataset.write_to(&file)?;
+    let dataset = Dataset::load_from(&file)?;
+    assert!(dataset.contains(CH) && dataset.contains("ch/rank"));
+    assert!(!dataset.contains("c") && !dataset.contains(LATITUDE));
+
+    let graph = OwnedGraph::load_from_routingkit_dir(&path)?;
+    let loaded_graph = dataset.get_graph(GRAPH)?;
+    assert_eq!(graph.first_out(), loaded_graph.first_out());
+    assert_eq!(graph.head(), loaded_graph.head());
+    assert_eq!(graph.weights(), loaded_graph.weights());
+
+    let flags = dataset.get_node_flags(PARKING_FLAGS)?;
+    assert_eq!(flags, BitVec::from_fn(graph.num_nodes(), |i| i % 2 == 0));
+
+    let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
+    let loaded_ch = dataset.get_ch(CH)?;
+    assert_eq!(ch.rank(), loaded_ch.rank());
+    assert_eq!(ch.order(), loaded_ch.order());
+    assert_eq!(ch.forward().head(), loaded_ch.forward().head());
+    assert_eq!(ch.backward().weights(), loaded_ch.backward().weights());
+
+    let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
+    let loaded_core_ch = dataset.get_core_ch(CORE_CH)?;
+    assert_eq!(core_ch.is_core(), loaded_core_ch.is_core());
+    assert_eq!(core_ch.forward.first_out(), loaded_core_ch.forward.first_out());
+
+    Ok(())
+}
+
+#[test]
+fn reject_corrupted_files() -> Result<(), Box<dyn Error>> {
+    let dir = test_dir()?;
+    let graph = OwnedGraph::new(vec![0, 1, 3, 4, 5, 5], vec![1, 2, 3, 4, 4], vec![1, 4, 3, 2, 4]);
+    let mut dataset = Dataset::new(graph.num_nodes(), graph.num_arcs());
+    dataset.insert_graph(GRAPH, &graph);
+    dataset.write_to(dir.join("valid.rtr"))?;
+    let bytes = fs::read(dir.join("valid.rtr"))?;
+
+    let mut flipped = bytes.clone();
+    *flipped.last_mut().unwrap() ^= 1;
+    fs::write(dir.join("flipped.rtr"), &flipped)?;
+    assert!(matches!(Dataset::load_from(dir.join("flipped.rtr")), Err(DatasetError::ChecksumMismatch(name)) if name == "graph/travel_time"));
+
+    fs::write(dir.join("truncated.rtr"), &bytes[..bytes.len() - 3])?;
+    assert!(matches!(Dataset::load_from(dir.join("truncated.rtr")), Err(DatasetError::Truncated)));
+
+    fs::write(dir.join("magic.rtr"), &bytes[1..])?;
+    assert!(matches!(Dataset::load_from(dir.join("magic.rtr")), Err(DatasetError::InvalidMagic)));
+
+    Ok(())
+}
+
+#[test]
+fn reject_huge_section_headers() -> Result<(), Box<dyn Error>> {
+    let dir = test_dir()?;
+    let mut dataset = Dataset::new(0, 0);
+    dataset.insert("huge", &[7u32]);
+    dataset.write_to(dir.join("huge.rtr"))?;
+    let bytes = fs::read(dir.join("huge.rtr"))?;
+    // the header of the only section starts after the 32 byte file header with the name length and the name
+    let (element_type, len, num_bytes) = (40..44, 44..52, 52..60);
+
+    // the byte size of the length wraps around to zero
+    let mut overflowing = bytes.clone();
+    overflowing[len.clone()].copy_from_slice(&(1u64 << 62).to_le_bytes());
+    overflowing[num_bytes.clone()].copy_from_slice(&0u64.to_le_bytes());
+    fs::write(dir.join("overflowing.rtr"), &overflowing)?;
+    assert!(matches!(Dataset::load_from(dir.join("overflowing.rtr")), Err(DatasetError::InvalidSectionHeader(name)) if name == "huge"));
+
+    // the end of the section is beyond the largest position
+    let mut beyond = bytes;
+    beyond[element_type].copy_from_slice(&0u32.to_le_bytes());
+    beyond[len].copy_from_slice(&u64::MAX.to_le_bytes());
+    beyond[num_bytes].copy_from_slice(&u64::MAX.to_le_bytes());
+    fs::write(dir.join("beyond.rtr"), &beyond)?;
+    assert!(matches!(Dataset::load_from(dir.join("beyond.rtr")), Err(DatasetError::Truncated)));
+
+    Ok(())
+}
+
+#[test]
+fn reject_mismatching_sections() {
+    let graph = OwnedGraph::new(vec![0, 1, 3, 4, 5, 5], vec![1, 2, 3, 4, 4], vec![1, 4, 3, 2, 4]);
+    let mut dataset = Dataset::new(4, graph.num_arcs());
+    dataset.insert_graph(GRAPH, &graph);
+    assert!(matches!(dataset.get_graph(GRAPH), Err(DatasetError::LengthMismatch { .. })));
+
+    dataset.insert_bitvector(PARKING_FLAGS, &BitVec::from_elem(3, false));
+    assert!(matches!(dataset.get_node_flags(PARKING_FLAGS), Err(DatasetError::LengthMismatch { .. })));
+
+    dataset.insert(PARKING_FLAGS, &[0u32, 1]);
+    assert!(matches!(dataset.get_bitvector(PARKING_FLAGS), Err(DatasetError::TypeMismatch { .. })));
+    assert!(matches!(dataset.get_ch(CH), Err(DatasetError::MissingSection(_))));
+}
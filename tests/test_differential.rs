@@ -0,0 +1,86 @@
+use bit_vec::BitVec;
+use rust_truck_router::{
+    experiments::{
+        differential::*,
+        queries::{load_queries, Query},
+    },
+    io::dataset::*,
+    query::*,
+    types::*,
+};
+use std::{error::Error, fs, path::Path};
+
+fn load_data() -> Result<RoutingData, Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let mut dataset = Dataset::from_routingkit_dir(&path)?;
+    dataset.insert_bitvector(PARKING_FLAGS, &BitVec::from_fn(dataset.num_nodes(), |i| i == 2 || i == 3));
+    Ok(RoutingData::from_dataset(&dataset)?)
+}
+
+fn all_pairs(num_nodes: usize) -> Vec<Query> {
+    (0..num_nodes as NodeId)
+        .flat_map(|s| {
+            (0..num_nodes as NodeId).map(move |t| Query {
+                s,
+                t,
+                dijkstra_rank_exponent: None,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn all_candidates_agree() -> Result<(), Box<dyn Error>> {
+    let data = load_data()?;
+    let queries = all_pairs(data.num_nodes());
+
+    for restriction in RestrictionPreset::ALL {
+        let report = run_differential(&data, &Candidate::all_with(restriction), &queries);
+        assert!(report.all_agree(), "{}", report);
+        assert_eq!(report.num_queries, queries.len());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn disagreements_are_reported() -> Result<(), Box<dyn Error>> {
+    let mut data = load_data()?;
+    // the hierarchies still use the original weights
+    data.graph = OwnedGraph::new(
+        data.graph.first_out().to_vec(),
+        data.graph.head().to_vec(),
+        data.graph.weights().iter().map(|w| 2 * w).collect(),
+    );
+    let queries = all_pairs(data.num_nodes());
+
+    let report = run_differential(&data, &[Candidate::Dijkstra, Candidate::Ch], &queries);
+    assert!(!report.all_agree());
+    for disagreement in &report.disagreements {
+        assert_eq!(disagreement.reference, Candidate::Dijkstra);
+        assert_eq!(disagreement.candidate, Candidate::Ch);
+        assert_eq!(disagreement.expected.map(|d| d / 2), disagreement.found);
+    }
+
+    let failing = report.failing_queries();
+    assert!(failing.iter().all(|q| q.s != q.t));
+    assert_eq!(report.disagreements.len(), failing.len());
+
+    let dir = std::env::temp_dir().join("rust_truck_router_test_differential");
+    fs::create_dir_all(&dir)?;
+    let corpus = dir.join("corpus.csv");
+    if corpus.exists() {
+        fs::remove_file(&corpus)?;
+    }
+
+    assert_eq!(report.update_corpus(&corpus)?, failing.len());
+    assert_eq!(report.update_corpus(&corpus)?, 0);
+    assert_eq!(load_queries(&corpus)?, failing);
+
+    report.write_disagreements(dir.join("disagreements.csv"))?;
+    let lines = fs::read_to_string(dir.join("disagreements.csv"))?;
+    assert_eq!(lines.lines().count(), report.disagreements.len() + 1);
+    assert!(lines.starts_with("source,target,reference,expected,candidate,found\n"));
+
+    Ok(())
+}
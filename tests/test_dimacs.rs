@@ -0,0 +1,80 @@
+use bit_vec::BitVec;
+use rust_truck_router::{
+    algo::dijkstra::{Dijkstra, DijkstraData},
+    io::dimacs::*,
+    types::*,
+};
+use std::{error::Error, fs, path::PathBuf};
+
+fn test_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join("rust_truck_router_test_dimacs");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+#[test]
+fn read_unsorted_arcs_with_comments() -> Result<(), Box<dyn Error>> {
+    // 0 -> 1 -> 2p -> 4
+    //      | -> 3p -> |
+    let path = test_dir()?.join("small.gr");
+    fs::write(
+        &path,
+        "c small test graph\np sp 5 5\nc arcs in arbitrary order\na 4 5 2\na 1 2 1\na 2 4 3\na 3 5 4\na 2 3 4\n",
+    )?;
+
+    let graph = read_dimacs_graph(&path)?;
+    assert_eq!(graph.first_out(), &[0, 1, 3, 4, 5, 5]);
+    assert_eq!(graph.head(), &[1, 3, 2, 4, 4]);
+    assert_eq!(graph.weights(), &[1, 3, 4, 4, 2]);
+
+    let mut state = DijkstraData::new(graph.num_nodes());
+    state.init_new_s(0);
+    assert_eq!(Dijkstra::new(graph.borrow()).dist_query(&mut state, 4), Some(6));
+
+    Ok(())
+}
+
+#[test]
+fn roundtrip() -> Result<(), Box<dyn Error>> {
+    let dir = test_dir()?;
+    let graph = OwnedGraph::new(vec![0, 1, 3, 4, 5, 5], vec![1, 2, 3, 4, 4], vec![1, 4, 3, 2, 4]);
+    write_dimacs_graph(&graph, dir.join("roundtrip.gr"))?;
+    let read_graph = read_dimacs_graph(dir.join("roundtrip.gr"))?;
+    assert_eq!(graph.first_out(), read_graph.first_out());
+    assert_eq!(graph.head(), read_graph.head());
+    assert_eq!(graph.weights(), read_graph.weights());
+
+    let latitude = vec![49.0, 49.0125, -33.5, 0.0, 89.75];
+    let longitude = vec![8.4, -73.5, 151.25, 0.0, -179.25];
+    write_dimacs_coordinates(&latitude, &longitude, dir.join("roundtrip.co"))?;
+    let (read_latitude, read_longitude) = read_dimacs_coordinates(dir.join("roundtrip.co"))?;
+    for i in 0..latitude.len() {
+        assert!((latitude[i] - read_latitude[i]).abs() < 1e-5);
+        assert!((longitude[i] - read_longitude[i]).abs() < 1e-5);
+    }
+
+    let flags = BitVec::from_fn(5, |i| i == 2 || i == 3);
+    write_dimacs_flags(&flags, graph.num_nodes(), dir.join("roundtrip.flg"))?;
+    assert_eq!(read_dimacs_flags(dir.join("roundtrip.flg"))?, flags);
+
+    Ok(())
+}
+
+#[test]
+fn reject_malformed_files() -> Result<(), Box<dyn Error>> {
+    let dir = test_dir()?;
+
+    fs::write(dir.join("no_problem.gr"), "a 1 2 3\n")?;
+    assert!(read_dimacs_graph(dir.join("no_problem.gr")).is_err());
+
+    fs::write(dir.join("out_of_range.gr"), "p sp 2 1\na 1 3 3\n")?;
+    assert!(read_dimacs_graph(dir.join("out_of_range.gr")).is_err());
+
+    fs::write(dir.join("wrong_count.gr"), "p sp 2 2\na 1 2 3\n")?;
+    assert!(read_dimacs_graph(dir.join("wrong_count.gr")).is_err());
+
+    fs::write(dir.join("missing.co"), "p aux sp co 2\nv 1 8400000 49000000\n")?;
+    assert!(read_dimacs_coordinates(dir.join("missing.co")).is_err());
+
+    Ok(())
+}
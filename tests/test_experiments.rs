@@ -0,0 +1,202 @@
+use bit_vec::BitVec;
+use rust_truck_router::{
+    algo::{
+        dijkstra::{Dijkstra, DijkstraData},
+        stats::QueryStats,
+    },
+    experiments::{measurement::MeasurementResult, queries::*, runner::*, thesis::*},
+    io::dataset::*,
+    query::{new_query, Algorithm, RestrictionPreset, RoutingData},
+    types::*,
+};
+use std::{error::Error, fs, path::Path};
+
+fn test_dir() -> Result<std::path::PathBuf, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join("rust_truck_router_test_experiments");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+#[test]
+fn generators_are_seeded() -> Result<(), Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let graph = OwnedGraph::load_from_routingkit_dir(&path)?;
+
+    let is_parking_node = BitVec::from_fn(graph.num_nodes(), |i| i == 2 || i == 3);
+
+    let generator = QueryGenerator::Random { count: 20, seed: 7 };
+    let queries = generator.generate(graph.borrow(), &is_parking_node)?;
+    assert_eq!(queries.len(), 20);
+    assert_eq!(queries, generator.generate(graph.borrow(), &is_parking_node)?);
+    assert_ne!(queries, random_queries(graph.num_nodes(), 20, 8));
+    assert!(queries.iter().all(|q| (q.s as usize) < graph.num_nodes() && (q.t as usize) < graph.num_nodes()));
+
+    let rank_queries = dijkstra_rank_queries(graph.borrow(), 10, 7);
+    assert_eq!(rank_queries, dijkstra_rank_queries(graph.borrow(), 10, 7));
+    for chunk in rank_queries.chunks(2) {
+        assert_eq!(chunk[0].dijkstra_rank_exponent, Some(0));
+        assert_eq!(chunk[0].t, chunk[0].s);
+        assert_eq!(chunk[1].dijkstra_rank_exponent, Some(1));
+    }
+
+    let parking_queries = parking_to_parking_queries(&is_parking_node, graph.num_nodes(), 20, 7);
+    assert_eq!(parking_queries.len(), 20);
+    assert!(parking_queries.iter().all(|q| is_parking_node[q.s as usize] && is_parking_node[q.t as usize]));
+    assert!(parking_to_parking_queries(&BitVec::from_elem(graph.num_nodes(), false), graph.num_nodes(), 20, 7).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn distance_band() -> Result<(), Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let graph = OwnedGraph::load_from_routingkit_dir(&path)?;
+    let dijkstra = Dijkstra::new(graph.borrow());
+    let mut state = DijkstraData::new(graph.num_nodes());
+
+    let queries = distance_band_queries(graph.borrow(), 10, 3, 2, 4);
+    assert_eq!(queries.len(), 10);
+    for query in &queries {
+        state.init_new_s(query.s);
+        let dist = dijkstra.dist_query(&mut state, query.t).unwrap();
+        assert!((2..4).contains(&dist), "{:?} has distance {}", query, dist);
+    }
+
+    assert!(distance_band_queries(graph.borrow(), 10, 3, 1000, 2000).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn save_and_load() -> Result<(), Box<dyn Error>> {
+    let dir = test_dir()?;
+    let queries = vec![
+        Query {
+            s: 1,
+            t: 2,
+            dijkstra_rank_exponent: None,
+        },
+        Query {
+            s: 3,
+            t: 0,
+            dijkstra_rank_exponent: Some(4),
+        },
+    ];
+
+    save_queries(dir.join("queries.csv"), &queries)?;
+    assert_eq!(
+        fs::read_to_string(dir.join("queries.csv"))?,
+        "source,target,dijkstra_rank_exponent\n1,2,NaN\n3,0,4\n"
+    );
+    assert_eq!(load_queries(dir.join("queries.csv"))?, queries);
+
+    fs::write(dir.join("plain.csv"), "1, 2\n\n3,0,4\n")?;
+    assert_eq!(load_queries(dir.join("plain.csv"))?, queries);
+
+    fs::write(dir.join("invalid.csv"), "1,2,3,4\n")?;
+    assert!(load_queries(dir.join("invalid.csv")).is_err());
+
+    let generator: QueryGenerator = serde_json::from_str(&format!(r#"{{ "type": "file", "path": {:?} }}"#, dir.join("queries.csv")))?;
+    assert_eq!(
+        generator.generate(OwnedGraph::new(vec![0; 5], vec![], vec![]).borrow(), &BitVec::new())?,
+        queries
+    );
+
+    Ok(())
+}
+
+#[test]
+fn run_config() -> Result<(), Box<dyn Error>> {
+    let dir = test_dir()?;
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let mut dataset = Dataset::from_routingkit_dir(&path)?;
+    dataset.insert_bitvector(PARKING_FLAGS, &BitVec::from_fn(dataset.num_nodes(), |i| i == 2 || i == 3));
+    dataset.write_to(dir.join("core_instance_2.rtr"))?;
+
+    let config_file = dir.join("config.json");
+    fs::write(
+        &config_file,
+        format!(
+            r#"{{
+                "dataset": {:?},
+                "algorithms": ["dijkstra", "core_ch_astar"],
+                "restrictions": ["eu_short", "eu_long"],
+                "queries": {{ "type": "dijkstra_rank", "count": 3, "seed": 1 }},
+                "repetitions": 2,
+                "output": {:?}
+            }}"#,
+            dir.join("core_instance_2.rtr"),
+            dir.join("results.csv")
+        ),
+    )?;
+
+    let config = ExperimentConfig::load_from(&config_file)?;
+    assert_eq!(config.algorithms, vec![Algorithm::Dijkstra, Algorithm::CoreChAstar]);
+    run_experiment(&config)?;
+
+    let csp = fs::read_to_string(config.output_for(RestrictionPreset::EuShort))?;
+    let mut lines = csp.lines();
+    assert_eq!(lines.next(), Some(format!("algo,dijkstra_rank_exponent,{}", QueryStats::get_header()).as_str()));
+    // sources which do not reach two nodes are skipped, every query yields a row per algorithm and repetition
+    let num_queries = dijkstra_rank_queries(OwnedGraph::load_from_routingkit_dir(&path)?.borrow(), 3, 1).len();
+    assert!(num_queries > 0);
+    let num_columns = QueryStats::get_header().split(',').count() + 2;
+    assert!(lines.clone().all(|line| line.split(',').count() == num_columns));
+    assert_eq!(lines.count(), num_queries * 2 * 2);
+
+    let csp_2 = fs::read_to_string(dir.join("results-eu_long.csv"))?;
+    assert!(csp_2.lines().next().unwrap().ends_with("path_number_short_pauses,path_number_long_pauses"));
+    assert!(csp_2
+        .lines()
+        .skip(1)
+        .all(|line| line.starts_with("dijkstra,") || line.starts_with("core_ch_astar,")));
+
+    Ok(())
+}
+
+#[test]
+fn thesis_configs() -> Result<(), Box<dyn Error>> {
+    let mut num_configs = 0;
+    for entry in fs::read_dir("experiment_configs")? {
+        let path = entry?.path();
+        let config = ExperimentConfig::load_from(&path)?;
+        // eval/plotting/make_plots.py runs the configs by name and reads one file per config
+        assert_eq!(config.restrictions.len(), 1);
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        assert!(name.ends_with(&format!("-{}", problem_name(config.restrictions[0]))));
+        num_configs += 1;
+    }
+    assert!(num_configs > 0);
+
+    Ok(())
+}
+
+#[test]
+fn sweep_rows() -> Result<(), Box<dyn Error>> {
+    let dir = test_dir()?;
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let mut dataset = Dataset::from_routingkit_dir(&path)?;
+    dataset.insert_bitvector(PARKING_FLAGS, &BitVec::from_fn(dataset.num_nodes(), |i| i == 2 || i == 3));
+    let data = RoutingData::from_dataset(&dataset)?;
+    let queries = random_queries(data.num_nodes(), 5, 1);
+
+    let mut sweep = Sweep::new("max_driving_time,pause_time");
+    for algorithm in [Algorithm::CoreCh, Algorithm::CoreChAstar] {
+        let mut query = new_query(&data, algorithm, RestrictionPreset::EuShort);
+        sweep.measure(algorithm.name(), "16200000,2700000", query.as_mut(), &queries)?;
+    }
+    sweep.write_to(dir.join("sweep.txt"))?;
+
+    let results = fs::read_to_string(dir.join("sweep.txt"))?;
+    let mut lines = results.lines();
+    assert_eq!(lines.next(), Some("algo,max_driving_time,pause_time,time_ms,path_distance"));
+    assert_eq!(lines.clone().count(), 2 * queries.len());
+    assert!(lines.all(|line| line.split(',').count() == 5 && line.contains(",16200000,2700000,")));
+
+    assert_eq!(
+        output_file("speed_cap", RestrictionPreset::EuLong, Path::new("/data/parking_europe_hgv_sc")),
+        Path::new("thesis_speed_cap-csp_2-parking_europe_hgv_sc.txt")
+    );
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use rust_truck_router::{
+    algo::csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+    label_arena::LabelArena,
+    synthetic::*,
+    types::*,
+};
+use std::cmp::Reverse;
+
+fn label(distance: Weight2, prev_label: Option<usize>) -> Reverse<Label<Weight2>> {
+    Reverse(Label {
+        distance_with_potential: distance[0],
+        distance,
+        prev_node: 0,
+        prev_label,
+    })
+}
+
+#[test]
+fn labels_are_settled_best_first() {
+    let mut labels: LabelArena<Weight2> = LabelArena::with_size(3);
+    for d in [[7, 0], [3, 5], [6, 1], [4, 2], [2, 8]] {
+        labels.get_mut(1).push(label(d, None), |_| ());
+    }
+    assert_eq!(labels.get(1).len(), 5);
+    assert_eq!(labels.get(1).peek().unwrap().0.distance, [2, 8]);
+
+    let mut popped = Vec::new();
+    while let Some(l) = labels.get_mut(1).pop() {
+        popped.push(l.0.distance);
+    }
+    assert_eq!(popped, [[2, 8], [3, 5], [4, 2], [6, 1], [7, 0]]);
+    assert_eq!(labels.get(1).popped().map(|l| l.0.distance).collect::<Vec<_>>(), popped);
+    assert!(labels.get(1).iter().next().is_none());
+    assert!(labels.get(0).is_empty() && labels.get(2).is_empty());
+}
+
+#[test]
+fn dominated_labels_stay_reachable() {
+    let mut labels: LabelArena<Weight2> = LabelArena::with_size(2);
+    let settled = labels.get_mut(0).push(label([4, 4], None), |_| ());
+    labels.get_mut(0).pop();
+    let child = labels.get_mut(1).push(label([6, 6], Some(settled)), |_| ());
+    labels.get_mut(0).push(label([5, 0], None), |_| ());
+
+    // a new label at node 0 dominates the settled one
+    assert!(!labels.get(0).is_dominated(&[3, 3]));
+    let mut removed = Vec::new();
+    labels.get_mut(0).push(label([3, 3], None), |l| removed.push(l.0.distance));
+    assert_eq!(removed, [[4, 4]]);
+    assert_eq!(labels.get(0).len(), 2);
+    assert_eq!(labels.get(0).best_distance(), 3);
+    assert_eq!(labels.get(0).popped().count(), 0);
+    let mut unsettled: Vec<_> = labels.get(0).iter().map(|l| l.0.distance).collect();
+    unsettled.sort_unstable();
+    assert_eq!(unsettled, [[3, 3], [5, 0]]);
+
+    // the predecessor of the child is still there
+    assert_eq!(labels.label(labels.label(child).0.prev_label.unwrap()).0.distance, [4, 4]);
+    assert_eq!(labels.num_labels(), 4);
+
+    // new labels never reuse the indices of removed ones
+    assert_eq!(labels.get_mut(0).push(label([1, 9], None), |_| ()), 4);
+}
+
+#[test]
+fn memory() {
+    let mut labels: LabelArena<Weight2> = LabelArena::with_size(10);
+    assert_eq!(labels.label_bytes(), 0);
+    for i in 0..100 {
+        labels.get_mut(i % 10).push(label([i as Weight, 1000 - i as Weight], None), |_| ());
+    }
+    let label_bytes = labels.label_bytes();
+    assert_eq!(label_bytes % 100, 0);
+    assert!(labels.allocated_bytes() >= label_bytes);
+
+    // the memory is kept for the next search
+    labels.reset();
+    assert_eq!(labels.label_bytes(), 0);
+    assert!(labels.allocated_bytes() >= label_bytes);
+    assert!(labels.get(3).is_empty());
+
+    labels.clean();
+    assert!(labels.allocated_bytes() < label_bytes);
+}
+
+#[test]
+fn search_reports_memory() {
+    let config = SyntheticConfig {
+        parking_density: 0.5,
+        ..SyntheticConfig::default()
+    };
+    let network = motorway_corridor(20, 30_000.0, 2, 3_000.0, &config);
+    let t = network.graph.num_nodes() as NodeId - 1;
+
+    let csp = OneRestrictionDijkstra::new(network.graph.borrow(), &network.is_parking_node);
+    let mut state = OneRestrictionDijkstraData::new(network.graph.num_nodes());
+    state.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    state.init_new_s(0);
+    let dist = csp.dist_query(&mut state, t);
+    assert!(dist.is_some());
+
+    // the start label and every propagated label
+    assert_eq!(state.per_node_labels.num_labels(), state.stats.num_labels_propagated as usize + 1);
+    assert_eq!(state.label_memory(), state.per_node_labels.label_bytes());
+    assert!(state.allocated_label_memory() >= state.label_memory());
+
+    let path = state.current_best_node_path_to(t).unwrap();
+    assert_eq!(path.first(), Some(&0));
+    assert_eq!(path.last(), Some(&t));
+
+    // a second query reuses the memory
+    let allocated = state.allocated_label_memory();
+    state.init_new_s(0);
+    assert_eq!(csp.dist_query(&mut state, t), dist);
+    assert_eq!(state.allocated_label_memory(), allocated);
+}
+
+#[test]
+fn dominated_labels_are_never_popped() {
+    let mut labels: LabelArena<Weight2> = LabelArena::with_size(1);
+    for d in [[2, 9], [3, 7], [4, 6], [5, 4], [6, 3], [8, 1]] {
+        labels.get_mut(0).push(label(d, None), |_| ());
+    }
+
+    // removes the best label and two labels inside the heap
+    let mut removed = Vec::new();
+    labels.get_mut(0).push(label([2, 5], None), |l| removed.push(l.0.distance));
+    removed.sort_unstable();
+    assert_eq!(removed, [[2, 9], [3, 7], [4, 6]]);
+    assert_eq!(labels.get(0).len(), 4);
+    assert_eq!(labels.get(0).iter().count(), 4);
+
+    let mut popped = Vec::new();
+    while let Some(l) = labels.get_mut(0).pop() {
+        popped.push(l.0.distance);
+    }
+    assert_eq!(popped, [[2, 5], [5, 4], [6, 3], [8, 1]]);
+    assert_eq!(labels.get(0).popped().count(), 4);
+}
@@ -0,0 +1,61 @@
+use rust_truck_router::{
+    algo::{
+        ch::{ContractionHierarchy, ContractionHierarchyQuery, MappedContractionHierarchy},
+        core_ch::{CoreContractionHierarchy, MappedCoreContractionHierarchy},
+    },
+    io::{mmap::MappedVec, *},
+    types::*,
+};
+use std::{error::Error, path::Path};
+
+#[test]
+fn mapped_equals_loaded() -> Result<(), Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+
+    let graph = OwnedGraph::load_from_routingkit_dir(&path)?;
+    let mapped_graph = MappedGraph::map_routingkit_dir(&path)?;
+    assert_eq!(graph.first_out(), mapped_graph.first_out());
+    assert_eq!(graph.head(), mapped_graph.head());
+    assert_eq!(graph.weights(), mapped_graph.weights());
+
+    let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
+    let mapped_ch = MappedContractionHierarchy::map_routingkit_dir(path.join("ch"))?;
+    mapped_ch.check()?;
+    assert_eq!(ch.rank(), mapped_ch.rank());
+    assert_eq!(ch.order(), mapped_ch.order());
+
+    let mut ch_query = ContractionHierarchyQuery::new(ch.borrow());
+    let mut mapped_ch_query = ContractionHierarchyQuery::new(mapped_ch.borrow());
+    for s in 0..graph.num_nodes() as NodeId {
+        ch_query.init_new_s(s);
+        mapped_ch_query.init_new_s(s);
+        for t in 0..graph.num_nodes() as NodeId {
+            ch_query.init_new_t(t);
+            mapped_ch_query.init_new_t(t);
+            assert_eq!(ch_query.run_query(), mapped_ch_query.run_query());
+        }
+    }
+
+    let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
+    let mapped_core_ch = MappedCoreContractionHierarchy::map_routingkit_dir(path.join("core_ch"))?;
+    assert_eq!(core_ch.rank(), mapped_core_ch.rank());
+    assert_eq!(core_ch.forward.head(), mapped_core_ch.forward.head());
+    assert_eq!(core_ch.backward.weights(), mapped_core_ch.backward.weights());
+
+    Ok(())
+}
+
+#[test]
+fn reject_truncated_file() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join("rust_truck_router_test_mmap");
+    std::fs::create_dir_all(&dir)?;
+
+    vec![1u8, 2, 3, 4, 5].write_to(&dir.join("odd"))?;
+    assert!(MappedVec::<u32>::map_from(dir.join("odd")).is_err());
+    assert_eq!(MappedVec::<u8>::map_from(dir.join("odd"))?.as_ref(), &[1, 2, 3, 4, 5]);
+
+    Vec::<u32>::new().write_to(&dir.join("empty"))?;
+    assert!(MappedVec::<u32>::map_from(dir.join("empty"))?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,354 @@
+use std::collections::BTreeMap;
+
+use bit_vec::BitVec;
+use rust_truck_router::{
+    algo::{
+        core_ch::{CoreContractionHierarchy, OwnedCoreContractionHierarchy},
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+        multi_stop::*,
+    },
+    synthetic::*,
+    types::*,
+};
+
+/// Restrictions short enough for breaks on the small random graphs
+const SHORT: DrivingTimeRestriction = DrivingTimeRestriction {
+    max_driving_time: 1_200_000,
+    pause_time: 300_000,
+};
+const LONG: DrivingTimeRestriction = DrivingTimeRestriction {
+    max_driving_time: 2_400_000,
+    pause_time: 900_000,
+};
+
+/// A directed line `0 -> 1 -> ... -> n`
+fn line(weights: Vec<Weight>) -> OwnedGraph {
+    let n = weights.len();
+    OwnedGraph::new(
+        (0..=n as EdgeId).chain(std::iter::once(n as EdgeId)).collect(),
+        (1..=n as NodeId).collect(),
+        weights,
+    )
+}
+
+#[test]
+fn driving_time_carries_over_stops() {
+    // two legs of three hours each, every node is a parking node
+    let graph = line(vec![HOUR; 6]);
+    let is_parking_node = BitVec::from_elem(7, true);
+    let mut query = MultiStopQuery::new(graph.borrow(), &is_parking_node);
+    query.set_restrictions(&[EU_SHORT]);
+
+    let stops = [
+        Stop {
+            node: 0,
+            service_time: 600_000,
+        },
+        Stop {
+            node: 3,
+            service_time: 1_800_000,
+        },
+        Stop::new(6),
+    ];
+    let trip = query.run(&stops).unwrap();
+
+    assert_eq!(trip.path, (0..=6).collect::<Vec<_>>());
+    assert_eq!(trip.driving_time, 6 * HOUR);
+    assert_eq!(trip.breaks.len(), 1);
+    assert_eq!(trip.distance, 6 * HOUR + EU_SHORT_PAUSE_TIME + 600_000 + 1_800_000);
+
+    assert_eq!(trip.stops.len(), 3);
+    assert_eq!(trip.stops[0].departure, 600_000);
+    assert_eq!(trip.stops[1].path_index, 3);
+    assert_eq!(trip.stops[2].departure, trip.distance);
+
+    // the break is taken before more than 4.5 hours are driven since the departure
+    let driven_before_break = trip.breaks[0].start - 600_000 - if trip.breaks[0].node > 3 { 1_800_000 } else { 0 };
+    assert_eq!(trip.breaks[0].node as Weight * HOUR, driven_before_break);
+    assert!(driven_before_break <= EU_SHORT_DRIVING_TIME);
+    assert!(6 * HOUR - driven_before_break <= EU_SHORT_DRIVING_TIME);
+}
+
+#[test]
+fn break_at_stop_which_is_not_the_fastest_arrival() {
+    // the last leg has no parking node, so the break has to be taken at the stop
+    let graph = line(vec![4 * HOUR, HOUR]);
+    let mut is_parking_node = BitVec::from_elem(3, false);
+    is_parking_node.set(1, true);
+    let mut query = MultiStopQuery::new(graph.borrow(), &is_parking_node);
+    query.set_restrictions(&[EU_SHORT]);
+
+    let trip = query
+        .run(&[
+            Stop::new(0),
+            Stop {
+                node: 1,
+                service_time: 1_800_000,
+            },
+            Stop::new(2),
+        ])
+        .unwrap();
+
+    assert_eq!(trip.breaks.len(), 1);
+    assert_eq!(trip.breaks[0].node, 1);
+    assert_eq!(trip.breaks[0].start, 4 * HOUR);
+    assert_eq!(trip.stops[1].arrival, 4 * HOUR);
+    assert_eq!(trip.stops[1].departure, 4 * HOUR + EU_SHORT_PAUSE_TIME + 1_800_000);
+    assert_eq!(trip.distance, 5 * HOUR + EU_SHORT_PAUSE_TIME + 1_800_000);
+}
+
+#[test]
+fn stop_on_optimal_path_does_not_change_the_trip() {
+    let config = SyntheticConfig {
+        parking_density: 1.0,
+        speed: 20,
+        ..SyntheticConfig::default()
+    };
+    let network = random_geometric(300, 100_000.0, 15_000.0, &config);
+    let graph = network.graph.borrow();
+    let is_parking_node = &network.is_parking_node;
+    let mut query = MultiStopQuery::new(graph, is_parking_node);
+
+    let mut csp_state = OneRestrictionDijkstraData::new(graph.num_nodes());
+    csp_state.set_restriction(EU_SHORT.max_driving_time, EU_SHORT.pause_time);
+    let mut csp_2_state = TwoRestrictionDijkstraData::new(graph.num_nodes());
+    csp_2_state.set_restriction(EU_LONG.max_driving_time, EU_LONG.pause_time, EU_SHORT.max_driving_time, EU_SHORT.pause_time);
+
+    for t in (1..graph.num_nodes() as NodeId).step_by(29) {
+        csp_state.init_new_s(0);
+        let path = match OneRestrictionDijkstra::new(graph, is_parking_node).dist_query(&mut csp_state, t) {
+            Some(_) => csp_state.current_best_node_path_to(t).unwrap(),
+            None => continue,
+        };
+        let stops = [Stop::new(0), Stop::new(path[path.len() / 2]), Stop::new(t)];
+
+        query.set_restrictions(&[EU_SHORT]);
+        let trip = query.run(&stops).unwrap();
+        assert_eq!(Some(trip.distance), csp_state.last_distance);
+
+        query.set_restrictions(&[EU_SHORT, EU_LONG]);
+        let trip = query.run(&stops).unwrap();
+        csp_2_state.init_new_s(0);
+        assert_eq!(
+            TwoRestrictionDijkstra::new(graph, is_parking_node).dist_query(&mut csp_2_state, t),
+            Some(trip.distance)
+        );
+    }
+}
+
+#[test]
+fn unreachable_stop() {
+    let graph = line(vec![HOUR, HOUR]);
+    let is_parking_node = BitVec::from_elem(3, true);
+    let mut query = MultiStopQuery::new(graph.borrow(), &is_parking_node);
+
+    assert!(query.run(&[Stop::new(0), Stop::new(2), Stop::new(1)]).is_none());
+    assert_eq!(query.run(&[Stop::new(0), Stop::new(1), Stop::new(2)]).unwrap().distance, 2 * HOUR);
+}
+
+#[test]
+fn search_without_start_labels_finds_nothing() {
+    let graph = line(vec![HOUR, HOUR]);
+    let is_parking_node = BitVec::from_elem(3, true);
+
+    let mut csp_state = OneRestrictionDijkstraData::new(3);
+    csp_state.init_new_s_with_labels(0, &[]);
+    assert_eq!(
+        OneRestrictionDijkstra::new(graph.borrow(), &is_parking_node).dist_query(&mut csp_state, 2),
+        None
+    );
+
+    let mut csp_2_state = TwoRestrictionDijkstraData::new(3);
+    csp_2_state.init_new_s_with_labels(0, &[]);
+    assert_eq!(
+        TwoRestrictionDijkstra::new(graph.borrow(), &is_parking_node).dist_query(&mut csp_2_state, 2),
+        None
+    );
+}
+
+#[test]
+fn stop_without_parking_keeps_labels_beyond_the_slack() {
+    // 0 -> 1 directly or with a break at 3, which arrives at the stop 1 later by more than the pause time but with
+    // less driving time, the only way to reach 2 as there is no parking node after the stop
+    let graph = OwnedGraph::new(vec![0, 2, 3, 3, 4], vec![1, 3, 2, 1], vec![4 * HOUR, HOUR, HOUR, 3 * HOUR + HOUR / 4]);
+    let mut is_parking_node = BitVec::from_elem(4, false);
+    is_parking_node.set(3, true);
+    let mut query = MultiStopQuery::new(graph.borrow(), &is_parking_node);
+    query.set_restrictions(&[EU_SHORT]);
+
+    let trip = query.run(&[Stop::new(0), Stop::new(1), Stop::new(2)]).unwrap();
+
+    assert_eq!(trip.path, vec![0, 3, 1, 2]);
+    assert_eq!(trip.breaks.len(), 1);
+    assert_eq!(trip.distance, 5 * HOUR + HOUR / 4 + EU_SHORT_PAUSE_TIME);
+}
+
+/// A copy of the graph per leg, the copies are connected by an arc of weight zero at the stops
+fn graph_per_leg(graph: &OwnedGraph, is_parking_node: &BitVec, stops: &[NodeId]) -> (OwnedGraph, BitVec) {
+    let n = graph.num_nodes();
+    let legs = stops.len() - 1;
+    let mut first_out = vec![0];
+    let (mut head, mut weight) = (Vec::new(), Vec::new());
+    for leg in 0..legs {
+        for node in 0..n as NodeId {
+            for (&w, &h) in graph.borrow().outgoing_edge_iter(node) {
+                head.push((leg * n) as NodeId + h);
+                weight.push(w);
+            }
+            if leg + 1 < legs && node == stops[leg + 1] {
+                head.push(((leg + 1) * n) as NodeId + node);
+                weight.push(0);
+            }
+            first_out.push(head.len() as EdgeId);
+        }
+    }
+    let is_parking_node_per_leg = (0..legs * n).map(|i| is_parking_node[i % n]).collect();
+    (OwnedGraph::new(first_out, head, weight), is_parking_node_per_leg)
+}
+
+fn random_stops(network: &SyntheticGraph, seed: usize) -> Vec<NodeId> {
+    let n = network.graph.num_nodes();
+    (0..4).map(|i| ((seed * 7919 + i * 104_729) % n) as NodeId).collect()
+}
+
+#[test]
+fn trip_equals_a_query_on_a_graph_copy_per_leg() {
+    for seed in 0..6 {
+        let config = SyntheticConfig {
+            parking_density: 0.3,
+            seed,
+            ..SyntheticConfig::default()
+        };
+        let network = random_geometric(150, 60_000.0, 12_000.0, &config);
+        let mut query = MultiStopQuery::new(network.graph.borrow(), &network.is_parking_node);
+
+        for i in 0..8 {
+            let stops = random_stops(&network, seed as usize * 8 + i);
+            let (graph, is_parking_node) = graph_per_leg(&network.graph, &network.is_parking_node, &stops);
+            let (s, t) = (stops[0], (2 * network.graph.num_nodes()) as NodeId + stops[3]);
+            let stops: Vec<Stop> = stops.into_iter().map(Stop::new).collect();
+
+            query.set_restrictions(&[SHORT]);
+            let mut csp_state = OneRestrictionDijkstraData::new(graph.num_nodes());
+            csp_state.set_restriction(SHORT.max_driving_time, SHORT.pause_time);
+            csp_state.init_new_s(s);
+            let expected = OneRestrictionDijkstra::new(graph.borrow(), &is_parking_node).dist_query(&mut csp_state, t);
+            assert_eq!(query.run(&stops).map(|trip| trip.distance), expected, "seed {seed}, stops {stops:?}");
+
+            query.set_restrictions(&[SHORT, LONG]);
+            let mut csp_2_state = TwoRestrictionDijkstraData::new(graph.num_nodes());
+            csp_2_state.set_restriction(LONG.max_driving_time, LONG.pause_time, SHORT.max_driving_time, SHORT.pause_time);
+            csp_2_state.init_new_s(s);
+            let expected = TwoRestrictionDijkstra::new(graph.borrow(), &is_parking_node).dist_query(&mut csp_2_state, t);
+            assert_eq!(query.run(&stops).map(|trip| trip.distance), expected, "seed {seed}, stops {stops:?}");
+        }
+    }
+}
+
+/// Contracts all nodes which are no parking nodes in the order of their ids and inserts every shortcut without
+/// witness searches, the parking nodes form the core
+fn contract_all_but_parking_nodes(graph: &OwnedGraph, is_parking_node: &BitVec) -> OwnedCoreContractionHierarchy {
+    let n = graph.num_nodes();
+    let order: Vec<NodeId> = (0..n as NodeId)
+        .filter(|&node| !is_parking_node[node as usize])
+        .chain((0..n as NodeId).filter(|&node| is_parking_node[node as usize]))
+        .collect();
+    let mut rank = vec![0; n];
+    for (r, &node) in order.iter().enumerate() {
+        rank[node as usize] = r as NodeId;
+    }
+    let num_contracted = n - is_parking_node.iter().filter(|&b| b).count();
+
+    let mut arcs = BTreeMap::new();
+    for tail in 0..n as NodeId {
+        for (&weight, &head) in graph.borrow().outgoing_edge_iter(tail) {
+            let arc = arcs.entry((rank[tail as usize], rank[head as usize])).or_insert(weight);
+            *arc = weight.min(*arc);
+        }
+    }
+    for node in 0..num_contracted as NodeId {
+        let incoming: Vec<_> = arcs
+            .iter()
+            .filter(|(&(tail, head), _)| head == node && tail > node)
+            .map(|(&(tail, _), &w)| (tail, w))
+            .collect();
+        let outgoing: Vec<_> = arcs
+            .iter()
+            .filter(|(&(tail, head), _)| tail == node && head > node)
+            .map(|(&(_, head), &w)| (head, w))
+            .collect();
+        for &(tail, first) in &incoming {
+            for &(head, second) in &outgoing {
+                if tail != head {
+                    let arc = arcs.entry((tail, head)).or_insert(first + second);
+                    *arc = (first + second).min(*arc);
+                }
+            }
+        }
+    }
+
+    let is_core = |node: NodeId| node as usize >= num_contracted;
+    let ranked_graph = |mut arcs: Vec<(NodeId, NodeId, Weight)>| {
+        arcs.sort_unstable();
+        let mut first_out = vec![0 as EdgeId; n + 1];
+        for &(tail, _, _) in &arcs {
+            first_out[tail as usize + 1] += 1;
+        }
+        for i in 1..first_out.len() {
+            first_out[i] += first_out[i - 1];
+        }
+        OwnedGraph::new(first_out, arcs.iter().map(|a| a.1).collect(), arcs.iter().map(|a| a.2).collect())
+    };
+    let forward = ranked_graph(
+        arcs.iter()
+            .filter(|(&(tail, head), _)| tail < head || is_core(tail) && is_core(head))
+            .map(|(&(tail, head), &w)| (tail, head, w))
+            .collect(),
+    );
+    let backward = ranked_graph(
+        arcs.iter()
+            .filter(|(&(tail, head), _)| tail > head || is_core(tail) && is_core(head))
+            .map(|(&(tail, head), &w)| (head, tail, w))
+            .collect(),
+    );
+    let core = order[num_contracted..].to_vec();
+    CoreContractionHierarchy::new(rank, order, core, forward, backward)
+}
+
+#[test]
+fn core_ch_trip_equals_the_trip_on_the_graph() {
+    for seed in 0..6 {
+        let config = SyntheticConfig {
+            parking_density: 0.3,
+            seed,
+            ..SyntheticConfig::default()
+        };
+        let network = random_geometric(150, 60_000.0, 12_000.0, &config);
+        let core_ch = contract_all_but_parking_nodes(&network.graph, &network.is_parking_node);
+        let mut query = MultiStopQuery::new(network.graph.borrow(), &network.is_parking_node);
+        let mut core_ch_query = MultiStopCoreCHQuery::new(core_ch.borrow(), &network.is_parking_node);
+
+        for i in 0..8 {
+            let stops: Vec<Stop> = random_stops(&network, seed as usize * 8 + i)
+                .into_iter()
+                .enumerate()
+                .map(|(j, node)| Stop {
+                    node,
+                    service_time: j as Weight * 60_000,
+                })
+                .collect();
+
+            for restrictions in [&[][..], &[SHORT], &[SHORT, LONG]] {
+                query.set_restrictions(restrictions);
+                core_ch_query.set_restrictions(restrictions);
+                assert_eq!(
+                    core_ch_query.run(&stops),
+                    query.run(&stops).map(|trip| trip.distance),
+                    "seed {seed}, stops {stops:?}, {} restrictions",
+                    restrictions.len()
+                );
+            }
+        }
+    }
+}
@@ -14,22 +14,22 @@ use rust_truck_router::{
         csp_core_ch_chpot::CSPAstarCoreCHQuery,
         dijkstra::{Dijkstra, DijkstraData},
     },
-    io::*,
+    experiments::{
+        differential::{run_differential, Candidate},
+        queries::{load_queries, random_queries},
+    },
+    io::{dataset::Dataset, *},
+    query::{RestrictionPreset, RoutingData},
     types::*,
 };
 use std::{error::Error, path::Path};
 
-// queries that like to fail
-fn critical_queries() -> Vec<(NodeId, NodeId)> {
-    vec![
-        (131578, 386915),
-        (170777, 226781),
-        (339025, 125566),
-        (366705, 330856),
-        (321615, 220642),
-        (3542, 261248),
-    ]
+// queries that like to fail, differential runs append to this corpus
+fn critical_queries() -> Result<Vec<(NodeId, NodeId)>, Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/regression/parking_ka_hgv.csv"));
+    Ok(load_queries(path)?.into_iter().map(|q| (q.s, q.t)).collect())
 }
+
 #[test]
 #[ignore]
 fn thousand_ka_queries_without_constraints() -> Result<(), Box<dyn Error>> {
@@ -41,9 +41,9 @@ fn thousand_ka_queries_without_constraints() -> Result<(), Box<dyn Error>> {
     let graph = OwnedGraph::new(first_out, head, travel_time);
     let bw_graph = OwnedGraph::reverse(graph.borrow());
     let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
-    ch.check();
+    ch.check()?;
     let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
-    core_ch.check();
+    core_ch.check()?;
     println!("Graph with {} nodes and {} edges", graph.num_nodes(), graph.num_arcs());
 
     let mut gen = rand::rngs::StdRng::seed_from_u64(1269803542210214824);
@@ -129,7 +129,7 @@ fn thousand_ka_queries_without_constraints() -> Result<(), Box<dyn Error>> {
         // assert_eq!(dijkstra_path, csp_2_path);
     };
 
-    for (s, t) in critical_queries() {
+    for (s, t) in critical_queries()? {
         println!("Critical Query without constraints from {} to {} ", s, t);
         run(s, t);
     }
@@ -155,9 +155,9 @@ fn thousand_ka_queries_csp() -> Result<(), Box<dyn Error>> {
     let graph = OwnedGraph::new(first_out, head, travel_time);
     let bw_graph = OwnedGraph::reverse(graph.borrow());
     let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
-    ch.check();
+    ch.check()?;
     let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
-    core_ch.check();
+    core_ch.check()?;
     let ch_pot = CHPotential::from_ch(ch.borrow());
     println!("Graph with {} nodes and {} edges", graph.num_nodes(), graph.num_arcs());
 
@@ -220,7 +220,7 @@ fn thousand_ka_queries_csp() -> Result<(), Box<dyn Error>> {
         // assert_eq!(csp_path, csp_prop_all_path);
     };
 
-    for (s, t) in critical_queries() {
+    for (s, t) in critical_queries()? {
         println!("Critical CSP Query from {} to {} ", s, t);
         run(s, t);
     }
@@ -246,9 +246,9 @@ fn thousand_ka_queries_csp_2() -> Result<(), Box<dyn Error>> {
     let graph = OwnedGraph::new(first_out, head, travel_time);
     let bw_graph = OwnedGraph::reverse(graph.borrow());
     let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
-    ch.check();
+    ch.check()?;
     let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
-    core_ch.check();
+    core_ch.check()?;
     let ch_pot = CHPotential::from_ch(ch.borrow());
     println!("Graph with {} nodes and {} edges", graph.num_nodes(), graph.num_arcs());
 
@@ -313,7 +313,7 @@ fn thousand_ka_queries_csp_2() -> Result<(), Box<dyn Error>> {
         // assert_eq!(csp_2_path, csp_2_prop_all_path);;
     };
 
-    for (s, t) in critical_queries() {
+    for (s, t) in critical_queries()? {
         println!("Critical CSP2 Query from {} to {} ", s, t);
         run(s, t);
     }
@@ -327,3 +327,24 @@ fn thousand_ka_queries_csp_2() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+#[ignore]
+fn differential_ka_queries() -> Result<(), Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/large/parking_ka_hgv/"));
+    let corpus = std::env::current_dir()?.as_path().join(Path::new("test_data/regression/parking_ka_hgv.csv"));
+    let data = RoutingData::from_dataset(&Dataset::from_routingkit_dir(&path)?)?;
+
+    let mut queries = load_queries(&corpus)?;
+    queries.extend(random_queries(data.num_nodes(), 1000, 1269803542210214824));
+
+    for restriction in RestrictionPreset::ALL {
+        let report = run_differential(&data, &Candidate::all_with(restriction), &queries);
+        if !report.all_agree() {
+            report.update_corpus(&corpus)?;
+            panic!("{}", report);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use bit_vec::BitVec;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rust_truck_router::{
+    algo::{ch::*, PointToPointQuery},
+    io::*,
+    osm_id_mapper::*,
+    types::*,
+};
+use std::{error::Error, path::Path};
+
+#[test]
+fn rank_select_matches_bitvec() {
+    let mut rng = StdRng::seed_from_u64(31);
+
+    for (len, density) in [(0, 0.5), (1, 1.0), (64, 1.0), (1000, 0.01), (5000, 0.5), (4096, 0.0)] {
+        let bv = BitVec::from_fn(len, |_| rng.gen_bool(density));
+        let mapper = RankSelectMapper::new(&bv);
+        assert_eq!(mapper.len(), len);
+        assert_eq!(mapper.num_ones(), bv.iter().filter(|b| *b).count());
+
+        for osm_id in 0..len as OSMNodeId + 2 {
+            assert_eq!(mapper.to_local(osm_id), bv.to_local(osm_id));
+        }
+
+        for local_id in 0..mapper.num_ones() as NodeId {
+            assert_eq!(mapper.to_osm(local_id), bv.to_osm(local_id));
+        }
+        assert_eq!(mapper.select(mapper.num_ones()), None);
+    }
+}
+
+#[test]
+fn load_mappers_from_files() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join("rust_truck_router_test_osm_id_mapper");
+    std::fs::create_dir_all(&dir)?;
+
+    let bv = BitVec::from_fn(200, |i| i % 3 == 0);
+    store_routingkit_bitvector(&bv, dir.join("is_routing_node"))?;
+    let rank_select = RankSelectMapper::load_from_routingkit_bitvector(dir.join("is_routing_node"))?;
+    assert_eq!(rank_select.to_local(99), Some(33));
+    assert_eq!(rank_select.to_osm(33), 99);
+
+    vec![3u64, 10_000_000_000, 10_000_000_007].write_to(&dir.join("osm_node_id"))?;
+    let sorted = SortedIDMapper::load_from(dir.join("osm_node_id"))?;
+    assert_eq!(sorted.to_local(10_000_000_007), Some(2));
+    assert_eq!(sorted.try_to_local(4), Err(UnknownOSMIDError(4)));
+    assert_eq!(sorted.path_to_osm(&[2, 0]), vec![10_000_000_007, 3]);
+
+    vec![3u64, 1].write_to(&dir.join("unsorted"))?;
+    assert!(SortedIDMapper::load_from(dir.join("unsorted")).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn query_with_osm_ids() -> Result<(), Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
+    let num_nodes = ch.rank().len();
+    let mapper = SortedIDMapper::new((0..num_nodes as OSMNodeId).map(|i| 1000 + 2 * i).collect()).unwrap();
+
+    let mut query = ContractionHierarchyQuery::new(ch.borrow());
+    let mut osm_query = ContractionHierarchyQuery::new(ch.borrow());
+    for s in 0..num_nodes as NodeId {
+        query.init_new_s(s);
+        osm_query.init_new_s_osm(&mapper, 1000 + 2 * s as OSMNodeId)?;
+        for t in 0..num_nodes as NodeId {
+            query.init_new_t(t);
+            osm_query.init_new_t_osm(&mapper, mapper.to_osm(t))?;
+            assert_eq!(query.run_query(), PointToPointQuery::run_query(&mut osm_query));
+        }
+    }
+
+    assert_eq!(osm_query.init_new_s_osm(&mapper, 1001), Err(UnknownOSMIDError(1001)));
+
+    Ok(())
+}
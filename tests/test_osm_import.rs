@@ -0,0 +1,241 @@
+use flate2::{write::ZlibEncoder, Compression};
+use rust_truck_router::{
+    io::*,
+    osm_import::{haversine_distance, OsmImportConfig, OsmRoutingGraph},
+    types::*,
+};
+use std::{error::Error, io::Write};
+
+// minimal protocol buffer encoder for writing test extracts
+fn varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn varint_field(out: &mut Vec<u8>, field: u64, v: u64) {
+    varint(out, field << 3);
+    varint(out, v);
+}
+
+fn bytes_field(out: &mut Vec<u8>, field: u64, data: &[u8]) {
+    varint(out, (field << 3) | 2);
+    varint(out, data.len() as u64);
+    out.extend_from_slice(data);
+}
+
+fn packed_field(out: &mut Vec<u8>, field: u64, values: &[u64]) {
+    let mut data = Vec::new();
+    for &v in values {
+        varint(&mut data, v);
+    }
+    bytes_field(out, field, &data);
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn delta_zigzag(values: &[i64]) -> Vec<u64> {
+    let mut last = 0;
+    values
+        .iter()
+        .map(|&v| {
+            let d = v - last;
+            last = v;
+            zigzag(d)
+        })
+        .collect()
+}
+
+struct StringTable(Vec<String>);
+
+impl StringTable {
+    fn index(&mut self, s: &str) -> u64 {
+        if let Some(i) = self.0.iter().position(|e| e == s) {
+            return i as u64;
+        }
+        self.0.push(s.to_owned());
+        (self.0.len() - 1) as u64
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for s in &self.0 {
+            bytes_field(&mut out, 1, s.as_bytes());
+        }
+        out
+    }
+}
+
+fn write_blob(out: &mut Vec<u8>, blob_type: &str, data: &[u8], compress: bool) {
+    let mut blob = Vec::new();
+    if compress {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        varint_field(&mut blob, 2, data.len() as u64);
+        bytes_field(&mut blob, 3, &encoder.finish().unwrap());
+    } else {
+        bytes_field(&mut blob, 1, data);
+    }
+    frame_blob(out, blob_type, &blob);
+}
+
+/// Prepends the blob header with its size
+fn frame_blob(out: &mut Vec<u8>, blob_type: &str, blob: &[u8]) {
+    let mut header = Vec::new();
+    bytes_field(&mut header, 1, blob_type.as_bytes());
+    varint_field(&mut header, 3, blob.len() as u64);
+
+    out.extend_from_slice(&(header.len() as u32).to_be_bytes());
+    out.extend(header);
+    out.extend_from_slice(blob);
+}
+
+type TestNode<'a> = (i64, f64, f64, Vec<(&'a str, &'a str)>);
+type TestWay<'a> = (i64, Vec<i64>, Vec<(&'a str, &'a str)>);
+
+fn dense_nodes_block(nodes: &[TestNode]) -> Vec<u8> {
+    let mut strings = StringTable(vec![String::new()]);
+    let mut keys_vals = Vec::new();
+    for (_, _, _, tags) in nodes {
+        for (k, v) in tags {
+            keys_vals.push(strings.index(k));
+            keys_vals.push(strings.index(v));
+        }
+        keys_vals.push(0);
+    }
+
+    let mut dense = Vec::new();
+    packed_field(&mut dense, 1, &delta_zigzag(&nodes.iter().map(|n| n.0).collect::<Vec<_>>()));
+    packed_field(&mut dense, 8, &delta_zigzag(&nodes.iter().map(|n| (n.1 * 1e7).round() as i64).collect::<Vec<_>>()));
+    packed_field(&mut dense, 9, &delta_zigzag(&nodes.iter().map(|n| (n.2 * 1e7).round() as i64).collect::<Vec<_>>()));
+    packed_field(&mut dense, 10, &keys_vals);
+
+    let mut group = Vec::new();
+    bytes_field(&mut group, 2, &dense);
+
+    let mut block = Vec::new();
+    bytes_field(&mut block, 1, &strings.encode());
+    bytes_field(&mut block, 2, &group);
+    block
+}
+
+fn ways_block(ways: &[TestWay]) -> Vec<u8> {
+    let mut strings = StringTable(vec![String::new()]);
+    let mut group = Vec::new();
+
+    for (id, refs, tags) in ways {
+        let mut way = Vec::new();
+        varint_field(&mut way, 1, *id as u64);
+        packed_field(&mut way, 2, &tags.iter().map(|t| strings.index(t.0)).collect::<Vec<_>>());
+        packed_field(&mut way, 3, &tags.iter().map(|t| strings.index(t.1)).collect::<Vec<_>>());
+        packed_field(&mut way, 8, &delta_zigzag(refs));
+        bytes_field(&mut group, 3, &way);
+    }
+
+    let mut block = Vec::new();
+    bytes_field(&mut block, 1, &strings.encode());
+    bytes_field(&mut block, 2, &group);
+    block
+}
+
+fn test_extract() -> Vec<u8> {
+    let nodes = vec![
+        (1, 49.0, 8.40, vec![]),
+        (2, 49.0, 8.41, vec![]),
+        (3, 49.0, 8.42, vec![]),
+        (4, 49.0, 8.43, vec![]),
+        (5, 49.01, 8.41, vec![]),
+        // truck parking next to the road close to node 4
+        (6, 49.0005, 8.4301, vec![("amenity", "parking"), ("hgv", "yes")]),
+        // rest area polygon close to node 1
+        (7, 49.0005, 8.3990, vec![]),
+        (8, 49.0010, 8.3995, vec![]),
+        (9, 49.0005, 8.4000, vec![]),
+        (10, 48.99, 8.42, vec![]),
+        // truck parking on the motorway itself
+        (11, 49.0, 8.425, vec![("amenity", "parking"), ("hgv", "designated")]),
+    ];
+
+    let ways = vec![
+        (100, vec![1, 2, 3], vec![("highway", "primary")]),
+        (101, vec![3, 11, 4], vec![("highway", "motorway")]),
+        (102, vec![2, 5], vec![("highway", "footway")]),
+        (103, vec![7, 8, 9, 7], vec![("highway", "rest_area"), ("area", "yes")]),
+        (104, vec![3, 10], vec![("highway", "residential"), ("hgv", "no")]),
+    ];
+
+    let mut header = Vec::new();
+    bytes_field(&mut header, 4, b"OsmSchema-V0.6");
+    bytes_field(&mut header, 4, b"DenseNodes");
+
+    let mut file = Vec::new();
+    write_blob(&mut file, "OSMHeader", &header, false);
+    write_blob(&mut file, "OSMData", &dense_nodes_block(&nodes), true);
+    write_blob(&mut file, "OSMData", &ways_block(&ways), false);
+    file
+}
+
+#[test]
+fn import_small_extract() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join("rust_truck_router_test_osm_import");
+    std::fs::create_dir_all(&dir)?;
+    let pbf_path = dir.join("test.osm.pbf");
+    std::fs::write(&pbf_path, test_extract())?;
+
+    let extract = OsmRoutingGraph::import_pbf(&pbf_path, &OsmImportConfig::default())?;
+    assert_eq!(extract.osm_node_ids, vec![1, 3, 4, 11]);
+    assert_eq!(extract.graph.first_out(), &[0, 1, 3, 3, 4]);
+    assert_eq!(extract.graph.head(), &[1, 0, 3, 2]);
+
+    // primary road in both directions, motorway speed is capped by the truck speed
+    let primary_length = haversine_distance((49.0, 8.40), (49.0, 8.41)) + haversine_distance((49.0, 8.41), (49.0, 8.42));
+    assert_eq!(extract.geo_distance[0], primary_length.round() as Weight);
+    assert_eq!(extract.graph.weights()[0], (primary_length * 3600.0 / 65.0).round() as Weight);
+    assert_eq!(extract.graph.weights()[0], extract.graph.weights()[1]);
+    let motorway_length = haversine_distance((49.0, 8.42), (49.0, 8.425));
+    assert_eq!(extract.graph.weights()[2], (motorway_length * 3600.0 / 80.0).round() as Weight);
+
+    let flagged: Vec<usize> = extract.is_parking_node.iter().enumerate().filter(|(_, b)| *b).map(|(i, _)| i).collect();
+    assert_eq!(flagged, vec![0, 2, 3]);
+
+    let out_dir = dir.join("graph");
+    extract.write_to_routingkit_dir(&out_dir)?;
+    let graph = OwnedGraph::load_from_routingkit_dir(&out_dir)?;
+    assert_eq!(graph.first_out(), extract.graph.first_out());
+    assert_eq!(graph.head(), extract.graph.head());
+    assert_eq!(graph.weights(), extract.graph.weights());
+    assert_eq!(Vec::<u64>::load_from(out_dir.join("osm_node_id"))?, extract.osm_node_ids);
+
+    let is_parking_node = load_routingkit_bitvector(out_dir.join("routing_parking_flags"))?;
+    for node in 0..graph.num_nodes() {
+        assert_eq!(is_parking_node.get(node), extract.is_parking_node.get(node));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn reject_blob_larger_than_its_raw_size() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join("rust_truck_router_test_osm_import_zlib_bomb");
+    std::fs::create_dir_all(&dir)?;
+    let pbf_path = dir.join("bomb.osm.pbf");
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&vec![0; 1 << 20])?;
+    let mut blob = Vec::new();
+    varint_field(&mut blob, 2, 16);
+    bytes_field(&mut blob, 3, &encoder.finish()?);
+    let mut file = Vec::new();
+    frame_blob(&mut file, "OSMHeader", &blob);
+    std::fs::write(&pbf_path, file)?;
+
+    let Err(error) = OsmRoutingGraph::import_pbf(&pbf_path, &OsmImportConfig::default()) else {
+        panic!("the blob is accepted");
+    };
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rust_truck_router::{
+    pareto_front::{ParetoFront, ParetoWeight},
+    types::*,
+};
+
+// inserts random labels and compares every step with a plain list of non dominated labels
+fn compare_with_list<W: ParetoWeight + PartialEq + std::fmt::Debug, G: FnMut(&mut StdRng) -> W>(mut random_weight: G) {
+    let mut rng = StdRng::seed_from_u64(1548);
+    for _ in 0..50 {
+        let mut front = ParetoFront::new();
+        let mut list: Vec<(W, u32)> = Vec::new();
+
+        for index in 0..200 {
+            let weight = random_weight(&mut rng);
+            let dominated = list.iter().any(|(w, _)| w.dominates(&weight));
+            assert_eq!(front.dominates(&weight), dominated, "{:?}", weight);
+            if dominated {
+                continue;
+            }
+
+            let mut removed = Vec::new();
+            front.insert(weight, index, |i| removed.push(i));
+            let mut expected_removed: Vec<u32> = list.iter().filter(|(w, _)| weight.dominates(w)).map(|&(_, i)| i).collect();
+            list.retain(|(w, _)| !weight.dominates(w));
+            list.push((weight, index));
+
+            removed.sort_unstable();
+            expected_removed.sort_unstable();
+            assert_eq!(removed, expected_removed);
+            assert_eq!(front.len(), list.len());
+            assert_eq!(front.best_distance(), list.iter().map(|(w, _)| w.distance()).min().unwrap());
+        }
+
+        let mut indices: Vec<u32> = front.iter().map(|&(_, i)| i).collect();
+        let mut expected: Vec<u32> = list.iter().map(|&(_, i)| i).collect();
+        indices.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(indices, expected);
+    }
+}
+
+#[test]
+fn two_criteria() {
+    compare_with_list::<Weight2, _>(|rng| [rng.gen_range(0..100), rng.gen_range(0..100)]);
+}
+
+#[test]
+fn three_criteria() {
+    compare_with_list::<Weight3, _>(|rng| [rng.gen_range(0..100), rng.gen_range(0..30), rng.gen_range(0..30)]);
+}
+
+#[test]
+fn front_is_sorted_by_driving_time() {
+    let mut front: ParetoFront<Weight2> = ParetoFront::new();
+    for (index, weight) in [[5, 5], [9, 1], [3, 8], [7, 2]].into_iter().enumerate() {
+        front.insert(weight, index as u32, |_| ());
+    }
+    assert_eq!(front.iter().map(|&(w, _)| w).collect::<Vec<_>>(), [[9, 1], [7, 2], [5, 5], [3, 8]]);
+
+    // equal labels dominate each other
+    assert!(front.dominates(&[7, 2]));
+
+    // removes a contiguous range
+    let mut removed = Vec::new();
+    front.insert([4, 2], 4, |i| removed.push(i));
+    assert_eq!(removed, [3, 0]);
+    assert_eq!(front.iter().map(|&(w, _)| w).collect::<Vec<_>>(), [[9, 1], [4, 2], [3, 8]]);
+    assert_eq!(front.best_distance(), 3);
+
+    front.clear();
+    assert!(front.is_empty());
+    assert_eq!(front.best_distance(), INFINITY);
+    assert!(!front.dominates(&[INFINITY, INFINITY]));
+}
+
+#[test]
+fn three_criteria_front() {
+    let mut front: ParetoFront<Weight3> = ParetoFront::new();
+    for (index, weight) in [[9, 1, 8], [5, 4, 4], [7, 2, 3], [6, 3, 6]].into_iter().enumerate() {
+        front.insert(weight, index as u32, |_| ());
+    }
+    // sorted by both driving time components
+    assert_eq!(front.iter().map(|&(w, _)| w).collect::<Vec<_>>(), [[9, 1, 8], [7, 2, 3], [6, 3, 6], [5, 4, 4]]);
+    assert_eq!(front.best_distance(), 5);
+
+    // a smaller first driving time only dominates together with a smaller second one
+    assert!(front.dominates(&[8, 3, 5]));
+    assert!(!front.dominates(&[8, 3, 2]));
+    assert!(!front.dominates(&[8, 1, 9]));
+    assert!(front.dominates(&[9, 1, 8]));
+
+    let mut removed = Vec::new();
+    front.insert([6, 2, 5], 4, |i| removed.push(i));
+    assert_eq!(removed, [3]);
+    assert_eq!(front.iter().map(|&(w, _)| w).collect::<Vec<_>>(), [[9, 1, 8], [7, 2, 3], [6, 2, 5], [5, 4, 4]]);
+
+    // the dominated labels are not contiguous
+    removed.clear();
+    front.insert([4, 1, 4], 5, |i| removed.push(i));
+    assert_eq!(removed, [0, 4, 1]);
+    assert_eq!(front.iter().map(|&(w, _)| w).collect::<Vec<_>>(), [[4, 1, 4], [7, 2, 3]]);
+    assert_eq!(front.best_distance(), 4);
+}
@@ -0,0 +1,205 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rust_truck_router::{
+    algo::{
+        astar::NoPotential,
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+        csp_2_bidir::CSP2BidirQuery,
+        csp_bidir::CSPBidirQuery,
+        dijkstra::{Dijkstra, DijkstraData},
+        PointToPointQuery,
+    },
+    bucket_queue::BucketQueue,
+    index_heap::IndexdMinHeap,
+    priority_queue::IndexedPriorityQueue,
+    radix_heap::RadixHeap,
+    types::*,
+};
+
+mod common;
+
+use common::corridor;
+
+const NUM_INDICES: usize = 50;
+
+// applies the same random operations to `queue` and the 4-ary heap
+fn compare_with_heap<Q: IndexedPriorityQueue<State<Weight>>>(mut queue: Q, monotone: bool, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut heap: IndexdMinHeap<State<Weight>> = IndexdMinHeap::new(NUM_INDICES);
+    let mut last_popped = 0;
+
+    for _ in 0..5000 {
+        let node = rng.gen_range(0..NUM_INDICES as NodeId);
+        let min_key = if monotone { last_popped } else { 0 };
+        match rng.gen_range(0..3) {
+            0 => {
+                if let Some(old) = heap.get_key_by_index(node as usize).copied() {
+                    if old.distance > min_key {
+                        let decreased = State {
+                            node,
+                            distance: rng.gen_range(min_key..old.distance),
+                        };
+                        heap.decrease_key(decreased);
+                        queue.decrease_key(decreased);
+                    }
+                } else {
+                    let element = State {
+                        node,
+                        distance: min_key + rng.gen_range(0..100_000),
+                    };
+                    heap.push(element);
+                    queue.push(element);
+                }
+            }
+            1 => {
+                let popped = heap.pop();
+                assert_eq!(queue.pop(), popped);
+                if let Some(popped) = popped {
+                    last_popped = popped.distance;
+                }
+            }
+            _ => {
+                assert_eq!(queue.peek(), heap.peek());
+                assert_eq!(queue.get_key_by_index(node as usize), heap.get_key_by_index(node as usize));
+            }
+        }
+        assert_eq!(queue.len(), heap.len());
+        assert_eq!(queue.contains_index(node as usize), heap.contains_index(node as usize));
+    }
+
+    queue.clear();
+    assert!(queue.is_empty());
+    assert!((0..NUM_INDICES).all(|i| !queue.contains_index(i)));
+}
+
+#[test]
+fn radix_heap() {
+    for seed in 0..10 {
+        compare_with_heap(RadixHeap::with_max_index(NUM_INDICES), true, seed);
+        compare_with_heap(RadixHeap::with_max_index(NUM_INDICES), false, seed);
+    }
+}
+
+#[test]
+fn bucket_queue() {
+    for seed in 0..10 {
+        compare_with_heap(BucketQueue::with_max_index(NUM_INDICES), true, seed);
+        compare_with_heap(BucketQueue::with_max_index(NUM_INDICES), false, seed);
+        // most elements in the overflow list
+        compare_with_heap(BucketQueue::with_buckets(NUM_INDICES, 100, 8), true, seed);
+        compare_with_heap(BucketQueue::with_buckets(NUM_INDICES, 7, 1), false, seed);
+    }
+}
+
+#[test]
+fn bucket_queue_window_moves_with_the_pops() {
+    // the window covers 40 keys, so after a few pops all keys are beyond the initial window
+    let mut queue = BucketQueue::with_buckets(NUM_INDICES, 10, 4);
+    let mut heap: IndexdMinHeap<State<Weight>> = IndexdMinHeap::new(NUM_INDICES);
+
+    for i in 0..2000 {
+        let node = (i % NUM_INDICES as Weight) as NodeId;
+        if !heap.contains_index(node as usize) {
+            // inside the window, just beyond it and far beyond it
+            let element = State {
+                node,
+                distance: 10 * i + [20, 50, 250][i as usize % 3],
+            };
+            heap.push(element);
+            queue.push(element);
+        }
+        if i % 7 == 0 && heap.contains_index(node as usize) {
+            // moves an element from the overflow list into the window
+            let decreased = State { node, distance: 10 * i };
+            if heap.get_key_by_index(node as usize).unwrap().distance > decreased.distance {
+                heap.decrease_key(decreased);
+                queue.decrease_key(decreased);
+            }
+        }
+        if i >= 10 {
+            assert_eq!(queue.pop(), heap.pop());
+        }
+        assert_eq!(queue.peek(), heap.peek());
+        assert_eq!(queue.len(), heap.len());
+    }
+
+    while let Some(popped) = heap.pop() {
+        assert_eq!(queue.pop(), Some(popped));
+    }
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn searches_find_the_same_distances() {
+    let network = corridor();
+    let graph = network.graph.borrow();
+    let n = graph.num_nodes();
+    let t = n as NodeId - 1;
+
+    let dijkstra = Dijkstra::new(graph);
+    let mut heap_state = DijkstraData::new(n);
+    let mut radix_state: DijkstraData<NoPotential, RadixHeap<State<Weight>>> = DijkstraData::with_queue(n, NoPotential {});
+    let mut bucket_state: DijkstraData<NoPotential, BucketQueue<State<Weight>>> = DijkstraData::with_queue(n, NoPotential {});
+    for s in [0, 5, 17] {
+        heap_state.init_new_s(s);
+        radix_state.init_new_s(s);
+        bucket_state.init_new_s(s);
+        let dist = dijkstra.dist_query(&mut heap_state, t);
+        assert_eq!(dijkstra.dist_query(&mut radix_state, t), dist);
+        assert_eq!(dijkstra.dist_query(&mut bucket_state, t), dist);
+    }
+
+    let csp = OneRestrictionDijkstra::new(graph, &network.is_parking_node);
+    let mut heap_state = OneRestrictionDijkstraData::new(n);
+    let mut radix_state: OneRestrictionDijkstraData<NoPotential, RadixHeap<State<Weight>>> = OneRestrictionDijkstraData::with_queue(n, NoPotential {});
+    heap_state.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    radix_state.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    heap_state.init_new_s(0);
+    radix_state.init_new_s(0);
+    let dist = csp.dist_query(&mut heap_state, t);
+    assert!(dist.is_some());
+    assert_eq!(csp.dist_query(&mut radix_state, t), dist);
+    assert_eq!(radix_state.stats.num_settled, heap_state.stats.num_settled);
+
+    let csp_2 = TwoRestrictionDijkstra::new(graph, &network.is_parking_node);
+    let mut bucket_state: TwoRestrictionDijkstraData<NoPotential, BucketQueue<State<Weight>>> = TwoRestrictionDijkstraData::with_queue(n, NoPotential {});
+    let mut heap_state = TwoRestrictionDijkstraData::new(n);
+    heap_state.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    heap_state.init_new_s(0);
+    bucket_state.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    bucket_state.init_new_s(0);
+    let dist = csp_2.dist_query(&mut heap_state, t);
+    assert_eq!(csp_2.dist_query(&mut bucket_state, t), dist);
+}
+
+fn check_queries<A: PointToPointQuery, B: PointToPointQuery>(a: &mut A, b: &mut B, n: usize) {
+    for s in (0..n as NodeId).step_by(7) {
+        for t in (0..n as NodeId).step_by(5) {
+            a.init_new_s(s);
+            a.init_new_t(t);
+            b.init_new_s(s);
+            b.init_new_t(t);
+            assert_eq!(a.run_query(), b.run_query(), "from {} to {}", s, t);
+        }
+    }
+}
+
+#[test]
+fn query_types_are_generic_over_the_queue() {
+    let network = corridor();
+    let graph = network.graph.borrow();
+    let bw_graph = OwnedGraph::reverse(graph);
+    let n = graph.num_nodes();
+
+    let mut heap_query = CSPBidirQuery::new(graph, bw_graph.borrow(), &network.is_parking_node);
+    let mut radix_query = CSPBidirQuery::<RadixHeap<State<Weight>>>::with_queue(graph, bw_graph.borrow(), &network.is_parking_node);
+    heap_query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    radix_query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    check_queries(&mut heap_query, &mut radix_query, n);
+
+    let mut heap_query = CSP2BidirQuery::new(graph, bw_graph.borrow(), &network.is_parking_node);
+    let mut bucket_query = CSP2BidirQuery::<BucketQueue<State<Weight>>>::with_queue(graph, bw_graph.borrow(), &network.is_parking_node);
+    heap_query.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    bucket_query.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    check_queries(&mut heap_query, &mut bucket_query, n);
+}
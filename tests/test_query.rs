@@ -0,0 +1,80 @@
+use bit_vec::BitVec;
+use rust_truck_router::{
+    algo::csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+    io::dataset::*,
+    query::*,
+    types::*,
+};
+use std::{error::Error, path::Path};
+
+fn load_data() -> Result<RoutingData, Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let mut dataset = Dataset::from_routingkit_dir(&path)?;
+    dataset.insert_bitvector(PARKING_FLAGS, &BitVec::from_fn(dataset.num_nodes(), |i| i == 2 || i == 3));
+    Ok(RoutingData::from_dataset(&dataset)?)
+}
+
+#[test]
+fn parse_names() {
+    for algorithm in Algorithm::ALL {
+        assert_eq!(algorithm.name().parse(), Ok(algorithm));
+    }
+    for restriction in RestrictionPreset::ALL {
+        assert_eq!(restriction.to_string().parse(), Ok(restriction));
+    }
+    assert_eq!("core-ch".parse::<Algorithm>(), Err(UnknownNameError("core-ch".to_owned())));
+}
+
+#[test]
+fn all_algorithms_agree() -> Result<(), Box<dyn Error>> {
+    let data = load_data()?;
+
+    for restriction in RestrictionPreset::ALL {
+        let mut queries: Vec<_> = Algorithm::ALL.iter().map(|&algorithm| new_query(&data, algorithm, restriction)).collect();
+
+        for s in 0..data.num_nodes() as NodeId {
+            for t in 0..data.num_nodes() as NodeId {
+                let distances: Vec<_> = queries
+                    .iter_mut()
+                    .map(|query| {
+                        query.init_new_s(s);
+                        query.init_new_t(t);
+                        query.run_query()
+                    })
+                    .collect();
+
+                assert!(
+                    distances.iter().all(|d| *d == distances[0]),
+                    "{} -> {} with {}: {:?}",
+                    s,
+                    t,
+                    restriction,
+                    distances
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn eu_long_uses_both_restrictions() -> Result<(), Box<dyn Error>> {
+    let data = load_data()?;
+    let mut query = new_query(&data, Algorithm::CoreChAstar, RestrictionPreset::EuLong);
+
+    let mut state = TwoRestrictionDijkstraData::new(data.num_nodes());
+    let dijkstra = TwoRestrictionDijkstra::new(data.graph.borrow(), &data.is_parking_node);
+
+    for s in 0..data.num_nodes() as NodeId {
+        for t in 0..data.num_nodes() as NodeId {
+            state.init_new_s(s);
+            state.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+            query.init_new_s(s);
+            query.init_new_t(t);
+            assert_eq!(query.run_query(), dijkstra.dist_query(&mut state, t));
+        }
+    }
+
+    Ok(())
+}
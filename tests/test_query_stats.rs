@@ -0,0 +1,144 @@
+use bit_vec::BitVec;
+use rust_truck_router::{
+    algo::{
+        core_ch::CoreContractionHierarchy,
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_bidir::CSPBidirQuery,
+        csp_core_ch::CSPCoreCHQuery,
+        stats::*,
+    },
+    experiments::measurement::MeasurementResult,
+    io::dataset::*,
+    query::*,
+    types::*,
+};
+use std::{error::Error, path::Path, rc::Rc, time::Duration};
+
+mod common;
+
+use common::corridor;
+
+#[test]
+fn unidirectional_counts_its_search() {
+    let network = corridor();
+    let dijkstra = OneRestrictionDijkstra::new(network.graph.borrow(), &network.is_parking_node);
+    let mut state = OneRestrictionDijkstraData::new(network.graph.num_nodes());
+    state.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    state.init_new_s(0);
+    let dist = dijkstra.dist_query(&mut state, 19);
+
+    let stats = state.query_stats();
+    assert_eq!(stats.distance, dist);
+    assert!(stats.forward.num_settled > 0);
+    assert!(stats.forward.num_labels_reset > 0);
+    assert!(stats.forward.max_label_set_size > 1);
+    // every pushed label needs the potential of its node
+    assert!(stats.forward.num_potential_evaluations >= stats.forward.num_labels_propagated);
+    assert_eq!(stats.backward, SearchStats::default());
+    assert_eq!(stats.core, SearchStats::default());
+    assert_eq!(stats.total(), stats.forward);
+
+    // statistics start over with every query
+    state.init_new_s(19);
+    dijkstra.dist_query(&mut state, 19);
+    assert_eq!(state.stats.num_settled, 1);
+}
+
+#[test]
+fn bidirectional_reports_both_searches() {
+    let network = corridor();
+    let bw_graph = OwnedGraph::reverse(network.graph.borrow());
+    let mut query = CSPBidirQuery::new(network.graph.borrow(), bw_graph.borrow(), &network.is_parking_node);
+    query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    query.init_new_s(0);
+    query.init_new_t(19);
+    let dist = query.run_query();
+
+    let stats = query.stats();
+    assert_eq!(stats.distance, dist);
+    assert!(stats.forward.num_settled > 0 && stats.backward.num_settled > 0);
+    assert_eq!(stats.total().num_settled, stats.forward.num_settled + stats.backward.num_settled);
+    // the searches alternate, so only the query as a whole is timed
+    assert_eq!(stats.forward.time + stats.backward.time, Duration::ZERO);
+}
+
+#[test]
+fn core_phase() {
+    let network = corridor();
+    let graph = &network.graph;
+    // all nodes in the core, so all work happens there
+    let identity: Vec<NodeId> = (0..graph.num_nodes() as NodeId).collect();
+    let core_ch = CoreContractionHierarchy::new(identity.clone(), identity.clone(), identity, graph.clone(), OwnedGraph::reverse(graph.borrow()));
+
+    let mut query = CSPCoreCHQuery::new(core_ch.borrow());
+    query.set_custom_reset_nodes(Rc::new(network.is_parking_node.clone()));
+    query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    query.init_new_s(0);
+    query.init_new_t(19);
+    query.run_query();
+
+    let stats = query.stats();
+    assert!(stats.core.num_settled > 0);
+    assert_eq!(stats.forward.num_settled, 0);
+    assert_eq!(stats.backward.num_settled, 0);
+    assert_eq!(stats.total().num_labels_propagated, stats.core.num_labels_propagated);
+}
+
+#[test]
+fn every_algorithm_fills_stats() -> Result<(), Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let mut dataset = Dataset::from_routingkit_dir(&path)?;
+    dataset.insert_bitvector(PARKING_FLAGS, &BitVec::from_fn(dataset.num_nodes(), |i| i == 2 || i == 3));
+    let data = RoutingData::from_dataset(&dataset)?;
+
+    for restriction in RestrictionPreset::ALL {
+        for algorithm in Algorithm::ALL {
+            let mut query = new_query(&data, algorithm, restriction);
+            query.init_new_s(0);
+            query.init_new_t(data.num_nodes() as NodeId - 1);
+            let dist = query.run_query();
+
+            let stats = query.stats();
+            assert_eq!(stats.distance, dist, "{} {}", algorithm, restriction);
+            assert!(stats.total().num_settled > 0, "{} {}", algorithm, restriction);
+            assert!(stats.num_nodes_searched > 0, "{} {}", algorithm, restriction);
+            if matches!(algorithm, Algorithm::Astar | Algorithm::BidirAstar | Algorithm::CoreChAstar) {
+                assert!(stats.total().num_potential_evaluations > 0, "{} {}", algorithm, restriction);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn export() -> Result<(), Box<dyn Error>> {
+    let stats = QueryStats {
+        distance: Some(42),
+        forward: SearchStats {
+            num_settled: 3,
+            max_label_set_size: 2,
+            ..SearchStats::default()
+        },
+        path: Some(PathStats {
+            num_nodes: 4,
+            num_flagged_nodes: 1,
+            num_short_pauses: 1,
+            num_long_pauses: 0,
+        }),
+        ..QueryStats::default()
+    };
+
+    let json: serde_json::Value = serde_json::from_str(&stats.to_json())?;
+    assert_eq!(json["distance"], 42);
+    assert_eq!(json["forward"]["num_settled"], 3);
+    assert_eq!(json["path"]["num_short_pauses"], 1);
+    assert_eq!(serde_json::from_str::<QueryStats>(&stats.to_json())?, stats);
+
+    let header = QueryStats::get_header();
+    assert!(header.contains("fw_num_settled") && header.contains("core_time_ms"));
+    assert_eq!(header.split(',').count(), stats.as_csv().split(',').count());
+    assert!(QueryStats::default().as_csv().starts_with("NaN,"));
+
+    Ok(())
+}
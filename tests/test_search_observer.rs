@@ -0,0 +1,155 @@
+use bit_vec::BitVec;
+use rust_truck_router::{
+    algo::{
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+        dijkstra::{Dijkstra, DijkstraData},
+        observer::*,
+    },
+    types::*,
+};
+
+// 0 -> 1 -> 2 -> 3 with shortcuts 1 -> 3 and 2 -> 4 -> 3, parking at 1 and 2
+fn example() -> (OwnedGraph, BitVec) {
+    let arcs: [(NodeId, NodeId, Weight); 6] = [(0, 1, 5), (1, 2, 5), (1, 3, 11), (2, 3, 5), (2, 4, 1), (4, 3, 10)];
+
+    let mut first_out = vec![0 as EdgeId; 6];
+    for &(tail, _, _) in &arcs {
+        first_out[tail as usize + 1] += 1;
+    }
+    for i in 1..first_out.len() {
+        first_out[i] += first_out[i - 1];
+    }
+
+    let graph = OwnedGraph::new(first_out, arcs.iter().map(|a| a.1).collect(), arcs.iter().map(|a| a.2).collect());
+    (graph, BitVec::from_fn(5, |i| i == 1 || i == 2))
+}
+
+fn count(log: &SearchLog, f: fn(&SearchEvent) -> bool) -> u32 {
+    log.events().iter().filter(|e| f(e)).count() as u32
+}
+
+#[test]
+fn one_restriction() {
+    let (graph, is_parking_node) = example();
+    let log = SearchLog::new();
+    let csp = OneRestrictionDijkstra::with_observer(graph.borrow(), &is_parking_node, &log);
+    let mut state = OneRestrictionDijkstraData::new(graph.num_nodes());
+    state.set_restriction(12, 2);
+    state.init_new_s(0);
+
+    assert_eq!(csp.dist_query(&mut state, 3), Some(17));
+
+    let events = log.events();
+    // the labels after the break at 2 dominate the later ones from 1
+    assert!(events.contains(&SearchEvent::LabelDominated {
+        node: 2,
+        distance: vec![12, 5]
+    }));
+    assert!(events.contains(&SearchEvent::LabelDominated {
+        node: 2,
+        distance: vec![14, 0]
+    }));
+    // an existing label at the target replaced by a better one
+    assert!(events.contains(&SearchEvent::LabelDominated {
+        node: 3,
+        distance: vec![18, 11]
+    }));
+    assert!(events.contains(&SearchEvent::PrunedByConstraint {
+        node: 3,
+        distance: vec![16, 16]
+    }));
+    assert!(events.contains(&SearchEvent::PrunedByTarget {
+        node: 3,
+        distance: vec![23, 11]
+    }));
+    assert_eq!(
+        events.last(),
+        Some(&SearchEvent::LabelSettled {
+            node: 3,
+            distance: vec![17, 5]
+        })
+    );
+    drop(events);
+
+    assert_eq!(count(&log, |e| matches!(e, SearchEvent::LabelSettled { .. })), state.stats.num_settled);
+    assert_eq!(count(&log, |e| matches!(e, SearchEvent::LabelPushed { .. })), state.stats.num_labels_propagated);
+    assert_eq!(count(&log, |e| matches!(e, SearchEvent::PrunedByBwLowerBound { .. })), 0);
+
+    // the observer does not change the search
+    let mut plain_state = OneRestrictionDijkstraData::new(graph.num_nodes());
+    plain_state.set_restriction(12, 2);
+    plain_state.init_new_s(0);
+    assert_eq!(
+        OneRestrictionDijkstra::new(graph.borrow(), &is_parking_node).dist_query(&mut plain_state, 3),
+        Some(17)
+    );
+    assert_eq!(plain_state.stats.num_settled, state.stats.num_settled);
+}
+
+#[test]
+fn bw_lower_bound_pruning() {
+    let (graph, is_parking_node) = example();
+    let bw_graph = OwnedGraph::reverse(graph.borrow());
+    let log = SearchLog::new();
+    let fw_search = OneRestrictionDijkstra::with_observer(graph.borrow(), &is_parking_node, &log);
+    let mut fw_state = OneRestrictionDijkstraData::new(graph.num_nodes());
+    fw_state.set_restriction(12, 2);
+    fw_state.init_new_s(0);
+    let mut bw_state = OneRestrictionDijkstraData::new(bw_graph.num_nodes());
+    bw_state.set_restriction(12, 2);
+    bw_state.init_new_s(3);
+
+    // with a tentative distance of 1 both labels at the parking node 1 are pruned
+    fw_search.settle_next_label_prune_bw_lower_bound(&mut fw_state, &mut bw_state, 1, 3);
+
+    assert_eq!(
+        log.take_events(),
+        vec![
+            SearchEvent::LabelSettled { node: 0, distance: vec![0, 0] },
+            SearchEvent::PrunedByBwLowerBound { node: 1, distance: vec![5, 5] },
+            SearchEvent::PrunedByBwLowerBound { node: 1, distance: vec![7, 0] },
+        ]
+    );
+    assert!(log.events().is_empty());
+}
+
+#[test]
+fn two_restrictions() {
+    let (graph, is_parking_node) = example();
+    let log = SearchLog::new();
+    let csp = TwoRestrictionDijkstra::with_observer(graph.borrow(), &is_parking_node, &log);
+    let mut state = TwoRestrictionDijkstraData::new(graph.num_nodes());
+    state.set_restriction(100, 5, 12, 2);
+    state.init_new_s(0);
+
+    assert_eq!(csp.dist_query(&mut state, 3), Some(17));
+    assert!(log.events().iter().all(|e| e.distance().len() == 3));
+    assert!(log.events().iter().any(|e| matches!(e, SearchEvent::PrunedByConstraint { node: 3, .. })));
+    assert_eq!(count(&log, |e| matches!(e, SearchEvent::LabelSettled { .. })), state.stats.num_settled);
+    assert_eq!(count(&log, |e| matches!(e, SearchEvent::LabelPushed { .. })), state.stats.num_labels_propagated);
+}
+
+#[test]
+fn dijkstra() {
+    let (graph, _) = example();
+    let log = SearchLog::new();
+    let dijkstra = Dijkstra::with_observer(graph.borrow(), &log);
+    let mut state = DijkstraData::new(graph.num_nodes());
+    state.init_new_s(0);
+
+    assert_eq!(dijkstra.dist_query(&mut state, 3), Some(15));
+    assert!(log.events().contains(&SearchEvent::LabelDominated { node: 3, distance: vec![21] }));
+    for event in log.events().iter() {
+        if let SearchEvent::LabelSettled { node, distance } = event {
+            assert_eq!(distance[..], [state.tentative_distance_at(*node)]);
+        }
+    }
+    assert_eq!(count(&log, |e| matches!(e, SearchEvent::LabelPushed { .. })), state.stats.num_labels_propagated);
+
+    log.take_events();
+    state.init_new_s(0);
+    while dijkstra.settle_next_node_not_exceeding(&mut state, 12).is_some() {}
+    assert!(log.events().contains(&SearchEvent::PrunedByConstraint { node: 3, distance: vec![16] }));
+    assert!(!log.events().iter().any(|e| matches!(e, SearchEvent::LabelSettled { node: 3, .. })));
+}
@@ -0,0 +1,132 @@
+use rust_truck_router::{
+    algo::{
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_bidir::CSPBidirQuery,
+        search_space::*,
+    },
+    synthetic::*,
+    types::*,
+};
+use std::{error::Error, fs, io};
+
+mod common;
+
+fn corridor() -> (SyntheticGraph, Vec<(f64, f64)>) {
+    let network = common::corridor();
+    let coordinates = network
+        .latitude
+        .iter()
+        .zip(&network.longitude)
+        .map(|(&lat, &lon)| (lat as f64, lon as f64))
+        .collect();
+    (network, coordinates)
+}
+
+#[test]
+fn unidirectional() -> Result<(), Box<dyn Error>> {
+    let (network, coordinates) = corridor();
+    let csp = OneRestrictionDijkstra::new(network.graph.borrow(), &network.is_parking_node);
+    let mut state = OneRestrictionDijkstraData::new(network.graph.num_nodes());
+    state.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    state.init_new_s(0);
+    assert!(csp.dist_query(&mut state, 19).is_some());
+
+    let route = state.current_best_node_path_to(19).unwrap();
+    let search_space = SearchSpace::forward(&state).with_route(route.clone());
+    let labels = state.get_per_node_number_of_labels();
+    assert_eq!(search_space.nodes.len(), labels.iter().filter(|&&l| l > 0).count());
+    for &(node, node_labels) in &search_space.nodes {
+        assert_eq!(node_labels.fw_labels, labels[node as usize]);
+        assert_eq!(node_labels.bw_labels, 0);
+        assert_eq!(node_labels.direction(), "forward");
+    }
+    assert_eq!(search_space.num_settled_labels(), state.stats.num_settled as usize);
+
+    let mut csv = Vec::new();
+    search_space.write_csv(&mut csv, Some(&coordinates))?;
+    let csv = String::from_utf8(csv)?;
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("node,latitude,longitude,fw_labels,fw_settled_labels,bw_labels,bw_settled_labels,route_index")
+    );
+    let rows: Vec<Vec<&str>> = lines.map(|l| l.split(',').collect()).collect();
+    assert_eq!(rows.len(), search_space.nodes.len());
+    assert_eq!(rows.iter().filter(|r| r[7] != "NaN").count(), route.len());
+    assert_eq!(
+        rows[0],
+        ["0", &coordinates[0].0.to_string(), &coordinates[0].1.to_string(), "1", "1", "0", "0", "0"]
+    );
+
+    let geojson = search_space.to_geojson(&coordinates);
+    let features = geojson["features"].as_array().unwrap();
+    assert_eq!(features.len(), search_space.nodes.len() + 1);
+    let line = features.last().unwrap();
+    assert_eq!(line["geometry"]["type"], "LineString");
+    assert_eq!(line["geometry"]["coordinates"].as_array().unwrap().len(), route.len());
+    assert_eq!(line["geometry"]["coordinates"][0][0], coordinates[route[0] as usize].1);
+
+    Ok(())
+}
+
+#[test]
+fn bidirectional() {
+    let (network, _) = corridor();
+    let bw_graph = OwnedGraph::reverse(network.graph.borrow());
+    let mut query = CSPBidirQuery::new(network.graph.borrow(), bw_graph.borrow(), &network.is_parking_node);
+    query.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    query.init_new_s(0);
+    query.init_new_t(19);
+    query.run_query();
+
+    let search_space = query.search_space();
+    assert!(search_space.route.is_empty());
+    assert_eq!(search_space.labels_at(0).unwrap().direction(), "forward");
+    assert_eq!(search_space.labels_at(19).unwrap().direction(), "backward");
+    // the searches meet somewhere
+    assert!(search_space.nodes.iter().any(|(_, l)| l.direction() == "both"));
+    assert_eq!(search_space.num_settled_labels(), query.stats().total().num_settled as usize);
+}
+
+#[test]
+fn ranks_to_node_ids() {
+    let labels = |fw_labels| NodeLabels {
+        fw_labels,
+        ..NodeLabels::default()
+    };
+    let search_space = SearchSpace {
+        nodes: vec![(0, labels(1)), (2, labels(3))],
+        route: vec![],
+    };
+
+    // rank 0 is node 2 and rank 2 is node 0
+    let mapped = search_space.with_order(&[2, 1, 0]);
+    assert_eq!(mapped.nodes, vec![(0, labels(3)), (2, labels(1))]);
+    assert_eq!(mapped.labels_at(1), None);
+}
+
+#[test]
+fn write_to() -> Result<(), Box<dyn Error>> {
+    let (_, coordinates) = corridor();
+    let search_space = SearchSpace {
+        nodes: vec![(3, NodeLabels::default())],
+        route: vec![3, 4],
+    };
+    let dir = std::env::temp_dir().join("rust_truck_router_test_search_space");
+    fs::create_dir_all(&dir)?;
+
+    let error = search_space.write_to(dir.join("no_coordinates.geojson"), None).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+    search_space.write_to(dir.join("search_space.geojson"), Some(&coordinates))?;
+    let geojson: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("search_space.geojson"))?)?;
+    assert_eq!(geojson["type"], "FeatureCollection");
+
+    // route nodes without labels are written as well
+    search_space.write_to(dir.join("search_space.csv"), None)?;
+    let csv = fs::read_to_string(dir.join("search_space.csv"))?;
+    assert_eq!(csv.lines().skip(1).collect::<Vec<_>>(), ["3,NaN,NaN,0,0,0,0,0", "4,NaN,NaN,0,0,0,0,1"]);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
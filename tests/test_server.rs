@@ -0,0 +1,207 @@
+use bit_vec::BitVec;
+use rust_truck_router::{
+    algo::{ch::ContractionHierarchy, core_ch::CoreContractionHierarchy, csp_2_core_ch_chpot::CSP2AstarCoreCHQuery},
+    io::dataset::*,
+    query::*,
+    server::*,
+    types::*,
+};
+use std::{
+    error::Error,
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    rc::Rc,
+    thread,
+};
+
+fn load_data() -> Result<RoutingData, Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let mut dataset = Dataset::from_routingkit_dir(&path)?;
+    dataset.insert_bitvector(PARKING_FLAGS, &BitVec::from_fn(dataset.num_nodes(), |i| i == 2 || i == 3));
+    dataset.insert(OSM_NODE_ID, &(0..dataset.num_nodes() as u64).map(|i| 1000 + 2 * i).collect::<Vec<_>>());
+    Ok(RoutingData::from_dataset(&dataset)?)
+}
+
+fn send(addr: SocketAddr, request: &str) -> Result<(u16, String), Box<dyn Error>> {
+    let mut stream = TcpStream::connect(addr)?;
+    stream.write_all(request.as_bytes())?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let status = response.split_whitespace().nth(1).unwrap_or_default().parse()?;
+    let body = response.split_once("\r\n\r\n").map(|(_, body)| body.to_owned()).unwrap_or_default();
+    Ok((status, body))
+}
+
+fn post_route(addr: SocketAddr, body: &str) -> Result<(u16, String), Box<dyn Error>> {
+    send(
+        addr,
+        &format!("POST /route HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}", body.len(), body),
+    )
+}
+
+#[test]
+fn route_matches_direct_query() -> Result<(), Box<dyn Error>> {
+    let data = load_data()?;
+    let mut server = RoutingServer::new(&data);
+
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
+    let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
+    let mut query = CSP2AstarCoreCHQuery::new(core_ch.borrow(), ch.borrow());
+    query.set_custom_reset_nodes(Rc::new(data.is_parking_node.clone()));
+    query.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+
+    for s in 0..data.graph.num_nodes() as NodeId {
+        for t in 0..data.graph.num_nodes() as NodeId {
+            query.init_new_s(s);
+            query.init_new_t(t);
+            let expected = query.run_query();
+
+            let response = server.route(&RouteRequest {
+                source: Location::Node { node: s },
+                target: Location::Osm { osm: 1000 + 2 * t as u64 },
+                restriction: RestrictionPreset::EuLong,
+                driver_state: DriverState::default(),
+                itinerary: true,
+            })?;
+            assert_eq!(response.distance, expected);
+
+            if let Some(itinerary) = response.itinerary {
+                assert_eq!(itinerary.first().map(|w| w.node), Some(s));
+                assert_eq!(itinerary.last().map(|w| w.node), Some(t));
+                assert_eq!(itinerary.last().map(|w| w.time), expected);
+                assert_eq!(itinerary.last().and_then(|w| w.osm), Some(1000 + 2 * t as u64));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn route_with_driver_state() -> Result<(), Box<dyn Error>> {
+    let data = load_data()?;
+    let mut server = RoutingServer::new(&data);
+
+    // at the parking node 2 the driver is one ms short of the maximum driving time and has to take a short break there
+    let request: RouteRequest = serde_json::from_str(&format!(
+        r#"{{"source": {{"node": 0}}, "target": {{"node": 4}}, "itinerary": true,
+            "driver_state": {{"driving_time_since_break": {}, "driving_time_since_rest": {}, "departure_offset": 5000}}}}"#,
+        EU_SHORT_DRIVING_TIME - 3,
+        EU_SHORT_DRIVING_TIME - 3
+    ))?;
+    let driver_state = request.driver_state;
+    assert_eq!(driver_state.driving_time_since_break, EU_SHORT_DRIVING_TIME - 3);
+    assert_eq!(driver_state.departure_offset, 5000);
+
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
+    let core_ch = CoreContractionHierarchy::load_from_routingkit_dir(path.join("core_ch"))?;
+    let mut query = CSP2AstarCoreCHQuery::new(core_ch.borrow(), ch.borrow());
+    query.set_custom_reset_nodes(Rc::new(data.is_parking_node.clone()));
+    query.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    query.set_driver_state(driver_state);
+    query.init_new_s(0);
+    query.init_new_t(4);
+    let expected = query.run_query().map(|distance| distance - driver_state.departure_offset);
+
+    let response = server.route(&request)?;
+    assert_eq!(response.distance, expected);
+    assert_eq!(response.distance, Some(4 + EU_SHORT_PAUSE_TIME));
+    let breaks = response.breaks.unwrap();
+    assert_eq!(breaks.len(), 1);
+    assert_eq!(breaks[0].location.node, 2);
+    assert_eq!(breaks[0].kind, BreakKind::Short);
+    let itinerary = response.itinerary.unwrap();
+    assert_eq!(itinerary.first().map(|w| w.time), Some(0));
+    assert_eq!(itinerary.last().map(|w| w.time), expected);
+
+    Ok(())
+}
+
+#[test]
+fn reject_driver_state_at_the_maximum_driving_time() -> Result<(), Box<dyn Error>> {
+    let data = load_data()?;
+    let mut server = RoutingServer::new(&data);
+    let request = |restriction, driving_time_since_break, driving_time_since_rest| RouteRequest {
+        source: Location::Node { node: 0 },
+        target: Location::Node { node: 4 },
+        restriction,
+        driver_state: DriverState {
+            driving_time_since_break,
+            driving_time_since_rest,
+            departure_offset: 0,
+        },
+        itinerary: false,
+    };
+
+    let error = server
+        .route(&request(RestrictionPreset::EuShort, EU_SHORT_DRIVING_TIME, EU_SHORT_DRIVING_TIME))
+        .unwrap_err();
+    assert!(error.0.contains("since the last break"), "{}", error);
+    assert!(error.0.contains(&EU_SHORT_DRIVING_TIME.to_string()), "{}", error);
+
+    let error = server.route(&request(RestrictionPreset::EuLong, 0, EU_LONG_DRIVING_TIME + 1)).unwrap_err();
+    assert!(error.0.contains("since the last rest"), "{}", error);
+    assert!(error.0.contains(&EU_LONG_DRIVING_TIME.to_string()), "{}", error);
+
+    // the short restriction does not limit the driving time since the last rest
+    assert!(server.route(&request(RestrictionPreset::EuShort, 0, EU_LONG_DRIVING_TIME)).is_ok());
+    assert!(server
+        .route(&request(RestrictionPreset::None, EU_LONG_DRIVING_TIME, EU_LONG_DRIVING_TIME))
+        .is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn serve_http_requests() -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let num_nodes = load_data()?.graph.num_nodes();
+
+    // the routing data is not Send, so the server thread loads its own copy
+    let handle = thread::spawn(move || -> Result<(), String> {
+        let data = load_data().map_err(|e| e.to_string())?;
+        let mut server = RoutingServer::new(&data);
+        for stream in listener.incoming().take(7) {
+            server.handle_connection(stream.map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    });
+
+    let (status, body) = send(addr, "GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+    assert_eq!(status, 200);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body)?["num_nodes"], num_nodes);
+
+    let (status, body) = post_route(addr, r#"{"source": {"node": 0}, "target": {"node": 4}, "restriction": "none"}"#)?;
+    assert_eq!(status, 200);
+    let response: RouteResponse = serde_json::from_str(&body)?;
+    assert_eq!((response.source, response.target), (0, 4));
+    assert!(response.distance.is_some());
+    assert_eq!(response.itinerary, None);
+
+    let (status, body) = post_route(addr, r#"{"source": {"node": 0}, "target": {"node": 100}}"#)?;
+    assert_eq!(status, 400);
+    assert!(serde_json::from_str::<serde_json::Value>(&body)?["error"].is_string());
+
+    let (status, _) = post_route(addr, "not json")?;
+    assert_eq!(status, 400);
+
+    let (status, body) = send(addr, &format!("POST /route HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_REQUEST_BODY_SIZE + 1))?;
+    assert_eq!(status, 413);
+    assert!(serde_json::from_str::<serde_json::Value>(&body)?["error"].is_string());
+
+    let (status, body) = send(addr, "POST /route HTTP/1.1\r\nContent-Length: many\r\n\r\n")?;
+    assert_eq!(status, 400);
+    assert!(serde_json::from_str::<serde_json::Value>(&body)?["error"].is_string());
+
+    let (status, _) = send(addr, "GET /nothing HTTP/1.1\r\n\r\n")?;
+    assert_eq!(status, 404);
+
+    handle.join().unwrap()?;
+
+    Ok(())
+}
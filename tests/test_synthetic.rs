@@ -0,0 +1,155 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rust_truck_router::{
+    algo::{
+        csp::{OneRestrictionDijkstra, OneRestrictionDijkstraData},
+        csp_2::{TwoRestrictionDijkstra, TwoRestrictionDijkstraData},
+        csp_2_bidir::CSP2BidirQuery,
+        csp_bidir::CSPBidirQuery,
+    },
+    io::{dataset::*, load_routingkit_bitvector},
+    synthetic::*,
+    types::*,
+};
+use std::{error::Error, fs};
+
+#[test]
+fn grid_structure() {
+    let config = SyntheticConfig::default();
+    let grid = grid(4, 3, 1000.0, &config);
+    assert_eq!(grid.graph.num_nodes(), 12);
+    assert_eq!(grid.graph.num_arcs(), 2 * (3 * 3 + 4 * 2));
+    // 1km at 80 km/h
+    assert!(grid.graph.weights().iter().all(|&w| w == 45_000));
+    assert_eq!(grid.is_parking_node.len(), 12);
+}
+
+#[test]
+fn generators_are_seeded() {
+    let config = SyntheticConfig {
+        parking_density: 0.3,
+        ..SyntheticConfig::default()
+    };
+    let a = random_geometric(200, 100_000.0, 15_000.0, &config);
+    let b = random_geometric(200, 100_000.0, 15_000.0, &config);
+    assert_eq!(a.graph.head(), b.graph.head());
+    assert_eq!(a.graph.weights(), b.graph.weights());
+    assert_eq!(a.is_parking_node, b.is_parking_node);
+
+    let num_parking = a.is_parking_node.iter().filter(|b| *b).count();
+    assert!((30..90).contains(&num_parking), "{} parking nodes", num_parking);
+
+    let c = random_geometric(200, 100_000.0, 15_000.0, &SyntheticConfig { seed: 7, ..config });
+    assert_ne!(a.graph.weights(), c.graph.weights());
+}
+
+#[test]
+fn random_geometric_is_symmetric() {
+    let network = random_geometric(300, 100_000.0, 10_000.0, &SyntheticConfig::default());
+    let graph = network.graph.borrow();
+    let max_weight = (10_000.0 * 3600.0 / 80.0) as Weight + 1;
+
+    for node in 0..graph.num_nodes() as NodeId {
+        for (&weight, &head) in graph.outgoing_edge_iter(node) {
+            assert!(weight <= max_weight);
+            assert!(graph
+                .outgoing_edge_iter(head)
+                .any(|(&back_weight, &back)| back == node && back_weight == weight));
+        }
+    }
+}
+
+#[test]
+fn corridor_needs_break() {
+    let config = SyntheticConfig {
+        parking_density: 0.5,
+        ..SyntheticConfig::default()
+    };
+    // 19 motorway sections of 22.5 min
+    let corridor = motorway_corridor(20, 30_000.0, 2, 3_000.0, &config);
+    assert_eq!(corridor.graph.num_nodes(), 20 * 3);
+    assert!(corridor.is_parking_node.iter().skip(20).all(|b| !b));
+
+    let dijkstra = OneRestrictionDijkstra::new(corridor.graph.borrow(), &corridor.is_parking_node);
+    let mut state = OneRestrictionDijkstraData::new(corridor.graph.num_nodes());
+    state.init_new_s(0);
+    let unrestricted = dijkstra.dist_query(&mut state, 19).unwrap();
+    assert_eq!(unrestricted, 19 * 1_350_000);
+
+    state.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    state.init_new_s(0);
+    assert_eq!(dijkstra.dist_query(&mut state, 19), Some(unrestricted + EU_SHORT_PAUSE_TIME));
+}
+
+#[test]
+fn bidirectional_agrees_on_random_geometric() {
+    let config = SyntheticConfig {
+        parking_density: 0.1,
+        ..SyntheticConfig::default()
+    };
+    let network = random_geometric(400, 700_000.0, 70_000.0, &config);
+    let graph = &network.graph;
+    let bw_graph = OwnedGraph::reverse(graph.borrow());
+
+    let csp = OneRestrictionDijkstra::new(graph.borrow(), &network.is_parking_node);
+    let mut csp_state = OneRestrictionDijkstraData::new(graph.num_nodes());
+    csp_state.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    let mut csp_bidir = CSPBidirQuery::new(graph.borrow(), bw_graph.borrow(), &network.is_parking_node);
+    csp_bidir.set_restriction(EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+
+    let csp_2 = TwoRestrictionDijkstra::new(graph.borrow(), &network.is_parking_node);
+    let mut csp_2_state = TwoRestrictionDijkstraData::new(graph.num_nodes());
+    csp_2_state.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+    let mut csp_2_bidir = CSP2BidirQuery::new(graph.borrow(), bw_graph.borrow(), &network.is_parking_node);
+    csp_2_bidir.set_restriction(EU_LONG_DRIVING_TIME, EU_LONG_PAUSE_TIME, EU_SHORT_DRIVING_TIME, EU_SHORT_PAUSE_TIME);
+
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut num_with_break = 0;
+    for _ in 0..50 {
+        let s = rng.gen_range(0..graph.num_nodes() as NodeId);
+        let t = rng.gen_range(0..graph.num_nodes() as NodeId);
+
+        csp_state.init_new_s(s);
+        let dist = csp.dist_query(&mut csp_state, t);
+        csp_bidir.init_new_s(s);
+        csp_bidir.init_new_t(t);
+        assert_eq!(dist, csp_bidir.run_query(), "{} -> {}", s, t);
+
+        csp_2_state.init_new_s(s);
+        let dist_2 = csp_2.dist_query(&mut csp_2_state, t);
+        csp_2_bidir.init_new_s(s);
+        csp_2_bidir.init_new_t(t);
+        assert_eq!(dist_2, csp_2_bidir.run_query(), "{} -> {}", s, t);
+
+        if matches!(dist, Some(d) if d > EU_SHORT_DRIVING_TIME) {
+            num_with_break += 1;
+        }
+    }
+
+    // the generated network is large enough for the restriction to matter
+    assert!(num_with_break > 0);
+}
+
+#[test]
+fn write_routingkit_dir() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join("rust_truck_router_test_synthetic");
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+
+    let network = motorway_corridor(10, 10_000.0, 1, 1_000.0, &SyntheticConfig::default());
+    network.write_to_routingkit_dir(&dir)?;
+
+    let graph = OwnedGraph::load_from_routingkit_dir(&dir)?;
+    assert_eq!(graph.first_out(), network.graph.first_out());
+    assert_eq!(graph.head(), network.graph.head());
+    assert_eq!(graph.weights(), network.graph.weights());
+    let mut flags = load_routingkit_bitvector(dir.join(PARKING_FLAGS))?;
+    flags.truncate(graph.num_nodes());
+    assert_eq!(flags, network.is_parking_node);
+
+    let dataset = Dataset::from_routingkit_dir(&dir)?;
+    assert_eq!(dataset.get_node_data::<f32>(LATITUDE)?, network.latitude);
+    assert_eq!(dataset.get_node_data::<f32>(LONGITUDE)?, network.longitude);
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use bit_vec::BitVec;
+use rust_truck_router::{
+    algo::{ch::ContractionHierarchy, core_ch::CoreContractionHierarchy},
+    io::Store,
+    types::*,
+    validation::{self, ValidationError},
+};
+use std::{error::Error, path::Path};
+
+#[test]
+fn valid_data_passes() -> Result<(), Box<dyn Error>> {
+    let path = std::env::current_dir()?.as_path().join(Path::new("test_data/ch_instances/core_instance_2"));
+    let graph = OwnedGraph::load_from_routingkit_dir(&path)?;
+    validation::check_graph("graph", &graph)?;
+    validation::check_reversed(&graph, &OwnedGraph::reverse(graph.borrow()))?;
+    validation::check_node_flags(&graph, &BitVec::from_elem(graph.num_nodes(), false))?;
+
+    let ch = ContractionHierarchy::load_from_routingkit_dir(path.join("ch"))?;
+    ch.check()?;
+    validation::check_node_count("ch", graph.num_nodes(), ch.rank().len())?;
+
+    Ok(())
+}
+
+#[test]
+fn broken_graphs() {
+    // 0 -> 1 -> 2p -> 4
+    //      | -> 3p -> |
+    let graph = OwnedGraph::new(vec![0, 1, 3, 4, 5, 5], vec![1, 2, 3, 4, 4], vec![1, 4, 3, 2, 4]);
+    assert_eq!(validation::check_graph("graph", &graph), Ok(()));
+
+    let unsorted = OwnedGraph::new(vec![0, 3, 1, 4, 5, 5], vec![1, 2, 3, 4, 4], vec![1, 4, 3, 2, 4]);
+    assert_eq!(
+        validation::check_graph("graph", &unsorted),
+        Err(ValidationError::FirstOutDecreasing { component: "graph", node: 1 })
+    );
+
+    let missing_weight = OwnedGraph::new(vec![0, 1, 3, 4, 5, 5], vec![1, 2, 3, 4, 4], vec![1, 4, 3, 2]);
+    assert_eq!(
+        validation::check_graph("graph", &missing_weight),
+        Err(ValidationError::ArcCountMismatch {
+            component: "graph",
+            expected: 5,
+            found: 4
+        })
+    );
+
+    let out_of_range = OwnedGraph::new(vec![0, 1, 3, 4, 5, 5], vec![1, 2, 3, 4, 5], vec![1, 4, 3, 2, 4]);
+    assert_eq!(
+        validation::check_graph("graph", &out_of_range),
+        Err(ValidationError::HeadOutOfRange {
+            component: "graph",
+            arc: 4,
+            head: 5
+        })
+    );
+
+    let not_reversed = OwnedGraph::new(vec![0, 0, 1, 2, 3, 5], vec![0, 1, 1, 2, 3], vec![1, 4, 3, 2, 5]);
+    assert_eq!(
+        validation::check_reversed(&graph, &not_reversed),
+        Err(ValidationError::MissingReverseArc { tail: 3, head: 4, weight: 4 })
+    );
+
+    assert!(validation::check_node_flags(&graph, &BitVec::from_elem(4, false)).is_err());
+}
+
+#[test]
+fn broken_hierarchies() {
+    let forward = OwnedGraph::new(vec![0, 1, 2, 2], vec![1, 2], vec![1, 1]);
+    let backward = OwnedGraph::new(vec![0, 1, 1, 1], vec![2], vec![2]);
+
+    let ch = ContractionHierarchy::new(vec![0, 1, 2], vec![0, 1, 2], forward.clone(), backward.clone());
+    assert_eq!(ch.check(), Ok(()));
+
+    let ch = ContractionHierarchy::new(vec![0, 1, 2], vec![0, 2, 1], forward.clone(), backward.clone());
+    assert_eq!(ch.check(), Err(ValidationError::RankOrderMismatch { node: 1 }));
+
+    let downward = OwnedGraph::new(vec![0, 1, 1, 2], vec![1, 0], vec![1, 1]);
+    let ch = ContractionHierarchy::new(vec![0, 1, 2], vec![0, 1, 2], forward, downward);
+    assert_eq!(
+        ch.check(),
+        Err(ValidationError::NotUpward {
+            component: "backward",
+            arc: 1,
+            tail: 2,
+            head: 0
+        })
+    );
+}
+
+#[test]
+fn broken_core_hierarchies() -> Result<(), Box<dyn Error>> {
+    let forward = OwnedGraph::new(vec![0, 1, 2, 2], vec![1, 2], vec![1, 1]);
+    let backward = OwnedGraph::new(vec![0, 1, 1, 1], vec![2], vec![2]);
+
+    let core_ch = CoreContractionHierarchy::try_new(vec![0, 1, 2], vec![0, 1, 2], vec![2], forward.clone(), backward.clone())?;
+    assert_eq!(core_ch.check(), Ok(()));
+
+    assert_eq!(
+        CoreContractionHierarchy::try_new(vec![0, 1, 2], vec![0, 1], vec![2], forward.clone(), backward.clone()).err(),
+        Some(ValidationError::NodeCountMismatch {
+            component: "order",
+            expected: 3,
+            found: 2
+        })
+    );
+    assert_eq!(
+        CoreContractionHierarchy::try_new(vec![0, 1, 2], vec![0, 1, 2], vec![3], forward.clone(), backward.clone()).err(),
+        Some(ValidationError::NodeOutOfRange { component: "core", node: 3 })
+    );
+    assert_eq!(
+        CoreContractionHierarchy::try_new(vec![0, 7, 2], vec![0, 1, 2], vec![1], forward.clone(), backward.clone()).err(),
+        Some(ValidationError::NodeOutOfRange { component: "rank", node: 7 })
+    );
+
+    // the loader rejects the files instead of panicking
+    let dir = std::env::temp_dir().join("rust_truck_router_test_validation_core_ch");
+    for (name, graph) in [("forward", &forward), ("backward", &backward)] {
+        std::fs::create_dir_all(dir.join(name))?;
+        graph.first_out().write_to(&dir.join(name).join("first_out"))?;
+        graph.head().write_to(&dir.join(name).join("head"))?;
+        graph.weights().write_to(&dir.join(name).join("travel_time"))?;
+    }
+    vec![0u32, 1, 2].write_to(&dir.join("rank"))?;
+    vec![0u32, 1].write_to(&dir.join("order"))?;
+    vec![2u32].write_to(&dir.join("core"))?;
+    let error = CoreContractionHierarchy::load_from_routingkit_dir(&dir).err().map(|error| error.kind());
+    assert_eq!(error, Some(std::io::ErrorKind::InvalidData));
+
+    Ok(())
+}
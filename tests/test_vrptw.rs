@@ -0,0 +1,346 @@
+use bit_vec::BitVec;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rust_truck_router::{
+    algo::{
+        ch::{ContractionHierarchy, OwnedContractionHierarchy},
+        ch_potential::CHPotential,
+        multi_stop::Stop,
+        vrptw::*,
+    },
+    synthetic::*,
+    types::*,
+};
+
+/// A line `0 - 1 - ... - n` with arcs in both directions
+fn two_way_line(weights: Vec<Weight>) -> OwnedGraph {
+    let n = weights.len();
+    let mut first_out = vec![0];
+    let mut head = Vec::new();
+    let mut weight = Vec::new();
+    for node in 0..=n {
+        if node > 0 {
+            head.push(node as NodeId - 1);
+            weight.push(weights[node - 1]);
+        }
+        if node < n {
+            head.push(node as NodeId + 1);
+            weight.push(weights[node]);
+        }
+        first_out.push(head.len() as EdgeId);
+    }
+    OwnedGraph::new(first_out, head, weight)
+}
+
+/// Contracting a line in the order of the node ids inserts no shortcuts, every arc points up at its higher end
+fn line_hierarchy(graph: &OwnedGraph) -> OwnedContractionHierarchy {
+    let n = graph.num_nodes();
+    let upward = |higher: fn(NodeId, NodeId) -> bool| {
+        let mut first_out = vec![0];
+        let mut head = Vec::new();
+        let mut weight = Vec::new();
+        for node in 0..n as NodeId {
+            if node + 1 < n as NodeId {
+                let arc = if higher(node, node + 1) { (node, node + 1) } else { (node + 1, node) };
+                head.push(node + 1);
+                weight.push(arc_weight(graph, arc.0, arc.1));
+            }
+            first_out.push(head.len() as EdgeId);
+        }
+        OwnedGraph::new(first_out, head, weight)
+    };
+    let identity: Vec<NodeId> = (0..n as NodeId).collect();
+    ContractionHierarchy::new(identity.clone(), identity, upward(|_, _| true), upward(|_, _| false))
+}
+
+fn vehicle(depot: NodeId, capacity: Weight) -> Vehicle {
+    Vehicle {
+        depot,
+        capacity,
+        driver_state: DriverState::default(),
+        latest_return: INFINITY,
+    }
+}
+
+fn customer(node: NodeId, earliest: Weight, latest: Weight) -> Customer {
+    Customer {
+        stop: Stop::new(node),
+        demand: 1,
+        time_window: TimeWindow { earliest, latest },
+    }
+}
+
+fn arc_weight(graph: &OwnedGraph, tail: NodeId, head: NodeId) -> Weight {
+    graph
+        .borrow()
+        .outgoing_edge_iter(tail)
+        .filter(|&(_, &h)| h == head)
+        .map(|(&weight, _)| weight)
+        .min()
+        .expect("consecutive nodes of a tour are connected")
+}
+
+/// Replays every tour on the graph and checks the capacities, time windows and driving times
+fn assert_valid(graph: &OwnedGraph, restrictions: &[DrivingTimeRestriction], vehicles: &[Vehicle], customers: &[Customer], solution: &VrptwSolution) {
+    let mut served: Vec<usize> = solution
+        .tours
+        .iter()
+        .flat_map(|tour| tour.customers())
+        .chain(solution.unserved.iter().copied())
+        .collect();
+    served.sort_unstable();
+    assert_eq!(served, (0..customers.len()).collect::<Vec<_>>(), "every customer is served once or unserved");
+
+    for tour in &solution.tours {
+        let vehicle = &vehicles[tour.vehicle];
+        assert!(tour.customers().map(|c| customers[c].demand).sum::<Weight>() <= vehicle.capacity);
+        assert_eq!(tour.path[0], vehicle.depot);
+        assert_eq!(*tour.path.last().unwrap(), vehicle.depot);
+        assert_eq!(tour.departure, vehicle.driver_state.departure_offset);
+
+        let mut clock = tour.departure;
+        let mut driven = [vehicle.driver_state.driving_time_since_break, vehicle.driver_state.driving_time_since_rest];
+        let mut breaks = tour.breaks.iter().peekable();
+        let mut visits = tour.visits.iter().peekable();
+        let mut driving_time = 0;
+        for i in 0..tour.path.len() {
+            if i > 0 {
+                let weight = arc_weight(graph, tour.path[i - 1], tour.path[i]);
+                clock += weight;
+                driving_time += weight;
+                for (r, restriction) in restrictions.iter().enumerate() {
+                    driven[r] += weight;
+                    assert!(driven[r] < restriction.max_driving_time, "driving time of restriction {} exceeded", r);
+                }
+            }
+            if let Some(b) = breaks.next_if(|b| b.node == tour.path[i] && b.start == clock) {
+                driven[..=b.restriction].iter_mut().for_each(|d| *d = 0);
+                clock += b.duration;
+            }
+            while let Some(visit) = visits.next_if(|visit| visit.path_index == i) {
+                let customer = &customers[visit.customer];
+                assert_eq!(customer.stop.node, tour.path[i]);
+                assert_eq!(visit.arrival, clock);
+                assert!(visit.service_start >= visit.arrival && visit.service_start >= customer.time_window.earliest);
+                assert!(visit.service_start <= customer.time_window.latest);
+                assert_eq!(visit.departure, visit.service_start + customer.stop.service_time);
+                clock = visit.departure;
+            }
+        }
+        assert!(breaks.next().is_none(), "every break is taken along the path");
+        assert!(visits.next().is_none(), "every visit is on the path");
+        assert_eq!(clock, tour.arrival);
+        assert_eq!(driving_time, tour.driving_time);
+        assert!(tour.arrival <= vehicle.latest_return);
+    }
+}
+
+#[test]
+fn time_windows_decide_the_order() {
+    let graph = two_way_line(vec![HOUR; 4]);
+    let is_parking_node = BitVec::from_elem(5, true);
+    let mut solver = VrptwSolver::new(graph.borrow(), &is_parking_node);
+
+    let vehicles = [vehicle(0, 10)];
+    let customers = [customer(2, 6 * HOUR, 20 * HOUR), customer(4, 0, 5 * HOUR)];
+    let solution = solver.solve(&vehicles, &customers);
+
+    assert_valid(&graph, &[], &vehicles, &customers, &solution);
+    assert!(solution.unserved.is_empty());
+    assert_eq!(solution.tours.len(), 1);
+    assert_eq!(solution.tours[0].customers().collect::<Vec<_>>(), vec![1, 0]);
+    assert_eq!(solution.tours[0].visits[0].arrival, 4 * HOUR);
+    assert_eq!(solution.tours[0].waiting_time, 0);
+    assert_eq!(solution.total_duration(), 8 * HOUR);
+}
+
+#[test]
+fn early_arrival_waits_for_the_time_window() {
+    let graph = two_way_line(vec![HOUR; 2]);
+    let is_parking_node = BitVec::from_elem(3, true);
+    let mut solver = VrptwSolver::new(graph.borrow(), &is_parking_node);
+
+    let vehicles = [vehicle(0, 10)];
+    let customers = [customer(2, 5 * HOUR, 6 * HOUR)];
+    let solution = solver.solve(&vehicles, &customers);
+
+    assert_valid(&graph, &[], &vehicles, &customers, &solution);
+    let visit = solution.tours[0].visits[0];
+    assert_eq!((visit.arrival, visit.service_start), (2 * HOUR, 5 * HOUR));
+    assert_eq!(solution.tours[0].waiting_time, 3 * HOUR);
+    assert_eq!(solution.total_duration(), 7 * HOUR);
+}
+
+#[test]
+fn breaks_follow_the_restrictions() {
+    let graph = two_way_line(vec![HOUR; 6]);
+    let is_parking_node = BitVec::from_elem(7, true);
+    let mut solver = VrptwSolver::new(graph.borrow(), &is_parking_node);
+
+    let vehicles = [vehicle(0, 10)];
+    let customers = [Customer {
+        stop: Stop { node: 6, service_time: HOUR },
+        demand: 1,
+        time_window: TimeWindow::unbounded(),
+    }];
+
+    solver.set_restrictions(&[EU_SHORT]);
+    let solution = solver.solve(&vehicles, &customers);
+    assert_valid(&graph, &[EU_SHORT], &vehicles, &customers, &solution);
+    assert_eq!(solution.tours[0].driving_time, 12 * HOUR);
+    assert_eq!(solution.tours[0].breaks.len(), 2);
+
+    solver.set_restrictions(&[EU_SHORT, EU_LONG]);
+    let solution = solver.solve(&vehicles, &customers);
+    assert_valid(&graph, &[EU_SHORT, EU_LONG], &vehicles, &customers, &solution);
+    assert!(solution.tours[0].breaks.iter().any(|b| b.restriction == 1));
+
+    // the restrictions of the previous solve do not carry over
+    solver.set_restrictions(&[EU_SHORT]);
+    solver.solve(&vehicles, &customers);
+    solver.clear_restrictions();
+    let solution = solver.solve(&vehicles, &customers);
+    assert_valid(&graph, &[], &vehicles, &customers, &solution);
+    assert!(solution.tours[0].breaks.is_empty());
+}
+
+#[test]
+fn driver_state_carries_into_the_tour() {
+    let graph = two_way_line(vec![HOUR; 2]);
+    let is_parking_node = BitVec::from_elem(3, true);
+    let mut solver = VrptwSolver::new(graph.borrow(), &is_parking_node);
+    solver.set_restrictions(&[EU_SHORT]);
+
+    let vehicles = [Vehicle {
+        driver_state: DriverState {
+            driving_time_since_break: 3 * HOUR,
+            driving_time_since_rest: 3 * HOUR,
+            departure_offset: HOUR,
+        },
+        ..vehicle(0, 10)
+    }];
+    let customers = [customer(2, 0, INFINITY)];
+    let solution = solver.solve(&vehicles, &customers);
+
+    assert_valid(&graph, &[EU_SHORT], &vehicles, &customers, &solution);
+    let tour = &solution.tours[0];
+    assert_eq!(tour.departure, HOUR);
+    assert_eq!(tour.breaks[0].node, 1);
+    assert_eq!(tour.duration(), 4 * HOUR + EU_SHORT_PAUSE_TIME);
+}
+
+#[test]
+fn capacity_needs_a_second_vehicle() {
+    let graph = two_way_line(vec![HOUR; 2]);
+    let is_parking_node = BitVec::from_elem(3, true);
+    let mut solver = VrptwSolver::new(graph.borrow(), &is_parking_node);
+
+    let vehicles = [vehicle(0, 5), vehicle(0, 5)];
+    let customers = [
+        Customer {
+            demand: 3,
+            ..customer(1, 0, INFINITY)
+        },
+        Customer {
+            demand: 3,
+            ..customer(2, 0, INFINITY)
+        },
+    ];
+    let solution = solver.solve(&vehicles, &customers);
+
+    assert_valid(&graph, &[], &vehicles, &customers, &solution);
+    assert!(solution.unserved.is_empty());
+    assert_eq!(solution.tours.len(), 2);
+    assert_eq!(solution.total_duration(), 6 * HOUR);
+}
+
+#[test]
+fn unreachable_time_window_is_unserved() {
+    let graph = two_way_line(vec![HOUR; 4]);
+    let is_parking_node = BitVec::from_elem(5, true);
+    let mut solver = VrptwSolver::new(graph.borrow(), &is_parking_node);
+
+    let vehicles = [Vehicle {
+        latest_return: 7 * HOUR,
+        ..vehicle(0, 10)
+    }];
+    let customers = [customer(4, 0, 3 * HOUR), customer(3, 0, INFINITY), customer(4, 0, INFINITY)];
+    let solution = solver.solve(&vehicles, &customers);
+
+    assert_valid(&graph, &[], &vehicles, &customers, &solution);
+    // the customer at node 4 without time window can only be served within the latest return
+    assert_eq!(solution.unserved, vec![0, 2]);
+}
+
+#[test]
+fn goal_directed_solver_finds_the_same_tours() {
+    let graph = two_way_line((1..=8).map(|i| i * HOUR / 2).collect());
+    let is_parking_node = BitVec::from_fn(9, |node| node % 2 == 0);
+    let ch = line_hierarchy(&graph);
+    let mut solver = VrptwSolver::new(graph.borrow(), &is_parking_node);
+    let mut goal_directed = VrptwSolver::new_with_potential(graph.borrow(), &is_parking_node, CHPotential::from_ch(ch.borrow()));
+
+    let vehicles = [vehicle(0, 10), vehicle(8, 10)];
+    let customers = [
+        customer(3, 0, INFINITY),
+        customer(5, 10 * HOUR, 30 * HOUR),
+        customer(7, 0, 20 * HOUR),
+        customer(2, 20 * HOUR, 40 * HOUR),
+    ];
+    for restrictions in [&[][..], &[EU_SHORT], &[EU_SHORT, EU_LONG]] {
+        solver.set_restrictions(restrictions);
+        goal_directed.set_restrictions(restrictions);
+        let solution = solver.solve(&vehicles, &customers);
+        assert_valid(&graph, restrictions, &vehicles, &customers, &solution);
+        assert!(solution.unserved.len() < customers.len());
+        assert_eq!(goal_directed.solve(&vehicles, &customers), solution);
+    }
+}
+
+#[test]
+fn local_search_does_not_lengthen_the_tours() {
+    let config = SyntheticConfig {
+        parking_density: 0.2,
+        ..SyntheticConfig::default()
+    };
+    let network = random_geometric(400, 600_000.0, 60_000.0, &config);
+    let graph = &network.graph;
+    let mut rng = StdRng::seed_from_u64(7);
+
+    let vehicles: Vec<Vehicle> = (0..3)
+        .map(|_| Vehicle {
+            latest_return: 30 * HOUR,
+            ..vehicle(0, 12)
+        })
+        .collect();
+    let customers: Vec<Customer> = (0..20)
+        .map(|_| {
+            let earliest = rng.gen_range(0..12 * HOUR);
+            Customer {
+                stop: Stop {
+                    node: rng.gen_range(1..graph.num_nodes() as NodeId),
+                    service_time: rng.gen_range(0..HOUR),
+                },
+                demand: rng.gen_range(1..5),
+                time_window: TimeWindow {
+                    earliest,
+                    latest: earliest + rng.gen_range(HOUR..8 * HOUR),
+                },
+            }
+        })
+        .collect();
+
+    let mut solver = VrptwSolver::new(graph.borrow(), &network.is_parking_node);
+    solver.set_restrictions(&[EU_SHORT, EU_LONG]);
+    solver.set_max_local_search_rounds(0);
+    let constructed = solver.solve(&vehicles, &customers);
+    assert_valid(graph, &[EU_SHORT, EU_LONG], &vehicles, &customers, &constructed);
+    assert!(constructed.unserved.len() < customers.len());
+
+    solver.set_max_local_search_rounds(1000);
+    let improved = solver.solve(&vehicles, &customers);
+    assert_valid(graph, &[EU_SHORT, EU_LONG], &vehicles, &customers, &improved);
+    assert!(improved.unserved.len() <= constructed.unserved.len());
+    if improved.unserved == constructed.unserved {
+        assert!(improved.total_duration() <= constructed.total_duration());
+    }
+}